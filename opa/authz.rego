@@ -1,3 +1,6 @@
+# safe-docker export-rego で生成したポリシー (手で編集せず、設定を変更して再生成すること)
+# 再生成: safe-docker export-rego --home /home/username > authz.rego
+
 package docker.authz
 
 import rego.v1
@@ -23,10 +26,9 @@ deny if {
     input.Body.HostConfig.Privileged == true
 }
 
-# 危険な capability をブロック
-# safe-docker の default_blocked_capabilities() と同期すること
+# 危険な capability をブロック (blocked_capabilities)
 deny if {
-    cap := input.Body.HostConfig.CapAdd[_]
+    cap := upper(input.Body.HostConfig.CapAdd[_])
     cap in {
         "SYS_ADMIN",
         "SYS_PTRACE",
@@ -66,22 +68,62 @@ deny if {
     input.Body.HostConfig.UsernsMode == "host"
 }
 
+# 他のコンテナとの名前空間の共有をブロック
+deny if {
+    startswith(input.Body.HostConfig.PidMode, "container:")
+}
+
+deny if {
+    startswith(input.Body.HostConfig.NetworkMode, "container:")
+}
+
+deny if {
+    startswith(input.Body.HostConfig.IpcMode, "container:")
+}
+
 # デバイスアクセスをブロック
 deny if {
     count(input.Body.HostConfig.Devices) > 0
 }
 
+# マウント伝搬 (shared / rshared) をブロック
+deny if {
+    mount := input.Body.HostConfig.Mounts[_]
+    mount.BindOptions.Propagation in {"shared", "rshared"}
+}
+
+# ボリュームのドライバオプション (device=) によるホストパスのマウントをブロック
+# (safe-docker はバインドマウントと同じくパスを検証する。OPA では一律に拒否する)
+deny if {
+    input.Body.DriverOpts.device
+}
+
+deny if {
+    mount := input.Body.HostConfig.Mounts[_]
+    mount.VolumeOptions.DriverConfig.Options.device
+}
+
 # 危険な security-opt をブロック
 deny if {
     opt := input.Body.HostConfig.SecurityOpt[_]
     contains(opt, "apparmor=unconfined")
 }
 
+deny if {
+    opt := input.Body.HostConfig.SecurityOpt[_]
+    contains(opt, "apparmor:unconfined")
+}
+
 deny if {
     opt := input.Body.HostConfig.SecurityOpt[_]
     contains(opt, "seccomp=unconfined")
 }
 
+deny if {
+    opt := input.Body.HostConfig.SecurityOpt[_]
+    contains(opt, "seccomp:unconfined")
+}
+
 deny if {
     opt := input.Body.HostConfig.SecurityOpt[_]
     contains(opt, "label=disable")
@@ -97,18 +139,65 @@ deny if {
     contains(opt, "no-new-privileges=false")
 }
 
+deny if {
+    opt := input.Body.HostConfig.SecurityOpt[_]
+    contains(opt, "no-new-privileges:false")
+}
+
 deny if {
     opt := input.Body.HostConfig.SecurityOpt[_]
     contains(opt, "systempaths=unconfined")
 }
 
-# バインドマウントパスを $HOME 配下のみに制限
-# 注意: "/home/username/" を実際のユーザーのホームディレクトリに変更すること
+deny if {
+    opt := input.Body.HostConfig.SecurityOpt[_]
+    contains(opt, "systempaths:unconfined")
+}
+
+# カーネルパラメータ (kernel.*) の変更をブロック
+deny if {
+    some key, _ in input.Body.HostConfig.Sysctls
+    startswith(key, "kernel.")
+}
+
+# 以下は safe-docker では確認 (ask) になる操作。OPA では確認できないため拒否する
+deny if {
+    some key, _ in input.Body.HostConfig.Sysctls
+    startswith(key, "net.")
+}
+
+deny if {
+    count(input.Body.HostConfig.VolumesFrom) > 0
+}
+
+deny if {
+    input.Body.HostConfig.CgroupParent != ""
+}
+
+deny if {
+    entry := input.Body.HostConfig.ExtraHosts[_]
+    contains(entry, "169.254.169.254")
+}
+
+# バインドマウントを $HOME 配下と allowed_paths に制限
+allowed_bind_prefixes := {
+    "/home/username/",
+}
+
+bind_allowed(path) if {
+    path == "/home/username"
+}
+
+bind_allowed(path) if {
+    some prefix in allowed_bind_prefixes
+    startswith(path, prefix)
+}
+
 deny if {
     bm := input.BindMounts[_]
     resolved := bm.Resolved
     resolved != ""
-    not startswith(resolved, "/home/username/")
+    not bind_allowed(resolved)
 }
 
 # Source が $HOME 配下でもリンク先が異なる場合を検出（パストラバーサル防止）
@@ -116,11 +205,92 @@ deny if {
     bm := input.BindMounts[_]
     startswith(bm.Source, "/home/username/")
     bm.Resolved != ""
-    not startswith(bm.Resolved, "/home/username/")
+    not bind_allowed(bm.Resolved)
+}
+
+# 機密パス (sensitive_paths) のマウントを拒否
+sensitive_paths := {
+    "/home/username/.ssh",
+    "/home/username/.aws",
+    "/home/username/.gnupg",
+    "/home/username/.docker",
+    "/home/username/.kube",
+    "/home/username/.config/gcloud",
+    "/home/username/.claude",
+    "/home/username/.terraform",
+    "/home/username/.vault-token",
+    "/home/username/.config/gh",
+    "/home/username/.npmrc",
+    "/home/username/.pypirc",
+}
+
+deny if {
+    bm := input.BindMounts[_]
+    some path in sensitive_paths
+    startswith(bm.Resolved, path)
+}
+
+# Docker ソケットのマウントを禁止 (block_docker_socket)
+deny if {
+    bm := input.BindMounts[_]
+    bm.Resolved in {"/var/run/docker.sock", "/run/docker.sock"}
+}
+
+deny if {
+    bm := input.BindMounts[_]
+    endswith(bm.Resolved, "/docker.sock")
+}
+
+# ホストの重要パス (/proc, /sys 等) とコンテナランタイムのソケットを禁止 (allowed_paths でも許可しない)
+critical_paths := {
+    "/proc",
+    "/sys",
+    "/dev",
+    "/boot",
+    "/var/lib/docker",
+    "/var/lib/containerd",
+}
+
+runtime_socket_suffixes := {
+    "/containerd.sock",
+    "/podman.sock",
+    "/buildkitd.sock",
+    "/crio.sock",
+}
+
+runtime_socket_paths := {
+    "/run/containerd/containerd.sock",
+    "/run/podman/podman.sock",
+    "/run/buildkit/buildkitd.sock",
+    "/run/crio/crio.sock",
+}
+
+protected_paths := critical_paths | runtime_socket_paths
+
+path_within(path, base) if {
+    path == base
+}
+
+path_within(path, base) if {
+    startswith(path, concat("", [trim_suffix(base, "/"), "/"]))
+}
+
+deny if {
+    bm := input.BindMounts[_]
+    some critical in critical_paths
+    path_within(bm.Resolved, critical)
+}
+
+# 重要パスやランタイムソケットを含む親ディレクトリ (/, /var, /run 等)
+deny if {
+    bm := input.BindMounts[_]
+    bm.Resolved != ""
+    some protected in protected_paths
+    path_within(protected, bm.Resolved)
 }
 
-# Docker ソケットのマウントを禁止
 deny if {
     bm := input.BindMounts[_]
-    bm.Resolved == "/var/run/docker.sock"
+    some suffix in runtime_socket_suffixes
+    endswith(bm.Resolved, suffix)
 }
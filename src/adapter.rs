@@ -0,0 +1,618 @@
+//! コーディングエージェントごとの hook プロトコルのアダプタ
+//!
+//! 各エージェントの実行前 hook の入力 JSON を Claude Code 形式の [`HookInput`] に正規化し、
+//! 判定結果 ([`HookResponse`]) を各エージェントの出力形式に変換する。
+//! 判定ロジックはすべてのプロトコルで共通。
+//!
+//! | プロトコル | 入力の特徴 | 出力 |
+//! |-----------|-----------|------|
+//! | `claude-code` | `tool_name` + `tool_input.command` | `hookSpecificOutput.permissionDecision` |
+//! | `codex` | `command` が argv 配列 | `decision`: `approved` / `denied` |
+//! | `gemini` | `hook_event_name`: `BeforeTool` / `AfterTool` | `decision` + `reason` |
+//! | `cursor` | `hook_event_name`: `beforeShellExecution` 等 | `permission` + `userMessage` / `agentMessage` |
+//! | `cline` | `hookName` + `preToolUse` / `postToolUse` | `cancel` + `errorMessage` |
+//! | `copilot` | `toolName` + `toolArgs` (JSON 文字列) | `permissionDecision` |
+//!
+//! 確認 (ask) を表現できないプロトコル (codex, cline) では ask を deny として扱う (fail-safe)。
+
+use serde_json::{Map, Value, json};
+use std::io::{self, Write as _};
+
+use crate::error::Result;
+use crate::hook::{self, Decision, HookInput, HookResponse};
+
+/// hook プロトコル
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    ClaudeCode,
+    Codex,
+    Gemini,
+    Cursor,
+    Cline,
+    Copilot,
+}
+
+/// `--protocol` に指定できる名前
+pub const PROTOCOL_NAMES: &[&str] = &[
+    "auto",
+    "claude-code",
+    "codex",
+    "gemini",
+    "cursor",
+    "cline",
+    "copilot",
+];
+
+impl Protocol {
+    /// `--protocol` の値からプロトコルを取得する。`auto` の場合は `Ok(None)`。
+    pub fn from_name(name: &str) -> std::result::Result<Option<Self>, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "auto" => Ok(None),
+            "claude-code" | "claude" => Ok(Some(Protocol::ClaudeCode)),
+            "codex" => Ok(Some(Protocol::Codex)),
+            "gemini" => Ok(Some(Protocol::Gemini)),
+            "cursor" => Ok(Some(Protocol::Cursor)),
+            "cline" => Ok(Some(Protocol::Cline)),
+            "copilot" => Ok(Some(Protocol::Copilot)),
+            _ => Err(format!(
+                "unknown protocol '{}' (expected one of: {})",
+                name,
+                PROTOCOL_NAMES.join(", ")
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Protocol::ClaudeCode => "claude-code",
+            Protocol::Codex => "codex",
+            Protocol::Gemini => "gemini",
+            Protocol::Cursor => "cursor",
+            Protocol::Cline => "cline",
+            Protocol::Copilot => "copilot",
+        }
+    }
+
+    /// updatedInput によるコマンド書き換えに対応しているか
+    pub fn supports_updated_input(&self) -> bool {
+        matches!(self, Protocol::ClaudeCode)
+    }
+
+    /// ask (ユーザーへの確認) を出力できるか
+    pub fn supports_ask(&self) -> bool {
+        !matches!(self, Protocol::Codex | Protocol::Cline)
+    }
+}
+
+/// 入力 JSON の形からプロトコルを推定する。判別できない場合は Claude Code とみなす。
+pub fn detect(raw: &Value) -> Protocol {
+    let has = |key: &str| raw.get(key).is_some();
+    let event = raw
+        .get("hook_event_name")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    if has("hookName") || has("preToolUse") || has("postToolUse") {
+        Protocol::Cline
+    } else if has("toolName") && has("toolArgs") {
+        Protocol::Copilot
+    } else if event.starts_with("before") || event.starts_with("after") {
+        Protocol::Cursor
+    } else if event == "BeforeTool" || event == "AfterTool" {
+        Protocol::Gemini
+    } else if raw.get("command").is_some_and(Value::is_array) {
+        Protocol::Codex
+    } else {
+        Protocol::ClaudeCode
+    }
+}
+
+/// 各プロトコルの入力 JSON を Claude Code 形式の HookInput に変換する
+pub fn to_hook_input(protocol: Protocol, raw: Value) -> Result<HookInput> {
+    let normalized = match protocol {
+        Protocol::ClaudeCode => raw,
+        Protocol::Codex => normalize_codex(&raw),
+        Protocol::Gemini => normalize_gemini(&raw),
+        Protocol::Cursor => normalize_cursor(&raw),
+        Protocol::Cline => normalize_cline(&raw),
+        Protocol::Copilot => normalize_copilot(&raw),
+    };
+    Ok(serde_json::from_value(normalized)?)
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(Value::as_str)
+}
+
+/// 配列フィールドの先頭の文字列 (workspace_roots 等)
+fn first_str<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .and_then(|a| a.first())
+        .and_then(Value::as_str)
+}
+
+/// エージェント固有のツール名を Claude Code のツール名に対応付ける
+fn claude_tool_name(tool_name: &str) -> &str {
+    match tool_name.to_ascii_lowercase().as_str() {
+        "run_shell_command" | "execute_command" | "bash" | "shell" => "Bash",
+        "write_file" | "write_to_file" | "create" => "Write",
+        "replace" | "replace_in_file" | "edit" | "str_replace" => "Edit",
+        _ => tool_name,
+    }
+}
+
+/// Claude Code 形式の入力を組み立てる
+fn claude_input(
+    event: &str,
+    session_id: Option<&str>,
+    cwd: Option<&str>,
+    tool_name: &str,
+    tool_input: Value,
+    tool_response: Option<Value>,
+) -> Value {
+    let mut map = Map::new();
+    map.insert("hook_event_name".to_string(), json!(event));
+    if let Some(session_id) = session_id {
+        map.insert("session_id".to_string(), json!(session_id));
+    }
+    if let Some(cwd) = cwd {
+        map.insert("cwd".to_string(), json!(cwd));
+    }
+    map.insert("tool_name".to_string(), json!(claude_tool_name(tool_name)));
+    map.insert("tool_input".to_string(), tool_input);
+    if let Some(tool_response) = tool_response {
+        map.insert("tool_response".to_string(), tool_response);
+    }
+    Value::Object(map)
+}
+
+/// ツール引数を Claude Code の tool_input に変換する (`path` → `file_path`)
+fn tool_input_from_args(args: &Value) -> Value {
+    let mut map = args.as_object().cloned().unwrap_or_default();
+    if !map.contains_key("file_path")
+        && let Some(path) = map.get("path").cloned()
+    {
+        map.insert("file_path".to_string(), path);
+    }
+    Value::Object(map)
+}
+
+/// Codex: `{"command": ["bash", "-lc", "..."], "cwd": ..., "conversation_id": ...}`
+fn normalize_codex(raw: &Value) -> Value {
+    let argv: Vec<&str> = raw
+        .get("command")
+        .and_then(Value::as_array)
+        .map(|a| a.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    // `bash -lc <script>` 形式はスクリプト部分を、それ以外は argv を結合して検査する
+    let command = match argv.as_slice() {
+        [shell, flag, script]
+            if matches!(*shell, "bash" | "sh" | "zsh" | "/bin/bash" | "/bin/sh")
+                && matches!(*flag, "-c" | "-lc") =>
+        {
+            script.to_string()
+        }
+        _ => shell_words::join(&argv),
+    };
+    claude_input(
+        "PreToolUse",
+        str_field(raw, "conversation_id").or_else(|| str_field(raw, "session_id")),
+        str_field(raw, "cwd"),
+        "Bash",
+        json!({ "command": command }),
+        None,
+    )
+}
+
+/// Gemini CLI: `{"hook_event_name": "BeforeTool", "tool_name": "run_shell_command", "tool_input": {...}}`
+fn normalize_gemini(raw: &Value) -> Value {
+    let event = match str_field(raw, "hook_event_name") {
+        Some("AfterTool") => "PostToolUse",
+        _ => "PreToolUse",
+    };
+    claude_input(
+        event,
+        str_field(raw, "session_id"),
+        str_field(raw, "cwd"),
+        str_field(raw, "tool_name").unwrap_or_default(),
+        tool_input_from_args(raw.get("tool_input").unwrap_or(&Value::Null)),
+        raw.get("tool_response").cloned(),
+    )
+}
+
+/// Cursor: `{"hook_event_name": "beforeShellExecution", "command": "...", "workspace_roots": [...]}`
+fn normalize_cursor(raw: &Value) -> Value {
+    let (event, tool_response) = match str_field(raw, "hook_event_name") {
+        Some("afterShellExecution") => (
+            "PostToolUse",
+            Some(json!({ "stdout": str_field(raw, "output").unwrap_or_default() })),
+        ),
+        _ => ("PreToolUse", None),
+    };
+    // シェル実行以外のイベントは判定対象外 (ツール名なしで正規化)
+    let tool_name = match str_field(raw, "hook_event_name") {
+        Some("beforeShellExecution" | "afterShellExecution") => "Bash",
+        Some(other) => other,
+        None => "",
+    };
+    claude_input(
+        event,
+        str_field(raw, "conversation_id"),
+        str_field(raw, "cwd").or_else(|| first_str(raw, "workspace_roots")),
+        tool_name,
+        json!({ "command": str_field(raw, "command") }),
+        tool_response,
+    )
+}
+
+/// Cline: `{"hookName": "PreToolUse", "taskId": ..., "preToolUse": {"toolName": ..., "parameters": {...}}}`
+fn normalize_cline(raw: &Value) -> Value {
+    let (event, body) = if let Some(post) = raw.get("postToolUse") {
+        let failed = post.get("success").and_then(Value::as_bool) == Some(false);
+        let event = if failed {
+            "PostToolUseFailure"
+        } else {
+            "PostToolUse"
+        };
+        (event, post)
+    } else {
+        ("PreToolUse", raw.get("preToolUse").unwrap_or(&Value::Null))
+    };
+    let tool_response = (event != "PreToolUse")
+        .then(|| json!({ "stdout": str_field(body, "result").unwrap_or_default() }));
+    claude_input(
+        event,
+        str_field(raw, "taskId"),
+        first_str(raw, "workspaceRoots"),
+        str_field(body, "toolName").unwrap_or_default(),
+        tool_input_from_args(body.get("parameters").unwrap_or(&Value::Null)),
+        tool_response,
+    )
+}
+
+/// Copilot: `{"toolName": "bash", "toolArgs": "{\"command\": \"...\"}", "cwd": ...}`
+fn normalize_copilot(raw: &Value) -> Value {
+    // toolArgs は JSON 文字列 (オブジェクトの場合もそのまま受け付ける)
+    let args = match raw.get("toolArgs") {
+        Some(Value::String(s)) => serde_json::from_str(s).unwrap_or(Value::Null),
+        Some(other) => other.clone(),
+        None => Value::Null,
+    };
+    let (event, tool_response) = match raw.get("toolResult") {
+        Some(result) => {
+            let failed = str_field(result, "resultType").is_some_and(|t| t != "success");
+            let event = if failed {
+                "PostToolUseFailure"
+            } else {
+                "PostToolUse"
+            };
+            let stdout = str_field(result, "textResultForLlm").unwrap_or_default();
+            (event, Some(json!({ "stdout": stdout })))
+        }
+        None => ("PreToolUse", None),
+    };
+    claude_input(
+        event,
+        str_field(raw, "sessionId"),
+        str_field(raw, "cwd"),
+        str_field(raw, "toolName").unwrap_or_default(),
+        tool_input_from_args(&args),
+        tool_response,
+    )
+}
+
+/// ask を表現できないプロトコル向けに ask を deny に変換した理由
+fn ask_as_deny(reason: &str) -> String {
+    format!(
+        "{} (confirmation is not supported by this agent's hook protocol; blocked)",
+        reason
+    )
+}
+
+/// HookResponse を各プロトコルの出力 JSON に変換する。出力しない場合は None。
+pub fn render(protocol: Protocol, response: &HookResponse) -> Option<Value> {
+    if protocol == Protocol::ClaudeCode {
+        return hook::build_output(response).and_then(|o| serde_json::to_value(o).ok());
+    }
+
+    // 確認できないプロトコルでは ask を deny に倒す
+    let decision = match &response.decision {
+        Decision::Ask(reason) if !protocol.supports_ask() => Decision::Deny(ask_as_deny(reason)),
+        other => other.clone(),
+    };
+    // 暗黙の Allow (auto_approve 以外) は承認として出力せず、エージェント自身の確認に任せる。
+    // Cline の cancel: false は承認ではなく中止しないことを表すため出力する
+    if decision == Decision::Allow && response.allow_reason.is_none() && protocol != Protocol::Cline
+    {
+        return None;
+    }
+    let reason = match &decision {
+        Decision::Allow => response.allow_reason.as_deref(),
+        Decision::Deny(r) | Decision::Ask(r) => Some(r.as_str()),
+    };
+    let context = response.additional_context.as_deref();
+
+    let mut map = Map::new();
+    match protocol {
+        Protocol::ClaudeCode => unreachable!(),
+        Protocol::Codex => {
+            let value = match decision {
+                Decision::Allow => "approved",
+                _ => "denied",
+            };
+            insert(&mut map, "decision", Some(value));
+            insert(&mut map, "reason", reason);
+        }
+        Protocol::Gemini => {
+            insert(&mut map, "decision", Some(decision_str(&decision)));
+            insert(&mut map, "reason", reason);
+            insert(
+                &mut map,
+                "systemMessage",
+                response.system_message.as_deref(),
+            );
+            if let Some(context) = context {
+                map.insert(
+                    "hookSpecificOutput".to_string(),
+                    json!({ "hookEventName": "BeforeTool", "additionalContext": context }),
+                );
+            }
+        }
+        Protocol::Cursor => {
+            insert(&mut map, "permission", Some(decision_str(&decision)));
+            insert(
+                &mut map,
+                "userMessage",
+                response.system_message.as_deref().or(reason),
+            );
+            insert(&mut map, "agentMessage", context.or(reason));
+        }
+        Protocol::Cline => {
+            map.insert(
+                "cancel".to_string(),
+                json!(!matches!(decision, Decision::Allow)),
+            );
+            if !matches!(decision, Decision::Allow) {
+                insert(&mut map, "errorMessage", reason);
+            }
+            insert(&mut map, "contextModification", context);
+        }
+        Protocol::Copilot => {
+            insert(
+                &mut map,
+                "permissionDecision",
+                Some(decision_str(&decision)),
+            );
+            insert(&mut map, "permissionDecisionReason", reason);
+        }
+    }
+    Some(Value::Object(map))
+}
+
+/// 値がある場合のみ文字列フィールドを追加する
+fn insert(map: &mut Map<String, Value>, key: &str, value: Option<&str>) {
+    if let Some(value) = value {
+        map.insert(key.to_string(), json!(value));
+    }
+}
+
+fn decision_str(decision: &Decision) -> &'static str {
+    match decision {
+        Decision::Allow => "allow",
+        Decision::Deny(_) => "deny",
+        Decision::Ask(_) => "ask",
+    }
+}
+
+/// HookResponse を各プロトコルの形式で stdout に出力する
+pub fn output_response(protocol: Protocol, response: &HookResponse) {
+    if let Some(output) = render(protocol, response) {
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        let _ = serde_json::to_writer(&mut handle, &output);
+        let _ = writeln!(handle);
+    }
+}
+
+/// deny を各プロトコルの形式で stdout に出力する
+pub fn output_deny(protocol: Protocol, reason: &str) {
+    output_response(
+        protocol,
+        &HookResponse::from_decision(Decision::Deny(reason.to_string())),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(decision: Decision) -> HookResponse {
+        HookResponse::from_decision(decision)
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Protocol::from_name("auto"), Ok(None));
+        assert_eq!(Protocol::from_name("Gemini"), Ok(Some(Protocol::Gemini)));
+        assert_eq!(
+            Protocol::from_name("claude"),
+            Ok(Some(Protocol::ClaudeCode))
+        );
+        assert!(Protocol::from_name("vim").is_err());
+        for name in PROTOCOL_NAMES {
+            let protocol = Protocol::from_name(name).unwrap();
+            if let Some(protocol) = protocol {
+                assert_eq!(protocol.as_str(), *name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        let cases = [
+            (
+                json!({"tool_name": "Bash", "tool_input": {"command": "ls"}}),
+                Protocol::ClaudeCode,
+            ),
+            (
+                json!({"hook_event_name": "PreToolUse", "tool_name": "Bash"}),
+                Protocol::ClaudeCode,
+            ),
+            (
+                json!({"command": ["bash", "-lc", "ls"], "cwd": "/tmp"}),
+                Protocol::Codex,
+            ),
+            (
+                json!({"hook_event_name": "BeforeTool", "tool_name": "run_shell_command"}),
+                Protocol::Gemini,
+            ),
+            (
+                json!({"hook_event_name": "beforeShellExecution", "command": "ls"}),
+                Protocol::Cursor,
+            ),
+            (
+                json!({"hookName": "PreToolUse", "preToolUse": {}}),
+                Protocol::Cline,
+            ),
+            (
+                json!({"toolName": "bash", "toolArgs": "{}"}),
+                Protocol::Copilot,
+            ),
+            (json!({}), Protocol::ClaudeCode),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(detect(&raw), expected, "input: {}", raw);
+        }
+    }
+
+    #[test]
+    fn test_normalize_codex_shell_script() {
+        let raw = json!({"command": ["bash", "-lc", "docker ps && ls"], "cwd": "/work"});
+        let input = to_hook_input(Protocol::Codex, raw).unwrap();
+        assert_eq!(hook::extract_command(&input), Some("docker ps && ls"));
+        assert_eq!(input.cwd.as_deref(), Some("/work"));
+    }
+
+    #[test]
+    fn test_normalize_codex_argv() {
+        let raw = json!({"command": ["docker", "run", "-v", "/a b:/x", "ubuntu"]});
+        let input = to_hook_input(Protocol::Codex, raw).unwrap();
+        assert_eq!(
+            hook::extract_command(&input),
+            Some("docker run -v '/a b:/x' ubuntu")
+        );
+    }
+
+    #[test]
+    fn test_normalize_gemini_write_file() {
+        let raw = json!({
+            "hook_event_name": "BeforeTool",
+            "tool_name": "write_file",
+            "tool_input": {"file_path": "/home/u/.claude/settings.json", "content": "{}"}
+        });
+        let input = to_hook_input(Protocol::Gemini, raw).unwrap();
+        assert_eq!(
+            hook::extract_file_paths(&input),
+            vec!["/home/u/.claude/settings.json"]
+        );
+    }
+
+    #[test]
+    fn test_normalize_cursor_non_shell_event() {
+        let raw = json!({"hook_event_name": "beforeReadFile", "file_path": "/etc/passwd"});
+        let input = to_hook_input(Protocol::Cursor, raw).unwrap();
+        assert_eq!(hook::extract_command(&input), None);
+        assert!(hook::extract_file_paths(&input).is_empty());
+    }
+
+    #[test]
+    fn test_normalize_cline_post_tool_use_failure() {
+        let raw = json!({
+            "hookName": "PostToolUse",
+            "taskId": "task-1",
+            "postToolUse": {
+                "toolName": "execute_command",
+                "parameters": {"command": "docker ps"},
+                "result": "error",
+                "success": false
+            }
+        });
+        let input = to_hook_input(Protocol::Cline, raw).unwrap();
+        assert_eq!(
+            hook::hook_event(&input),
+            hook::HookEvent::PostToolUseFailure
+        );
+        assert_eq!(input.session_id.as_deref(), Some("task-1"));
+        assert_eq!(hook::tool_result(&input).outcome, "failed");
+    }
+
+    #[test]
+    fn test_normalize_copilot_tool_args_string() {
+        let raw = json!({
+            "toolName": "bash",
+            "toolArgs": "{\"command\": \"docker run ubuntu\"}",
+            "cwd": "/repo"
+        });
+        let input = to_hook_input(Protocol::Copilot, raw).unwrap();
+        assert_eq!(hook::extract_command(&input), Some("docker run ubuntu"));
+    }
+
+    #[test]
+    fn test_render_allow_without_output() {
+        let allow = response(Decision::Allow);
+        assert_eq!(render(Protocol::ClaudeCode, &allow), None);
+        assert_eq!(render(Protocol::Copilot, &allow), None);
+        assert_eq!(render(Protocol::Cursor, &allow), None);
+        assert_eq!(render(Protocol::Gemini, &allow), None);
+        assert_eq!(render(Protocol::Codex, &allow), None);
+        assert_eq!(
+            render(Protocol::Cline, &allow),
+            Some(json!({"cancel": false}))
+        );
+    }
+
+    #[test]
+    fn test_render_explicit_allow() {
+        let mut allow = response(Decision::Allow);
+        allow.allow_reason = Some("[safe-docker] auto-approved".to_string());
+        assert_eq!(
+            render(Protocol::Cursor, &allow),
+            Some(json!({
+                "permission": "allow",
+                "userMessage": "[safe-docker] auto-approved",
+                "agentMessage": "[safe-docker] auto-approved",
+            }))
+        );
+        assert_eq!(
+            render(Protocol::Gemini, &allow).unwrap()["decision"],
+            "allow"
+        );
+        assert_eq!(
+            render(Protocol::Codex, &allow).unwrap()["decision"],
+            "approved"
+        );
+        assert_eq!(
+            render(Protocol::Copilot, &allow).unwrap()["permissionDecision"],
+            "allow"
+        );
+    }
+
+    #[test]
+    fn test_render_ask_fails_closed_without_ask_support() {
+        let ask = response(Decision::Ask("[safe-docker] check".to_string()));
+        let codex = render(Protocol::Codex, &ask).unwrap();
+        assert_eq!(codex["decision"], "denied");
+        let cline = render(Protocol::Cline, &ask).unwrap();
+        assert_eq!(cline["cancel"], true);
+        assert!(
+            cline["errorMessage"]
+                .as_str()
+                .unwrap()
+                .contains("not supported")
+        );
+        let gemini = render(Protocol::Gemini, &ask).unwrap();
+        assert_eq!(gemini["decision"], "ask");
+    }
+}
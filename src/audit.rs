@@ -34,6 +34,24 @@ pub struct AuditEvent {
     /// - "{path} (FAILED, using defaults)" — 設定ファイルのパース失敗時
     #[serde(skip_serializing_if = "Option::is_none")]
     pub config_source: Option<String>,
+    /// hook イベント名 ("PreToolUse" / "PostToolUse" / "PostToolUseFailure")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hook_event: Option<String>,
+    /// PreToolUse と PostToolUse のイベントを関連付けるためのコマンドハッシュ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_hash: Option<String>,
+    /// 実行結果 ("succeeded" / "failed" / "interrupted")。PostToolUse のみ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<String>,
+    /// 終了コード (ツール応答に含まれる場合)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// 作成されたコンテナ ID (docker run -d / docker create の出力)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub container_ids: Vec<String>,
+    /// 書き換え前のコマンド (`[hook.rewrite]` で updatedInput を返した場合)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_command: Option<String>,
 }
 
 /// DockerCommand からメタデータを蓄積するコレクター
@@ -120,9 +138,26 @@ pub fn build_event(ctx: &AuditContext<'_>) -> AuditEvent {
         environment,
         mode: ctx.mode.to_string(),
         config_source: ctx.config_source.map(String::from),
+        hook_event: None,
+        command_hash: None,
+        outcome: None,
+        exit_code: None,
+        container_ids: Vec::new(),
+        original_command: None,
     }
 }
 
+/// コマンド文字列のハッシュ (FNV-1a 64bit, 16 桁の16進数)。
+/// PreToolUse と PostToolUse の監査イベントの関連付けに使う。
+pub fn command_hash(command: &str) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    let hash = command.trim().bytes().fold(OFFSET_BASIS, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(PRIME)
+    });
+    format!("{:016x}", hash)
+}
+
 /// 監査イベントを出力する
 pub fn emit(event: &AuditEvent, config: &AuditConfig) {
     match config.format {
@@ -214,6 +249,27 @@ fn write_otlp(event: &AuditEvent, path: &str) {
             &event.dangerous_flags,
         ));
     }
+    if let Some(ref hook_event) = event.hook_event {
+        attributes.push(kv_string("safe_docker.hook_event", hook_event));
+    }
+    if let Some(ref command_hash) = event.command_hash {
+        attributes.push(kv_string("safe_docker.command_hash", command_hash));
+    }
+    if let Some(ref outcome) = event.outcome {
+        attributes.push(kv_string("safe_docker.outcome", outcome));
+    }
+    if let Some(exit_code) = event.exit_code {
+        attributes.push(kv_int("process.exit_code", exit_code as i64));
+    }
+    if !event.container_ids.is_empty() {
+        attributes.push(kv_string_array(
+            "docker.container_ids",
+            &event.container_ids,
+        ));
+    }
+    if let Some(ref original_command) = event.original_command {
+        attributes.push(kv_string("safe_docker.original_command", original_command));
+    }
     attributes.push(kv_int("process.pid", event.pid as i64));
     attributes.push(kv_string("safe_docker.mode", &event.mode));
     if let Some(ref config_source) = event.config_source {
@@ -496,6 +552,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
 
         collector.record_docker_command(&cmd);
@@ -516,6 +577,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let cmd2 = DockerCommand {
             subcommand: DockerSubcommand::Build,
@@ -524,6 +590,11 @@ mod tests {
             compose_file: None,
             image: None,
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
 
         collector.record_docker_command(&cmd1);
@@ -559,6 +630,11 @@ mod tests {
             compose_file: None,
             image: Some("nginx:latest".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         collector.record_docker_command(&cmd);
 
@@ -905,6 +981,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         collector.record_docker_command(&cmd);
 
@@ -941,6 +1022,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_write_jsonl_post_tool_use_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("post.jsonl");
+        let path_str = path.to_str().unwrap();
+
+        let collector = AuditCollector::new();
+        let mut event = build_event(&AuditContext {
+            command: "docker run -d nginx",
+            decision: "allow",
+            reason: None,
+            collector: &collector,
+            session_id: Some("sess-001"),
+            cwd: "/home/user",
+            mode: "hook",
+            config_source: None,
+        });
+        event.hook_event = Some("PostToolUse".to_string());
+        event.command_hash = Some(command_hash("docker run -d nginx"));
+        event.outcome = Some("succeeded".to_string());
+        event.exit_code = Some(0);
+        event.container_ids = vec!["a".repeat(64)];
+
+        write_jsonl(&event, path_str);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(content.trim()).unwrap();
+        assert_eq!(parsed["hook_event"], "PostToolUse");
+        assert_eq!(parsed["command_hash"], command_hash("docker run -d nginx"));
+        assert_eq!(parsed["outcome"], "succeeded");
+        assert_eq!(parsed["exit_code"], 0);
+        assert_eq!(parsed["container_ids"][0], "a".repeat(64));
+    }
+
+    #[test]
+    fn test_build_event_omits_post_tool_use_fields() {
+        let collector = AuditCollector::new();
+        let event = build_event(&AuditContext {
+            command: "docker ps",
+            decision: "allow",
+            reason: None,
+            collector: &collector,
+            session_id: None,
+            cwd: "/tmp",
+            mode: "wrapper",
+            config_source: None,
+        });
+        let json = serde_json::to_value(&event).unwrap();
+        assert!(json.get("hook_event").is_none());
+        assert!(json.get("outcome").is_none());
+        assert!(json.get("container_ids").is_none());
+        assert!(json.get("original_command").is_none());
+    }
+
+    #[test]
+    fn test_command_hash_stable() {
+        assert_eq!(command_hash("docker ps"), command_hash("  docker ps\n"));
+        assert_ne!(command_hash("docker ps"), command_hash("docker ps -a"));
+        assert_eq!(command_hash("").len(), 16);
+        // FNV-1a 64bit の既知の値
+        assert_eq!(command_hash(""), "cbf29ce484222325");
+        assert_eq!(command_hash("a"), "af63dc4c8601ec8c");
+    }
+
     // --- emit() フォーマット分岐テスト ---
 
     #[test]
@@ -1000,6 +1145,11 @@ mod tests {
                 compose_file: None,
                 image: Some("ubuntu".to_string()),
                 host_paths: vec![],
+                host_write_paths: vec![],
+                ports: vec![],
+                publish_all: false,
+                build: None,
+                network: Default::default(),
             };
             collector.record_docker_command(&cmd);
 
@@ -0,0 +1,317 @@
+//! Docker 認可プラグインモード (`safe-docker authz-plugin`)
+//!
+//! Docker デーモンの authorization plugin プロトコル (HTTP over Unix ソケット) を実装し、
+//! デーモンに届くすべての API リクエストを hook / wrapper モードと同じポリシーで評価する。
+//! OPA と `authz.rego` を別途配置・同期する必要がない Layer 2 として使う。
+//!
+//! - `POST /Plugin.Activate` → `{"Implements": ["authz"]}`
+//! - `POST /AuthZPlugin.AuthZReq` → `{"Allow": bool, "Msg": ...}` (`engine_api` で検査)
+//! - `POST /AuthZPlugin.AuthZRes` → 常に許可 (検査はリクエスト時に行う)
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::{Value, json};
+use std::io::{self, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::Arc;
+use std::thread;
+
+use crate::config::Config;
+use crate::engine_api::{self, Inspector};
+use crate::proxy;
+
+/// Docker がプラグインを探すソケットの場所 (`/run/docker/plugins/<name>.sock`)
+const DEFAULT_LISTEN: &str = "/run/docker/plugins/safe-docker.sock";
+
+/// プラグインリクエストのボディの最大サイズ (デーモンは 1MB を超える API ボディを送らない)
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+const CONTENT_TYPE: &str = "application/vnd.docker.plugins.v1+json";
+
+/// authz-plugin サブコマンドのエントリポイント。args は "authz-plugin" 以降の引数。
+pub fn run(args: &[String], config: Config, config_source: String) -> i32 {
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        print_authz_plugin_help();
+        return 0;
+    }
+    let listen = match proxy::option_value(args, "--listen") {
+        Ok(v) => proxy::expand_tilde(v.as_deref().unwrap_or(DEFAULT_LISTEN)),
+        Err(e) => {
+            eprintln!("[safe-docker] ERROR: {}", e);
+            return 1;
+        }
+    };
+    let listener = match proxy::bind(&listen) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!(
+                "[safe-docker] ERROR: Cannot listen on {}: {}",
+                listen.display(),
+                e
+            );
+            return 1;
+        }
+    };
+    eprintln!(
+        "[safe-docker] AuthZ plugin listening on {}",
+        listen.display()
+    );
+
+    let inspector = Arc::new(Inspector::new(config, config_source, "authz"));
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let inspector = Arc::clone(&inspector);
+                thread::spawn(move || {
+                    if let Err(e) = handle_connection(&inspector, stream) {
+                        log::debug!("authz plugin connection error: {}", e);
+                    }
+                });
+            }
+            Err(e) => log::warn!("authz plugin accept error: {}", e),
+        }
+    }
+    0
+}
+
+fn print_authz_plugin_help() {
+    eprintln!("USAGE:");
+    eprintln!("  safe-docker authz-plugin [--listen PATH]");
+    eprintln!();
+    eprintln!("OPTIONS:");
+    eprintln!(
+        "  --listen PATH     Plugin socket (default: {})",
+        DEFAULT_LISTEN
+    );
+    eprintln!();
+    eprintln!("Enable with \"authorization-plugins\": [\"safe-docker\"] in daemon.json");
+}
+
+/// デーモンは接続を再利用するため、接続が閉じられるまでリクエストを処理する
+fn handle_connection(inspector: &Inspector, stream: UnixStream) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    loop {
+        let head = match proxy::read_head(&mut reader) {
+            Ok(Some(head)) => head,
+            Ok(None) => return Ok(()),
+            Err(e) => return write_response(&mut writer, 400, &json!({ "Err": e })),
+        };
+        let body = match head
+            .body_framing()
+            .and_then(|framing| proxy::read_body(&mut reader, framing, MAX_BODY_BYTES))
+        {
+            Ok(body) => body,
+            Err(e) => return write_response(&mut writer, 400, &json!({ "Err": e })),
+        };
+        let (status, response) = handle_request(inspector, &head.target, &body);
+        write_response(&mut writer, status, &response)?;
+    }
+}
+
+/// プラグイン API のリクエストを処理し、(ステータスコード, 応答) を返す
+pub fn handle_request(inspector: &Inspector, path: &str, body: &[u8]) -> (u16, Value) {
+    match path {
+        "/Plugin.Activate" => (200, json!({ "Implements": ["authz"] })),
+        "/AuthZPlugin.AuthZReq" => match serde_json::from_slice::<Value>(body) {
+            Ok(request) => (200, authorize(inspector, &request)),
+            Err(e) => (
+                200,
+                json!({ "Allow": false, "Err": format!("invalid AuthZReq: {}", e) }),
+            ),
+        },
+        "/AuthZPlugin.AuthZRes" => (200, json!({ "Allow": true })),
+        _ => (
+            404,
+            json!({ "Err": format!("unknown plugin endpoint: {}", path) }),
+        ),
+    }
+}
+
+/// AuthZReq の API リクエストをポリシーで評価する
+fn authorize(inspector: &Inspector, request: &Value) -> Value {
+    let method = request
+        .get("RequestMethod")
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    let uri = request
+        .get("RequestURI")
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    // RequestBody は []byte のため base64 文字列で届く (JSON 以外や大きいボディは省略される)
+    let body = match request.get("RequestBody") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(encoded)) => match STANDARD.decode(encoded) {
+            Ok(body) => Some(body),
+            Err(_) => {
+                return json!({
+                    "Allow": false,
+                    "Msg": "[safe-docker] Cannot decode AuthZReq RequestBody. Blocking for safety",
+                });
+            }
+        },
+        Some(other) => {
+            return json!({
+                "Allow": false,
+                "Msg": format!("[safe-docker] Unsupported AuthZReq RequestBody: {}", other),
+            });
+        }
+    };
+
+    if body.is_none() && engine_api::needs_body(method, uri) {
+        // デーモンが省略したボディは検査できないため、空のボディとして扱わずブロックする
+        return json!({
+            "Allow": false,
+            "Msg": format!(
+                "[safe-docker] AuthZReq for {} {} has no RequestBody. Blocking for safety",
+                method, uri
+            ),
+        });
+    }
+
+    match inspector.check(method, uri, body.as_deref()) {
+        None => json!({ "Allow": true }),
+        Some(reason) => json!({ "Allow": false, "Msg": reason }),
+    }
+}
+
+fn write_response(writer: &mut UnixStream, status: u16, body: &Value) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        _ => "Not Found",
+    };
+    let body = body.to_string();
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n{}",
+        status,
+        reason,
+        CONTENT_TYPE,
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inspector() -> Inspector {
+        Inspector::new(Config::default(), "(default)".to_string(), "authz")
+    }
+
+    fn authz_req(method: &str, uri: &str, body: Option<&Value>) -> Vec<u8> {
+        let mut request = json!({
+            "User": "",
+            "UserAuthNMethod": "",
+            "RequestMethod": method,
+            "RequestURI": uri,
+            "RequestHeaders": {"Content-Type": "application/json"},
+        });
+        if let Some(body) = body {
+            request["RequestBody"] = json!(STANDARD.encode(body.to_string()));
+        }
+        request.to_string().into_bytes()
+    }
+
+    #[test]
+    fn test_activate() {
+        let (status, response) = handle_request(&inspector(), "/Plugin.Activate", b"");
+        assert_eq!(status, 200);
+        assert_eq!(response, json!({"Implements": ["authz"]}));
+    }
+
+    #[test]
+    fn test_authz_req_allow_and_deny() {
+        let inspector = inspector();
+        let allowed = authz_req(
+            "POST",
+            "/v1.45/containers/create",
+            Some(&json!({"Image": "ubuntu", "HostConfig": {"Binds": ["cache:/data"]}})),
+        );
+        let (_, response) = handle_request(&inspector, "/AuthZPlugin.AuthZReq", &allowed);
+        assert_eq!(response, json!({"Allow": true}));
+
+        let denied = authz_req(
+            "POST",
+            "/v1.45/containers/create",
+            Some(&json!({"Image": "ubuntu", "HostConfig": {"Privileged": true}})),
+        );
+        let (_, response) = handle_request(&inspector, "/AuthZPlugin.AuthZReq", &denied);
+        assert_eq!(response["Allow"], false);
+        assert!(response["Msg"].as_str().unwrap().contains("--privileged"));
+    }
+
+    #[test]
+    fn test_authz_req_other_requests_allowed() {
+        let request = authz_req("GET", "/v1.45/containers/json?all=1", None);
+        let (_, response) = handle_request(&inspector(), "/AuthZPlugin.AuthZReq", &request);
+        assert_eq!(response, json!({"Allow": true}));
+    }
+
+    #[test]
+    fn test_authz_req_missing_body_is_denied() {
+        // ボディが省略された create は内容を検査できないためブロックする
+        let inspector = inspector();
+        for uri in [
+            "/v1.45/containers/create",
+            "/v1.45/containers/web/exec",
+            "/v1.45/networks/create",
+        ] {
+            let request = authz_req("POST", uri, None);
+            let (_, response) = handle_request(&inspector, "/AuthZPlugin.AuthZReq", &request);
+            assert_eq!(response["Allow"], false, "{}", uri);
+            assert!(
+                response["Msg"]
+                    .as_str()
+                    .unwrap()
+                    .contains("has no RequestBody"),
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[test]
+    fn test_authz_req_keys_are_case_insensitive() {
+        // dockerd と同じくキーの大文字小文字を区別せずに検査する
+        let inspector = inspector();
+        for body in [
+            json!({"image": "ubuntu", "hostconfig": {"privileged": true}}),
+            json!({"Image": "ubuntu", "hostConfig": {"PRIVILEGED": true}}),
+        ] {
+            let request = authz_req("POST", "/v1.45/containers/create", Some(&body));
+            let (_, response) = handle_request(&inspector, "/AuthZPlugin.AuthZReq", &request);
+            assert_eq!(response["Allow"], false, "{}", body);
+            assert!(
+                response["Msg"].as_str().unwrap().contains("--privileged"),
+                "{}",
+                body
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let inspector = inspector();
+        let (_, response) = handle_request(&inspector, "/AuthZPlugin.AuthZReq", b"not json");
+        assert_eq!(response["Allow"], false);
+        assert!(response["Err"].as_str().is_some());
+
+        let request = json!({"RequestMethod": "POST", "RequestURI": "/containers/create", "RequestBody": "!!"});
+        let (_, response) = handle_request(
+            &inspector,
+            "/AuthZPlugin.AuthZReq",
+            request.to_string().as_bytes(),
+        );
+        assert_eq!(response["Allow"], false);
+
+        let (status, _) = handle_request(&inspector, "/VolumeDriver.Create", b"{}");
+        assert_eq!(status, 404);
+
+        let (_, response) = handle_request(&inspector, "/AuthZPlugin.AuthZRes", b"{}");
+        assert_eq!(response, json!({"Allow": true}));
+    }
+}
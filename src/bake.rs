@@ -0,0 +1,225 @@
+//! docker buildx bake のファイル解析 (ターゲットのビルドコンテキスト・Dockerfile・イメージコンテキスト)
+//!
+//! JSON 形式は serde_json で読み、HCL 形式は `target "NAME" { ... }` ブロックの
+//! `context` / `dockerfile` 属性と `docker-image://` の文字列だけを行単位で抽出する。
+//! 変数 (`${VAR}`) を含む値は解決できないため無視する。
+
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// -f を指定しない場合に読み込む bake ファイル (カレントディレクトリ)
+pub const DEFAULT_BAKE_FILES: &[&str] = &[
+    "docker-bake.json",
+    "docker-bake.override.json",
+    "docker-bake.hcl",
+    "docker-bake.override.hcl",
+];
+
+/// 読み込む bake ファイルの上限サイズ
+const MAX_BAKE_FILE_SIZE: u64 = 1024 * 1024;
+
+/// bake のターゲット
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BakeTarget {
+    pub name: String,
+    /// ビルドコンテキスト (省略時は ".")
+    pub context: Option<String>,
+    /// Dockerfile (省略時はコンテキストの Dockerfile)
+    pub dockerfile: Option<String>,
+    /// `contexts` の `docker-image://` で参照するイメージ
+    pub image_contexts: Vec<String>,
+}
+
+impl BakeTarget {
+    /// Dockerfile のパス (base は bake を実行するディレクトリ)
+    pub fn dockerfile_path(&self, base: &Path) -> PathBuf {
+        let context = base.join(self.context.as_deref().unwrap_or("."));
+        context.join(self.dockerfile.as_deref().unwrap_or("Dockerfile"))
+    }
+}
+
+static TARGET_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^\s*target\s+"([^"]+)"\s*\{"#).unwrap());
+
+static ATTR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^\s*(context|dockerfile)\s*=\s*"([^"]*)""#).unwrap());
+
+static IMAGE_CONTEXT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""docker-image://([^"]+)""#).unwrap());
+
+/// 指定された bake ファイル、なければ既定の bake ファイルを返す
+pub fn bake_files(specified: &[String], cwd: &Path) -> Vec<PathBuf> {
+    if specified.is_empty() {
+        DEFAULT_BAKE_FILES
+            .iter()
+            .map(|f| cwd.join(f))
+            .filter(|p| p.is_file())
+            .collect()
+    } else {
+        specified.iter().map(|f| cwd.join(f)).collect()
+    }
+}
+
+/// bake ファイルからターゲットを読み込む (拡張子が .json なら JSON、それ以外は HCL)
+pub fn parse_file(path: &Path) -> Result<Vec<BakeTarget>, String> {
+    use std::io::Read;
+    let mut content = String::new();
+    std::fs::File::open(path)
+        .and_then(|f| f.take(MAX_BAKE_FILE_SIZE).read_to_string(&mut content))
+        .map_err(|e| format!("Cannot read bake file {}: {}", path.display(), e))?;
+    if path.extension().is_some_and(|e| e == "json") {
+        parse_json(&content)
+            .map_err(|e| format!("Cannot parse bake file {}: {}", path.display(), e))
+    } else {
+        Ok(parse_hcl(&content))
+    }
+}
+
+/// JSON 形式 (`{"target": {"NAME": {...}}}`)
+pub fn parse_json(content: &str) -> Result<Vec<BakeTarget>, String> {
+    let value: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let Some(targets) = value.get("target").and_then(|t| t.as_object()) else {
+        return Ok(Vec::new());
+    };
+    Ok(targets
+        .iter()
+        .map(|(name, target)| {
+            let attr = |key: &str| {
+                target
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .filter(|v| !v.contains("${"))
+                    .map(String::from)
+            };
+            let image_contexts = target
+                .get("contexts")
+                .and_then(|c| c.as_object())
+                .map(|contexts| {
+                    contexts
+                        .values()
+                        .filter_map(|v| v.as_str()?.strip_prefix("docker-image://"))
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default();
+            BakeTarget {
+                name: name.clone(),
+                context: attr("context"),
+                dockerfile: attr("dockerfile"),
+                image_contexts,
+            }
+        })
+        .collect())
+}
+
+/// HCL 形式 (target ブロックの属性を行単位で抽出する)
+pub fn parse_hcl(content: &str) -> Vec<BakeTarget> {
+    let mut targets = Vec::new();
+    let mut current: Option<BakeTarget> = None;
+    let mut depth = 0usize;
+
+    for line in content.lines() {
+        let code = line.split_once('#').map_or(line, |(code, _)| code);
+        if current.is_none() {
+            if let Some(caps) = TARGET_RE.captures(code) {
+                current = Some(BakeTarget {
+                    name: caps[1].to_string(),
+                    ..Default::default()
+                });
+                depth = 0;
+            } else {
+                continue;
+            }
+        }
+        let Some(target) = current.as_mut() else {
+            continue;
+        };
+
+        if depth == 1
+            && let Some(caps) = ATTR_RE.captures(code)
+            && !caps[2].contains("${")
+        {
+            let value = Some(caps[2].to_string());
+            match &caps[1] {
+                "context" => target.context = value,
+                _ => target.dockerfile = value,
+            }
+        }
+        for caps in IMAGE_CONTEXT_RE.captures_iter(code) {
+            if !caps[1].contains("${") {
+                target.image_contexts.push(caps[1].to_string());
+            }
+        }
+
+        for c in code.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if depth == 0
+            && let Some(target) = current.take()
+        {
+            targets.push(target);
+        }
+    }
+    targets.extend(current);
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hcl() {
+        let content = r#"
+variable "TAG" { default = "dev" }
+
+group "default" {
+  targets = ["app", "docs"]
+}
+
+target "app" {
+  context = "./app"
+  dockerfile = "build/Dockerfile"
+  contexts = {
+    base = "docker-image://alpine:3.19"
+  }
+  tags = ["ghcr.io/ourorg/app:${TAG}"]
+}
+
+target "docs" {
+  context = "${DOCS_DIR}" # unresolved
+}
+"#;
+        let targets = parse_hcl(content);
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].name, "app");
+        assert_eq!(targets[0].context.as_deref(), Some("./app"));
+        assert_eq!(targets[0].dockerfile.as_deref(), Some("build/Dockerfile"));
+        assert_eq!(targets[0].image_contexts, vec!["alpine:3.19"]);
+        assert_eq!(
+            targets[0].dockerfile_path(Path::new("/w")),
+            PathBuf::from("/w/./app/build/Dockerfile")
+        );
+        assert_eq!(targets[1].context, None);
+        assert_eq!(
+            targets[1].dockerfile_path(Path::new("/w")),
+            PathBuf::from("/w/./Dockerfile")
+        );
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let content = r#"{"target": {"web": {"context": "web", "contexts": {"src": "../src", "py": "docker-image://python:3.12"}}}}"#;
+        let targets = parse_json(content).unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].context.as_deref(), Some("web"));
+        assert_eq!(targets[0].image_contexts, vec!["python:3.12"]);
+        assert!(parse_json("{").is_err());
+    }
+}
@@ -0,0 +1,239 @@
+//! docker build のビルドコンテキストの検査 (`[build_context_scan]`)
+//!
+//! デーモンに送られるコンテキストを `.dockerignore` を適用して走査し、機密情報らしいファイル
+//! (`patterns`) と大きすぎるファイル (`max_file_size`) を列挙する。
+//!
+//! - シンボリックリンクはたどらない (コンテキストにはリンクとして送られる)
+//! - 除外されたディレクトリには入らない (`.dockerignore` に `!` がある場合を除く)
+//! - 走査するエントリ数 (`max_files`) と時間 (`timeout_ms`) に上限がある。
+//!   上限に達した場合の扱いは `incomplete` で決める
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::config::BuildContextScanConfig;
+use crate::dockerignore::DockerIgnore;
+use crate::path_pattern;
+
+/// 判定理由に列挙するファイル数の上限
+const MAX_LISTED_FILES: usize = 10;
+
+/// 走査結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanResult {
+    /// patterns に一致したファイル・ディレクトリ (コンテキストからの相対パス)
+    pub sensitive: Vec<String>,
+    /// max_file_size を超えるファイルとサイズ
+    pub large: Vec<(String, u64)>,
+    /// 上限に達して走査を打ち切った理由
+    pub incomplete: Option<String>,
+}
+
+/// ビルドコンテキストを走査する
+pub fn scan(context: &Path, ignore: &DockerIgnore, config: &BuildContextScanConfig) -> ScanResult {
+    let deadline = Instant::now() + Duration::from_millis(config.timeout_ms);
+    let mut result = ScanResult::default();
+    let mut entries = 0usize;
+    let mut dirs: VecDeque<String> = VecDeque::from([String::new()]);
+
+    while let Some(dir) = dirs.pop_front() {
+        let Ok(read_dir) = std::fs::read_dir(context.join(&dir)) else {
+            continue;
+        };
+        for entry in read_dir.filter_map(|e| e.ok()) {
+            entries += 1;
+            if entries > config.max_files {
+                result.incomplete = Some(format!(
+                    "stopped after build_context_scan.max_files ({}) entries",
+                    config.max_files
+                ));
+                return result;
+            }
+            if Instant::now() >= deadline {
+                result.incomplete = Some(format!(
+                    "stopped after build_context_scan.timeout_ms ({} ms)",
+                    config.timeout_ms
+                ));
+                return result;
+            }
+
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = if dir.is_empty() {
+                name
+            } else {
+                format!("{}/{}", dir, name)
+            };
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if ignore.is_excluded(&path) {
+                if file_type.is_dir() && ignore.has_exceptions() {
+                    dirs.push_back(path);
+                }
+                continue;
+            }
+
+            if is_sensitive(&config.patterns, &path) {
+                result.sensitive.push(if file_type.is_dir() {
+                    format!("{}/", path)
+                } else {
+                    path
+                });
+            } else if file_type.is_dir() {
+                dirs.push_back(path);
+            } else if file_type.is_file()
+                && let Ok(metadata) = entry.metadata()
+                && metadata.len() > config.max_file_size
+            {
+                result.large.push((path, metadata.len()));
+            }
+        }
+    }
+    result
+}
+
+/// パターン (後ろで一致したものが優先、`!` は除外) に一致するか
+fn is_sensitive(patterns: &[String], path: &str) -> bool {
+    let mut matched = false;
+    for raw in patterns {
+        let (negated, pattern) = path_pattern::split_negation(raw);
+        if path_pattern::matches(pattern, path) {
+            matched = !negated;
+        }
+    }
+    matched
+}
+
+impl ScanResult {
+    /// 機密ファイル・巨大ファイルの判定理由 (問題がなければ空)
+    pub fn reasons(&self, context: &Path) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if !self.sensitive.is_empty() {
+            reasons.push(format!(
+                "Build context {} contains files that look like secrets and are not excluded by .dockerignore: {}. They are sent to the Docker daemon and can end up in the image via COPY",
+                context.display(),
+                list(self.sensitive.iter().cloned())
+            ));
+        }
+        if !self.large.is_empty() {
+            reasons.push(format!(
+                "Build context {} contains files larger than build_context_scan.max_file_size: {}",
+                context.display(),
+                list(self.large.iter().map(|(path, size)| format!(
+                    "{} ({} MiB)",
+                    path,
+                    size / (1024 * 1024)
+                )))
+            ));
+        }
+        reasons
+    }
+
+    /// 走査を打ち切った場合の判定理由
+    pub fn incomplete_reason(&self, context: &Path) -> Option<String> {
+        self.incomplete.as_ref().map(|incomplete| {
+            format!(
+                "Build context {} could not be fully scanned for secrets ({})",
+                context.display(),
+                incomplete
+            )
+        })
+    }
+}
+
+/// 先頭の MAX_LISTED_FILES 件を列挙する
+fn list(items: impl ExactSizeIterator<Item = String>) -> String {
+    let total = items.len();
+    let mut listed: Vec<String> = items.take(MAX_LISTED_FILES).collect();
+    if total > MAX_LISTED_FILES {
+        listed.push(format!("and {} more", total - MAX_LISTED_FILES));
+    }
+    listed.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, path: &str, size: usize) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, vec![b'x'; size]).unwrap();
+    }
+
+    #[test]
+    fn test_scan_sensitive_and_large_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for path in [
+            ".env",
+            ".env.example",
+            "src/main.rs",
+            "deploy/.aws/credentials",
+            "certs/server.pem",
+            "node_modules/pkg/test.pem",
+            "home/.ssh/id_rsa",
+        ] {
+            write(root, path, 1);
+        }
+        write(root, "data/model.bin", 2048);
+        let config = BuildContextScanConfig {
+            max_file_size: 1024,
+            ..Default::default()
+        };
+        let ignore = DockerIgnore::parse("node_modules\n");
+
+        let mut result = scan(root, &ignore, &config);
+        result.sensitive.sort();
+        assert_eq!(
+            result.sensitive,
+            vec![".env", "certs/server.pem", "deploy/.aws/", "home/.ssh/"]
+        );
+        assert_eq!(result.large, vec![("data/model.bin".to_string(), 2048)]);
+        assert_eq!(result.incomplete, None);
+        let reasons = result.reasons(Path::new("/w/app"));
+        assert_eq!(reasons.len(), 2);
+        assert!(reasons[0].contains("/w/app contains files that look like secrets"));
+        assert!(reasons[0].contains(".env, certs/server.pem"));
+
+        // .dockerignore の除外と取り消し
+        let ignore = DockerIgnore::parse("*\n!src\n!certs\ncerts/*.pem\n");
+        let result = scan(root, &ignore, &config);
+        assert_eq!(result, ScanResult::default());
+    }
+
+    #[test]
+    fn test_scan_is_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..20 {
+            write(dir.path(), &format!("f{}.txt", i), 1);
+        }
+        let config = BuildContextScanConfig {
+            max_files: 5,
+            ..Default::default()
+        };
+        let result = scan(dir.path(), &DockerIgnore::default(), &config);
+        assert!(
+            result
+                .incomplete
+                .as_deref()
+                .is_some_and(|r| r.contains("max_files (5)"))
+        );
+        assert!(result.reasons(dir.path()).is_empty());
+        assert!(
+            result
+                .incomplete_reason(dir.path())
+                .is_some_and(|r| r.contains("could not be fully scanned"))
+        );
+    }
+
+    #[test]
+    fn test_list_truncates() {
+        let items: Vec<String> = (0..12).map(|i| i.to_string()).collect();
+        assert_eq!(
+            list(items.into_iter()),
+            "0, 1, 2, 3, 4, 5, 6, 7, 8, 9, and 2 more"
+        );
+    }
+}
@@ -0,0 +1,1016 @@
+//! ポリシー拡張ルール用の CEL (Common Expression Language) サブセット
+//!
+//! `[[policy.rules]]` の `when` 式を評価する。値は `serde_json::Value` で表し、
+//! 以下をサポートする:
+//!
+//! - リテラル: `true` / `false` / `null` / 整数 / 小数 / 文字列 (`"..."` / `'...'`) / リスト `[a, b]`
+//! - 変数・フィールド参照 (`command.image`)、添字 (`command.args[0]`, `m["key"]`)
+//! - 演算子: `!` `-` `*` `/` `%` `+` `-` `<` `<=` `>` `>=` `==` `!=` `in` `&&` `||` `? :`
+//! - 関数: `size`, `has`, `int`, `string`, `startsWith`, `endsWith`, `contains`, `matches`,
+//!   `lowerAscii`, `upperAscii`
+//! - マクロ: `exists`, `all`, `exists_one`, `filter`, `map`
+//!
+//! 存在しないフィールドの参照や型の合わない演算はエラーになる (呼び出し側で fail-safe に扱う)。
+
+use serde_json::{Map, Number, Value};
+
+/// 式の構文木
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    List(Vec<Expr>),
+    Ident(String),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    /// `has(a.b)`: フィールドの存在確認
+    Has(Box<Expr>, String),
+    /// 関数呼び出し (target はメソッド呼び出しのレシーバ)
+    Call {
+        target: Option<Box<Expr>>,
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// `list.exists(x, pred)` 等のマクロ
+    Macro {
+        kind: MacroKind,
+        range: Box<Expr>,
+        var: String,
+        body: Box<Expr>,
+    },
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+/// 内包表記マクロの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacroKind {
+    Exists,
+    All,
+    ExistsOne,
+    Filter,
+    Map,
+}
+
+/// 二項演算子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Punct(&'static str),
+}
+
+/// 長い記号から順に照合する
+const PUNCTS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "+", "-", "*", "/", "%", "(", ")", "[", "]",
+    ".", ",", "?", ":",
+];
+
+/// 式の入れ子の上限 (悪意のある深い式によるスタック溢れを防ぐ)
+const MAX_DEPTH: usize = 64;
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let is_float = i + 1 < chars.len() && chars[i] == '.' && chars[i + 1].is_ascii_digit();
+            if is_float {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(if is_float {
+                Token::Float(
+                    text.parse()
+                        .map_err(|_| format!("invalid number '{}'", text))?,
+                )
+            } else {
+                Token::Int(
+                    text.parse()
+                        .map_err(|_| format!("invalid number '{}'", text))?,
+                )
+            });
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            let mut s = String::new();
+            loop {
+                let Some(&ch) = chars.get(i) else {
+                    return Err("unterminated string literal".to_string());
+                };
+                i += 1;
+                match ch {
+                    _ if ch == c => break,
+                    '\\' => {
+                        let Some(&esc) = chars.get(i) else {
+                            return Err("unterminated string literal".to_string());
+                        };
+                        i += 1;
+                        s.push(match esc {
+                            'n' => '\n',
+                            't' => '\t',
+                            'r' => '\r',
+                            '\\' | '"' | '\'' => esc,
+                            _ => return Err(format!("unknown escape sequence '\\{}'", esc)),
+                        });
+                    }
+                    _ => s.push(ch),
+                }
+            }
+            tokens.push(Token::Str(s));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let Some(p) = PUNCTS.iter().find(|p| rest.starts_with(**p)) else {
+                return Err(format!("unexpected character '{}'", c));
+            };
+            tokens.push(Token::Punct(p));
+            i += p.len();
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek_punct(&self, p: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Punct(q)) if *q == p)
+    }
+
+    fn eat_punct(&mut self, p: &str) -> bool {
+        if self.peek_punct(p) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_punct(&mut self, p: &str) -> Result<(), String> {
+        if self.eat_punct(p) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}'{}", p, self.position()))
+        }
+    }
+
+    fn position(&self) -> String {
+        match self.tokens.get(self.pos) {
+            Some(Token::Ident(s)) => format!(" near '{}'", s),
+            Some(Token::Punct(p)) => format!(" near '{}'", p),
+            Some(Token::Str(s)) => format!(" near \"{}\"", s),
+            Some(Token::Int(n)) => format!(" near '{}'", n),
+            Some(Token::Float(n)) => format!(" near '{}'", n),
+            None => " at end of expression".to_string(),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("expression is nested too deeply".to_string());
+        }
+        let cond = self.or()?;
+        let result = if self.eat_punct("?") {
+            let then = self.expr()?;
+            self.expect_punct(":")?;
+            let otherwise = self.expr()?;
+            Expr::Conditional(Box::new(cond), Box::new(then), Box::new(otherwise))
+        } else {
+            cond
+        };
+        self.depth -= 1;
+        Ok(result)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.and()?;
+        while self.eat_punct("||") {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.relation()?;
+        while self.eat_punct("&&") {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.relation()?));
+        }
+        Ok(lhs)
+    }
+
+    fn relation(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.addition()?;
+        loop {
+            let op = match self.tokens.get(self.pos) {
+                Some(Token::Punct("==")) => BinaryOp::Eq,
+                Some(Token::Punct("!=")) => BinaryOp::Ne,
+                Some(Token::Punct("<")) => BinaryOp::Lt,
+                Some(Token::Punct("<=")) => BinaryOp::Le,
+                Some(Token::Punct(">")) => BinaryOp::Gt,
+                Some(Token::Punct(">=")) => BinaryOp::Ge,
+                Some(Token::Ident(s)) if s == "in" => BinaryOp::In,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.addition()?));
+        }
+    }
+
+    fn addition(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.multiplication()?;
+        loop {
+            let op = if self.eat_punct("+") {
+                BinaryOp::Add
+            } else if self.eat_punct("-") {
+                BinaryOp::Sub
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.multiplication()?));
+        }
+    }
+
+    fn multiplication(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = if self.eat_punct("*") {
+                BinaryOp::Mul
+            } else if self.eat_punct("/") {
+                BinaryOp::Div
+            } else if self.eat_punct("%") {
+                BinaryOp::Rem
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat_punct("!") {
+            self.nested(|p| Ok(Expr::Not(Box::new(p.unary()?))))
+        } else if self.eat_punct("-") {
+            self.nested(|p| Ok(Expr::Neg(Box::new(p.unary()?))))
+        } else {
+            self.member()
+        }
+    }
+
+    fn nested(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("expression is nested too deeply".to_string());
+        }
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn member(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat_punct(".") {
+                let name = self.ident()?;
+                if self.eat_punct("(") {
+                    expr = self.method_call(expr, name)?;
+                } else {
+                    expr = Expr::Field(Box::new(expr), name);
+                }
+            } else if self.eat_punct("[") {
+                let index = self.expr()?;
+                self.expect_punct("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn method_call(&mut self, target: Expr, name: String) -> Result<Expr, String> {
+        let kind = match name.as_str() {
+            "exists" => Some(MacroKind::Exists),
+            "all" => Some(MacroKind::All),
+            "exists_one" => Some(MacroKind::ExistsOne),
+            "filter" => Some(MacroKind::Filter),
+            "map" => Some(MacroKind::Map),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            let var = self.ident()?;
+            self.expect_punct(",")?;
+            let body = self.expr()?;
+            self.expect_punct(")")?;
+            return Ok(Expr::Macro {
+                kind,
+                range: Box::new(target),
+                var,
+                body: Box::new(body),
+            });
+        }
+        let args = self.call_args()?;
+        Ok(Expr::Call {
+            target: Some(Box::new(target)),
+            name,
+            args,
+        })
+    }
+
+    /// `(` の後の引数リストを `)` まで読む
+    fn call_args(&mut self) -> Result<Vec<Expr>, String> {
+        let mut args = Vec::new();
+        if self.eat_punct(")") {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expr()?);
+            if self.eat_punct(")") {
+                return Ok(args);
+            }
+            self.expect_punct(",")?;
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Ident(s)) => {
+                self.pos += 1;
+                Ok(s.clone())
+            }
+            _ => Err(format!("expected identifier{}", self.position())),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let Some(token) = self.tokens.get(self.pos).cloned() else {
+            return Err("unexpected end of expression".to_string());
+        };
+        self.pos += 1;
+        match token {
+            Token::Int(n) => Ok(Expr::Literal(Value::from(n))),
+            Token::Float(n) => Ok(Expr::Literal(Value::from(n))),
+            Token::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            Token::Punct("(") => {
+                let expr = self.expr()?;
+                self.expect_punct(")")?;
+                Ok(expr)
+            }
+            Token::Punct("[") => {
+                let mut items = Vec::new();
+                if !self.eat_punct("]") {
+                    loop {
+                        items.push(self.expr()?);
+                        if self.eat_punct("]") {
+                            break;
+                        }
+                        self.expect_punct(",")?;
+                    }
+                }
+                Ok(Expr::List(items))
+            }
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                "has" if self.eat_punct("(") => {
+                    let arg = self.expr()?;
+                    self.expect_punct(")")?;
+                    match arg {
+                        Expr::Field(target, field) => Ok(Expr::Has(target, field)),
+                        _ => Err("has() requires a field selection (e.g. has(a.b))".to_string()),
+                    }
+                }
+                _ if self.eat_punct("(") => {
+                    let args = self.call_args()?;
+                    Ok(Expr::Call {
+                        target: None,
+                        name,
+                        args,
+                    })
+                }
+                _ => Ok(Expr::Ident(name)),
+            },
+            Token::Punct(p) => {
+                self.pos -= 1;
+                Err(format!("unexpected '{}'{}", p, self.position()))
+            }
+        }
+    }
+}
+
+/// 式をパースする
+pub fn parse(src: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+        depth: 0,
+    };
+    if parser.tokens.is_empty() {
+        return Err("empty expression".to_string());
+    }
+    let expr = parser.expr()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(format!("unexpected token{}", parser.position()));
+    }
+    Ok(expr)
+}
+
+/// 式を評価する。root のトップレベルのキーが変数になる。
+pub fn evaluate(expr: &Expr, root: &Map<String, Value>) -> Result<Value, String> {
+    let mut scope = Vec::new();
+    eval(expr, root, &mut scope)
+}
+
+/// 式を評価し、結果が bool であることを確認する
+pub fn evaluate_bool(expr: &Expr, root: &Map<String, Value>) -> Result<bool, String> {
+    match evaluate(expr, root)? {
+        Value::Bool(b) => Ok(b),
+        other => Err(format!(
+            "expression returned {}, expected bool",
+            type_name(&other)
+        )),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(n) if n.is_f64() => "double",
+        Value::Number(_) => "int",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "map",
+    }
+}
+
+fn eval(
+    expr: &Expr,
+    root: &Map<String, Value>,
+    scope: &mut Vec<(String, Value)>,
+) -> Result<Value, String> {
+    match expr {
+        Expr::Literal(v) => Ok(v.clone()),
+        Expr::List(items) => items
+            .iter()
+            .map(|item| eval(item, root, scope))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Expr::Ident(name) => scope
+            .iter()
+            .rev()
+            .find(|(var, _)| var == name)
+            .map(|(_, v)| v.clone())
+            .or_else(|| root.get(name).cloned())
+            .ok_or_else(|| format!("undeclared reference to '{}'", name)),
+        Expr::Field(target, field) => match eval(target, root, scope)? {
+            Value::Object(map) => map
+                .get(field)
+                .cloned()
+                .ok_or_else(|| format!("no such key: '{}'", field)),
+            other => Err(format!(
+                "cannot select field '{}' from {}",
+                field,
+                type_name(&other)
+            )),
+        },
+        Expr::Has(target, field) => match eval(target, root, scope)? {
+            Value::Object(map) => Ok(Value::Bool(map.contains_key(field))),
+            other => Err(format!(
+                "has() cannot select field '{}' from {}",
+                field,
+                type_name(&other)
+            )),
+        },
+        Expr::Index(target, index) => {
+            let target = eval(target, root, scope)?;
+            let index = eval(index, root, scope)?;
+            match (&target, &index) {
+                (Value::Array(items), Value::Number(n)) => n
+                    .as_i64()
+                    .and_then(|i| usize::try_from(i).ok())
+                    .and_then(|i| items.get(i))
+                    .cloned()
+                    .ok_or_else(|| format!("index out of range: {}", n)),
+                (Value::Object(map), Value::String(key)) => map
+                    .get(key)
+                    .cloned()
+                    .ok_or_else(|| format!("no such key: '{}'", key)),
+                _ => Err(format!(
+                    "cannot index {} with {}",
+                    type_name(&target),
+                    type_name(&index)
+                )),
+            }
+        }
+        Expr::Not(inner) => match eval(inner, root, scope)? {
+            Value::Bool(b) => Ok(Value::Bool(!b)),
+            other => Err(format!("'!' requires bool, got {}", type_name(&other))),
+        },
+        Expr::Neg(inner) => match eval(inner, root, scope)? {
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => i
+                    .checked_neg()
+                    .map(Value::from)
+                    .ok_or_else(|| "integer overflow".to_string()),
+                None => Ok(Value::from(-n.as_f64().unwrap_or_default())),
+            },
+            other => Err(format!("'-' requires a number, got {}", type_name(&other))),
+        },
+        Expr::And(lhs, rhs) => {
+            if !as_bool(eval(lhs, root, scope)?, "&&")? {
+                return Ok(Value::Bool(false));
+            }
+            Ok(Value::Bool(as_bool(eval(rhs, root, scope)?, "&&")?))
+        }
+        Expr::Or(lhs, rhs) => {
+            if as_bool(eval(lhs, root, scope)?, "||")? {
+                return Ok(Value::Bool(true));
+            }
+            Ok(Value::Bool(as_bool(eval(rhs, root, scope)?, "||")?))
+        }
+        Expr::Conditional(cond, then, otherwise) => {
+            if as_bool(eval(cond, root, scope)?, "?:")? {
+                eval(then, root, scope)
+            } else {
+                eval(otherwise, root, scope)
+            }
+        }
+        Expr::Binary(op, lhs, rhs) => {
+            let lhs = eval(lhs, root, scope)?;
+            let rhs = eval(rhs, root, scope)?;
+            binary(*op, &lhs, &rhs)
+        }
+        Expr::Call { target, name, args } => {
+            let target = match target {
+                Some(t) => Some(eval(t, root, scope)?),
+                None => None,
+            };
+            let args = args
+                .iter()
+                .map(|a| eval(a, root, scope))
+                .collect::<Result<Vec<_>, _>>()?;
+            call(target, name, &args)
+        }
+        Expr::Macro {
+            kind,
+            range,
+            var,
+            body,
+        } => {
+            let items = match eval(range, root, scope)? {
+                Value::Array(items) => items,
+                Value::Object(map) => map.keys().map(|k| Value::String(k.clone())).collect(),
+                other => {
+                    return Err(format!(
+                        "macro requires a list or map, got {}",
+                        type_name(&other)
+                    ));
+                }
+            };
+            let mut matched = 0usize;
+            let mut results = Vec::new();
+            for item in items {
+                scope.push((var.clone(), item.clone()));
+                let result = eval(body, root, scope);
+                scope.pop();
+                let result = result?;
+                match kind {
+                    MacroKind::Map => results.push(result),
+                    _ => {
+                        if as_bool(result, "macro predicate")? {
+                            matched += 1;
+                            match kind {
+                                MacroKind::Exists => return Ok(Value::Bool(true)),
+                                MacroKind::Filter => results.push(item),
+                                _ => {}
+                            }
+                        } else if *kind == MacroKind::All {
+                            return Ok(Value::Bool(false));
+                        }
+                    }
+                }
+            }
+            Ok(match kind {
+                MacroKind::Exists => Value::Bool(false),
+                MacroKind::All => Value::Bool(true),
+                MacroKind::ExistsOne => Value::Bool(matched == 1),
+                MacroKind::Filter | MacroKind::Map => Value::Array(results),
+            })
+        }
+    }
+}
+
+fn as_bool(value: Value, context: &str) -> Result<bool, String> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        other => Err(format!(
+            "'{}' requires bool, got {}",
+            context,
+            type_name(&other)
+        )),
+    }
+}
+
+/// 整数同士は整数、それ以外の数値は小数として扱う
+enum Num {
+    Int(i64),
+    Float(f64),
+}
+
+fn num(n: &Number) -> Num {
+    match n.as_i64() {
+        Some(i) => Num::Int(i),
+        None => Num::Float(n.as_f64().unwrap_or(f64::NAN)),
+    }
+}
+
+fn to_f64(n: &Num) -> f64 {
+    match n {
+        Num::Int(i) => *i as f64,
+        Num::Float(f) => *f,
+    }
+}
+
+fn values_equal(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => match (num(a), num(b)) {
+            (Num::Int(a), Num::Int(b)) => a == b,
+            (a, b) => to_f64(&a) == to_f64(&b),
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(x, y)| values_equal(x, y))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(k, v)| b.get(k).is_some_and(|w| values_equal(v, w)))
+        }
+        _ => lhs == rhs,
+    }
+}
+
+fn binary(op: BinaryOp, lhs: &Value, rhs: &Value) -> Result<Value, String> {
+    let mismatch = || {
+        Err(format!(
+            "no such overload: {} {:?} {}",
+            type_name(lhs),
+            op,
+            type_name(rhs)
+        ))
+    };
+    match op {
+        BinaryOp::Eq => Ok(Value::Bool(values_equal(lhs, rhs))),
+        BinaryOp::Ne => Ok(Value::Bool(!values_equal(lhs, rhs))),
+        BinaryOp::In => match rhs {
+            Value::Array(items) => Ok(Value::Bool(items.iter().any(|v| values_equal(lhs, v)))),
+            Value::Object(map) => match lhs {
+                Value::String(key) => Ok(Value::Bool(map.contains_key(key))),
+                _ => mismatch(),
+            },
+            _ => mismatch(),
+        },
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let ordering = match (lhs, rhs) {
+                (Value::Number(a), Value::Number(b)) => match (num(a), num(b)) {
+                    (Num::Int(a), Num::Int(b)) => Some(a.cmp(&b)),
+                    (a, b) => to_f64(&a).partial_cmp(&to_f64(&b)),
+                },
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                _ => return mismatch(),
+            };
+            let Some(ordering) = ordering else {
+                return Ok(Value::Bool(false));
+            };
+            Ok(Value::Bool(match op {
+                BinaryOp::Lt => ordering.is_lt(),
+                BinaryOp::Le => ordering.is_le(),
+                BinaryOp::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }))
+        }
+        BinaryOp::Add => match (lhs, rhs) {
+            (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
+            (Value::Array(a), Value::Array(b)) => {
+                Ok(Value::Array(a.iter().chain(b).cloned().collect()))
+            }
+            (Value::Number(a), Value::Number(b)) => arithmetic(op, a, b),
+            _ => mismatch(),
+        },
+        BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => match (lhs, rhs) {
+            (Value::Number(a), Value::Number(b)) => arithmetic(op, a, b),
+            _ => mismatch(),
+        },
+    }
+}
+
+fn arithmetic(op: BinaryOp, a: &Number, b: &Number) -> Result<Value, String> {
+    match (num(a), num(b)) {
+        (Num::Int(a), Num::Int(b)) => {
+            let result = match op {
+                BinaryOp::Add => a.checked_add(b),
+                BinaryOp::Sub => a.checked_sub(b),
+                BinaryOp::Mul => a.checked_mul(b),
+                BinaryOp::Div if b == 0 => return Err("division by zero".to_string()),
+                BinaryOp::Div => a.checked_div(b),
+                BinaryOp::Rem if b == 0 => return Err("modulus by zero".to_string()),
+                _ => a.checked_rem(b),
+            };
+            result
+                .map(Value::from)
+                .ok_or_else(|| "integer overflow".to_string())
+        }
+        (a, b) => {
+            let (a, b) = (to_f64(&a), to_f64(&b));
+            let result = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div => a / b,
+                _ => a % b,
+            };
+            Ok(Value::from(result))
+        }
+    }
+}
+
+fn call(target: Option<Value>, name: &str, args: &[Value]) -> Result<Value, String> {
+    // size(x) / x.size() のように、グローバル関数はレシーバを第 1 引数として扱う
+    let (receiver, rest) = match &target {
+        Some(t) => (t, args),
+        None => match args.split_first() {
+            Some((first, rest)) => (first, rest),
+            None => return Err(format!("{}() requires an argument", name)),
+        },
+    };
+    let arity = |n: usize| {
+        if rest.len() == n {
+            Ok(())
+        } else {
+            Err(format!(
+                "{}() expects {} argument(s), got {}",
+                name,
+                n,
+                rest.len()
+            ))
+        }
+    };
+    let string_arg = || match rest.first() {
+        Some(Value::String(s)) => Ok(s.as_str()),
+        Some(other) => Err(format!(
+            "{}() requires a string argument, got {}",
+            name,
+            type_name(other)
+        )),
+        None => Err(format!("{}() requires an argument", name)),
+    };
+    match (name, receiver) {
+        ("size", _) => {
+            arity(0)?;
+            let size = match receiver {
+                Value::String(s) => s.chars().count(),
+                Value::Array(items) => items.len(),
+                Value::Object(map) => map.len(),
+                other => return Err(format!("size() is not defined for {}", type_name(other))),
+            };
+            Ok(Value::from(size as i64))
+        }
+        ("int", _) if target.is_none() => {
+            arity(0)?;
+            match receiver {
+                Value::Number(n) => match num(n) {
+                    Num::Int(i) => Ok(Value::from(i)),
+                    Num::Float(f) => Ok(Value::from(f.trunc() as i64)),
+                },
+                Value::String(s) => s
+                    .trim()
+                    .parse::<i64>()
+                    .map(Value::from)
+                    .map_err(|_| format!("int() cannot convert '{}'", s)),
+                Value::Bool(b) => Ok(Value::from(i64::from(*b))),
+                other => Err(format!("int() is not defined for {}", type_name(other))),
+            }
+        }
+        ("string", _) if target.is_none() => {
+            arity(0)?;
+            match receiver {
+                Value::String(s) => Ok(Value::String(s.clone())),
+                Value::Number(_) | Value::Bool(_) => Ok(Value::String(receiver.to_string())),
+                other => Err(format!("string() is not defined for {}", type_name(other))),
+            }
+        }
+        ("startsWith", Value::String(s)) => {
+            arity(1)?;
+            Ok(Value::Bool(s.starts_with(string_arg()?)))
+        }
+        ("endsWith", Value::String(s)) => {
+            arity(1)?;
+            Ok(Value::Bool(s.ends_with(string_arg()?)))
+        }
+        ("contains", Value::String(s)) => {
+            arity(1)?;
+            Ok(Value::Bool(s.contains(string_arg()?)))
+        }
+        ("matches", Value::String(s)) => {
+            arity(1)?;
+            let re = regex::Regex::new(string_arg()?)
+                .map_err(|e| format!("matches(): invalid regex: {}", e))?;
+            Ok(Value::Bool(re.is_match(s)))
+        }
+        ("lowerAscii", Value::String(s)) => {
+            arity(0)?;
+            Ok(Value::String(s.to_ascii_lowercase()))
+        }
+        ("upperAscii", Value::String(s)) => {
+            arity(0)?;
+            Ok(Value::String(s.to_ascii_uppercase()))
+        }
+        _ => Err(format!(
+            "no such function: {}() for {}",
+            name,
+            type_name(receiver)
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn root() -> Map<String, Value> {
+        json!({
+            "command": {
+                "subcommand": "run",
+                "image": "ghcr.io/ourorg/app:1.0",
+                "cap_add": ["NET_ADMIN", "NET_RAW"],
+                "args": ["run", "-p", "0.0.0.0:80:80", "ghcr.io/ourorg/app:1.0"],
+                "labels": {"team": "infra"},
+            },
+            "time": {"hour": 19, "weekday": 5},
+        })
+        .as_object()
+        .unwrap()
+        .clone()
+    }
+
+    fn eval_str(src: &str) -> Result<Value, String> {
+        evaluate(&parse(src)?, &root())
+    }
+
+    #[test]
+    fn test_field_access_and_string_functions() {
+        assert_eq!(
+            eval_str(r#"command.image.startsWith("ghcr.io/ourorg/")"#),
+            Ok(json!(true))
+        );
+        assert_eq!(eval_str("command.image.endsWith(':1.0')"), Ok(json!(true)));
+        assert_eq!(
+            eval_str(r#"command.image.matches("^ghcr\\.io/[a-z]+/")"#),
+            Ok(json!(true))
+        );
+        assert_eq!(
+            eval_str("command.subcommand.upperAscii()"),
+            Ok(json!("RUN"))
+        );
+        assert_eq!(eval_str("command.labels['team']"), Ok(json!("infra")));
+        assert_eq!(eval_str("command.args[1]"), Ok(json!("-p")));
+    }
+
+    #[test]
+    fn test_operators_and_precedence() {
+        assert_eq!(eval_str("1 + 2 * 3 == 7"), Ok(json!(true)));
+        assert_eq!(eval_str("-(1 + 2) % 2"), Ok(json!(-1)));
+        assert_eq!(eval_str("1.5 < 2 && !(3 >= 4)"), Ok(json!(true)));
+        assert_eq!(eval_str("'a' + 'b' == \"ab\""), Ok(json!(true)));
+        assert_eq!(
+            eval_str("\"NET_ADMIN\" in command.cap_add"),
+            Ok(json!(true))
+        );
+        assert_eq!(eval_str("'team' in command.labels"), Ok(json!(true)));
+        assert_eq!(
+            eval_str("time.hour >= 18 ? 'late' : 'day'"),
+            Ok(json!("late"))
+        );
+        assert_eq!(eval_str("[1, 2] + [3] == [1, 2, 3]"), Ok(json!(true)));
+        assert_eq!(eval_str("1 == 1.0"), Ok(json!(true)));
+    }
+
+    #[test]
+    fn test_macros() {
+        assert_eq!(
+            eval_str("command.args.exists(a, a.startsWith('0.0.0.0:'))"),
+            Ok(json!(true))
+        );
+        assert_eq!(
+            eval_str("command.cap_add.all(c, c.startsWith('NET_'))"),
+            Ok(json!(true))
+        );
+        assert_eq!(
+            eval_str("command.cap_add.exists_one(c, c == 'NET_RAW')"),
+            Ok(json!(true))
+        );
+        assert_eq!(
+            eval_str("command.cap_add.filter(c, c != 'NET_RAW')"),
+            Ok(json!(["NET_ADMIN"]))
+        );
+        assert_eq!(
+            eval_str("command.cap_add.map(c, c.lowerAscii())"),
+            Ok(json!(["net_admin", "net_raw"]))
+        );
+        assert_eq!(
+            eval_str("command.labels.exists(k, k == 'team')"),
+            Ok(json!(true))
+        );
+        assert_eq!(eval_str("size(command.cap_add) == 2"), Ok(json!(true)));
+    }
+
+    #[test]
+    fn test_short_circuit_and_has() {
+        // 右辺のエラーは左辺で決まる場合は評価されない
+        assert_eq!(eval_str("false && command.missing"), Ok(json!(false)));
+        assert_eq!(eval_str("true || command.missing"), Ok(json!(true)));
+        assert_eq!(eval_str("has(command.image)"), Ok(json!(true)));
+        assert_eq!(eval_str("has(command.missing)"), Ok(json!(false)));
+    }
+
+    #[test]
+    fn test_evaluation_errors() {
+        assert!(
+            eval_str("command.missing")
+                .unwrap_err()
+                .contains("no such key")
+        );
+        assert!(eval_str("unknown").unwrap_err().contains("undeclared"));
+        assert!(eval_str("command.image > 1").is_err());
+        assert!(eval_str("1 / 0").unwrap_err().contains("division by zero"));
+        assert!(eval_str("command.image.frobnicate()").is_err());
+        assert!(
+            eval_str("'x'.matches('(')")
+                .unwrap_err()
+                .contains("invalid regex")
+        );
+        assert!(evaluate_bool(&parse("command.image").unwrap(), &root()).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("").is_err());
+        assert!(parse("command.").is_err());
+        assert!(parse("(1 + 2").is_err());
+        assert!(parse("'unterminated").is_err());
+        assert!(parse("1 2").is_err());
+        assert!(parse("a ? b").is_err());
+        assert!(parse("has(a)").is_err());
+        assert!(parse("x.exists(1, true)").is_err());
+        assert!(parse("a # b").is_err());
+        let deep = format!("{}true{}", "(".repeat(200), ")".repeat(200));
+        assert!(parse(&deep).unwrap_err().contains("too deeply"));
+        let negations = format!("{}true", "!".repeat(200));
+        assert!(parse(&negations).unwrap_err().contains("too deeply"));
+    }
+}
@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use crate::docker_args::{BindMount, DangerousFlag, MountSource};
+use crate::docker_args::{
+    BindMount, DangerousFlag, MountSource, NetworkCreate, volume_opts_host_path,
+};
 use crate::error::{Result, SafeDockerError};
 
 /// Compose ファイルの解析結果
@@ -13,6 +15,38 @@ pub struct ComposeAnalysis {
     pub host_paths: Vec<String>,
     /// env_file ディレクティブで参照されるホストパス
     pub env_file_paths: Vec<String>,
+    /// build を持たないサービスの image (サービス名, イメージ)
+    pub images: Vec<(String, String)>,
+    /// build を持つサービスの Dockerfile (サービス名, パス)
+    pub dockerfiles: Vec<(String, String)>,
+    /// ports で公開するポート (サービス名, `-p` と同じ書式)
+    pub ports: Vec<(String, String)>,
+    /// トップレベルの networks の定義
+    pub networks: Vec<ComposeNetwork>,
+    /// サービスの接続先ネットワーク
+    pub service_networks: Vec<ServiceNetworks>,
+}
+
+/// トップレベルの networks の定義
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposeNetwork {
+    /// networks のキー (サービスの networks から参照する名前)
+    pub key: String,
+    /// external: true (compose の外で作成済みのネットワーク)
+    pub external: bool,
+    /// 定義の内容 (name は `name:`、なければキー)
+    pub network: NetworkCreate,
+}
+
+/// サービスの接続先ネットワーク
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceNetworks {
+    pub service: String,
+    pub image: Option<String>,
+    /// network_mode (`none` / `host` / `service:NAME` 等)
+    pub network_mode: Option<String>,
+    /// networks のキー (network_mode も networks もない場合は `default`)
+    pub networks: Vec<String>,
 }
 
 /// docker-compose.yml からバインドマウントを抽出する
@@ -61,6 +95,34 @@ pub fn analyze_compose(compose_path: &Path) -> Result<ComposeAnalysis> {
         }
     }
 
+    // トップレベルの volumes の driver_opts (type: none, o: bind, device: /path) によるバインドマウント
+    if let Some(volumes) = yaml.get("volumes").and_then(|v| v.as_mapping()) {
+        for definition in volumes.values() {
+            // external のボリュームは compose が作成しないため driver_opts は使われない
+            if definition
+                .get("external")
+                .is_some_and(|e| e.as_bool() != Some(false))
+            {
+                continue;
+            }
+            let Some(driver_opts) = definition.get("driver_opts").and_then(|d| d.as_mapping())
+            else {
+                continue;
+            };
+            let opts = driver_opts
+                .iter()
+                .filter_map(|(k, v)| Some((k.as_str()?, v.as_str()?)));
+            if let Some((host_path, read_only)) = volume_opts_host_path(opts) {
+                analysis.bind_mounts.push(BindMount {
+                    host_path,
+                    container_path: String::new(),
+                    source: MountSource::ComposeVolumes,
+                    read_only,
+                });
+            }
+        }
+    }
+
     // サービスの env_file パス抽出
     if let Some(services) = yaml.get("services").and_then(|s| s.as_mapping()) {
         for (_service_name, service) in services {
@@ -68,6 +130,74 @@ pub fn analyze_compose(compose_path: &Path) -> Result<ComposeAnalysis> {
         }
     }
 
+    // サービスのイメージとビルドする Dockerfile の抽出
+    if let Some(services) = yaml.get("services").and_then(|s| s.as_mapping()) {
+        for (service_name, service) in services {
+            let name = service_name.as_str().unwrap_or_default().to_string();
+            match service.get("build") {
+                Some(build) => {
+                    if let Some(dockerfile) = service_dockerfile(build, &compose_dir) {
+                        analysis.dockerfiles.push((name, dockerfile));
+                    }
+                }
+                None => {
+                    if let Some(image) = service.get("image").and_then(|v| v.as_str()) {
+                        analysis.images.push((name, image.to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    // サービスの公開ポート抽出
+    if let Some(services) = yaml.get("services").and_then(|s| s.as_mapping()) {
+        for (service_name, service) in services {
+            let name = service_name.as_str().unwrap_or_default();
+            for spec in service_ports(service) {
+                analysis.ports.push((name.to_string(), spec));
+            }
+        }
+    }
+
+    // ネットワークの定義とサービスの接続先
+    if let Some(networks) = yaml.get("networks").and_then(|n| n.as_mapping()) {
+        for (key, definition) in networks {
+            let key = key.as_str().unwrap_or_default().to_string();
+            analysis.networks.push(compose_network(key, definition));
+        }
+    }
+    if let Some(services) = yaml.get("services").and_then(|s| s.as_mapping()) {
+        for (service_name, service) in services {
+            let network_mode = service
+                .get("network_mode")
+                .and_then(|v| v.as_str())
+                .map(String::from);
+            let mut networks: Vec<String> = match service.get("networks") {
+                Some(serde_yaml_ng::Value::Sequence(seq)) => seq
+                    .iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect(),
+                Some(serde_yaml_ng::Value::Mapping(map)) => map
+                    .keys()
+                    .filter_map(|k| k.as_str().map(String::from))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            if networks.is_empty() && network_mode.is_none() {
+                networks.push("default".to_string());
+            }
+            analysis.service_networks.push(ServiceNetworks {
+                service: service_name.as_str().unwrap_or_default().to_string(),
+                image: service
+                    .get("image")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+                network_mode,
+                networks,
+            });
+        }
+    }
+
     // include ディレクティブ解析
     extract_include_paths(&yaml, &compose_dir, &mut analysis.host_paths);
 
@@ -128,6 +258,49 @@ fn extract_service_volumes(
     }
 }
 
+/// サービス定義の ports を `-p` と同じ書式で返す
+///
+/// Short syntax (`"127.0.0.1:8080:80/udp"`, `80`) はそのまま、Long syntax
+/// (`{ target, published, host_ip, protocol }`) は `host_ip:published:target/protocol` に変換する
+fn service_ports(service: &serde_yaml_ng::Value) -> Vec<String> {
+    let Some(ports) = service.get("ports").and_then(|v| v.as_sequence()) else {
+        return Vec::new();
+    };
+    let scalar = |value: &serde_yaml_ng::Value| match value {
+        serde_yaml_ng::Value::String(s) => Some(s.clone()),
+        serde_yaml_ng::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+    ports
+        .iter()
+        .filter_map(|port| {
+            let Some(mapping) = port.as_mapping() else {
+                return scalar(port);
+            };
+            let field = |key: &str| {
+                mapping
+                    .get(serde_yaml_ng::Value::String(key.to_string()))
+                    .and_then(scalar)
+            };
+            let target = field("target")?;
+            let mut spec = match (field("host_ip"), field("published")) {
+                (Some(ip), published) if ip.contains(':') => {
+                    format!("[{}]:{}:{}", ip, published.unwrap_or_default(), target)
+                }
+                (Some(ip), published) => {
+                    format!("{}:{}:{}", ip, published.unwrap_or_default(), target)
+                }
+                (None, Some(published)) => format!("{}:{}", published, target),
+                (None, None) => target,
+            };
+            if let Some(protocol) = field("protocol") {
+                spec = format!("{}/{}", spec, protocol);
+            }
+            Some(spec)
+        })
+        .collect()
+}
+
 /// サービス定義から危険な設定を抽出
 fn extract_service_dangerous_settings(
     service: &serde_yaml_ng::Value,
@@ -330,6 +503,96 @@ fn extract_service_env_file_paths(
     }
 }
 
+/// トップレベルの networks の 1 つの定義を解析する
+fn compose_network(key: String, definition: &serde_yaml_ng::Value) -> ComposeNetwork {
+    let string = |key: &str| definition.get(key).and_then(|v| v.as_str());
+    // external: true と、旧形式の external: { name: ... }
+    let external = match definition.get("external") {
+        Some(serde_yaml_ng::Value::Bool(b)) => *b,
+        Some(serde_yaml_ng::Value::Mapping(_)) => true,
+        _ => false,
+    };
+    let name = definition
+        .get("external")
+        .and_then(|e| e.get("name"))
+        .and_then(|v| v.as_str())
+        .or_else(|| string("name"))
+        .unwrap_or(&key)
+        .to_string();
+    let options = definition
+        .get("driver_opts")
+        .and_then(|o| o.as_mapping())
+        .map(|opts| {
+            opts.iter()
+                .filter_map(|(k, v)| {
+                    let value = match v {
+                        serde_yaml_ng::Value::String(s) => s.clone(),
+                        serde_yaml_ng::Value::Number(n) => n.to_string(),
+                        serde_yaml_ng::Value::Bool(b) => b.to_string(),
+                        _ => return None,
+                    };
+                    Some(format!("{}={}", k.as_str()?, value))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let subnets = definition
+        .get("ipam")
+        .and_then(|i| i.get("config"))
+        .and_then(|c| c.as_sequence())
+        .map(|configs| {
+            configs
+                .iter()
+                .filter_map(|c| c.get("subnet").and_then(|s| s.as_str()).map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    ComposeNetwork {
+        key,
+        external,
+        network: NetworkCreate {
+            name,
+            driver: string("driver").unwrap_or("bridge").to_string(),
+            options,
+            internal: definition
+                .get("internal")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            subnets,
+        },
+    }
+}
+
+/// サービスの build から Dockerfile のパスを求める (リモートのコンテキスト・dockerfile_inline は None)
+///
+/// 形式:
+/// - `build: ./dir`
+/// - `build: {context: ./dir, dockerfile: Dockerfile.dev}` (dockerfile はコンテキスト基準)
+fn service_dockerfile(build: &serde_yaml_ng::Value, compose_dir: &Path) -> Option<String> {
+    let (context, dockerfile) = match build {
+        serde_yaml_ng::Value::String(context) => (context.as_str(), "Dockerfile"),
+        serde_yaml_ng::Value::Mapping(map) => {
+            let get = |key: &str| {
+                map.get(serde_yaml_ng::Value::String(key.to_string()))
+                    .and_then(|v| v.as_str())
+            };
+            if get("dockerfile_inline").is_some() {
+                return None;
+            }
+            (
+                get("context").unwrap_or("."),
+                get("dockerfile").unwrap_or("Dockerfile"),
+            )
+        }
+        _ => return None,
+    };
+    if context.contains("://") || context.starts_with("git@") {
+        return None;
+    }
+    let context = resolve_path(context, compose_dir);
+    Some(resolve_path(dockerfile, Path::new(&context)))
+}
+
 /// include ディレクティブからファイルパスを抽出
 ///
 /// 形式:
@@ -753,6 +1016,37 @@ services:
         assert_eq!(mounts[0].host_path, "/host/path");
     }
 
+    #[test]
+    fn test_parse_top_level_volume_driver_opts() {
+        let yaml_str = r#"
+services:
+  web:
+    image: nginx
+    volumes:
+      - etc:/data
+volumes:
+  etc:
+    driver: local
+    driver_opts:
+      type: none
+      o: bind,ro
+      device: /etc
+  cache: {}
+  shared:
+    external: true
+    driver_opts:
+      device: /srv
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        let compose_path = dir.path().join("compose.yml");
+        std::fs::write(&compose_path, yaml_str).unwrap();
+
+        let mounts = extract_bind_mounts(&compose_path).unwrap();
+        assert_eq!(mounts.len(), 1);
+        assert_eq!(mounts[0].host_path, "/etc");
+        assert!(mounts[0].read_only);
+    }
+
     #[test]
     fn test_parse_empty_services() {
         let yaml_str = r#"
@@ -1364,4 +1658,157 @@ services:
         assert_eq!(cp.len(), 1);
         assert!(matches!(&cp[0], DangerousFlag::CgroupParent(s) if s == "/custom-cgroup"));
     }
+
+    #[test]
+    fn test_parse_compose_images_and_dockerfiles() {
+        let yaml_str = r#"
+services:
+  db:
+    image: postgres:16
+  web:
+    image: ghcr.io/ourorg/web:dev
+    build:
+      context: ./web
+      dockerfile: Dockerfile.dev
+  worker:
+    build: ./worker
+  remote:
+    build: https://github.com/org/repo.git
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        let compose_path = dir.path().join("compose.yml");
+        std::fs::write(&compose_path, yaml_str).unwrap();
+
+        let analysis = analyze_compose(&compose_path).unwrap();
+        // build を持つサービスの image はビルド結果の名前なので対象外
+        assert_eq!(
+            analysis.images,
+            vec![("db".to_string(), "postgres:16".to_string())]
+        );
+        let base = dir.path().to_string_lossy();
+        assert_eq!(
+            analysis.dockerfiles,
+            vec![
+                ("web".to_string(), format!("{}/./web/Dockerfile.dev", base)),
+                (
+                    "worker".to_string(),
+                    format!("{}/./worker/Dockerfile", base)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_compose_ports() {
+        let yaml_str = r#"
+services:
+  db:
+    ports:
+      - "5432:5432"
+      - 9000
+      - "127.0.0.1:8080-8081:80-81/udp"
+  web:
+    ports:
+      - target: 80
+        published: "8080"
+        host_ip: 127.0.0.1
+      - target: 443
+        host_ip: "::1"
+        protocol: tcp
+      - target: 53
+        published: 5353
+        protocol: udp
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        let compose_path = dir.path().join("compose.yml");
+        std::fs::write(&compose_path, yaml_str).unwrap();
+
+        let analysis = analyze_compose(&compose_path).unwrap();
+        let ports: Vec<(&str, &str)> = analysis
+            .ports
+            .iter()
+            .map(|(service, spec)| (service.as_str(), spec.as_str()))
+            .collect();
+        assert_eq!(
+            ports,
+            vec![
+                ("db", "5432:5432"),
+                ("db", "9000"),
+                ("db", "127.0.0.1:8080-8081:80-81/udp"),
+                ("web", "127.0.0.1:8080:80"),
+                ("web", "[::1]::443/tcp"),
+                ("web", "5353:53/udp"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_compose_networks() {
+        let yaml_str = r#"
+services:
+  web:
+    image: nginx
+  scanner:
+    image: ghcr.io/ourorg/scanner:1
+    networks: [sandbox]
+  probe:
+    image: busybox
+    network_mode: none
+networks:
+  sandbox:
+    internal: true
+  lan:
+    driver: macvlan
+    driver_opts:
+      parent: eth0
+    ipam:
+      config:
+        - subnet: 192.168.1.0/24
+  shared:
+    external: true
+    name: prod-shared
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        let compose_path = dir.path().join("compose.yml");
+        std::fs::write(&compose_path, yaml_str).unwrap();
+
+        let analysis = analyze_compose(&compose_path).unwrap();
+        let lan = analysis.networks.iter().find(|n| n.key == "lan").unwrap();
+        assert_eq!(lan.network.driver, "macvlan");
+        assert_eq!(lan.network.options, vec!["parent=eth0"]);
+        assert_eq!(lan.network.subnets, vec!["192.168.1.0/24"]);
+        assert!(
+            analysis
+                .networks
+                .iter()
+                .any(|n| n.key == "sandbox" && n.network.internal)
+        );
+        let shared = analysis
+            .networks
+            .iter()
+            .find(|n| n.key == "shared")
+            .unwrap();
+        assert!(shared.external);
+        assert_eq!(shared.network.name, "prod-shared");
+
+        let services: Vec<(&str, Option<&str>, Vec<&str>)> = analysis
+            .service_networks
+            .iter()
+            .map(|s| {
+                (
+                    s.service.as_str(),
+                    s.network_mode.as_deref(),
+                    s.networks.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            services,
+            vec![
+                ("web", None, vec!["default"]),
+                ("scanner", None, vec!["sandbox"]),
+                ("probe", Some("none"), vec![]),
+            ]
+        );
+    }
 }
@@ -3,6 +3,8 @@ use std::collections::HashSet;
 use std::path::PathBuf;
 
 use crate::error::{Result, SafeDockerError};
+use crate::image_ref::ImagePattern;
+use crate::path_pattern;
 
 /// バリデーション結果の問題点
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -32,6 +34,235 @@ pub enum NonInteractiveAsk {
     Allow,
 }
 
+/// 保護対象ファイル (safe-docker 自身の設定等) への書き込みの扱い
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SelfProtection {
+    #[default]
+    Deny,
+    Ask,
+}
+
+/// hook の updatedInput によるコマンド書き換えルール
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RewriteRule {
+    /// 機密パスのバインドマウントを読み取り専用にする
+    ReadonlySensitiveMounts,
+    /// --security-opt no-new-privileges を付与する
+    NoNewPrivileges,
+    /// --cap-drop ALL を付与する
+    CapDropAll,
+    /// -p 8080:80 を -p 127.0.0.1:8080:80 にする
+    LoopbackPorts,
+    /// 機密情報を含む --build-arg を --secret に置き換える
+    BuildSecrets,
+}
+
+/// コマンド書き換え設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RewriteConfig {
+    /// 書き換えを有効にする
+    pub enabled: bool,
+    /// 適用するルール
+    pub rules: Vec<RewriteRule>,
+}
+
+impl Default for RewriteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: vec![
+                RewriteRule::ReadonlySensitiveMounts,
+                RewriteRule::NoNewPrivileges,
+                RewriteRule::CapDropAll,
+                RewriteRule::LoopbackPorts,
+                RewriteRule::BuildSecrets,
+            ],
+        }
+    }
+}
+
+impl RewriteConfig {
+    /// ルールが有効かどうか
+    pub fn has_rule(&self, rule: RewriteRule) -> bool {
+        self.enabled && self.rules.contains(&rule)
+    }
+}
+
+/// MCP ツール呼び出しを docker コマンドとして検査するための対応付け
+///
+/// 各フィールドはツール引数のキー (ドット区切りでネストしたキーを指定可能)。
+/// 対応付けにも `ignore_keys` にもない引数を含む呼び出しは検査できないため ask にする。
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct McpToolMapping {
+    /// MCP ツール名 (例: "mcp__docker__run_container")。末尾の `*` は前方一致
+    pub tool: String,
+    /// 対応する docker サブコマンド ("run" / "create")
+    pub subcommand: String,
+    /// イメージ名
+    pub image: Option<String>,
+    /// ボリューム ("SRC:DST[:ro]" の配列、{source, target, read_only} の配列、{SRC: DST} のマップ)
+    pub volumes: Option<String>,
+    /// --privileged (bool)
+    pub privileged: Option<String>,
+    /// ポート公開 ("8080:80" の配列、{"80/tcp": 8080} のマップ)
+    pub ports: Option<String>,
+    /// 環境変数 ("KEY=VALUE" の配列、{KEY: VALUE} のマップ)
+    pub env: Option<String>,
+    /// ネットワーク名
+    pub network: Option<String>,
+    /// 追加する capability (文字列または配列)
+    pub cap_add: Option<String>,
+    /// ネットワークモード ("host", "container:NAME" 等)
+    pub network_mode: Option<String>,
+    /// マウント ({type, source, target, read_only, propagation} の配列、または --mount の値)
+    pub mounts: Option<String>,
+    /// デバイス ("/dev/sda:/dev/xvda:rwm" の配列)
+    pub devices: Option<String>,
+    /// PID 名前空間 ("host", "container:NAME")
+    pub pid_mode: Option<String>,
+    /// セキュリティオプション (文字列または配列)
+    pub security_opt: Option<String>,
+    /// 安全性に影響しないため検査せずに受け付ける引数のキー
+    pub ignore_keys: Vec<String>,
+}
+
+impl Default for McpToolMapping {
+    fn default() -> Self {
+        Self {
+            tool: String::new(),
+            subcommand: "run".to_string(),
+            image: None,
+            volumes: None,
+            privileged: None,
+            ports: None,
+            env: None,
+            network: None,
+            cap_add: None,
+            network_mode: None,
+            mounts: None,
+            devices: None,
+            pid_mode: None,
+            security_opt: None,
+            ignore_keys: Vec::new(),
+        }
+    }
+}
+
+impl McpToolMapping {
+    /// docker SDK (docker-py) の containers.run と同じ引数名を使う MCP サーバー向けの対応付け
+    fn docker_py(tool: &str, subcommand: &str) -> Self {
+        Self {
+            tool: tool.to_string(),
+            subcommand: subcommand.to_string(),
+            image: Some("image".to_string()),
+            volumes: Some("volumes".to_string()),
+            privileged: Some("privileged".to_string()),
+            ports: Some("ports".to_string()),
+            env: Some("environment".to_string()),
+            network: Some("network".to_string()),
+            cap_add: Some("cap_add".to_string()),
+            network_mode: Some("network_mode".to_string()),
+            mounts: Some("mounts".to_string()),
+            devices: Some("devices".to_string()),
+            pid_mode: Some("pid_mode".to_string()),
+            security_opt: Some("security_opt".to_string()),
+            ignore_keys: [
+                "name",
+                "command",
+                "entrypoint",
+                "detach",
+                "remove",
+                "auto_remove",
+                "tty",
+                "stdin_open",
+                "working_dir",
+                "labels",
+            ]
+            .iter()
+            .map(|k| k.to_string())
+            .collect(),
+        }
+    }
+
+    /// 対応付けたキーと ignore_keys (検査の対象として扱う引数のキー)
+    pub fn known_keys(&self) -> impl Iterator<Item = &str> {
+        [
+            &self.image,
+            &self.volumes,
+            &self.privileged,
+            &self.ports,
+            &self.env,
+            &self.network,
+            &self.cap_add,
+            &self.network_mode,
+            &self.mounts,
+            &self.devices,
+            &self.pid_mode,
+            &self.security_opt,
+        ]
+        .into_iter()
+        .filter_map(|key| key.as_deref())
+        .chain(self.ignore_keys.iter().map(String::as_str))
+    }
+
+    /// ツール名が対応付けの対象か
+    pub fn matches(&self, tool_name: &str) -> bool {
+        match self.tool.strip_suffix('*') {
+            Some(prefix) => tool_name.starts_with(prefix),
+            None => tool_name == self.tool,
+        }
+    }
+}
+
+/// MCP ツールの対応付けのデフォルト
+fn default_mcp_tools() -> Vec<McpToolMapping> {
+    vec![
+        McpToolMapping::docker_py("mcp__docker__run_container", "run"),
+        McpToolMapping::docker_py("mcp__docker__create_container", "create"),
+    ]
+}
+
+/// Hook モード設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HookConfig {
+    /// safe-docker の設定・Claude Code の設定・docker ラッパーへの書き込みの扱い ("deny" / "ask")
+    pub self_protection: SelfProtection,
+    /// ポリシーを完全に通過した docker コマンドに対して明示的に allow を返す
+    pub auto_approve: bool,
+    /// auto_approve の対象サブコマンド (空=全サブコマンド)。"ps", "logs", "build", "compose-up" 等
+    pub auto_approve_subcommands: Vec<String>,
+    /// 判定結果に additionalContext (エージェント向けの説明) を付与する
+    pub additional_context: bool,
+    /// 判定結果に systemMessage (ユーザー向けの説明) を付与する
+    pub system_message: bool,
+    /// updatedInput による安全なコマンドへの書き換え
+    pub rewrite: RewriteConfig,
+    /// PostToolUse で記録するセッションごとの作成コンテナの保存先
+    pub session_dir: String,
+    /// docker を操作する MCP ツールの引数の対応付け
+    pub mcp_tools: Vec<McpToolMapping>,
+}
+
+impl Default for HookConfig {
+    fn default() -> Self {
+        Self {
+            self_protection: SelfProtection::default(),
+            auto_approve: false,
+            auto_approve_subcommands: Vec::new(),
+            additional_context: false,
+            system_message: false,
+            rewrite: RewriteConfig::default(),
+            session_dir: "~/.local/share/safe-docker/sessions".to_string(),
+            mcp_tools: default_mcp_tools(),
+        }
+    }
+}
+
 /// ラッパーモード設定
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
@@ -40,6 +271,8 @@ pub struct WrapperConfig {
     pub docker_path: String,
     /// 非対話環境での ask の扱い ("deny" / "allow")
     pub non_interactive_ask: NonInteractiveAsk,
+    /// バインドマウント元を解決済みのパスに書き換えてから docker を実行する
+    pub pin_mount_sources: bool,
 }
 
 impl Default for WrapperConfig {
@@ -47,10 +280,445 @@ impl Default for WrapperConfig {
         Self {
             docker_path: String::new(),
             non_interactive_ask: NonInteractiveAsk::Deny,
+            pin_mount_sources: false,
+        }
+    }
+}
+
+/// $HOME 配下でバインドマウントを許可する範囲の決め方
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkspaceMode {
+    /// $HOME 全体 (従来の動作)
+    #[default]
+    Home,
+    /// 作業ディレクトリ (Hook モードでは hook 入力の cwd)
+    Cwd,
+    /// 作業ディレクトリを含む git リポジトリのルート (リポジトリ外なら作業ディレクトリ)
+    GitRoot,
+}
+
+/// ワークスペースの指定 (モード名、または明示的なパスのリスト)
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum WorkspaceRoot {
+    Mode(WorkspaceMode),
+    Paths(Vec<String>),
+}
+
+impl Default for WorkspaceRoot {
+    fn default() -> Self {
+        Self::Mode(WorkspaceMode::Home)
+    }
+}
+
+/// ホストパスへのアクセスモード
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessMode {
+    /// 読み取りのみ (`:ro` マウント、docker cp のコピー元、build コンテキスト等)
+    Ro,
+    /// 書き込みを含む (`:ro` なしのマウント、docker cp のコピー先、build --output 等)
+    Rw,
+}
+
+impl std::fmt::Display for AccessMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ro => write!(f, "ro"),
+            Self::Rw => write!(f, "rw"),
+        }
+    }
+}
+
+/// allowed_paths / sensitive_paths の要素 (パス文字列、または `{ path, mode }`)
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum PathEntry {
+    Path(String),
+    WithMode {
+        path: String,
+        #[serde(default)]
+        mode: Option<AccessMode>,
+    },
+}
+
+impl PathEntry {
+    /// パス
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Path(path) | Self::WithMode { path, .. } => path,
+        }
+    }
+
+    /// 確認なしに許可するアクセスモード (未指定は None)
+    pub fn mode(&self) -> Option<AccessMode> {
+        match self {
+            Self::Path(_) => None,
+            Self::WithMode { mode, .. } => *mode,
+        }
+    }
+}
+
+impl From<&str> for PathEntry {
+    fn from(path: &str) -> Self {
+        Self::Path(path.to_string())
+    }
+}
+
+impl std::fmt::Display for PathEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.mode() {
+            Some(mode) => write!(f, "{} ({})", self.path(), mode),
+            None => write!(f, "{}", self.path()),
+        }
+    }
+}
+
+/// ポリシー拡張ルールが一致したときの判定
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyRuleDecision {
+    #[default]
+    Deny,
+    Ask,
+}
+
+/// ポリシー拡張ルール ([[policy.rules]] またはルールファイルの [[rules]])
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PolicyRule {
+    /// ルール名 (判定理由に表示する)
+    pub name: String,
+    /// 一致条件 (CEL 式。true を返すとルールが一致する)
+    pub when: String,
+    /// 一致したときの判定 ("deny" / "ask")
+    pub decision: PolicyRuleDecision,
+    /// 一致したときの理由 (空=ルール名から生成)
+    pub message: String,
+}
+
+/// ルールファイルの形式
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PolicyRuleFile {
+    rules: Vec<PolicyRule>,
+}
+
+/// ポリシー拡張設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PolicyConfig {
+    /// 設定ファイルに直接書くルール
+    pub rules: Vec<PolicyRule>,
+    /// ルールファイル ([[rules]] を含む TOML) のパス
+    pub files: Vec<String>,
+    /// ルールに渡す時刻 (time.hour 等) の UTC からのオフセット ("+09:00" 等)
+    pub utc_offset: String,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            files: Vec::new(),
+            utc_offset: "+00:00".to_string(),
+        }
+    }
+}
+
+impl PolicyConfig {
+    /// ルールが設定されているか
+    pub fn is_enabled(&self) -> bool {
+        !self.rules.is_empty() || !self.files.is_empty()
+    }
+
+    /// 設定ファイル内のルールとルールファイルのルールをまとめて返す
+    pub fn load_rules(&self) -> std::result::Result<Vec<PolicyRule>, String> {
+        let mut rules = self.rules.clone();
+        for file in &self.files {
+            let path = crate::path_validator::expand_env(file);
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| format!("cannot read policy file {}: {}", path, e))?;
+            let parsed: PolicyRuleFile = toml::from_str(&content)
+                .map_err(|e| format!("invalid policy file {}: {}", path, e))?;
+            rules.extend(parsed.rules);
+        }
+        Ok(rules)
+    }
+
+    /// utc_offset を分単位で返す ("+09:00" → 540)
+    pub fn utc_offset_minutes(&self) -> Option<i64> {
+        let (sign, rest) = match self.utc_offset.split_at_checked(1)? {
+            ("+", rest) => (1, rest),
+            ("-", rest) => (-1, rest),
+            _ => return None,
+        };
+        let (hours, minutes) = rest.split_once(':')?;
+        if hours.len() != 2 || minutes.len() != 2 {
+            return None;
+        }
+        let hours: i64 = hours.parse().ok()?;
+        let minutes: i64 = minutes.parse().ok()?;
+        if hours > 14 || minutes > 59 {
+            return None;
+        }
+        Some(sign * (hours * 60 + minutes))
+    }
+}
+
+/// 外部ポリシープラグイン ([[plugins]])
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PluginConfig {
+    /// プラグイン名 (判定理由に表示する)
+    pub name: String,
+    /// 実行するコマンド (シェルを介さず、空白区切りで引数に分割する)
+    pub command: String,
+    /// タイムアウト (ミリ秒)。超えた場合は deny
+    pub timeout_ms: u64,
+    /// 対象のサブコマンド (空=全サブコマンド)。"run", "compose-up" 等
+    pub subcommands: Vec<String>,
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            command: String::new(),
+            timeout_ms: 100,
+            subcommands: Vec::new(),
+        }
+    }
+}
+
+/// イメージのピン留め違反の扱い (Allow < Ask < Deny の順に厳しい)
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum PinningAction {
+    #[default]
+    Allow,
+    Ask,
+    Deny,
+}
+
+/// イメージのピン留めポリシー ([image_pinning])
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ImagePinningConfig {
+    /// latest タグ・タグなしのイメージの扱い ("allow" / "ask" / "deny")
+    pub mutable_tags: PinningAction,
+    /// @sha256 ダイジェストでの指定を必須にするレジストリ (`*.example.com` 等のパターン可)
+    pub require_digest: Vec<String>,
+    /// require_digest のレジストリでダイジェストがない場合の扱い
+    pub missing_digest: PinningAction,
+}
+
+impl Default for ImagePinningConfig {
+    fn default() -> Self {
+        Self {
+            mutable_tags: PinningAction::Allow,
+            require_digest: Vec::new(),
+            missing_digest: PinningAction::Ask,
+        }
+    }
+}
+
+impl ImagePinningConfig {
+    /// いずれかの検査が有効か
+    pub fn is_enabled(&self) -> bool {
+        self.mutable_tags != PinningAction::Allow
+            || (!self.require_digest.is_empty() && self.missing_digest != PinningAction::Allow)
+    }
+}
+
+/// Dockerfile の静的解析 ([dockerfile_lint])
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DockerfileLintConfig {
+    /// docker build の Dockerfile を解析する
+    pub enabled: bool,
+    /// 無効にするルール ID ("DF004" 等)
+    pub ignore_rules: Vec<String>,
+}
+
+impl Default for DockerfileLintConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ignore_rules: Vec::new(),
+        }
+    }
+}
+
+/// ビルドコンテキストの検査 ([build_context_scan])
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BuildContextScanConfig {
+    /// docker build のコンテキストを検査する
+    pub enabled: bool,
+    /// 機密情報とみなすパス (コンテキストからの相対。sensitive_paths と同じ書式で "!" の除外も可)
+    pub patterns: Vec<String>,
+    /// これを超えるファイルを確認する (バイト)
+    pub max_file_size: u64,
+    /// 走査するエントリ数の上限
+    pub max_files: usize,
+    /// 走査時間の上限 (ミリ秒)
+    pub timeout_ms: u64,
+    /// 上限に達して走査しきれなかった場合の扱い
+    pub incomplete: PinningAction,
+}
+
+impl Default for BuildContextScanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            patterns: default_build_context_patterns(),
+            max_file_size: 100 * 1024 * 1024,
+            max_files: 10_000,
+            timeout_ms: 500,
+            incomplete: PinningAction::Allow,
+        }
+    }
+}
+
+fn default_build_context_patterns() -> Vec<String> {
+    [
+        "**/.env",
+        "**/.env.*",
+        "!**/.env.example",
+        "!**/.env.sample",
+        "!**/.env.template",
+        "!**/.env.dist",
+        "**/*.pem",
+        "**/*.key",
+        "**/*.p12",
+        "**/*.pfx",
+        "**/id_rsa",
+        "**/id_dsa",
+        "**/id_ecdsa",
+        "**/id_ed25519",
+        "**/.ssh",
+        "**/.aws",
+        "**/.gnupg",
+        "**/.docker/config.json",
+        "**/.kube/config",
+        "**/.npmrc",
+        "**/.pypirc",
+        "**/.netrc",
+        "**/.git-credentials",
+        "**/.vault-token",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// build_context_scan.timeout_ms の上限 (ミリ秒)
+pub const MAX_BUILD_CONTEXT_SCAN_TIMEOUT_MS: u64 = 5000;
+
+/// 公開ポートのポリシー ([published_ports])
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PublishedPortsConfig {
+    /// loopback 以外 (アドレス省略時の 0.0.0.0 / [::] を含む) に公開する場合の扱い
+    pub non_loopback: PinningAction,
+    /// 1024 未満のホストポートを公開する場合の扱い
+    pub privileged: PinningAction,
+    /// 公開してよいホストポート ("8080" / "8000-8999"。空=制限なし)
+    pub allowed_host_ports: Vec<String>,
+    /// allowed_host_ports の範囲外 (Docker が選ぶエフェメラルポートを含む) の場合の扱い
+    pub outside_allowed: PinningAction,
+}
+
+impl Default for PublishedPortsConfig {
+    fn default() -> Self {
+        Self {
+            non_loopback: PinningAction::Allow,
+            privileged: PinningAction::Allow,
+            allowed_host_ports: Vec::new(),
+            outside_allowed: PinningAction::Ask,
         }
     }
 }
 
+impl PublishedPortsConfig {
+    /// いずれかの検査が有効か
+    pub fn is_enabled(&self) -> bool {
+        self.non_loopback != PinningAction::Allow
+            || self.privileged != PinningAction::Allow
+            || (!self.allowed_host_ports.is_empty() && self.outside_allowed != PinningAction::Allow)
+    }
+}
+
+/// ネットワークのポリシー ([network_policy])
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NetworkPolicyConfig {
+    /// コンテナをホストの物理ネットワークに直接つなぐドライバ
+    pub bridging_drivers: Vec<String>,
+    /// bridging_drivers のネットワークの作成・接続の扱い
+    pub bridging: PinningAction,
+    /// ネットワークから分離して実行するイメージ (allowed_images と同じパターン)
+    pub isolated_images: Vec<String>,
+    /// isolated_images のコンテナが --network none・--internal 以外のネットワークにつながる場合の扱い
+    pub not_isolated: PinningAction,
+}
+
+impl Default for NetworkPolicyConfig {
+    fn default() -> Self {
+        Self {
+            bridging_drivers: vec!["macvlan".to_string(), "ipvlan".to_string()],
+            bridging: PinningAction::Ask,
+            isolated_images: Vec::new(),
+            not_isolated: PinningAction::Ask,
+        }
+    }
+}
+
+impl NetworkPolicyConfig {
+    /// いずれかの検査が有効か
+    pub fn is_enabled(&self) -> bool {
+        (!self.bridging_drivers.is_empty() && self.bridging != PinningAction::Allow)
+            || (!self.isolated_images.is_empty() && self.not_isolated != PinningAction::Allow)
+    }
+}
+
+/// イメージ署名の検証 ([image_signatures])
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ImageSignaturesConfig {
+    /// 署名の検証に使う公開鍵のファイル (PEM, ECDSA P-256)
+    pub keys: Vec<String>,
+    /// 署名・アテステーションを保存した OCI イメージレイアウトのディレクトリ (`cosign save` の出力等)
+    pub layouts: Vec<String>,
+    /// 署名がなくても許可するレジストリ (`*.example.com` 等のパターン可)
+    pub exempt_registries: Vec<String>,
+    /// 署名を検証できないイメージの扱い
+    pub unsigned: PinningAction,
+}
+
+impl Default for ImageSignaturesConfig {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            layouts: Vec::new(),
+            exempt_registries: Vec::new(),
+            unsigned: PinningAction::Ask,
+        }
+    }
+}
+
+impl ImageSignaturesConfig {
+    /// 署名の検証が有効か
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty() && self.unsigned != PinningAction::Allow
+    }
+}
+
+/// プラグインのタイムアウトの上限 (ミリ秒)
+pub const MAX_PLUGIN_TIMEOUT_MS: u64 = 5000;
+
 /// 監査ログ設定
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
@@ -94,21 +762,24 @@ pub fn config_path() -> PathBuf {
 }
 
 /// $HOME 配下で ask にするデフォルトの機密パス (相対)
-fn default_sensitive_paths() -> Vec<String> {
-    vec![
-        ".ssh".to_string(),
-        ".aws".to_string(),
-        ".gnupg".to_string(),
-        ".docker".to_string(),
-        ".kube".to_string(),
-        ".config/gcloud".to_string(),
-        ".claude".to_string(),
-        ".terraform".to_string(),
-        ".vault-token".to_string(),
-        ".config/gh".to_string(),
-        ".npmrc".to_string(),
-        ".pypirc".to_string(),
+fn default_sensitive_paths() -> Vec<PathEntry> {
+    [
+        ".ssh",
+        ".aws",
+        ".gnupg",
+        ".docker",
+        ".kube",
+        ".config/gcloud",
+        ".claude",
+        ".terraform",
+        ".vault-token",
+        ".config/gh",
+        ".npmrc",
+        ".pypirc",
     ]
+    .into_iter()
+    .map(PathEntry::from)
+    .collect()
 }
 
 /// デフォルトでブロックする危険フラグ
@@ -139,11 +810,14 @@ fn default_blocked_capabilities() -> Vec<String> {
 #[derive(Debug, Deserialize, Clone)]
 #[serde(default)]
 pub struct Config {
-    /// $HOME 外で追加許可するパス
-    pub allowed_paths: Vec<String>,
+    /// $HOME 外で追加許可するパス (mode = "ro" は読み取り専用のアクセスのみ許可)
+    pub allowed_paths: Vec<PathEntry>,
+
+    /// $HOME 配下で ask にする機密パス (ホームからの相対。mode = "ro" は読み取り専用なら許可)
+    pub sensitive_paths: Vec<PathEntry>,
 
-    /// $HOME 配下で ask にする機密パス (ホームからの相対)
-    pub sensitive_paths: Vec<String>,
+    /// $HOME 配下でも拒否するパス (allowed_paths より優先)
+    pub denied_paths: Vec<String>,
 
     /// 追加ブロックフラグ
     pub blocked_flags: Vec<String>,
@@ -151,12 +825,25 @@ pub struct Config {
     /// ブロックする capability
     pub blocked_capabilities: Vec<String>,
 
-    /// イメージホワイトリスト (空=制限なし)
+    /// イメージホワイトリスト (空=制限なし)。`ghcr.io/ourorg/*` や `node:20-*` のパターンも書ける
     pub allowed_images: Vec<String>,
 
+    /// 拒否するイメージ (allowed_images より優先)
+    pub denied_images: Vec<String>,
+
+    /// イメージを取得してよいレジストリ (空=制限なし)
+    pub allowed_registries: Vec<String>,
+
     /// Docker ソケットマウントの禁止
     pub block_docker_socket: bool,
 
+    /// $HOME 配下で確認なしにマウントできる範囲 ("home" / "cwd" / "git-root" / パスのリスト)
+    pub workspace_root: WorkspaceRoot,
+
+    /// 存在しないバインドマウント元を確認にする (判定後にシンボリックリンクへ差し替えられるのを防ぐ)。
+    /// デフォルト true
+    pub ask_missing_mount_sources: bool,
+
     /// 監査ログ設定
     #[serde(default)]
     pub audit: AuditConfig,
@@ -164,6 +851,42 @@ pub struct Config {
     /// ラッパーモード設定
     #[serde(default)]
     pub wrapper: WrapperConfig,
+
+    /// Hook モード設定
+    #[serde(default)]
+    pub hook: HookConfig,
+
+    /// ポリシー拡張 (組み込みポリシーの後に評価するルール)
+    #[serde(default)]
+    pub policy: PolicyConfig,
+
+    /// 外部ポリシープラグイン
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+
+    /// イメージのピン留めポリシー
+    #[serde(default)]
+    pub image_pinning: ImagePinningConfig,
+
+    /// イメージ署名の検証
+    #[serde(default)]
+    pub image_signatures: ImageSignaturesConfig,
+
+    /// Dockerfile の静的解析
+    #[serde(default)]
+    pub dockerfile_lint: DockerfileLintConfig,
+
+    /// ビルドコンテキストの検査
+    #[serde(default)]
+    pub build_context_scan: BuildContextScanConfig,
+
+    /// 公開ポートのポリシー
+    #[serde(default)]
+    pub published_ports: PublishedPortsConfig,
+
+    /// ネットワークのポリシー
+    #[serde(default)]
+    pub network_policy: NetworkPolicyConfig,
 }
 
 impl Default for Config {
@@ -171,12 +894,26 @@ impl Default for Config {
         Self {
             allowed_paths: Vec::new(),
             sensitive_paths: default_sensitive_paths(),
+            denied_paths: Vec::new(),
             blocked_flags: default_blocked_flags(),
             blocked_capabilities: default_blocked_capabilities(),
             allowed_images: Vec::new(),
+            denied_images: Vec::new(),
+            allowed_registries: Vec::new(),
             block_docker_socket: true,
+            workspace_root: WorkspaceRoot::default(),
+            ask_missing_mount_sources: true,
             audit: AuditConfig::default(),
             wrapper: WrapperConfig::default(),
+            hook: HookConfig::default(),
+            policy: PolicyConfig::default(),
+            plugins: Vec::new(),
+            image_pinning: ImagePinningConfig::default(),
+            image_signatures: ImageSignaturesConfig::default(),
+            dockerfile_lint: DockerfileLintConfig::default(),
+            build_context_scan: BuildContextScanConfig::default(),
+            published_ports: PublishedPortsConfig::default(),
+            network_policy: NetworkPolicyConfig::default(),
         }
     }
 }
@@ -201,28 +938,36 @@ impl Config {
         Ok(config)
     }
 
-    /// パスが allowed_paths に含まれるか判定
-    pub fn is_path_allowed(&self, canonical_path: &str) -> bool {
-        self.allowed_paths.iter().any(|allowed| {
-            // canonicalize 後のパスでマッチング（シンボリックリンク解決済み）
-            let allowed_canonical = std::fs::canonicalize(allowed)
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|_| allowed.clone());
-            if canonical_path.starts_with(&allowed_canonical) {
-                return true;
-            }
-            // macOS では /tmp → /private/tmp 等のシンボリックリンクがあるため、
-            // 入力パスが logical_normalize 由来（canonicalize 失敗）の場合に備え、
-            // 元の allowed_paths 文字列でもフォールバックマッチングを行う
-            canonical_path.starts_with(allowed.as_str())
-        })
+    /// パスが allowed_paths に含まれ、access のアクセスが許可されるか判定
+    pub fn is_path_allowed(&self, canonical_path: &str, access: AccessMode) -> bool {
+        match last_match(&self.allowed_paths, |p| {
+            matches_host_pattern(p, canonical_path)
+        }) {
+            Some(entry) => !(entry.mode() == Some(AccessMode::Ro) && access == AccessMode::Rw),
+            None => false,
+        }
     }
 
-    /// パスが sensitive_paths に含まれるか判定
-    pub fn is_path_sensitive(&self, path_relative_to_home: &str) -> bool {
-        self.sensitive_paths
-            .iter()
-            .any(|sensitive| path_relative_to_home.starts_with(sensitive))
+    /// パスが sensitive_paths に含まれ、access のアクセスに確認が必要か判定
+    pub fn is_path_sensitive(&self, path_relative_to_home: &str, access: AccessMode) -> bool {
+        match last_match(&self.sensitive_paths, |p| {
+            path_pattern::matches(p, path_relative_to_home)
+        }) {
+            Some(entry) => !(entry.mode() == Some(AccessMode::Ro) && access == AccessMode::Ro),
+            None => false,
+        }
+    }
+
+    /// パスが denied_paths に含まれる場合、一致したパターンを返す
+    pub fn denied_path_match(&self, path: &str) -> Option<&str> {
+        let mut matched = None;
+        for raw in &self.denied_paths {
+            let (negated, pattern) = path_pattern::split_negation(raw);
+            if matches_host_pattern(pattern, path) {
+                matched = (!negated).then_some(raw.as_str());
+            }
+        }
+        matched
     }
 
     /// フラグがブロック対象か判定
@@ -245,7 +990,8 @@ impl Config {
         let mut issues = Vec::new();
 
         // allowed_paths: 絶対パスであること
-        for (i, path) in self.allowed_paths.iter().enumerate() {
+        for (i, entry) in self.allowed_paths.iter().enumerate() {
+            let (_, path) = path_pattern::split_negation(entry.path());
             if path.is_empty() {
                 issues.push(ConfigIssue::Error(format!(
                     "allowed_paths[{}]: empty string",
@@ -260,8 +1006,11 @@ impl Config {
         }
 
         // allowed_paths: 存在しないパスの警告
-        for path in &self.allowed_paths {
-            if !path.is_empty() && path.starts_with('/') && !PathBuf::from(path).exists() {
+        for path in self.allowed_paths.iter().map(PathEntry::path) {
+            if path.starts_with('/')
+                && !path_pattern::is_glob(path)
+                && !PathBuf::from(path).exists()
+            {
                 issues.push(ConfigIssue::Warning(format!(
                     "allowed_paths: '{}' does not exist",
                     path
@@ -270,10 +1019,21 @@ impl Config {
         }
 
         // allowed_paths: 重複チェック
-        check_duplicates(&self.allowed_paths, "allowed_paths", &mut issues);
+        let allowed: Vec<String> = self
+            .allowed_paths
+            .iter()
+            .map(|p| p.path().to_string())
+            .collect();
+        check_duplicates(&allowed, "allowed_paths", &mut issues);
+        check_patterns(
+            self.allowed_paths.iter().map(PathEntry::path),
+            "allowed_paths",
+            &mut issues,
+        );
 
         // sensitive_paths: 相対パスであること
-        for (i, path) in self.sensitive_paths.iter().enumerate() {
+        for (i, entry) in self.sensitive_paths.iter().enumerate() {
+            let (_, path) = path_pattern::split_negation(entry.path());
             if path.is_empty() {
                 issues.push(ConfigIssue::Error(format!(
                     "sensitive_paths[{}]: empty string",
@@ -285,10 +1045,53 @@ impl Config {
                     i, path
                 )));
             }
+            if entry.mode() == Some(AccessMode::Rw) {
+                issues.push(ConfigIssue::Warning(format!(
+                    "sensitive_paths[{}]: mode \"rw\" has no effect (only \"ro\" allows access without confirmation)",
+                    i
+                )));
+            }
         }
 
         // sensitive_paths: 重複チェック
-        check_duplicates(&self.sensitive_paths, "sensitive_paths", &mut issues);
+        let sensitive: Vec<String> = self
+            .sensitive_paths
+            .iter()
+            .map(|p| p.path().to_string())
+            .collect();
+        check_duplicates(&sensitive, "sensitive_paths", &mut issues);
+        check_patterns(
+            self.sensitive_paths.iter().map(PathEntry::path),
+            "sensitive_paths",
+            &mut issues,
+        );
+
+        // denied_paths: 絶対パス ($HOME / ~ を含む) または ** で始まるパターンであること
+        for (i, raw) in self.denied_paths.iter().enumerate() {
+            let (_, path) = path_pattern::split_negation(raw);
+            if path.is_empty() {
+                issues.push(ConfigIssue::Error(format!(
+                    "denied_paths[{}]: empty string",
+                    i
+                )));
+            } else if !path.starts_with('/')
+                && !path.starts_with('~')
+                && !path.starts_with("$HOME")
+                && !path.starts_with("${HOME}")
+                && !path.starts_with("**")
+            {
+                issues.push(ConfigIssue::Error(format!(
+                    "denied_paths[{}]: '{}' must be an absolute path or start with '~' or '**'",
+                    i, raw
+                )));
+            }
+        }
+        check_duplicates(&self.denied_paths, "denied_paths", &mut issues);
+        check_patterns(
+            self.denied_paths.iter().map(String::as_str),
+            "denied_paths",
+            &mut issues,
+        );
 
         // blocked_flags: -- で始まること
         for (i, flag) in self.blocked_flags.iter().enumerate() {
@@ -317,38 +1120,315 @@ impl Config {
                 )));
             } else if !is_valid_capability_name(cap) {
                 issues.push(ConfigIssue::Error(format!(
-                    "blocked_capabilities[{}]: '{}' is not a valid Linux capability name (expected uppercase like SYS_ADMIN, NET_RAW, ALL)",
-                    i, cap
+                    "blocked_capabilities[{}]: '{}' is not a valid Linux capability name (expected uppercase like SYS_ADMIN, NET_RAW, ALL)",
+                    i, cap
+                )));
+            }
+        }
+
+        // blocked_capabilities: 重複チェック (大文字小文字無視)
+        {
+            let mut seen = HashSet::new();
+            for cap in &self.blocked_capabilities {
+                let upper = cap.to_uppercase();
+                if !seen.insert(upper) {
+                    issues.push(ConfigIssue::Warning(format!(
+                        "blocked_capabilities: '{}' is duplicated",
+                        cap
+                    )));
+                }
+            }
+        }
+
+        // allowed_images / denied_images / network_policy.isolated_images:
+        // 空文字列でなく、イメージのパターンとして解析できること
+        for (field, images) in [
+            ("allowed_images", &self.allowed_images),
+            ("denied_images", &self.denied_images),
+            (
+                "network_policy.isolated_images",
+                &self.network_policy.isolated_images,
+            ),
+        ] {
+            for (i, image) in images.iter().enumerate() {
+                if image.is_empty() {
+                    issues.push(ConfigIssue::Error(format!(
+                        "{}[{}]: empty string",
+                        field, i
+                    )));
+                } else if let Err(e) = ImagePattern::parse(image) {
+                    issues.push(ConfigIssue::Error(format!(
+                        "{}[{}]: invalid image pattern '{}': {}",
+                        field, i, image, e
+                    )));
+                }
+            }
+            check_duplicates(images, field, &mut issues);
+        }
+
+        // allowed_registries: レジストリのホスト名 (パスを含まない)
+        for (i, registry) in self.allowed_registries.iter().enumerate() {
+            if registry.is_empty() {
+                issues.push(ConfigIssue::Error(format!(
+                    "allowed_registries[{}]: empty string",
+                    i
+                )));
+            } else if registry.contains('/') {
+                issues.push(ConfigIssue::Error(format!(
+                    "allowed_registries[{}]: '{}' must be a registry host (e.g. ghcr.io), not a repository",
+                    i, registry
+                )));
+            }
+        }
+        check_duplicates(&self.allowed_registries, "allowed_registries", &mut issues);
+
+        // hook.auto_approve_subcommands: 空文字列でないこと
+        for (i, sub) in self.hook.auto_approve_subcommands.iter().enumerate() {
+            if sub.is_empty() {
+                issues.push(ConfigIssue::Error(format!(
+                    "hook.auto_approve_subcommands[{}]: empty string",
+                    i
+                )));
+            }
+        }
+        check_duplicates(
+            &self.hook.auto_approve_subcommands,
+            "hook.auto_approve_subcommands",
+            &mut issues,
+        );
+        if !self.hook.auto_approve && !self.hook.auto_approve_subcommands.is_empty() {
+            issues.push(ConfigIssue::Warning(
+                "hook.auto_approve_subcommands is set but hook.auto_approve is false (no effect)"
+                    .to_string(),
+            ));
+        }
+
+        // hook.mcp_tools: ツール名とサブコマンド
+        for (i, mapping) in self.hook.mcp_tools.iter().enumerate() {
+            if mapping.tool.is_empty() {
+                issues.push(ConfigIssue::Error(format!(
+                    "hook.mcp_tools[{}].tool: empty string",
+                    i
+                )));
+            }
+            if !matches!(mapping.subcommand.as_str(), "run" | "create") {
+                issues.push(ConfigIssue::Error(format!(
+                    "hook.mcp_tools[{}].subcommand: '{}' is not supported (expected 'run' or 'create')",
+                    i, mapping.subcommand
+                )));
+            }
+        }
+        let mcp_tool_names: Vec<String> =
+            self.hook.mcp_tools.iter().map(|m| m.tool.clone()).collect();
+        check_duplicates(&mcp_tool_names, "hook.mcp_tools", &mut issues);
+
+        // workspace_root: 明示的なパスは絶対パスであること
+        if let WorkspaceRoot::Paths(paths) = &self.workspace_root {
+            if paths.is_empty() {
+                issues.push(ConfigIssue::Error(
+                    "workspace_root: empty list (use \"home\" to allow all of $HOME)".to_string(),
+                ));
+            }
+            for (i, path) in paths.iter().enumerate() {
+                if !path.starts_with('/') && !path.starts_with('~') {
+                    issues.push(ConfigIssue::Error(format!(
+                        "workspace_root[{}]: '{}' is not an absolute path (must start with '/' or '~')",
+                        i, path
+                    )));
+                } else if !PathBuf::from(crate::path_validator::expand_env(path)).exists() {
+                    issues.push(ConfigIssue::Warning(format!(
+                        "workspace_root[{}]: '{}' does not exist",
+                        i, path
+                    )));
+                }
+            }
+        }
+
+        // policy: ルールの式とルールファイル
+        if self.policy.utc_offset_minutes().is_none() {
+            issues.push(ConfigIssue::Error(format!(
+                "policy.utc_offset: '{}' is invalid (expected '+HH:MM' or '-HH:MM')",
+                self.policy.utc_offset
+            )));
+        }
+        match self.policy.load_rules() {
+            Ok(rules) => {
+                for (i, rule) in rules.iter().enumerate() {
+                    if rule.name.is_empty() {
+                        issues.push(ConfigIssue::Error(format!(
+                            "policy.rules[{}].name: empty string",
+                            i
+                        )));
+                    }
+                    if let Err(e) = crate::cel::parse(&rule.when) {
+                        issues.push(ConfigIssue::Error(format!(
+                            "policy.rules[{}].when: {} (rule '{}')",
+                            i, e, rule.name
+                        )));
+                    }
+                }
+                let names: Vec<String> = rules.iter().map(|r| r.name.clone()).collect();
+                check_duplicates(&names, "policy.rules", &mut issues);
+            }
+            Err(e) => issues.push(ConfigIssue::Error(format!("policy.files: {}", e))),
+        }
+
+        // plugins: コマンドとタイムアウト
+        for (i, plugin) in self.plugins.iter().enumerate() {
+            if plugin.name.is_empty() {
+                issues.push(ConfigIssue::Error(format!(
+                    "plugins[{}].name: empty string",
+                    i
+                )));
+            }
+            match shell_words::split(&plugin.command) {
+                Ok(argv) if argv.is_empty() => issues.push(ConfigIssue::Error(format!(
+                    "plugins[{}].command: empty string",
+                    i
+                ))),
+                Ok(argv) => {
+                    if argv[0].starts_with('/') && !PathBuf::from(&argv[0]).exists() {
+                        issues.push(ConfigIssue::Warning(format!(
+                            "plugins[{}].command: '{}' does not exist (every docker command will be denied)",
+                            i, argv[0]
+                        )));
+                    }
+                }
+                Err(e) => issues.push(ConfigIssue::Error(format!("plugins[{}].command: {}", i, e))),
+            }
+            if plugin.timeout_ms == 0 || plugin.timeout_ms > MAX_PLUGIN_TIMEOUT_MS {
+                issues.push(ConfigIssue::Error(format!(
+                    "plugins[{}].timeout_ms: {} is out of range (1-{})",
+                    i, plugin.timeout_ms, MAX_PLUGIN_TIMEOUT_MS
+                )));
+            }
+        }
+        let plugin_names: Vec<String> = self.plugins.iter().map(|p| p.name.clone()).collect();
+        check_duplicates(&plugin_names, "plugins", &mut issues);
+
+        // image_pinning.require_digest: レジストリのホスト名 (パスを含まない)
+        for (i, registry) in self.image_pinning.require_digest.iter().enumerate() {
+            if registry.is_empty() {
+                issues.push(ConfigIssue::Error(format!(
+                    "image_pinning.require_digest[{}]: empty string",
+                    i
+                )));
+            } else if registry.contains('/') {
+                issues.push(ConfigIssue::Error(format!(
+                    "image_pinning.require_digest[{}]: '{}' must be a registry host (e.g. ghcr.io), not a repository",
+                    i, registry
+                )));
+            }
+        }
+        check_duplicates(
+            &self.image_pinning.require_digest,
+            "image_pinning.require_digest",
+            &mut issues,
+        );
+        if !self.image_pinning.require_digest.is_empty()
+            && self.image_pinning.missing_digest == PinningAction::Allow
+        {
+            issues.push(ConfigIssue::Warning(
+                "image_pinning.require_digest has no effect because missing_digest is \"allow\""
+                    .to_string(),
+            ));
+        }
+
+        // image_signatures: 公開鍵が読めること
+        let signatures = &self.image_signatures;
+        if signatures.keys.is_empty() {
+            if !signatures.layouts.is_empty() || !signatures.exempt_registries.is_empty() {
+                issues.push(ConfigIssue::Warning(
+                    "image_signatures has no effect because image_signatures.keys is empty"
+                        .to_string(),
+                ));
+            }
+        } else if signatures.unsigned == PinningAction::Allow {
+            issues.push(ConfigIssue::Warning(
+                "image_signatures.keys has no effect because unsigned is \"allow\"".to_string(),
+            ));
+        } else if !cfg!(feature = "cosign") {
+            issues.push(ConfigIssue::Warning(
+                "image_signatures: safe-docker was built without the 'cosign' feature, so every image is treated as unsigned"
+                    .to_string(),
+            ));
+        } else if let Err(e) = crate::image_signature::check_keys(&signatures.keys) {
+            issues.push(ConfigIssue::Error(e));
+        }
+        for (i, dir) in signatures.layouts.iter().enumerate() {
+            if !PathBuf::from(crate::path_validator::expand_env(dir))
+                .join("index.json")
+                .is_file()
+            {
+                issues.push(ConfigIssue::Warning(format!(
+                    "image_signatures.layouts[{}]: '{}' is not an OCI image layout (index.json not found)",
+                    i, dir
+                )));
+            }
+        }
+
+        // dockerfile_lint.ignore_rules: 既知のルール ID
+        for (i, rule) in self.dockerfile_lint.ignore_rules.iter().enumerate() {
+            if !crate::dockerfile_lint::RULES
+                .iter()
+                .any(|(id, _, _)| id == rule)
+            {
+                issues.push(ConfigIssue::Error(format!(
+                    "dockerfile_lint.ignore_rules[{}]: unknown rule '{}'",
+                    i, rule
                 )));
             }
         }
 
-        // blocked_capabilities: 重複チェック (大文字小文字無視)
-        {
-            let mut seen = HashSet::new();
-            for cap in &self.blocked_capabilities {
-                let upper = cap.to_uppercase();
-                if !seen.insert(upper) {
-                    issues.push(ConfigIssue::Warning(format!(
-                        "blocked_capabilities: '{}' is duplicated",
-                        cap
-                    )));
-                }
+        // build_context_scan: パターンと上限
+        for (i, pattern) in self.build_context_scan.patterns.iter().enumerate() {
+            if let Err(e) = path_pattern::validate(pattern) {
+                issues.push(ConfigIssue::Error(format!(
+                    "build_context_scan.patterns[{}]: {} ('{}')",
+                    i, e, pattern
+                )));
             }
         }
+        if self.build_context_scan.max_files == 0 {
+            issues.push(ConfigIssue::Error(
+                "build_context_scan.max_files: must be greater than 0".to_string(),
+            ));
+        }
+        if self.build_context_scan.timeout_ms == 0
+            || self.build_context_scan.timeout_ms > MAX_BUILD_CONTEXT_SCAN_TIMEOUT_MS
+        {
+            issues.push(ConfigIssue::Error(format!(
+                "build_context_scan.timeout_ms: {} is out of range (1-{})",
+                self.build_context_scan.timeout_ms, MAX_BUILD_CONTEXT_SCAN_TIMEOUT_MS
+            )));
+        }
 
-        // allowed_images: 空文字列でないこと
-        for (i, image) in self.allowed_images.iter().enumerate() {
-            if image.is_empty() {
+        // published_ports.allowed_host_ports: ポート番号または範囲
+        for (i, range) in self.published_ports.allowed_host_ports.iter().enumerate() {
+            if let Err(e) = crate::published_ports::parse_port_range(range) {
                 issues.push(ConfigIssue::Error(format!(
-                    "allowed_images[{}]: empty string",
-                    i
+                    "published_ports.allowed_host_ports[{}]: {}",
+                    i, e
                 )));
             }
         }
+        if !self.published_ports.allowed_host_ports.is_empty()
+            && self.published_ports.outside_allowed == PinningAction::Allow
+        {
+            issues.push(ConfigIssue::Warning(
+                "published_ports.allowed_host_ports has no effect because outside_allowed is \"allow\""
+                    .to_string(),
+            ));
+        }
 
-        // allowed_images: 重複チェック
-        check_duplicates(&self.allowed_images, "allowed_images", &mut issues);
+        if !self.network_policy.isolated_images.is_empty()
+            && self.network_policy.not_isolated == PinningAction::Allow
+        {
+            issues.push(ConfigIssue::Warning(
+                "network_policy.isolated_images has no effect because not_isolated is \"allow\""
+                    .to_string(),
+            ));
+        }
 
         // audit パスの検証
         if self.audit.enabled {
@@ -385,6 +1465,55 @@ fn is_valid_capability_name(name: &str) -> bool {
         && upper.starts_with(|c: char| c.is_ascii_uppercase())
 }
 
+/// 一覧の後ろから path に一致する要素を探す。否定パターン (`!`) が一致した場合は None
+fn last_match(entries: &[PathEntry], matches: impl Fn(&str) -> bool) -> Option<&PathEntry> {
+    entries.iter().rev().find_map(|entry| {
+        let (negated, pattern) = path_pattern::split_negation(entry.path());
+        matches(pattern).then_some((!negated).then_some(entry))
+    })?
+}
+
+/// ホストの絶対パスのパターン (`~` / `$HOME` 展開) が path に一致するか
+fn matches_host_pattern(pattern: &str, path: &str) -> bool {
+    let pattern = crate::path_validator::expand_env(pattern);
+    if !path_pattern::is_glob(&pattern) {
+        // canonicalize 後のパスでマッチング（シンボリックリンク解決済み）
+        if let Ok(canonical) = std::fs::canonicalize(&pattern)
+            && path_pattern::matches(&canonical.to_string_lossy(), path)
+        {
+            return true;
+        }
+    }
+    // macOS では /tmp → /private/tmp 等のシンボリックリンクがあるため、
+    // 入力パスが logical_normalize 由来（canonicalize 失敗）の場合に備え、
+    // 元のパターンでもフォールバックマッチングを行う
+    path_pattern::matches(&pattern, path)
+}
+
+/// パスパターンの構文チェック
+fn check_patterns<'a>(
+    patterns: impl Iterator<Item = &'a str>,
+    field_name: &str,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    for (i, pattern) in patterns.enumerate() {
+        if pattern.is_empty() {
+            continue;
+        }
+        if let Err(e) = path_pattern::validate(pattern) {
+            issues.push(ConfigIssue::Error(format!(
+                "{}[{}]: invalid pattern '{}': {}",
+                field_name, i, pattern, e
+            )));
+        } else if i == 0 && path_pattern::split_negation(pattern).0 {
+            issues.push(ConfigIssue::Warning(format!(
+                "{}[0]: negated pattern '{}' has no effect (negation only excludes paths matched by earlier entries)",
+                field_name, pattern
+            )));
+        }
+    }
+}
+
 /// 重複チェック
 fn check_duplicates(items: &[String], field_name: &str, issues: &mut Vec<ConfigIssue>) {
     let mut seen = HashSet::new();
@@ -405,7 +1534,7 @@ mod tests {
     #[test]
     fn test_default_config() {
         let config = Config::default();
-        assert!(config.sensitive_paths.contains(&".ssh".to_string()));
+        assert!(config.sensitive_paths.contains(&".ssh".into()));
         assert!(config.blocked_flags.contains(&"--privileged".to_string()));
         assert!(config.allowed_paths.is_empty());
         assert!(config.block_docker_socket);
@@ -436,10 +1565,10 @@ mod tests {
     #[test]
     fn test_is_path_sensitive() {
         let config = Config::default();
-        assert!(config.is_path_sensitive(".ssh"));
-        assert!(config.is_path_sensitive(".ssh/id_rsa"));
-        assert!(config.is_path_sensitive(".aws/credentials"));
-        assert!(!config.is_path_sensitive("projects/myapp"));
+        assert!(config.is_path_sensitive(".ssh", AccessMode::Rw));
+        assert!(config.is_path_sensitive(".ssh/id_rsa", AccessMode::Rw));
+        assert!(config.is_path_sensitive(".aws/credentials", AccessMode::Rw));
+        assert!(!config.is_path_sensitive("projects/myapp", AccessMode::Rw));
     }
 
     #[test]
@@ -459,7 +1588,10 @@ mod tests {
             block_docker_socket = true
         "#;
         let config: Config = toml::from_str(toml_str).unwrap();
-        assert_eq!(config.allowed_paths, vec!["/tmp/docker-data"]);
+        assert_eq!(
+            config.allowed_paths,
+            vec![PathEntry::from("/tmp/docker-data")]
+        );
         assert_eq!(config.allowed_images, vec!["ubuntu", "alpine"]);
     }
 
@@ -478,7 +1610,7 @@ mod tests {
             allowed_paths = ["/opt/data"]
         "#;
         let config: Config = toml::from_str(toml_str).unwrap();
-        assert_eq!(config.allowed_paths, vec!["/opt/data"]);
+        assert_eq!(config.allowed_paths, vec![PathEntry::from("/opt/data")]);
         // 未指定キーはデフォルト
         assert_eq!(config.sensitive_paths, default_sensitive_paths());
         assert_eq!(config.blocked_flags, default_blocked_flags());
@@ -500,20 +1632,20 @@ mod tests {
     fn test_is_path_allowed_canonicalize() {
         // allowed_paths に /tmp を追加
         let mut config = Config::default();
-        config.allowed_paths = vec!["/tmp".to_string()];
+        config.allowed_paths = vec!["/tmp".into()];
         // /tmp 配下のパスは許可される
-        assert!(config.is_path_allowed("/tmp/docker-data"));
-        assert!(config.is_path_allowed("/tmp"));
+        assert!(config.is_path_allowed("/tmp/docker-data", AccessMode::Rw));
+        assert!(config.is_path_allowed("/tmp", AccessMode::Rw));
         // /tmp 外は不許可
-        assert!(!config.is_path_allowed("/etc/passwd"));
+        assert!(!config.is_path_allowed("/etc/passwd", AccessMode::Rw));
     }
 
     #[test]
     fn test_is_path_allowed_nonexistent() {
         // 存在しないパスの場合は文字列比較
         let mut config = Config::default();
-        config.allowed_paths = vec!["/nonexistent/path".to_string()];
-        assert!(config.is_path_allowed("/nonexistent/path/subdir"));
+        config.allowed_paths = vec!["/nonexistent/path".into()];
+        assert!(config.is_path_allowed("/nonexistent/path/subdir", AccessMode::Rw));
     }
 
     #[test]
@@ -614,6 +1746,126 @@ mod tests {
         assert_eq!(config.audit.format, AuditFormat::Jsonl);
     }
 
+    // --- HookConfig テスト ---
+
+    #[test]
+    fn test_hook_config_default() {
+        let config = Config::default();
+        assert_eq!(config.hook.self_protection, SelfProtection::Deny);
+        assert!(!config.hook.auto_approve);
+        assert!(config.hook.auto_approve_subcommands.is_empty());
+        assert!(!config.hook.additional_context);
+        assert!(!config.hook.system_message);
+        assert_eq!(
+            config.hook.session_dir,
+            "~/.local/share/safe-docker/sessions"
+        );
+    }
+
+    #[test]
+    fn test_parse_hook_config() {
+        let toml_str = r#"
+            [hook]
+            self_protection = "ask"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.hook.self_protection, SelfProtection::Ask);
+    }
+
+    #[test]
+    fn test_parse_hook_auto_approve() {
+        let toml_str = r#"
+            [hook]
+            auto_approve = true
+            auto_approve_subcommands = ["ps", "logs", "build"]
+            additional_context = true
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.hook.auto_approve);
+        assert_eq!(
+            config.hook.auto_approve_subcommands,
+            vec!["ps", "logs", "build"]
+        );
+        assert!(config.hook.additional_context);
+        assert!(!config.hook.system_message);
+    }
+
+    #[test]
+    fn test_parse_hook_rewrite() {
+        let config = Config::default();
+        assert!(!config.hook.rewrite.enabled);
+        assert!(!config.hook.rewrite.has_rule(RewriteRule::CapDropAll));
+
+        let toml_str = r#"
+            [hook.rewrite]
+            enabled = true
+            rules = ["readonly-sensitive-mounts", "loopback-ports"]
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.hook.rewrite.has_rule(RewriteRule::LoopbackPorts));
+        assert!(!config.hook.rewrite.has_rule(RewriteRule::CapDropAll));
+    }
+
+    #[test]
+    fn test_parse_hook_mcp_tools() {
+        let config = Config::default();
+        assert!(
+            config
+                .hook
+                .mcp_tools
+                .iter()
+                .any(|m| m.matches("mcp__docker__run_container"))
+        );
+
+        let toml_str = r#"
+            [[hook.mcp_tools]]
+            tool = "mcp__containers__*"
+            image = "spec.image"
+            volumes = "spec.binds"
+            ignore_keys = ["spec.name"]
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.hook.mcp_tools.len(), 1);
+        let mapping = &config.hook.mcp_tools[0];
+        assert_eq!(mapping.subcommand, "run");
+        assert_eq!(mapping.image.as_deref(), Some("spec.image"));
+        assert_eq!(mapping.privileged, None);
+        assert_eq!(
+            mapping.known_keys().collect::<Vec<_>>(),
+            vec!["spec.image", "spec.binds", "spec.name"]
+        );
+        assert!(mapping.matches("mcp__containers__start"));
+        assert!(!mapping.matches("mcp__docker__run_container"));
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate_mcp_tools() {
+        let toml_str = r#"
+            [[hook.mcp_tools]]
+            tool = ""
+            subcommand = "exec"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let issues = config.validate();
+        assert!(issues.iter().any(
+            |i| matches!(i, ConfigIssue::Error(msg) if msg.contains("hook.mcp_tools[0].tool"))
+        ));
+        assert!(issues.iter().any(
+            |i| matches!(i, ConfigIssue::Error(msg) if msg.contains("'exec' is not supported"))
+        ));
+    }
+
+    #[test]
+    fn test_validate_auto_approve_subcommands_without_auto_approve() {
+        let mut config = Config::default();
+        config.hook.auto_approve_subcommands = vec!["ps".to_string()];
+        let issues = config.validate();
+        assert!(issues.iter().any(
+            |i| matches!(i, ConfigIssue::Warning(msg) if msg.contains("auto_approve is false"))
+        ));
+    }
+
     // --- validate() テスト ---
 
     #[test]
@@ -630,7 +1882,7 @@ mod tests {
     #[test]
     fn test_validate_allowed_paths_relative() {
         let mut config = Config::default();
-        config.allowed_paths = vec!["relative/path".to_string()];
+        config.allowed_paths = vec!["relative/path".into()];
         let issues = config.validate();
         assert!(
             issues.iter().any(
@@ -642,7 +1894,7 @@ mod tests {
     #[test]
     fn test_validate_allowed_paths_tilde() {
         let mut config = Config::default();
-        config.allowed_paths = vec!["~/projects".to_string()];
+        config.allowed_paths = vec!["~/projects".into()];
         let issues = config.validate();
         // ~ で始まるパスはエラーにならない
         assert!(
@@ -657,7 +1909,7 @@ mod tests {
     #[test]
     fn test_validate_allowed_paths_empty_string() {
         let mut config = Config::default();
-        config.allowed_paths = vec!["".to_string()];
+        config.allowed_paths = vec!["".into()];
         let issues = config.validate();
         assert!(
             issues
@@ -669,7 +1921,7 @@ mod tests {
     #[test]
     fn test_validate_allowed_paths_nonexistent_warning() {
         let mut config = Config::default();
-        config.allowed_paths = vec!["/nonexistent/path/12345".to_string()];
+        config.allowed_paths = vec!["/nonexistent/path/12345".into()];
         let issues = config.validate();
         assert!(
             issues
@@ -681,7 +1933,7 @@ mod tests {
     #[test]
     fn test_validate_sensitive_paths_absolute() {
         let mut config = Config::default();
-        config.sensitive_paths = vec!["/absolute/path".to_string()];
+        config.sensitive_paths = vec!["/absolute/path".into()];
         let issues = config.validate();
         assert!(
             issues
@@ -690,6 +1942,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_path_entries_with_mode() {
+        let config: Config = toml::from_str(
+            r#"
+            allowed_paths = ["/opt/data", { path = "/data/datasets", mode = "ro" }]
+            sensitive_paths = [{ path = ".config/git", mode = "ro" }, { path = ".ssh", mode = "rw" }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.allowed_paths[0], PathEntry::from("/opt/data"));
+        assert_eq!(config.allowed_paths[1].path(), "/data/datasets");
+        assert_eq!(config.allowed_paths[1].mode(), Some(AccessMode::Ro));
+        assert_eq!(config.allowed_paths[1].to_string(), "/data/datasets (ro)");
+
+        let issues = config.validate();
+        assert!(issues.iter().any(|i| matches!(
+            i,
+            ConfigIssue::Warning(msg) if msg.contains("sensitive_paths[1]") && msg.contains("no effect")
+        )));
+        assert!(
+            toml::from_str::<Config>(r#"allowed_paths = [{ path = "/x", mode = "wo" }]"#).is_err()
+        );
+    }
+
     #[test]
     fn test_validate_blocked_flags_no_prefix() {
         let mut config = Config::default();
@@ -740,10 +2016,60 @@ mod tests {
             .any(|i| matches!(i, ConfigIssue::Error(msg) if msg.contains("allowed_images") && msg.contains("empty string"))));
     }
 
+    #[test]
+    fn test_validate_path_patterns() {
+        let config: Config = toml::from_str(
+            r#"
+            allowed_paths = ["!/srv/private", "/srv/*/data"]
+            sensitive_paths = ["**/*.pem", "projects/[abc"]
+            denied_paths = ["~/vault", "**/.env", "relative/dir", "/srv/a**"]
+            "#,
+        )
+        .unwrap();
+        let issues = config.validate();
+        let has_error = |needle: &str| {
+            issues
+                .iter()
+                .any(|i| matches!(i, ConfigIssue::Error(msg) if msg.contains(needle)))
+        };
+        assert!(has_error(
+            "sensitive_paths[1]: invalid pattern 'projects/[abc'"
+        ));
+        assert!(has_error(
+            "denied_paths[2]: 'relative/dir' must be an absolute path"
+        ));
+        assert!(has_error("denied_paths[3]: invalid pattern"));
+        assert!(!has_error("denied_paths[0]"));
+        assert!(!has_error("denied_paths[1]"));
+        assert!(issues.iter().any(|i| matches!(
+            i,
+            ConfigIssue::Warning(msg) if msg.contains("allowed_paths[0]") && msg.contains("no effect")
+        )));
+        // glob は存在チェックの対象外
+        assert!(!issues.iter().any(|i| matches!(
+            i,
+            ConfigIssue::Warning(msg) if msg.contains("'/srv/*/data' does not exist")
+        )));
+    }
+
+    #[test]
+    fn test_denied_path_match_negation() {
+        let config = Config {
+            denied_paths: vec!["/data/**/secrets".to_string(), "!/data/demo".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.denied_path_match("/data/app/secrets/key"),
+            Some("/data/**/secrets")
+        );
+        assert_eq!(config.denied_path_match("/data/demo/secrets"), None);
+        assert_eq!(config.denied_path_match("/data/app/public"), None);
+    }
+
     #[test]
     fn test_validate_duplicate_allowed_paths() {
         let mut config = Config::default();
-        config.allowed_paths = vec!["/tmp".to_string(), "/tmp".to_string()];
+        config.allowed_paths = vec!["/tmp".into(), "/tmp".into()];
         let issues = config.validate();
         assert!(
             issues
@@ -800,8 +2126,8 @@ mod tests {
     #[test]
     fn test_validate_multiple_errors() {
         let mut config = Config::default();
-        config.allowed_paths = vec!["relative".to_string()];
-        config.sensitive_paths = vec!["/absolute".to_string()];
+        config.allowed_paths = vec!["relative".into()];
+        config.sensitive_paths = vec!["/absolute".into()];
         config.blocked_flags = vec!["noprefixed".to_string()];
         let issues = config.validate();
         let error_count = issues
@@ -815,6 +2141,351 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_policy_rules_from_toml_and_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let rule_file = dir.path().join("org.toml");
+        std::fs::write(
+            &rule_file,
+            "[[rules]]\nname = \"from-file\"\nwhen = \"true\"\ndecision = \"ask\"\n",
+        )
+        .unwrap();
+        let toml_str = format!(
+            r#"
+[policy]
+utc_offset = "+09:00"
+files = ["{}"]
+
+[[policy.rules]]
+name = "inline"
+when = "command.image == 'x'"
+message = "no x"
+"#,
+            rule_file.display()
+        );
+        let config: Config = toml::from_str(&toml_str).unwrap();
+        assert_eq!(config.policy.utc_offset_minutes(), Some(540));
+        let rules = config.policy.load_rules().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].decision, PolicyRuleDecision::Deny);
+        assert_eq!(rules[1].name, "from-file");
+        assert_eq!(rules[1].decision, PolicyRuleDecision::Ask);
+        assert!(config.validate().is_empty(), "{:?}", config.validate());
+    }
+
+    #[test]
+    fn test_policy_utc_offset() {
+        let offset = |s: &str| {
+            PolicyConfig {
+                utc_offset: s.to_string(),
+                ..Default::default()
+            }
+            .utc_offset_minutes()
+        };
+        assert_eq!(offset("+00:00"), Some(0));
+        assert_eq!(offset("-05:30"), Some(-330));
+        assert_eq!(offset("09:00"), None);
+        assert_eq!(offset("+9:00"), None);
+        assert_eq!(offset("+25:00"), None);
+        assert_eq!(offset(""), None);
+    }
+
+    #[test]
+    fn test_validate_policy_errors() {
+        let config = Config {
+            policy: PolicyConfig {
+                rules: vec![
+                    PolicyRule {
+                        name: "broken".to_string(),
+                        when: "command.image ==".to_string(),
+                        ..Default::default()
+                    },
+                    PolicyRule {
+                        when: "true".to_string(),
+                        ..Default::default()
+                    },
+                ],
+                utc_offset: "JST".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let issues = config.validate();
+        assert!(
+            issues.iter().any(
+                |i| matches!(i, ConfigIssue::Error(msg) if msg.contains("policy.rules[0].when"))
+            )
+        );
+        assert!(
+            issues.iter().any(
+                |i| matches!(i, ConfigIssue::Error(msg) if msg.contains("policy.rules[1].name"))
+            )
+        );
+        assert!(
+            issues
+                .iter()
+                .any(|i| matches!(i, ConfigIssue::Error(msg) if msg.contains("policy.utc_offset")))
+        );
+
+        let missing = Config {
+            policy: PolicyConfig {
+                files: vec!["/nonexistent/safe-docker-policy.toml".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(
+            missing
+                .validate()
+                .iter()
+                .any(|i| matches!(i, ConfigIssue::Error(msg) if msg.contains("policy.files")))
+        );
+    }
+
+    #[test]
+    fn test_plugins_from_toml() {
+        let toml_str = r#"
+[[plugins]]
+name = "cmdb"
+command = "/usr/local/bin/check-cmdb --strict"
+subcommands = ["run"]
+
+[[plugins]]
+name = "slow"
+command = "check-slow"
+timeout_ms = 500
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.plugins.len(), 2);
+        assert_eq!(config.plugins[0].timeout_ms, 100);
+        assert_eq!(config.plugins[0].subcommands, vec!["run"]);
+        assert_eq!(config.plugins[1].timeout_ms, 500);
+    }
+
+    #[test]
+    fn test_validate_image_signatures() {
+        let has_warning = |config: &Config, needle: &str| {
+            config
+                .validate()
+                .iter()
+                .any(|i| matches!(i, ConfigIssue::Warning(msg) if msg.contains(needle)))
+        };
+        let mut config: Config = toml::from_str(
+            "[image_signatures]\nlayouts = [\"/nonexistent/layout\"]\nunsigned = \"deny\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.image_signatures.unsigned, PinningAction::Deny);
+        assert!(!config.image_signatures.is_enabled());
+        assert!(has_warning(&config, "image_signatures.keys is empty"));
+        assert!(has_warning(&config, "image_signatures.layouts[0]"));
+
+        config.image_signatures.keys = vec!["/nonexistent/cosign.pub".to_string()];
+        assert!(config.image_signatures.is_enabled());
+        let issues = config.validate();
+        if cfg!(feature = "cosign") {
+            assert!(issues.iter().any(
+                |i| matches!(i, ConfigIssue::Error(msg) if msg.contains("cannot read image_signatures.keys"))
+            ));
+        } else {
+            assert!(has_warning(&config, "without the 'cosign' feature"));
+        }
+
+        config.image_signatures.unsigned = PinningAction::Allow;
+        assert!(!config.image_signatures.is_enabled());
+        assert!(has_warning(&config, "unsigned is \"allow\""));
+    }
+
+    #[test]
+    fn test_validate_published_ports() {
+        let config: Config = toml::from_str(
+            "[published_ports]\nallowed_host_ports = [\"8000-8999\", \"0\", \"9000-8000\", \"http\"]\n",
+        )
+        .unwrap();
+        assert!(config.published_ports.is_enabled());
+        let errors: Vec<String> = config
+            .validate()
+            .into_iter()
+            .filter_map(|i| match i {
+                ConfigIssue::Error(msg) => Some(msg),
+                ConfigIssue::Warning(_) => None,
+            })
+            .collect();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].starts_with("published_ports.allowed_host_ports[1]"));
+
+        let config: Config = toml::from_str(
+            "[published_ports]\nallowed_host_ports = [\"8080\"]\noutside_allowed = \"allow\"\n",
+        )
+        .unwrap();
+        assert!(!config.published_ports.is_enabled());
+        assert!(config.validate().iter().any(
+            |i| matches!(i, ConfigIssue::Warning(msg) if msg.contains("outside_allowed is \"allow\""))
+        ));
+        assert!(!Config::default().published_ports.is_enabled());
+    }
+
+    #[test]
+    fn test_network_policy_config() {
+        let config = Config::default();
+        assert!(config.network_policy.is_enabled());
+        assert_eq!(
+            config.network_policy.bridging_drivers,
+            vec!["macvlan", "ipvlan"]
+        );
+        assert_eq!(config.network_policy.not_isolated, PinningAction::Ask);
+
+        let config: Config = toml::from_str(
+            "[network_policy]\nbridging = \"allow\"\nisolated_images = [\"\", \"ghcr.io/ourorg/scanner\"]\nnot_isolated = \"deny\"\n",
+        )
+        .unwrap();
+        assert!(config.network_policy.is_enabled());
+        assert!(config.validate().iter().any(
+            |i| matches!(i, ConfigIssue::Error(msg) if msg.starts_with("network_policy.isolated_images[0]"))
+        ));
+
+        let config: Config = toml::from_str(
+            "[network_policy]\nbridging = \"allow\"\nisolated_images = [\"busybox\"]\nnot_isolated = \"allow\"\n",
+        )
+        .unwrap();
+        assert!(!config.network_policy.is_enabled());
+        assert!(config.validate().iter().any(
+            |i| matches!(i, ConfigIssue::Warning(msg) if msg.contains("not_isolated is \"allow\""))
+        ));
+    }
+
+    #[test]
+    fn test_build_context_scan_config() {
+        let config = Config::default();
+        assert!(config.build_context_scan.enabled);
+        assert!(
+            config
+                .build_context_scan
+                .patterns
+                .contains(&"**/.env".to_string())
+        );
+        assert_eq!(config.build_context_scan.incomplete, PinningAction::Allow);
+
+        let config: Config = toml::from_str(
+            "[build_context_scan]\npatterns = [\"**/[\"]\nmax_files = 0\ntimeout_ms = 60000\nincomplete = \"deny\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.build_context_scan.incomplete, PinningAction::Deny);
+        let errors: Vec<String> = config
+            .validate()
+            .into_iter()
+            .filter_map(|i| match i {
+                ConfigIssue::Error(msg) => Some(msg),
+                ConfigIssue::Warning(_) => None,
+            })
+            .collect();
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.contains("build_context_scan.patterns[0]"))
+        );
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.contains("build_context_scan.max_files"))
+        );
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.contains("build_context_scan.timeout_ms"))
+        );
+    }
+
+    #[test]
+    fn test_validate_plugins() {
+        let plugin = |name: &str, command: &str, timeout_ms: u64| PluginConfig {
+            name: name.to_string(),
+            command: command.to_string(),
+            timeout_ms,
+            ..Default::default()
+        };
+        let config = Config {
+            plugins: vec![
+                plugin("", "check", 100),
+                plugin("unterminated", "check 'oops", 100),
+                plugin("no-command", "", 100),
+                plugin("too-slow", "check", 60_000),
+                plugin("missing", "/nonexistent/safe-docker-plugin", 100),
+                plugin("ok", "check", 100),
+                plugin("ok", "check", 100),
+            ],
+            ..Default::default()
+        };
+        let issues = config.validate();
+        let has_error = |needle: &str| {
+            issues
+                .iter()
+                .any(|i| matches!(i, ConfigIssue::Error(msg) if msg.contains(needle)))
+        };
+        assert!(has_error("plugins[0].name"));
+        assert!(has_error("plugins[1].command"));
+        assert!(has_error("plugins[2].command: empty string"));
+        assert!(has_error("plugins[3].timeout_ms"));
+        assert!(
+            issues.iter().any(
+                |i| matches!(i, ConfigIssue::Warning(msg) if msg.contains("plugins[4].command"))
+            )
+        );
+        assert!(
+            issues.iter().any(
+                |i| matches!(i, ConfigIssue::Warning(msg) if msg.contains("'ok' is duplicated"))
+            )
+        );
+    }
+
+    #[test]
+    fn test_workspace_root_from_toml() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(
+            config.workspace_root,
+            WorkspaceRoot::Mode(WorkspaceMode::Home)
+        );
+        let config: Config = toml::from_str("workspace_root = \"git-root\"").unwrap();
+        assert_eq!(
+            config.workspace_root,
+            WorkspaceRoot::Mode(WorkspaceMode::GitRoot)
+        );
+        let config: Config = toml::from_str("workspace_root = [\"/tmp\"]").unwrap();
+        assert_eq!(
+            config.workspace_root,
+            WorkspaceRoot::Paths(vec!["/tmp".to_string()])
+        );
+        assert!(toml::from_str::<Config>("workspace_root = \"everywhere\"").is_err());
+    }
+
+    #[test]
+    fn test_validate_workspace_root_paths() {
+        let config = Config {
+            workspace_root: WorkspaceRoot::Paths(vec![
+                "relative/dir".to_string(),
+                "/nonexistent/safe-docker-workspace".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let issues = config.validate();
+        assert!(
+            issues
+                .iter()
+                .any(|i| matches!(i, ConfigIssue::Error(msg) if msg.contains("workspace_root[0]")))
+        );
+        assert!(
+            issues.iter().any(
+                |i| matches!(i, ConfigIssue::Warning(msg) if msg.contains("workspace_root[1]"))
+            )
+        );
+        let empty = Config {
+            workspace_root: WorkspaceRoot::Paths(vec![]),
+            ..Default::default()
+        };
+        assert!(empty.validate().iter().any(
+            |i| matches!(i, ConfigIssue::Error(msg) if msg.contains("workspace_root: empty list"))
+        ));
+    }
+
     #[test]
     fn test_is_valid_capability_name() {
         assert!(is_valid_capability_name("SYS_ADMIN"));
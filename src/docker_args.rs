@@ -1,4 +1,5 @@
 use regex::Regex;
+use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 
 /// Docker サブコマンド
@@ -39,6 +40,7 @@ pub enum MountSource {
     VolumeFlag,     // -v / --volume
     MountFlag,      // --mount
     ComposeVolumes, // docker-compose.yml の volumes
+    VolumeOpt,      // docker volume create --opt / --mount の volume-opt (device=)
 }
 
 /// バインドマウント情報
@@ -121,8 +123,71 @@ pub struct DockerCommand {
     pub dangerous_flags: Vec<DangerousFlag>,
     pub compose_file: Option<String>,
     pub image: Option<String>,
-    /// docker cp や docker build でのホストパス
+    /// docker cp や docker build で読み取るホストパス
     pub host_paths: Vec<String>,
+    /// docker cp のコピー先や docker build --output で書き込むホストパス
+    pub host_write_paths: Vec<String>,
+    /// -p / --publish の値
+    pub ports: Vec<String>,
+    /// -P / --publish-all
+    pub publish_all: bool,
+    /// docker build / buildx bake のビルド定義
+    pub build: Option<BuildSpec>,
+    /// ネットワークの指定 (run / create、docker network create / connect)
+    pub network: NetworkSpec,
+}
+
+/// ネットワークの指定
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkSpec {
+    /// 接続するネットワーク (run / create の --network、network connect のネットワーク)
+    pub networks: Vec<String>,
+    /// --dns で指定した DNS サーバー
+    pub dns: Vec<String>,
+    /// --link で指定したコンテナ (`NAME[:ALIAS]`)
+    pub links: Vec<String>,
+    /// docker network create で作成するネットワーク
+    pub create: Option<NetworkCreate>,
+    /// docker network connect で接続するコンテナ
+    pub container: Option<String>,
+}
+
+/// docker network create で作成するネットワーク
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkCreate {
+    pub name: String,
+    /// -d / --driver (省略時は bridge)
+    pub driver: String,
+    /// -o / --opt (`KEY=VALUE`)
+    pub options: Vec<String>,
+    /// --internal (外部との通信を持たない)
+    pub internal: bool,
+    /// --subnet
+    pub subnets: Vec<String>,
+}
+
+/// docker build / buildx bake で読み込むファイル
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildSpec {
+    /// ビルドコンテキストのディレクトリ (URL・標準入力・bake の場合は None)
+    pub context: Option<String>,
+    /// -f / --file で指定したファイル (build は Dockerfile、bake は bake ファイル)
+    pub files: Vec<String>,
+    /// docker buildx bake か
+    pub bake: bool,
+}
+
+impl BuildSpec {
+    /// docker build が読み込む Dockerfile (-f、なければコンテキストの Dockerfile)。
+    /// bake、標準入力の Dockerfile、ローカルでないコンテキストの場合は None
+    pub fn dockerfile_path(&self, cwd: &Path) -> Option<PathBuf> {
+        let context = self.context.as_ref().filter(|_| !self.bake)?;
+        match self.files.first() {
+            Some(file) if file == "-" => None,
+            Some(file) => Some(cwd.join(file)),
+            None => Some(cwd.join(context).join("Dockerfile")),
+        }
+    }
 }
 
 static MOUNT_TYPE_BIND_RE: LazyLock<Regex> =
@@ -183,9 +248,9 @@ fn parse_volume_flag(value: &str, dangerous_flags: &mut Vec<DangerousFlag>) -> O
 /// --mount フラグの値からバインドマウントをパースする
 /// propagation が検出された場合は dangerous_flags に追加
 fn parse_mount_flag(value: &str, dangerous_flags: &mut Vec<DangerousFlag>) -> Option<BindMount> {
-    // type=bind のみ対象
+    // type=bind のほか、volume-opt でホストのパスをマウントするボリュームが対象
     if !MOUNT_TYPE_BIND_RE.is_match(value) {
-        return None;
+        return parse_volume_opt_mount(value);
     }
 
     let source = MOUNT_SOURCE_RE
@@ -217,6 +282,88 @@ fn parse_mount_flag(value: &str, dangerous_flags: &mut Vec<DangerousFlag>) -> Op
     })
 }
 
+/// --mount の値をフィールドに分ける (docker と同じく、ダブルクォートで囲んだ部分はカンマを含められる)
+fn mount_fields(value: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// `--mount type=volume,volume-opt=type=none,volume-opt=o=bind,volume-opt=device=/path` を
+/// device のバインドマウントとしてパースする (type 省略時は volume)
+fn parse_volume_opt_mount(value: &str) -> Option<BindMount> {
+    let fields = mount_fields(value);
+    let kind = fields
+        .iter()
+        .find_map(|f| f.strip_prefix("type="))
+        .unwrap_or("volume");
+    if kind != "volume" {
+        return None;
+    }
+    let (host_path, read_only) = volume_opts_host_path(
+        fields
+            .iter()
+            .filter_map(|f| f.strip_prefix("volume-opt=")?.split_once('=')),
+    )?;
+    let container_path = fields
+        .iter()
+        .find_map(|f| {
+            f.strip_prefix("target=")
+                .or_else(|| f.strip_prefix("dst="))
+                .or_else(|| f.strip_prefix("destination="))
+        })
+        .unwrap_or_default()
+        .to_string();
+    let read_only = read_only
+        || fields
+            .iter()
+            .any(|f| matches!(f.as_str(), "readonly" | "ro" | "readonly=true" | "ro=true"));
+    Some(BindMount {
+        host_path,
+        container_path,
+        source: MountSource::VolumeOpt,
+        read_only,
+    })
+}
+
+/// local ドライバのボリュームオプション (`type=none`, `o=bind`, `device=/path`) から、
+/// マウントされるホストのパスと読み取り専用かを返す
+///
+/// device が絶対パスなら o=bind 以外 (ブロックデバイスのマウント等) もホストのパスとして扱う。
+/// `~` や `$HOME` で始まる device はバインドマウントのソースと同様にそのまま返し、検証時に展開する。
+/// o=bind の相対パスは dockerd のカレントディレクトリ (通常は `/`) から解決される
+pub fn volume_opts_host_path<'a>(
+    opts: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Option<(String, bool)> {
+    let mut device = None;
+    let mut mount_opts: Vec<&str> = Vec::new();
+    for (key, value) in opts {
+        match key {
+            "device" => device = Some(value),
+            "o" => mount_opts.extend(value.split(',')),
+            _ => {}
+        }
+    }
+    let device = device.filter(|d| !d.is_empty())?;
+    let read_only = mount_opts.contains(&"ro");
+    if device.starts_with('/') || device.starts_with('~') || device.starts_with('$') {
+        Some((device.to_string(), read_only))
+    } else if mount_opts.iter().any(|o| matches!(*o, "bind" | "rbind")) {
+        Some((format!("/{}", device), read_only))
+    } else {
+        None
+    }
+}
+
 /// --security-opt seccomp=PATH からパスを抽出してホストパス検証対象に追加する
 /// seccomp=unconfined は DangerousFlag で処理済みなのでスキップ
 fn extract_seccomp_path(opt: &str, host_paths: &mut Vec<String>) {
@@ -233,7 +380,7 @@ fn extract_seccomp_path(opt: &str, host_paths: &mut Vec<String>) {
 }
 
 /// --build-arg の KEY 名が機密情報を含むパターンかどうか判定
-fn is_secret_build_arg(arg: &str) -> bool {
+pub fn is_secret_build_arg(arg: &str) -> bool {
     // KEY=VALUE の KEY 部分を取り出す (VALUE がない場合は arg 全体が KEY)
     let key = arg.split('=').next().unwrap_or(arg);
     let key_upper = key.to_uppercase();
@@ -273,6 +420,11 @@ pub fn parse_docker_args(args: &[&str]) -> DockerCommand {
         compose_file: None,
         image: None,
         host_paths: Vec::new(),
+        host_write_paths: Vec::new(),
+        ports: Vec::new(),
+        publish_all: false,
+        build: None,
+        network: Default::default(),
     };
 
     if args.is_empty() {
@@ -321,6 +473,14 @@ pub fn parse_docker_args(args: &[&str]) -> DockerCommand {
         "build" => DockerSubcommand::Build,
         "cp" => DockerSubcommand::Cp,
         "exec" => DockerSubcommand::Exec,
+        "network" => {
+            i += 1; // "network" を消費
+            DockerSubcommand::Other(format!("network-{}", args.get(i).unwrap_or(&"unknown")))
+        }
+        "volume" => {
+            i += 1; // "volume" を消費
+            DockerSubcommand::Other(format!("volume-{}", args.get(i).unwrap_or(&"unknown")))
+        }
         "buildx" => {
             i += 1; // "buildx" を消費
             if i < args.len() && args[i] == "build" {
@@ -347,6 +507,28 @@ pub fn parse_docker_args(args: &[&str]) -> DockerCommand {
         return cmd;
     }
 
+    // docker buildx bake のパース: docker buildx bake [OPTIONS] [TARGET...]
+    if cmd.subcommand == DockerSubcommand::Other("buildx-bake".to_string()) {
+        parse_bake_args(args, i, &mut cmd);
+        return cmd;
+    }
+
+    // docker network create / connect のパース
+    if cmd.subcommand == DockerSubcommand::Other("network-create".to_string()) {
+        parse_network_create_args(args, i, &mut cmd);
+        return cmd;
+    }
+    if cmd.subcommand == DockerSubcommand::Other("network-connect".to_string()) {
+        parse_network_connect_args(args, i, &mut cmd);
+        return cmd;
+    }
+
+    // docker volume create のパース
+    if cmd.subcommand == DockerSubcommand::Other("volume-create".to_string()) {
+        parse_volume_create_args(args, i, &mut cmd);
+        return cmd;
+    }
+
     // docker exec のパース: docker exec [OPTIONS] CONTAINER COMMAND
     if cmd.subcommand == DockerSubcommand::Exec {
         parse_exec_args(args, i, &mut cmd);
@@ -481,6 +663,7 @@ pub fn parse_docker_args(args: &[&str]) -> DockerCommand {
         if arg == "--network" || arg == "--net" {
             if i + 1 < args.len() {
                 let val = args[i + 1];
+                cmd.network.networks.push(network_name(val));
                 if val == "host" {
                     cmd.dangerous_flags.push(DangerousFlag::NetworkHost);
                 } else if let Some(name) = val.strip_prefix("container:") {
@@ -494,6 +677,7 @@ pub fn parse_docker_args(args: &[&str]) -> DockerCommand {
             .strip_prefix("--network=")
             .or_else(|| arg.strip_prefix("--net="))
         {
+            cmd.network.networks.push(network_name(val));
             if val == "host" {
                 cmd.dangerous_flags.push(DangerousFlag::NetworkHost);
             } else if let Some(name) = val.strip_prefix("container:") {
@@ -672,6 +856,55 @@ pub fn parse_docker_args(args: &[&str]) -> DockerCommand {
             continue;
         }
 
+        // --dns / --link
+        if arg == "--dns" || arg == "--link" {
+            if i + 1 < args.len() {
+                let list = if arg == "--dns" {
+                    &mut cmd.network.dns
+                } else {
+                    &mut cmd.network.links
+                };
+                list.push(args[i + 1].to_string());
+                i += 2;
+                continue;
+            }
+        } else if let Some(value) = arg.strip_prefix("--dns=") {
+            cmd.network.dns.push(value.to_string());
+            i += 1;
+            continue;
+        } else if let Some(value) = arg.strip_prefix("--link=") {
+            cmd.network.links.push(value.to_string());
+            i += 1;
+            continue;
+        }
+
+        // -p / --publish (公開ポート)
+        if arg == "-p" || arg == "--publish" {
+            if i + 1 < args.len() {
+                cmd.ports.push(args[i + 1].to_string());
+                i += 2;
+                continue;
+            }
+        } else if let Some(value) = arg
+            .strip_prefix("--publish=")
+            .or_else(|| arg.strip_prefix("-p="))
+            .or_else(|| arg.strip_prefix("-p"))
+        {
+            cmd.ports.push(value.to_string());
+            i += 1;
+            continue;
+        }
+
+        // -P / --publish-all (-dP のようにまとめた短いフラグを含む)
+        if arg == "--publish-all"
+            || arg == "--publish-all=true"
+            || is_short_flags_with_publish_all(arg)
+        {
+            cmd.publish_all = true;
+            i += 1;
+            continue;
+        }
+
         // 値付きオプションのスキップ (-e, --env, --name, -w, --workdir, etc.)
         if is_flag_with_value(arg) {
             i += 2;
@@ -691,6 +924,179 @@ pub fn parse_docker_args(args: &[&str]) -> DockerCommand {
     cmd
 }
 
+/// 値を取らない短いフラグをまとめた引数 (`-P`, `-dP`, `-itP`) に -P が含まれるか
+fn is_short_flags_with_publish_all(arg: &str) -> bool {
+    arg.strip_prefix('-').is_some_and(|flags| {
+        flags.contains('P') && flags.chars().all(|c| matches!(c, 'P' | 'd' | 'i' | 't'))
+    })
+}
+
+/// --network の値からネットワーク名を取り出す (`name=NET,alias=...` の形式にも対応)
+fn network_name(value: &str) -> String {
+    if value.contains('=') {
+        value
+            .split(',')
+            .find_map(|opt| opt.strip_prefix("name="))
+            .unwrap_or_default()
+            .to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// docker network create で値を取るフラグ
+const NETWORK_CREATE_VALUE_FLAGS: &[&str] = &[
+    "-d",
+    "--driver",
+    "-o",
+    "--opt",
+    "--subnet",
+    "--gateway",
+    "--ip-range",
+    "--aux-address",
+    "--ipam-driver",
+    "--ipam-opt",
+    "--label",
+    "--scope",
+    "--config-from",
+];
+
+/// docker network create 引数をパース: docker network create [OPTIONS] NETWORK
+fn parse_network_create_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
+    let mut create = NetworkCreate {
+        name: String::new(),
+        driver: "bridge".to_string(),
+        options: Vec::new(),
+        internal: false,
+        subnets: Vec::new(),
+    };
+    let mut i = start;
+    while i < args.len() {
+        let arg = args[i];
+        if arg == "--" {
+            if create.name.is_empty()
+                && let Some(name) = args.get(i + 1)
+            {
+                create.name = name.to_string();
+            }
+            break;
+        }
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with('-') => (flag, Some(value)),
+            _ => (arg, None),
+        };
+        if NETWORK_CREATE_VALUE_FLAGS.contains(&flag) {
+            let value = match value {
+                Some(value) => value,
+                None if i + 1 < args.len() => {
+                    i += 1;
+                    args[i]
+                }
+                None => break,
+            };
+            match flag {
+                "-d" | "--driver" => create.driver = value.to_string(),
+                "-o" | "--opt" => create.options.push(value.to_string()),
+                "--subnet" => create.subnets.push(value.to_string()),
+                _ => {}
+            }
+        } else if flag == "--internal" {
+            create.internal = value.is_none_or(|v| v == "true");
+        } else if !arg.starts_with('-') && create.name.is_empty() {
+            create.name = arg.to_string();
+        }
+        i += 1;
+    }
+    cmd.network.create = Some(create);
+}
+
+/// docker network connect 引数をパース: docker network connect [OPTIONS] NETWORK CONTAINER
+fn parse_network_connect_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
+    let mut i = start;
+    let mut positional_only = false;
+    while i < args.len() {
+        let arg = args[i];
+        if !positional_only && arg == "--" {
+            positional_only = true;
+            i += 1;
+            continue;
+        }
+        if !positional_only
+            && matches!(
+                arg,
+                "--alias" | "--driver-opt" | "--ip" | "--ip6" | "--link" | "--link-local-ip"
+            )
+        {
+            i += 2;
+            continue;
+        }
+        if positional_only || !arg.starts_with('-') {
+            if cmd.network.networks.is_empty() {
+                cmd.network.networks.push(arg.to_string());
+            } else {
+                cmd.network.container = Some(arg.to_string());
+                return;
+            }
+        }
+        i += 1;
+    }
+}
+
+/// docker volume create で値を取るフラグ (-o / --opt 以外)
+const VOLUME_CREATE_VALUE_FLAGS: &[&str] = &[
+    "-d",
+    "--driver",
+    "--label",
+    "--name",
+    "--availability",
+    "--group",
+    "--limit-bytes",
+    "--required-bytes",
+    "--scope",
+    "--secret",
+    "--sharing",
+    "--topology-preferred",
+    "--topology-required",
+    "--type",
+];
+
+/// docker volume create 引数をパース: docker volume create [OPTIONS] [VOLUME]
+/// `--opt device=/path` でホストのパスをマウントするボリュームをバインドマウントとして扱う
+fn parse_volume_create_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
+    let mut opts = Vec::new();
+    let mut i = start;
+    while i < args.len() {
+        let arg = args[i];
+        if arg == "--" {
+            break;
+        }
+        let value = match arg {
+            "-o" | "--opt" => {
+                i += 1;
+                args.get(i).copied()
+            }
+            _ => arg
+                .strip_prefix("--opt=")
+                .or_else(|| arg.strip_prefix("-o="))
+                .or_else(|| arg.strip_prefix("-o").filter(|v| !v.is_empty())),
+        };
+        if let Some((key, value)) = value.and_then(|v| v.split_once('=')) {
+            opts.push((key, value));
+        } else if VOLUME_CREATE_VALUE_FLAGS.contains(&arg) {
+            i += 1;
+        }
+        i += 1;
+    }
+    if let Some((host_path, read_only)) = volume_opts_host_path(opts) {
+        cmd.bind_mounts.push(BindMount {
+            host_path,
+            container_path: String::new(),
+            source: MountSource::VolumeOpt,
+            read_only,
+        });
+    }
+}
+
 /// docker cp 引数をパース: docker cp [OPTIONS] SRC DEST
 /// container:path はコンテナパス、それ以外はホストパス
 fn parse_cp_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
@@ -708,21 +1114,40 @@ fn parse_cp_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
         i += 1;
     }
 
-    // SRC と DEST がホストパスかコンテナパスかを判定
-    for path in &positional {
+    // SRC と DEST がホストパスかコンテナパスかを判定。
+    // SRC はホストから読み取り (host→container)、DEST はホストへの書き込み (container→host)
+    for (index, path) in positional.iter().enumerate() {
         // container:path パターン (コロン含みだがドライブレターでない)
         if path.contains(':') && !path.starts_with('/') && !path.starts_with('.') {
             // コンテナパスなのでスキップ
             continue;
         }
         // ホストパス
-        cmd.host_paths.push(path.to_string());
+        if index == 0 {
+            cmd.host_paths.push(path.to_string());
+        } else {
+            cmd.host_write_paths.push(path.to_string());
+        }
     }
 }
 
+/// docker build --output の値から書き込み先のホストパスを抽出する
+/// ("./out"、"type=local,dest=./out"、"type=tar,dest=out.tar" 等。"-" は stdout)
+fn extract_build_output_path(value: &str) -> Option<String> {
+    if !value.contains('=') {
+        return (!value.is_empty() && value != "-").then(|| value.to_string());
+    }
+    value
+        .split(',')
+        .find_map(|part| part.strip_prefix("dest="))
+        .filter(|dest| !dest.is_empty() && *dest != "-")
+        .map(|dest| dest.to_string())
+}
+
 /// docker build 引数をパース: docker build [OPTIONS] PATH
 fn parse_build_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
     let mut i = start;
+    let build = cmd.build.insert(BuildSpec::default());
 
     while i < args.len() {
         let arg = args[i];
@@ -731,10 +1156,27 @@ fn parse_build_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
         if arg == "--" {
             if i + 1 < args.len() {
                 cmd.host_paths.push(args[i + 1].to_string());
+                build.context = build_context_dir(args[i + 1]);
             }
             break;
         }
 
+        // -f / --file: Dockerfile
+        if arg == "-f" || arg == "--file" {
+            if let Some(file) = args.get(i + 1) {
+                build.files.push(file.to_string());
+            }
+            i += 2;
+            continue;
+        } else if let Some(file) = arg
+            .strip_prefix("--file=")
+            .or_else(|| arg.strip_prefix("-f="))
+        {
+            build.files.push(file.to_string());
+            i += 1;
+            continue;
+        }
+
         // --build-arg: 機密情報パターン検出
         if arg == "--build-arg" {
             if i + 1 < args.len() {
@@ -788,6 +1230,26 @@ fn parse_build_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
             continue;
         }
 
+        // --output: ビルド結果の書き込み先
+        if arg == "--output" || arg == "-o" {
+            if i + 1 < args.len() {
+                if let Some(path) = extract_build_output_path(args[i + 1]) {
+                    cmd.host_write_paths.push(path);
+                }
+                i += 2;
+                continue;
+            }
+        } else if let Some(value) = arg
+            .strip_prefix("--output=")
+            .or_else(|| arg.strip_prefix("-o="))
+        {
+            if let Some(path) = extract_build_output_path(value) {
+                cmd.host_write_paths.push(path);
+            }
+            i += 1;
+            continue;
+        }
+
         // 値を取るフラグをスキップ
         if matches!(
             arg,
@@ -800,8 +1262,6 @@ fn parse_build_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
                 | "--cache-from"
                 | "--network"
                 | "--progress"
-                | "--output"
-                | "-o"
                 | "--iidfile"
                 | "--load"
                 | "--push"
@@ -827,10 +1287,46 @@ fn parse_build_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
 
         // 最初の非フラグ引数 = コンテキストパス
         cmd.host_paths.push(arg.to_string());
+        build.context = build_context_dir(arg);
         break;
     }
 }
 
+/// ビルドコンテキストがローカルのディレクトリならそのパス (URL・標準入力は None)
+fn build_context_dir(context: &str) -> Option<String> {
+    let remote = context == "-"
+        || context.contains("://")
+        || context.starts_with("git@")
+        || context.starts_with("github.com/");
+    (!remote).then(|| context.to_string())
+}
+
+/// docker buildx bake 引数をパース: -f / --file で指定した bake ファイルを記録する
+fn parse_bake_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
+    let build = cmd.build.insert(BuildSpec {
+        bake: true,
+        ..Default::default()
+    });
+    let mut i = start;
+    while i < args.len() {
+        let arg = args[i];
+        if arg == "-f" || arg == "--file" {
+            if let Some(file) = args.get(i + 1) {
+                build.files.push(file.to_string());
+            }
+            i += 2;
+            continue;
+        }
+        if let Some(file) = arg
+            .strip_prefix("--file=")
+            .or_else(|| arg.strip_prefix("-f="))
+        {
+            build.files.push(file.to_string());
+        }
+        i += 1;
+    }
+}
+
 /// docker exec 引数をパース: docker exec [OPTIONS] CONTAINER COMMAND [ARG...]
 fn parse_exec_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
     let mut i = start;
@@ -897,7 +1393,7 @@ fn parse_compose_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
             "run" => {
                 cmd.subcommand = DockerSubcommand::ComposeRun;
                 i += 1;
-                // compose run のフラグから -v を抽出
+                // compose run のフラグから -v と -p を抽出
                 while i < args.len() {
                     if (args[i] == "-v" || args[i] == "--volume") && i + 1 < args.len() {
                         if let Some(bm) = parse_volume_flag(args[i + 1], &mut cmd.dangerous_flags) {
@@ -906,6 +1402,14 @@ fn parse_compose_args(args: &[&str], start: usize, cmd: &mut DockerCommand) {
                         i += 2;
                         continue;
                     }
+                    if (args[i] == "-p" || args[i] == "--publish") && i + 1 < args.len() {
+                        cmd.ports.push(args[i + 1].to_string());
+                        i += 2;
+                        continue;
+                    }
+                    if let Some(value) = args[i].strip_prefix("--publish=") {
+                        cmd.ports.push(value.to_string());
+                    }
                     i += 1;
                 }
                 return;
@@ -1011,6 +1515,7 @@ const VALUE_FLAGS: &[&str] = &[
     "--isolation",
     // Security
     "--sysctl",
+    "--cap-drop",
     // Logging
     "--log-driver",
     "--log-opt",
@@ -1036,7 +1541,7 @@ const VALUE_FLAGS: &[&str] = &[
 ];
 
 /// 値を取るフラグかどうか判定 (次の引数をスキップするため)
-fn is_flag_with_value(arg: &str) -> bool {
+pub fn is_flag_with_value(arg: &str) -> bool {
     VALUE_FLAGS.contains(&arg)
 }
 
@@ -2027,6 +2532,48 @@ mod tests {
         assert!(non_context_paths.is_empty());
     }
 
+    #[test]
+    fn test_parse_cp_direction() {
+        // host→container: SRC は読み取り
+        let cmd = parse_docker_args(&["cp", "./data", "web:/srv/data"]);
+        assert_eq!(cmd.host_paths, vec!["./data"]);
+        assert!(cmd.host_write_paths.is_empty());
+
+        // container→host: DEST は書き込み
+        let cmd = parse_docker_args(&["cp", "-a", "web:/var/log", "/tmp/logs"]);
+        assert!(cmd.host_paths.is_empty());
+        assert_eq!(cmd.host_write_paths, vec!["/tmp/logs"]);
+    }
+
+    #[test]
+    fn test_parse_build_output_write_paths() {
+        let cases: &[(&[&str], &[&str])] = &[
+            (&["build", "-o", "./out", "."], &["./out"]),
+            (
+                &["build", "--output=type=local,dest=/tmp/out", "."],
+                &["/tmp/out"],
+            ),
+            (
+                &[
+                    "buildx",
+                    "build",
+                    "--output",
+                    "type=tar,dest=/tmp/a.tar",
+                    ".",
+                ],
+                &["/tmp/a.tar"],
+            ),
+            (&["build", "--output", "type=docker", "."], &[]),
+            (&["build", "-o", "-", "."], &[]),
+        ];
+        for (args, expected) in cases {
+            let cmd = parse_docker_args(args);
+            assert_eq!(&cmd.host_write_paths, expected, "{:?}", args);
+            // コンテキストパスは引き続き読み取りとして扱う
+            assert_eq!(cmd.host_paths, vec!["."], "{:?}", args);
+        }
+    }
+
     // --- is_flag_with_value() リグレッション防止テスト ---
     //
     // is_flag_with_value() に漏れがあると、フラグの値が後続の引数として
@@ -2067,12 +2614,207 @@ mod tests {
         assert_privileged_detected_after_flag("--workdir", "/app");
     }
 
+    #[test]
+    fn test_flag_with_value_cap_drop() {
+        assert_privileged_detected_after_flag("--cap-drop", "ALL");
+    }
+
     #[test]
     fn test_flag_with_value_publish() {
         assert_privileged_detected_after_flag("-p", "8080:80");
         assert_privileged_detected_after_flag("--publish", "8080:80");
     }
 
+    #[test]
+    fn test_parse_publish() {
+        let cmd = parse_docker_args(&[
+            "run",
+            "-p",
+            "5432:5432",
+            "--publish=127.0.0.1:8080:80",
+            "-p=9000",
+            "-p[::1]:53:53/udp",
+            "-itP",
+            "postgres",
+            "-p",
+            "1:1",
+        ]);
+        assert_eq!(
+            cmd.ports,
+            vec!["5432:5432", "127.0.0.1:8080:80", "9000", "[::1]:53:53/udp"]
+        );
+        assert!(cmd.publish_all);
+        assert_eq!(cmd.image.as_deref(), Some("postgres"));
+
+        assert!(!parse_docker_args(&["run", "-it", "ubuntu"]).publish_all);
+        assert!(parse_docker_args(&["create", "--publish-all", "ubuntu"]).publish_all);
+        let cmd = parse_docker_args(&["compose", "run", "-p", "8080:80", "web"]);
+        assert_eq!(cmd.ports, vec!["8080:80"]);
+    }
+
+    #[test]
+    fn test_parse_volume_create_device() {
+        let cmd = parse_docker_args(&[
+            "volume",
+            "create",
+            "--driver",
+            "local",
+            "--opt",
+            "type=none",
+            "-o",
+            "o=bind,ro",
+            "--opt=device=/etc",
+            "etc",
+        ]);
+        assert_eq!(
+            cmd.subcommand,
+            DockerSubcommand::Other("volume-create".to_string())
+        );
+        assert_eq!(cmd.bind_mounts.len(), 1);
+        assert_eq!(cmd.bind_mounts[0].host_path, "/etc");
+        assert_eq!(cmd.bind_mounts[0].source, MountSource::VolumeOpt);
+        assert!(cmd.bind_mounts[0].read_only);
+
+        // o=bind の相対パスは dockerd のカレントディレクトリ (/) から解決される
+        let cmd = parse_docker_args(&["volume", "create", "-o", "o=bind", "-odevice=etc"]);
+        assert_eq!(cmd.bind_mounts[0].host_path, "/etc");
+
+        // ~ や $HOME はバインドマウントのソースと同様に検証時に展開される
+        let cmd = parse_docker_args(&["volume", "create", "-o", "o=bind", "-o", "device=~/proj"]);
+        assert_eq!(cmd.bind_mounts[0].host_path, "~/proj");
+        let cmd = parse_docker_args(&["volume", "create", "-o", "o=bind", "-odevice=$HOME/proj"]);
+        assert_eq!(cmd.bind_mounts[0].host_path, "$HOME/proj");
+
+        // NFS や tmpfs の device はホストのパスではない
+        let cmd = parse_docker_args(&[
+            "volume",
+            "create",
+            "-o",
+            "type=nfs",
+            "-o",
+            "o=addr=10.0.0.1",
+            "-o",
+            "device=:/export",
+        ]);
+        assert!(cmd.bind_mounts.is_empty());
+        assert!(
+            parse_docker_args(&["volume", "create", "cache"])
+                .bind_mounts
+                .is_empty()
+        );
+        assert!(
+            parse_docker_args(&["volume", "rm", "cache"])
+                .bind_mounts
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_mount_volume_opt_device() {
+        let cmd = parse_docker_args(&[
+            "run",
+            "--mount",
+            "type=volume,source=etc,target=/data,volume-opt=type=none,volume-opt=o=bind,volume-opt=device=/etc",
+            "ubuntu",
+        ]);
+        assert_eq!(cmd.bind_mounts.len(), 1);
+        assert_eq!(cmd.bind_mounts[0].host_path, "/etc");
+        assert_eq!(cmd.bind_mounts[0].container_path, "/data");
+        assert_eq!(cmd.bind_mounts[0].source, MountSource::VolumeOpt);
+        assert!(!cmd.bind_mounts[0].read_only);
+        assert_eq!(cmd.image.as_deref(), Some("ubuntu"));
+
+        // type 省略時は volume。クォートしたフィールドはカンマを含められる
+        let cmd = parse_docker_args(&[
+            "run",
+            "--mount=src=etc,dst=/data,volume-opt=device=/etc,\"volume-opt=o=bind,ro\"",
+            "ubuntu",
+        ]);
+        assert_eq!(cmd.bind_mounts[0].host_path, "/etc");
+        assert!(cmd.bind_mounts[0].read_only);
+
+        let cmd = parse_docker_args(&[
+            "run",
+            "--mount",
+            "type=volume,source=cache,target=/cache",
+            "ubuntu",
+        ]);
+        assert!(cmd.bind_mounts.is_empty());
+    }
+
+    #[test]
+    fn test_parse_run_networks_dns_links() {
+        let cmd = parse_docker_args(&[
+            "run",
+            "--network",
+            "app",
+            "--network=name=lan,alias=web",
+            "--dns",
+            "10.0.0.53",
+            "--dns=1.1.1.1",
+            "--link",
+            "db:db",
+            "alpine",
+        ]);
+        assert_eq!(cmd.network.networks, vec!["app", "lan"]);
+        assert_eq!(cmd.network.dns, vec!["10.0.0.53", "1.1.1.1"]);
+        assert_eq!(cmd.network.links, vec!["db:db"]);
+        assert_eq!(cmd.image.as_deref(), Some("alpine"));
+    }
+
+    #[test]
+    fn test_parse_network_create() {
+        let cmd = parse_docker_args(&[
+            "network",
+            "create",
+            "-d",
+            "macvlan",
+            "-o",
+            "parent=eth0",
+            "--subnet=192.168.1.0/24",
+            "--gateway",
+            "192.168.1.1",
+            "--internal",
+            "lan",
+        ]);
+        assert_eq!(
+            cmd.subcommand,
+            DockerSubcommand::Other("network-create".to_string())
+        );
+        assert_eq!(
+            cmd.network.create,
+            Some(NetworkCreate {
+                name: "lan".to_string(),
+                driver: "macvlan".to_string(),
+                options: vec!["parent=eth0".to_string()],
+                internal: true,
+                subnets: vec!["192.168.1.0/24".to_string()],
+            })
+        );
+
+        let create = parse_docker_args(&["network", "create", "app"])
+            .network
+            .create
+            .unwrap();
+        assert_eq!(create.driver, "bridge");
+        assert!(!create.internal);
+    }
+
+    #[test]
+    fn test_parse_network_connect() {
+        let cmd = parse_docker_args(&["network", "connect", "--alias", "db", "lan", "web"]);
+        assert_eq!(
+            cmd.subcommand,
+            DockerSubcommand::Other("network-connect".to_string())
+        );
+        assert_eq!(cmd.network.networks, vec!["lan"]);
+        assert_eq!(cmd.network.container.as_deref(), Some("web"));
+
+        let cmd = parse_docker_args(&["network", "connect", "--", "lan", "web"]);
+        assert_eq!(cmd.network.networks, vec!["lan"]);
+        assert_eq!(cmd.network.container.as_deref(), Some("web"));
+    }
+
     #[test]
     fn test_flag_with_value_label() {
         assert_privileged_detected_after_flag("-l", "app=web");
@@ -0,0 +1,317 @@
+//! Dockerfile の解析 (命令への分割と、FROM / COPY --from で参照するイメージの抽出)
+//!
+//! BuildKit の構文をすべて解釈するものではなく、ポリシー判定に必要な範囲を扱う:
+//! - 行継続 (既定は `\`、`# escape=` ディレクティブで変更可能) とコメント行
+//! - `RUN <<EOF` 等のヒアドキュメント (本文は命令の引数に含める)
+//! - 最初の FROM より前の `ARG NAME=value` による `$NAME` / `${NAME}` の置換
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 読み込む Dockerfile の上限サイズ (これを超える部分は解析しない)
+pub const MAX_DOCKERFILE_SIZE: u64 = 1024 * 1024;
+
+/// Dockerfile の命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// 命令が始まる行番号 (1 始まり)
+    pub line: usize,
+    /// 命令名 (大文字)
+    pub keyword: String,
+    /// 引数 (行継続を結合したもの。ヒアドキュメントの本文は改行区切りで続く)
+    pub args: String,
+}
+
+/// Dockerfile で参照するイメージ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageReference {
+    /// イメージ参照 (ARG を置換したもの)
+    pub image: String,
+    /// 参照している行番号
+    pub line: usize,
+    /// 参照している命令 (`FROM` / `COPY --from`)
+    pub instruction: &'static str,
+}
+
+/// Dockerfile を読み込む (MAX_DOCKERFILE_SIZE まで)
+pub fn read(path: &Path) -> std::io::Result<String> {
+    use std::io::Read;
+    let mut content = String::new();
+    std::fs::File::open(path)?
+        .take(MAX_DOCKERFILE_SIZE)
+        .read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Dockerfile を命令に分割する
+pub fn parse(content: &str) -> Vec<Instruction> {
+    let lines: Vec<&str> = content.lines().collect();
+    let escape = escape_char(&lines);
+    let mut instructions = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let trimmed = lines[i].trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            i += 1;
+            continue;
+        }
+
+        // 行継続を結合する (継続中のコメント行と空行は読み飛ばす)
+        let start = i;
+        let mut text = String::new();
+        loop {
+            let line = if text.is_empty() {
+                lines[i].trim_end()
+            } else {
+                lines[i].trim()
+            };
+            i += 1;
+            match line.strip_suffix(escape) {
+                Some(rest) => {
+                    text.push_str(rest.trim_end());
+                    text.push(' ');
+                    while i < lines.len() && {
+                        let next = lines[i].trim();
+                        next.is_empty() || next.starts_with('#')
+                    } {
+                        i += 1;
+                    }
+                    if i >= lines.len() {
+                        break;
+                    }
+                }
+                None => {
+                    text.push_str(line);
+                    break;
+                }
+            }
+        }
+
+        let text = text.trim();
+        let (keyword, args) = match text.split_once(char::is_whitespace) {
+            Some((keyword, args)) => (keyword, args.trim().to_string()),
+            None => (text, String::new()),
+        };
+        let mut args = args;
+
+        // ヒアドキュメントの本文を引数に含める
+        for (marker, strip_tabs) in heredoc_markers(&args) {
+            while i < lines.len() {
+                let line = lines[i];
+                i += 1;
+                let body = if strip_tabs {
+                    line.trim_start_matches('\t')
+                } else {
+                    line
+                };
+                if body == marker {
+                    break;
+                }
+                args.push('\n');
+                args.push_str(line);
+            }
+        }
+
+        instructions.push(Instruction {
+            line: start + 1,
+            keyword: keyword.to_ascii_uppercase(),
+            args,
+        });
+    }
+    instructions
+}
+
+/// 先頭のパーサーディレクティブ (`# escape=`) から行継続の文字を求める
+fn escape_char(lines: &[&str]) -> char {
+    for line in lines {
+        let Some(directive) = line.trim().strip_prefix('#') else {
+            break;
+        };
+        let Some((key, value)) = directive.split_once('=') else {
+            break;
+        };
+        if key.trim().eq_ignore_ascii_case("escape")
+            && let Some(c) = value.trim().chars().next()
+        {
+            return c;
+        }
+    }
+    '\\'
+}
+
+/// 引数中のヒアドキュメントの終端マーカー (`<<EOF`, `<<-"EOF"`)。`<<-` はタブを取り除く
+fn heredoc_markers(args: &str) -> Vec<(String, bool)> {
+    let mut markers = Vec::new();
+    let mut rest = args;
+    while let Some(pos) = rest.find("<<") {
+        rest = &rest[pos + 2..];
+        let (strip_tabs, body) = match rest.strip_prefix('-') {
+            Some(body) => (true, body),
+            None => (false, rest),
+        };
+        let body = body.trim_start_matches(['"', '\'']);
+        let marker: String = body
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect();
+        if !marker.is_empty() {
+            markers.push((marker, strip_tabs));
+        }
+    }
+    markers
+}
+
+/// FROM と COPY --from で参照するイメージを抽出する。
+/// ステージ名・`scratch`・ステージ番号は除く
+pub fn image_references(instructions: &[Instruction]) -> Vec<ImageReference> {
+    let mut args: HashMap<String, String> = HashMap::new();
+    let mut stages: Vec<String> = Vec::new();
+    let mut seen_from = false;
+    let mut refs = Vec::new();
+
+    for inst in instructions {
+        match inst.keyword.as_str() {
+            // 最初の FROM より前の ARG は FROM で参照できる
+            "ARG" if !seen_from => {
+                for decl in inst.args.split_whitespace() {
+                    if let Some((name, value)) = decl.split_once('=') {
+                        args.insert(name.to_string(), value.trim_matches('"').to_string());
+                    }
+                }
+            }
+            "FROM" => {
+                seen_from = true;
+                let words: Vec<&str> = inst
+                    .args
+                    .split_whitespace()
+                    .filter(|w| !w.starts_with("--"))
+                    .collect();
+                let Some(image) = words.first() else {
+                    continue;
+                };
+                let image = substitute_args(image, &args);
+                if !image.eq_ignore_ascii_case("scratch")
+                    && !stages.iter().any(|s| s.eq_ignore_ascii_case(&image))
+                {
+                    refs.push(ImageReference {
+                        image,
+                        line: inst.line,
+                        instruction: "FROM",
+                    });
+                }
+                if let [_, kw, name, ..] = words.as_slice()
+                    && kw.eq_ignore_ascii_case("as")
+                {
+                    stages.push(name.to_string());
+                }
+            }
+            "COPY" => {
+                let from = inst
+                    .args
+                    .split_whitespace()
+                    .take_while(|w| w.starts_with("--"))
+                    .find_map(|w| w.strip_prefix("--from="));
+                if let Some(from) = from {
+                    let from = substitute_args(from, &args);
+                    let is_stage = from.parse::<usize>().is_ok()
+                        || stages.iter().any(|s| s.eq_ignore_ascii_case(&from));
+                    if !is_stage {
+                        refs.push(ImageReference {
+                            image: from,
+                            line: inst.line,
+                            instruction: "COPY --from",
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    refs
+}
+
+/// `$NAME` / `${NAME}` / `${NAME:-default}` を ARG の既定値で置換する (未定義の変数はそのまま残す)
+fn substitute_args(value: &str, args: &HashMap<String, String>) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+        let (expr, consumed) = if let Some(inner) = rest.strip_prefix('{') {
+            match inner.find('}') {
+                Some(end) => (&inner[..end], end + 2),
+                None => {
+                    out.push('$');
+                    continue;
+                }
+            }
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            (&rest[..end], end)
+        };
+        let (name, default) = match expr.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expr, None),
+        };
+        match args
+            .get(name)
+            .filter(|v| !v.is_empty())
+            .map(String::as_str)
+            .or(default)
+        {
+            Some(v) => out.push_str(v),
+            None => {
+                out.push('$');
+                out.push_str(&rest[..consumed]);
+            }
+        }
+        rest = &rest[consumed..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_continuations_and_heredocs() {
+        let content = "# syntax=docker/dockerfile:1\nFROM alpine:3.19\n\nRUN apk add \\\n    # comment\n    curl\nRUN <<EOF\necho hi\nEOF\ncopy . /app\n";
+        let instructions = parse(content);
+        assert_eq!(instructions.len(), 4);
+        assert_eq!(instructions[1].keyword, "RUN");
+        assert_eq!(instructions[1].line, 4);
+        assert_eq!(instructions[1].args, "apk add curl");
+        assert_eq!(instructions[2].args, "<<EOF\necho hi");
+        assert_eq!(instructions[3].keyword, "COPY");
+        assert_eq!(instructions[3].line, 10);
+
+        let content = "# escape=`\nFROM mcr.microsoft.com/windows\nRUN dir `\n  c:\\\n";
+        let instructions = parse(content);
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[1].args, "dir c:\\");
+    }
+
+    #[test]
+    fn test_image_references() {
+        let content = "ARG BASE=node\nARG VERSION\nFROM --platform=$BUILDPLATFORM ${BASE}:20 AS build\nFROM build AS test\nFROM scratch\nCOPY --from=build /app /app\nCOPY --from=nginx:1.25 /etc/nginx /etc/nginx\nCOPY --from=0 /x /x\nFROM python:${VERSION:-3.12}\nFROM ubuntu:$VERSION\n";
+        let refs = image_references(&parse(content));
+        let images: Vec<(&str, usize, &str)> = refs
+            .iter()
+            .map(|r| (r.image.as_str(), r.line, r.instruction))
+            .collect();
+        assert_eq!(
+            images,
+            vec![
+                ("node:20", 3, "FROM"),
+                ("nginx:1.25", 7, "COPY --from"),
+                ("python:3.12", 9, "FROM"),
+                ("ubuntu:$VERSION", 10, "FROM"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,454 @@
+//! docker build の Dockerfile の静的解析 (`[dockerfile_lint]`)
+//!
+//! ビルドする Dockerfile の危険な構成をルール ID 付きで検出する。各ルールの判定 (ask / deny) は
+//! RULES の一覧のとおりで、`dockerfile_lint.ignore_rules` でルールごとに無効にできる。
+
+use std::path::Path;
+
+use crate::docker_args::is_secret_build_arg;
+use crate::dockerfile::{self, Instruction};
+use crate::dockerignore::DockerIgnore;
+use crate::path_pattern;
+
+/// ルールの判定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Ask,
+    Deny,
+}
+
+/// ルール (ID, 判定, 概要)
+pub const RULES: &[(&str, Severity, &str)] = &[
+    (
+        "DF001",
+        Severity::Ask,
+        "RUN --mount=type=bind,from= of an image or build context",
+    ),
+    ("DF002", Severity::Deny, "RUN --security=insecure"),
+    ("DF003", Severity::Ask, "RUN --network=host"),
+    (
+        "DF004",
+        Severity::Ask,
+        "ADD of a remote URL without --checksum",
+    ),
+    (
+        "DF005",
+        Severity::Ask,
+        "COPY / ADD of .env files or keys not excluded by .dockerignore",
+    ),
+    (
+        "DF006",
+        Severity::Ask,
+        "secret-like names in ENV / ARG defaults",
+    ),
+    ("DF007", Severity::Ask, "USER root in the final stage"),
+];
+
+/// ビルドコンテキストに含めるべきでないファイル名のパターン
+pub const SENSITIVE_FILE_PATTERNS: &[&str] = &[
+    ".env",
+    ".env.*",
+    "*.pem",
+    "*.key",
+    "*.p12",
+    "*.pfx",
+    "id_rsa",
+    "id_dsa",
+    "id_ecdsa",
+    "id_ed25519",
+    ".npmrc",
+    ".pypirc",
+    ".netrc",
+    ".git-credentials",
+];
+
+/// .env のひな形として一般的なファイル名 (SENSITIVE_FILE_PATTERNS の例外)
+const ENV_TEMPLATE_SUFFIXES: &[&str] = &[".example", ".sample", ".template", ".dist"];
+
+/// COPY / ADD のソースのディレクトリから調べるエントリ数の上限
+const MAX_DIR_ENTRIES: usize = 1000;
+
+/// 検出結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    /// Dockerfile の行番号
+    pub line: usize,
+    pub message: String,
+}
+
+impl LintFinding {
+    /// 判定理由 (`Dockerfile rule DF003 (./Dockerfile:4): ...`)
+    pub fn reason(&self, path: &Path) -> String {
+        format!(
+            "Dockerfile rule {} ({}:{}): {}",
+            self.rule,
+            path.display(),
+            self.line,
+            self.message
+        )
+    }
+}
+
+/// ルールの判定
+fn severity(rule: &str) -> Severity {
+    RULES
+        .iter()
+        .find(|(id, _, _)| *id == rule)
+        .map_or(Severity::Ask, |(_, severity, _)| *severity)
+}
+
+/// ファイル名が鍵・認証情報らしいか
+pub fn is_sensitive_file_name(name: &str) -> bool {
+    if name.starts_with(".env") && ENV_TEMPLATE_SUFFIXES.iter().any(|s| name.ends_with(s)) {
+        return false;
+    }
+    SENSITIVE_FILE_PATTERNS
+        .iter()
+        .any(|pattern| path_pattern::match_component(pattern, name))
+}
+
+/// Dockerfile を解析する。context はビルドコンテキストのディレクトリ (COPY / ADD のソースの確認に使う)
+pub fn lint(
+    instructions: &[Instruction],
+    context: Option<&Path>,
+    ignore: &DockerIgnore,
+) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    let mut stages: Vec<String> = Vec::new();
+    let mut stage_count = 0usize;
+    // 最後のステージで最後に指定した USER
+    let mut final_user: Option<(usize, String)> = None;
+
+    for inst in instructions {
+        let mut push = |rule: &'static str, message: String| {
+            findings.push(LintFinding {
+                rule,
+                severity: severity(rule),
+                line: inst.line,
+                message,
+            });
+        };
+        let (flags, rest) = split_flags(&inst.args);
+        match inst.keyword.as_str() {
+            "FROM" => {
+                stage_count += 1;
+                final_user = None;
+                let words: Vec<&str> = rest.split_whitespace().collect();
+                if let [_, kw, name, ..] = words.as_slice()
+                    && kw.eq_ignore_ascii_case("as")
+                {
+                    stages.push(name.to_ascii_lowercase());
+                }
+            }
+            "RUN" => {
+                for flag in &flags {
+                    if let Some(mount) = flag.strip_prefix("--mount=") {
+                        let options = key_values(mount);
+                        let kind = options.iter().find(|(k, _)| *k == "type").map(|(_, v)| *v);
+                        let from = options.iter().find(|(k, _)| *k == "from").map(|(_, v)| *v);
+                        if matches!(kind, None | Some("bind"))
+                            && let Some(from) = from
+                            && !from.parse::<usize>().is_ok_and(|n| n < stage_count)
+                            && !stages.contains(&from.to_ascii_lowercase())
+                        {
+                            push(
+                                "DF001",
+                                format!(
+                                    "RUN bind-mounts from '{}', which is not an earlier stage of this Dockerfile (an image or --build-context can expose files outside the build context)",
+                                    from
+                                ),
+                            );
+                        }
+                    } else if flag.eq_ignore_ascii_case("--security=insecure") {
+                        push(
+                            "DF002",
+                            "RUN --security=insecure runs the build step without sandboxing (equivalent to --privileged)".to_string(),
+                        );
+                    } else if flag.eq_ignore_ascii_case("--network=host") {
+                        push(
+                            "DF003",
+                            "RUN --network=host gives the build step access to the host network"
+                                .to_string(),
+                        );
+                    }
+                }
+            }
+            "ADD" | "COPY" => {
+                let from_other_stage = flags.iter().any(|f| f.starts_with("--from="));
+                let has_checksum = flags.iter().any(|f| f.starts_with("--checksum="));
+                let mut sources = copy_args(rest);
+                // 最後の引数はコピー先
+                sources.pop();
+                for source in &sources {
+                    if is_remote(source) {
+                        if inst.keyword == "ADD" && !has_checksum {
+                            push(
+                                "DF004",
+                                format!(
+                                    "ADD downloads '{}' without --checksum, so its content can change without notice. Add --checksum=sha256:<digest>",
+                                    source
+                                ),
+                            );
+                        }
+                        continue;
+                    }
+                    if from_other_stage {
+                        continue;
+                    }
+                    let Some(context) = context else {
+                        continue;
+                    };
+                    for file in sensitive_sources(context, source, ignore) {
+                        push(
+                            "DF005",
+                            format!(
+                                "{} copies '{}' into the image, and .dockerignore does not exclude it. Exclude it or use RUN --mount=type=secret",
+                                inst.keyword, file
+                            ),
+                        );
+                    }
+                }
+            }
+            "ENV" => {
+                for (key, _) in env_pairs(&inst.args) {
+                    if is_secret_build_arg(&key) {
+                        push(
+                            "DF006",
+                            format!(
+                                "ENV '{}' appears to contain a secret; ENV values are stored in the image config. Use RUN --mount=type=secret instead",
+                                key
+                            ),
+                        );
+                    }
+                }
+            }
+            "ARG" => {
+                for decl in inst.args.split_whitespace() {
+                    if let Some((key, value)) = decl.split_once('=')
+                        && !value.trim_matches(['"', '\'']).is_empty()
+                        && is_secret_build_arg(key)
+                    {
+                        push(
+                            "DF006",
+                            format!(
+                                "ARG '{}' has a secret-like default value, which is visible via 'docker history'. Use RUN --mount=type=secret instead",
+                                key
+                            ),
+                        );
+                    }
+                }
+            }
+            "USER" => {
+                let user = inst.args.split_whitespace().next().unwrap_or_default();
+                let name = user.split(':').next().unwrap_or_default();
+                final_user = Some((inst.line, name.to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((line, user)) = final_user
+        && (user == "root" || user == "0")
+    {
+        findings.push(LintFinding {
+            rule: "DF007",
+            severity: severity("DF007"),
+            line,
+            message: "the final stage runs as USER root. Switch to an unprivileged user before the end of the Dockerfile".to_string(),
+        });
+    }
+    findings
+}
+
+/// Dockerfile を読み込んで解析する (読めない場合は空)
+pub fn lint_file(path: &Path, context: Option<&Path>) -> Vec<LintFinding> {
+    let Ok(content) = dockerfile::read(path) else {
+        return Vec::new();
+    };
+    let ignore = context
+        .map(|dir| DockerIgnore::load(dir, Some(path)))
+        .unwrap_or_default();
+    lint(&dockerfile::parse(&content), context, &ignore)
+}
+
+/// 先頭の `--flag` を取り出し、残りの引数と分ける
+fn split_flags(args: &str) -> (Vec<&str>, &str) {
+    let mut flags = Vec::new();
+    let mut rest = args.trim_start();
+    while rest.starts_with("--") {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        flags.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    (flags, rest)
+}
+
+/// `key=value,key=value` を分割する (値のない要素は値を空にする)
+fn key_values(options: &str) -> Vec<(&str, &str)> {
+    options
+        .split(',')
+        .map(|part| part.split_once('=').unwrap_or((part, "")))
+        .collect()
+}
+
+/// COPY / ADD の引数 (JSON 配列形式にも対応。ヒアドキュメントの本文は除く)
+fn copy_args(rest: &str) -> Vec<String> {
+    let first_line = rest.lines().next().unwrap_or_default().trim();
+    if first_line.starts_with('[')
+        && let Ok(args) = serde_json::from_str::<Vec<String>>(first_line)
+    {
+        return args;
+    }
+    first_line
+        .split_whitespace()
+        .filter(|a| !a.starts_with("<<"))
+        .map(String::from)
+        .collect()
+}
+
+/// `ENV KEY=VALUE ...` と旧形式の `ENV KEY VALUE` のキーと値
+fn env_pairs(args: &str) -> Vec<(String, String)> {
+    let words = shell_words::split(args)
+        .unwrap_or_else(|_| args.split_whitespace().map(String::from).collect());
+    match words.first() {
+        Some(first) if !first.contains('=') => {
+            vec![(first.clone(), words[1..].join(" "))]
+        }
+        _ => words
+            .iter()
+            .filter_map(|w| w.split_once('='))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    }
+}
+
+fn is_remote(source: &str) -> bool {
+    source.contains("://") || source.starts_with("git@")
+}
+
+/// COPY / ADD のソースに含まれる、除外されていない鍵・認証情報らしいファイル (コンテキストからの相対パス)。
+/// ディレクトリは直下のエントリだけを調べる
+fn sensitive_sources(context: &Path, source: &str, ignore: &DockerIgnore) -> Vec<String> {
+    let relative = source.trim_start_matches("./").trim_start_matches('/');
+    let relative = if relative == "." { "" } else { relative };
+    let (dir, name) = match relative.rsplit_once('/') {
+        Some((dir, name)) => (dir, name),
+        None => ("", relative),
+    };
+
+    let join = |dir: &str, name: &str| {
+        if dir.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", dir, name)
+        }
+    };
+    let list_dir = |dir: &str| -> Vec<String> {
+        std::fs::read_dir(context.join(dir))
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .take(MAX_DIR_ENTRIES)
+                    .map(|e| e.file_name().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let candidates: Vec<String> = if path_pattern::is_glob(name) {
+        list_dir(dir)
+            .into_iter()
+            .filter(|entry| path_pattern::match_component(name, entry))
+            .map(|entry| join(dir, &entry))
+            .collect()
+    } else if relative.is_empty() || context.join(relative).is_dir() {
+        list_dir(relative)
+            .into_iter()
+            .map(|entry| join(relative, &entry))
+            .collect()
+    } else {
+        vec![relative.to_string()]
+    };
+
+    candidates
+        .into_iter()
+        .filter(|path| {
+            let file_name = path.rsplit('/').next().unwrap_or(path);
+            is_sensitive_file_name(file_name) && !ignore.is_excluded(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(findings: &[LintFinding]) -> Vec<&str> {
+        findings.iter().map(|f| f.rule).collect()
+    }
+
+    #[test]
+    fn test_lint_run_flags_and_add() {
+        let content = "FROM golang:1.22 AS build\nRUN --mount=type=bind,from=build,target=/src true\nRUN --mount=type=cache,target=/root/.cache --mount=from=hostfiles,target=/h make\nRUN --security=insecure --network=host make\nADD https://example.com/tool.tar.gz /opt/\nADD --checksum=sha256:abc https://example.com/ok.tar.gz /opt/\nCOPY --from=build /out /out\n";
+        let findings = lint(&dockerfile::parse(content), None, &DockerIgnore::default());
+        assert_eq!(rules(&findings), vec!["DF001", "DF002", "DF003", "DF004"]);
+        assert_eq!(findings[0].line, 3);
+        assert!(findings[0].message.contains("'hostfiles'"));
+        assert_eq!(findings[1].severity, Severity::Deny);
+        assert!(
+            findings[3]
+                .message
+                .contains("https://example.com/tool.tar.gz")
+        );
+    }
+
+    #[test]
+    fn test_lint_env_arg_and_user() {
+        let content = "ARG GITHUB_TOKEN\nARG NPM_TOKEN=abc123\nFROM node:20\nENV API_KEY=xyz NODE_ENV=production\nENV DB_PASSWORD hunter2\nUSER node\nFROM debian:12\nUSER app\nUSER 0:0\n";
+        let findings = lint(&dockerfile::parse(content), None, &DockerIgnore::default());
+        assert_eq!(rules(&findings), vec!["DF006", "DF006", "DF006", "DF007"]);
+        assert!(findings[0].message.contains("'NPM_TOKEN'"));
+        assert!(findings[1].message.contains("'API_KEY'"));
+        assert!(findings[2].message.contains("'DB_PASSWORD'"));
+        assert_eq!(findings[3].line, 9);
+
+        let content = "FROM debian:12\nUSER root\nRUN apt-get update\nUSER nobody\n";
+        assert!(lint(&dockerfile::parse(content), None, &DockerIgnore::default()).is_empty());
+    }
+
+    #[test]
+    fn test_lint_sensitive_copy_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let context = dir.path();
+        for file in [".env", ".env.example", "tls.key", "main.go"] {
+            std::fs::write(context.join(file), "x").unwrap();
+        }
+        std::fs::create_dir(context.join("config")).unwrap();
+        std::fs::write(context.join("config/server.pem"), "x").unwrap();
+
+        let content = "FROM golang:1.22\nCOPY . /src\nCOPY config /etc/app\nCOPY [\"*.key\", \"/keys/\"]\nCOPY main.go /src/\n";
+        let instructions = dockerfile::parse(content);
+        let findings = lint(&instructions, Some(context), &DockerIgnore::default());
+        let mut messages: Vec<&str> = findings.iter().map(|f| f.message.as_str()).collect();
+        messages.sort();
+        assert_eq!(findings.len(), 4);
+        assert!(messages[0].contains("'.env'"));
+        assert!(messages[1].contains("'config/server.pem'"));
+        assert!(findings.iter().all(|f| f.rule == "DF005"));
+
+        let ignore = DockerIgnore::parse(".env\n*.key\nconfig/*.pem\n");
+        assert!(lint(&instructions, Some(context), &ignore).is_empty());
+    }
+
+    #[test]
+    fn test_is_sensitive_file_name() {
+        assert!(is_sensitive_file_name(".env"));
+        assert!(is_sensitive_file_name(".env.production"));
+        assert!(!is_sensitive_file_name(".env.example"));
+        assert!(is_sensitive_file_name("id_ed25519"));
+        assert!(!is_sensitive_file_name("id_ed25519.pub"));
+        assert!(!is_sensitive_file_name("environment.ts"));
+    }
+}
@@ -0,0 +1,114 @@
+//! `.dockerignore` の解釈 (ビルドコンテキストから除外されるファイルの判定)
+//!
+//! - パターンはコンテキストのルートからの相対パスとして比較する (先頭の `/` と `./` は無視)
+//! - `*` / `?` / `[abc]` はパス要素の中だけで一致し、`**` は 0 個以上のパス要素に一致する
+//! - 除外されたディレクトリの配下も除外される。先頭の `!` は除外の取り消し (後ろの行が優先)
+//! - Dockerfile と同じ場所の `<Dockerfile>.dockerignore` があれば、コンテキストの `.dockerignore` より優先する
+
+use std::path::Path;
+
+use crate::path_pattern;
+
+/// 読み込む .dockerignore の上限サイズ
+const MAX_DOCKERIGNORE_SIZE: u64 = 256 * 1024;
+
+/// .dockerignore のパターン
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DockerIgnore {
+    /// (否定パターンか, パターン)
+    patterns: Vec<(bool, String)>,
+}
+
+impl DockerIgnore {
+    /// コンテキストの .dockerignore を読み込む (ない場合は何も除外しない)
+    pub fn load(context: &Path, dockerfile: Option<&Path>) -> Self {
+        let specific = dockerfile.and_then(|path| {
+            let name = path.file_name()?.to_string_lossy();
+            Some(path.with_file_name(format!("{}.dockerignore", name)))
+        });
+        specific
+            .into_iter()
+            .chain(std::iter::once(context.join(".dockerignore")))
+            .find_map(|path| read(&path))
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// .dockerignore の内容を解析する
+    pub fn parse(content: &str) -> Self {
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (negated, pattern) = path_pattern::split_negation(line);
+                let pattern = pattern.trim();
+                (!pattern.is_empty()).then(|| (negated, pattern.to_string()))
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    /// 除外の取り消し (`!`) を含むか (含む場合は除外されたディレクトリの配下も送られうる)
+    pub fn has_exceptions(&self) -> bool {
+        self.patterns.iter().any(|(negated, _)| *negated)
+    }
+
+    /// コンテキストからの相対パスがビルドコンテキストから除外されるか
+    pub fn is_excluded(&self, path: &str) -> bool {
+        let mut excluded = false;
+        for (negated, pattern) in &self.patterns {
+            if path_pattern::matches(pattern, path) {
+                excluded = !negated;
+            }
+        }
+        excluded
+    }
+}
+
+fn read(path: &Path) -> Option<String> {
+    use std::io::Read;
+    let mut content = String::new();
+    std::fs::File::open(path)
+        .and_then(|f| f.take(MAX_DOCKERIGNORE_SIZE).read_to_string(&mut content))
+        .ok()?;
+    Some(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_excluded() {
+        let ignore = DockerIgnore::parse(
+            "# comment\n/.env\n*.pem\n!keep.pem\nsecrets/\n**/id_rsa\n./build\n",
+        );
+        assert!(ignore.is_excluded(".env"));
+        assert!(!ignore.is_excluded("app/.env"));
+        assert!(ignore.is_excluded("server.pem"));
+        assert!(!ignore.is_excluded("certs/server.pem"));
+        assert!(!ignore.is_excluded("keep.pem"));
+        assert!(ignore.is_excluded("secrets/token.txt"));
+        assert!(ignore.is_excluded("home/user/.ssh/id_rsa"));
+        assert!(ignore.is_excluded("build/out.bin"));
+        assert!(!ignore.is_excluded("src/main.rs"));
+    }
+
+    #[test]
+    fn test_load_prefers_dockerfile_specific_ignore() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".dockerignore"), ".env\n").unwrap();
+        std::fs::write(dir.path().join("app.Dockerfile.dockerignore"), "*.key\n").unwrap();
+
+        let ignore = DockerIgnore::load(dir.path(), Some(&dir.path().join("Dockerfile")));
+        assert!(ignore.is_excluded(".env"));
+        let ignore = DockerIgnore::load(dir.path(), Some(&dir.path().join("app.Dockerfile")));
+        assert!(!ignore.is_excluded(".env"));
+        assert!(ignore.is_excluded("tls.key"));
+        assert_eq!(
+            DockerIgnore::load(&dir.path().join("missing"), None),
+            DockerIgnore::default()
+        );
+    }
+}
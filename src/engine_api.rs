@@ -0,0 +1,1079 @@
+//! Docker Engine API リクエストの docker コマンドへの変換
+//!
+//! proxy モードでは docker CLI を経由しない API 呼び出し (curl や SDK) も検査するため、
+//! ポリシー上意味のあるリクエストを等価な docker CLI 引数に変換し、
+//! 通常の docker コマンドと同じパーサー・ポリシーで評価する (proxy / authz-plugin モード)。
+//!
+//! 対象:
+//! - `POST /containers/create` (HostConfig のバインドマウント・危険な設定)
+//! - `POST /build` (クエリの buildargs)
+//! - `POST /containers/{id}/exec` (Privileged)
+//! - `POST /networks/create`、`POST /networks/{id}/connect` (ドライバ・接続先のネットワーク)
+//! - `POST /volumes/create` (DriverOpts の device)
+
+use serde_json::Value;
+
+use crate::config::{Config, NonInteractiveAsk};
+use crate::hook::Decision;
+use crate::{audit, wrapper};
+
+/// Engine API リクエストのポリシー検査 (proxy / authz-plugin モード共通)
+pub struct Inspector {
+    config: Config,
+    config_source: String,
+    cwd: String,
+    /// 監査ログの mode
+    mode: &'static str,
+}
+
+impl Inspector {
+    pub fn new(config: Config, config_source: String, mode: &'static str) -> Self {
+        let cwd = std::env::current_dir()
+            .ok()
+            .and_then(|p| p.to_str().map(String::from))
+            .unwrap_or_else(|| "/".to_string());
+        Self {
+            config,
+            config_source,
+            cwd,
+            mode,
+        }
+    }
+
+    /// ポリシーで評価し、ブロックする場合はその理由を返す
+    pub fn check(&self, method: &str, target: &str, body: Option<&[u8]>) -> Option<String> {
+        let path = split_target(target).0;
+        let docker_args = match to_docker_args(method, target, body) {
+            Ok(Some(args)) => args,
+            Ok(None) if is_known_safe(method, target) => return None,
+            Ok(None) => {
+                // 変換できない変更系のエンドポイントは検査できないため確認を求める
+                let decision = Decision::Ask(format!(
+                    "[safe-docker] Engine API request {} {} is not inspected by safe-docker",
+                    method, path
+                ));
+                self.emit_audit(
+                    &format!("{} {}", method, path),
+                    &decision,
+                    &audit::AuditCollector::new(),
+                );
+                return self.blocking_reason(decision);
+            }
+            Err(e) => {
+                let reason = format!(
+                    "[safe-docker] Cannot interpret Engine API request {} {}: {}. Blocking for safety",
+                    method, path, e
+                );
+                self.emit_audit(
+                    &format!("{} {}", method, path),
+                    &Decision::Deny(reason.clone()),
+                    &audit::AuditCollector::new(),
+                );
+                return Some(reason);
+            }
+        };
+
+        let command = format!("docker {}", shell_words::join(&docker_args));
+        let mut collector = audit::AuditCollector::new();
+        let decision = wrapper::evaluate_docker_args(
+            &docker_args,
+            &self.config,
+            &self.cwd,
+            Some(&mut collector),
+        );
+        self.emit_audit(&command, &decision, &collector);
+        self.blocking_reason(decision)
+    }
+
+    /// 判定をブロックの理由に変換する (許可なら None)
+    fn blocking_reason(&self, decision: Decision) -> Option<String> {
+        match decision {
+            Decision::Allow => None,
+            Decision::Deny(reason) => Some(reason),
+            Decision::Ask(reason) => {
+                // 確認を求める相手がいないため、非対話環境の設定に従う
+                if wrapper::non_interactive_ask(&self.config) == NonInteractiveAsk::Allow {
+                    None
+                } else {
+                    Some(format!(
+                        "{}\n[safe-docker] Confirmation is not available for Docker API requests; blocked (set SAFE_DOCKER_ASK=allow to permit)",
+                        reason
+                    ))
+                }
+            }
+        }
+    }
+
+    fn emit_audit(&self, command: &str, decision: &Decision, collector: &audit::AuditCollector) {
+        if !audit::is_enabled(&self.config.audit) {
+            return;
+        }
+        let (decision_str, reason) = match decision {
+            Decision::Allow => ("allow", None),
+            Decision::Deny(r) => ("deny", Some(r.as_str())),
+            Decision::Ask(r) => ("ask", Some(r.as_str())),
+        };
+        let event = audit::build_event(&audit::AuditContext {
+            command,
+            decision: decision_str,
+            reason,
+            collector,
+            session_id: None,
+            cwd: &self.cwd,
+            mode: self.mode,
+            config_source: Some(&self.config_source),
+        });
+        audit::emit(&event, &self.config.audit);
+    }
+}
+
+/// リクエストボディを読み込んで検査する必要があるか
+pub fn needs_body(method: &str, target: &str) -> bool {
+    if !method.eq_ignore_ascii_case("POST") {
+        return false;
+    }
+    let (path, _) = split_target(target);
+    let segments: Vec<&str> = strip_version(path).trim_matches('/').split('/').collect();
+    matches!(
+        segments.as_slice(),
+        ["containers", "create"]
+            | ["containers", _, "exec"]
+            | ["networks", "create"]
+            | ["networks", _, "connect"]
+            | ["volumes", "create"]
+    )
+}
+
+/// docker コマンドに変換せずに転送してよいリクエストか。
+/// 読み取り (GET / HEAD) と、ホストへのアクセスや権限に関わらない既知の操作だけを許す。
+/// プラグイン、swarm (services 等)、`/containers/{id}/update` 等はここに含めない。
+/// BuildKit の `/session` と `/grpc` (h2c への Upgrade) はビルドのファイル転送と制御に使われ、
+/// ホストのパスを含まないため許可する (docker build 自体は `POST /build` で検査する)
+pub fn is_known_safe(method: &str, target: &str) -> bool {
+    if method.eq_ignore_ascii_case("GET") || method.eq_ignore_ascii_case("HEAD") {
+        return true;
+    }
+    let (path, _) = split_target(target);
+    let segments: Vec<&str> = strip_version(path).trim_matches('/').split('/').collect();
+    let method = method.to_ascii_uppercase();
+    match method.as_str() {
+        "POST" => matches!(
+            segments.as_slice(),
+            ["auth"]
+                | ["session"]
+                | ["grpc"]
+                | ["commit"]
+                | ["build", "prune"]
+                | ["containers", "prune"]
+                | [
+                    "containers",
+                    _,
+                    "start"
+                        | "stop"
+                        | "restart"
+                        | "kill"
+                        | "pause"
+                        | "unpause"
+                        | "wait"
+                        | "resize"
+                        | "rename"
+                        | "attach"
+                ]
+                | ["exec", _, "start" | "resize"]
+                | ["images", "create" | "load" | "prune"]
+                | ["images", .., "tag" | "push"]
+                | ["networks", "prune"]
+                | ["networks", _, "disconnect"]
+                | ["volumes", "prune"]
+        ),
+        // docker cp のコンテナへのコピー (tar はクライアントから送られ、ホストのパスを含まない)
+        "PUT" => matches!(segments.as_slice(), ["containers", _, "archive"]),
+        "DELETE" => matches!(
+            segments.as_slice(),
+            ["containers", _] | ["images", ..] | ["networks", _] | ["volumes", _]
+        ),
+        _ => false,
+    }
+}
+
+/// リクエストを docker CLI 引数に変換する。
+/// ポリシー検査の対象外なら Ok(None)、内容を解釈できない場合は Err。
+pub fn to_docker_args(
+    method: &str,
+    target: &str,
+    body: Option<&[u8]>,
+) -> Result<Option<Vec<String>>, String> {
+    if !method.eq_ignore_ascii_case("POST") {
+        return Ok(None);
+    }
+    let (path, query) = split_target(target);
+    let segments: Vec<&str> = strip_version(path).trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["containers", "create"] => create_args(&parse_json(body)?).map(Some),
+        ["containers", id, "exec"] => exec_args(&percent_decode(id)?, &parse_json(body)?).map(Some),
+        ["build"] => build_args(query).map(Some),
+        ["networks", "create"] => network_create_args(&parse_json(body)?).map(Some),
+        ["volumes", "create"] => volume_create_args(&parse_json(body)?).map(Some),
+        ["networks", id, "connect"] => {
+            network_connect_args(&percent_decode(id)?, &parse_json(body)?).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// リクエストターゲットをパスとクエリに分ける
+pub fn split_target(target: &str) -> (&str, &str) {
+    target.split_once('?').unwrap_or((target, ""))
+}
+
+/// パス先頭の API バージョン (`/v1.45`) を取り除く
+pub fn strip_version(path: &str) -> &str {
+    if let Some(rest) = path.strip_prefix("/v") {
+        let end = rest.find('/').unwrap_or(rest.len());
+        let version = &rest[..end];
+        if !version.is_empty() && version.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return &rest[end..];
+        }
+    }
+    path
+}
+
+fn parse_json(body: Option<&[u8]>) -> Result<Value, String> {
+    match body {
+        None | Some(b"") => Ok(Value::Object(Default::default())),
+        Some(bytes) => serde_json::from_slice(bytes)
+            .map(|StrictValue(value)| value)
+            .map_err(|e| format!("invalid JSON body: {}", e)),
+    }
+}
+
+/// 重複したキーを拒否する JSON 値。
+/// dockerd (Go の encoding/json) は同じキーを順に同じフィールドへデコードして値を重ねるため、
+/// 最後の値だけを検査すると前の値 (`"Privileged": true` 等) が漏れる
+struct StrictValue(Value);
+
+impl<'de> serde::Deserialize<'de> for StrictValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(StrictVisitor)
+    }
+}
+
+struct StrictVisitor;
+
+impl<'de> serde::de::Visitor<'de> for StrictVisitor {
+    type Value = StrictValue;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<StrictValue, E> {
+        Ok(StrictValue(Value::Bool(v)))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<StrictValue, E> {
+        Ok(StrictValue(Value::from(v)))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<StrictValue, E> {
+        Ok(StrictValue(Value::from(v)))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<StrictValue, E> {
+        Ok(StrictValue(Value::from(v)))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<StrictValue, E> {
+        Ok(StrictValue(Value::String(v.to_string())))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<StrictValue, E> {
+        Ok(StrictValue(Value::String(v)))
+    }
+
+    fn visit_unit<E>(self) -> Result<StrictValue, E> {
+        Ok(StrictValue(Value::Null))
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<StrictValue, A::Error> {
+        let mut items = Vec::new();
+        while let Some(StrictValue(item)) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(StrictValue(Value::Array(items)))
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<StrictValue, A::Error> {
+        let mut obj = serde_json::Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let StrictValue(value) = map.next_value()?;
+            if obj.contains_key(&key) {
+                return Err(serde::de::Error::custom(format!("duplicate key '{}'", key)));
+            }
+            obj.insert(key, value);
+        }
+        Ok(StrictValue(Value::Object(obj)))
+    }
+}
+
+/// `%XX` と `+` をデコードする
+fn percent_decode(s: &str) -> Result<String, String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = s
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| format!("invalid percent-encoding in '{}'", s))?;
+                out.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).map_err(|_| format!("invalid UTF-8 in '{}'", s))
+}
+
+/// クエリ文字列から値を取り出す
+fn query_param(query: &str, name: &str) -> Result<Option<String>, String> {
+    for pair in query.split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        if percent_decode(key)? == name {
+            return percent_decode(value).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+fn invalid(key: &str, value: &Value) -> String {
+    format!("unsupported value for '{}': {}", key, value)
+}
+
+/// encoding/json と同じく、大文字・小文字を区別せずにキーを比べる
+/// (`ſ` は `s`、`K` (U+212A) は `k` と同じ)
+fn key_matches(key: &str, name: &str) -> bool {
+    let fold = |c: char| match c {
+        'ſ' => 's',
+        '\u{212A}' => 'k',
+        c => c.to_ascii_lowercase(),
+    };
+    key.chars().map(fold).eq(name.chars().map(fold))
+}
+
+/// オブジェクトのフィールド (null・未指定は None)。dockerd と同じく大文字・小文字を区別しない。
+/// 綴りの異なる同じフィールドが複数あると dockerd は値を重ねてデコードするため、拒否する
+fn field<'a>(obj: &'a Value, name: &str) -> Result<Option<&'a Value>, String> {
+    let Some(map) = obj.as_object() else {
+        return Ok(None);
+    };
+    let mut found = map.iter().filter(|(key, _)| key_matches(key, name));
+    match (found.next(), found.next()) {
+        (Some((_, value)), None) => Ok(Some(value).filter(|v| !v.is_null())),
+        (None, _) => Ok(None),
+        (Some((first, _)), Some((second, _))) => Err(format!(
+            "ambiguous keys '{}' and '{}' for '{}'",
+            first, second, name
+        )),
+    }
+}
+
+/// ネストしたオブジェクトのフィールド (`HostConfig` → `Mounts` 等)
+fn nested_field<'a>(obj: &'a Value, path: &[&str]) -> Result<Option<&'a Value>, String> {
+    let mut current = obj;
+    for name in path {
+        match field(current, name)? {
+            Some(value) => current = value,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(current))
+}
+
+/// 文字列フィールド (null・未指定は None)
+fn string_field(obj: &Value, key: &str) -> Result<Option<String>, String> {
+    match field(obj, key)? {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(other) => Err(invalid(key, other)),
+    }
+}
+
+/// 文字列配列フィールド
+fn string_list(obj: &Value, key: &str) -> Result<Vec<String>, String> {
+    match field(obj, key)? {
+        None => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(String::from)
+                    .ok_or_else(|| invalid(key, item))
+            })
+            .collect(),
+        Some(other) => Err(invalid(key, other)),
+    }
+}
+
+fn bool_field(obj: &Value, key: &str) -> Result<bool, String> {
+    match field(obj, key)? {
+        None => Ok(false),
+        Some(Value::Bool(b)) => Ok(*b),
+        Some(other) => Err(invalid(key, other)),
+    }
+}
+
+/// `POST /containers/create` のボディを docker create の引数に変換する
+fn create_args(body: &Value) -> Result<Vec<String>, String> {
+    let mut args = vec!["create".to_string()];
+    let host = match field(body, "HostConfig")? {
+        None => &Value::Null,
+        Some(h @ Value::Object(_)) => h,
+        Some(other) => return Err(invalid("HostConfig", other)),
+    };
+
+    for bind in string_list(host, "Binds")? {
+        args.extend(["-v".to_string(), bind]);
+    }
+    if let Some(mounts) = field(host, "Mounts")? {
+        let mounts = mounts.as_array().ok_or_else(|| invalid("Mounts", mounts))?;
+        for mount in mounts {
+            if let Some(spec) = mount_spec(mount)? {
+                args.extend(["--mount".to_string(), spec]);
+            }
+        }
+    }
+    if bool_field(host, "Privileged")? {
+        args.push("--privileged".to_string());
+    }
+    for cap in string_list(host, "CapAdd")? {
+        args.push(format!("--cap-add={}", cap));
+    }
+    for opt in string_list(host, "SecurityOpt")? {
+        // seccomp プロファイルは CLI がファイルを読んで JSON 本文を送る。本文はホストパスではない
+        if opt.starts_with("seccomp=") && opt[8..].trim_start().starts_with('{') {
+            continue;
+        }
+        args.push(format!("--security-opt={}", opt));
+    }
+    for (key, flag) in [
+        ("NetworkMode", "--network"),
+        ("PidMode", "--pid"),
+        ("IpcMode", "--ipc"),
+        ("UTSMode", "--uts"),
+        ("UsernsMode", "--userns"),
+        ("CgroupnsMode", "--cgroupns"),
+        ("CgroupParent", "--cgroup-parent"),
+    ] {
+        if let Some(value) = string_field(host, key)?.filter(|v| !v.is_empty()) {
+            args.push(format!("{}={}", flag, value));
+        }
+    }
+    if let Some(devices) = field(host, "Devices")? {
+        let devices = devices
+            .as_array()
+            .ok_or_else(|| invalid("Devices", devices))?;
+        for device in devices {
+            let path =
+                string_field(device, "PathOnHost")?.ok_or_else(|| invalid("Devices", device))?;
+            args.push(match string_field(device, "PathInContainer")? {
+                Some(target) if !target.is_empty() => format!("--device={}:{}", path, target),
+                _ => format!("--device={}", path),
+            });
+        }
+    }
+    for source in string_list(host, "VolumesFrom")? {
+        args.push(format!("--volumes-from={}", source));
+    }
+    if let Some(sysctls) = field(host, "Sysctls")? {
+        let sysctls = sysctls
+            .as_object()
+            .ok_or_else(|| invalid("Sysctls", sysctls))?;
+        for (key, value) in sysctls {
+            let value = value.as_str().ok_or_else(|| invalid("Sysctls", value))?;
+            args.push(format!("--sysctl={}={}", key, value));
+        }
+    }
+    for entry in string_list(host, "ExtraHosts")? {
+        args.push(format!("--add-host={}", entry));
+    }
+    if let Some(bindings) = field(host, "PortBindings")? {
+        let bindings = bindings
+            .as_object()
+            .ok_or_else(|| invalid("PortBindings", bindings))?;
+        for (container_port, hosts) in bindings {
+            let hosts = match hosts {
+                Value::Null => continue,
+                Value::Array(hosts) => hosts,
+                other => return Err(invalid("PortBindings", other)),
+            };
+            for binding in hosts {
+                let ip = string_field(binding, "HostIp")?.unwrap_or_default();
+                let port = string_field(binding, "HostPort")?.unwrap_or_default();
+                let ip = if ip.contains(':') {
+                    format!("[{}]", ip)
+                } else {
+                    ip
+                };
+                args.push(format!("--publish={}:{}:{}", ip, port, container_port));
+            }
+        }
+    }
+    if bool_field(host, "PublishAllPorts")? {
+        args.push("--publish-all".to_string());
+    }
+    for server in string_list(host, "Dns")? {
+        args.push(format!("--dns={}", server));
+    }
+    for link in string_list(host, "Links")? {
+        args.push(format!("--link={}", link));
+    }
+    // NetworkingConfig で NetworkMode 以外のネットワークにも接続できる
+    let network_mode = string_field(host, "NetworkMode")?.unwrap_or_default();
+    if let Some(endpoints) = nested_field(body, &["NetworkingConfig", "EndpointsConfig"])? {
+        let endpoints = endpoints
+            .as_object()
+            .ok_or_else(|| invalid("EndpointsConfig", endpoints))?;
+        for network in endpoints.keys().filter(|n| **n != network_mode) {
+            args.push(format!("--network={}", network));
+        }
+    }
+
+    let image = string_field(body, "Image")?
+        .filter(|i| !i.is_empty())
+        .ok_or("missing 'Image'")?;
+    // イメージ名をフラグとして解釈させない
+    args.push("--".to_string());
+    args.push(image);
+    Ok(args)
+}
+
+/// HostConfig.Mounts の要素を --mount の値に変換する
+/// (bind と、ドライバのオプションを持つ volume 以外は None)
+fn mount_spec(mount: &Value) -> Result<Option<String>, String> {
+    let kind = string_field(mount, "Type")?.unwrap_or_default();
+    if kind == "volume" {
+        return volume_mount_spec(mount);
+    }
+    if kind != "bind" {
+        return Ok(None);
+    }
+    let source = string_field(mount, "Source")?.unwrap_or_default();
+    let target = string_field(mount, "Target")?.unwrap_or_default();
+    // --mount の値はカンマ区切りのため、カンマを含むパスは等価な指定に変換できない
+    if source.contains(',') || target.contains(',') {
+        return Err(format!("unsupported bind mount source '{}'", source));
+    }
+    let mut spec = format!("type=bind,source={},target={}", source, target);
+    if bool_field(mount, "ReadOnly")? {
+        spec.push_str(",readonly");
+    }
+    if let Some(options) = field(mount, "BindOptions")?
+        && let Some(propagation) = string_field(options, "Propagation")?.filter(|p| !p.is_empty())
+    {
+        spec.push_str(&format!(",bind-propagation={}", propagation));
+    }
+    Ok(Some(spec))
+}
+
+/// Type が volume の HostConfig.Mounts の要素を、VolumeOptions.DriverConfig.Options を
+/// volume-opt に変換した --mount の値にする (オプションがなければ None)
+fn volume_mount_spec(mount: &Value) -> Result<Option<String>, String> {
+    let options = nested_field(mount, &["VolumeOptions", "DriverConfig", "Options"])?;
+    let Some(options) = options else {
+        return Ok(None);
+    };
+    let options = options
+        .as_object()
+        .ok_or_else(|| invalid("DriverConfig.Options", options))?;
+    if options.is_empty() {
+        return Ok(None);
+    }
+    let target = string_field(mount, "Target")?.unwrap_or_default();
+    if target.contains(',') || target.contains('"') {
+        return Err(format!("unsupported volume mount target '{}'", target));
+    }
+    let mut spec = format!("type=volume,target={}", target);
+    if bool_field(mount, "ReadOnly")? {
+        spec.push_str(",readonly");
+    }
+    for (key, value) in options {
+        let value = value
+            .as_str()
+            .ok_or_else(|| invalid("DriverConfig.Options", value))?;
+        if key.contains('"') || value.contains('"') {
+            return Err(invalid(
+                "DriverConfig.Options",
+                &Value::String(value.to_string()),
+            ));
+        }
+        // o=bind,ro 等のカンマを含む値はクォートする
+        spec.push_str(&format!(",\"volume-opt={}={}\"", key, value));
+    }
+    Ok(Some(spec))
+}
+
+/// `POST /volumes/create` のボディを docker volume create の引数に変換する
+fn volume_create_args(body: &Value) -> Result<Vec<String>, String> {
+    let mut args = vec!["volume".to_string(), "create".to_string()];
+    if let Some(driver) = string_field(body, "Driver")?.filter(|d| !d.is_empty()) {
+        args.push(format!("--driver={}", driver));
+    }
+    if let Some(options) = field(body, "DriverOpts")? {
+        let options = options
+            .as_object()
+            .ok_or_else(|| invalid("DriverOpts", options))?;
+        for (key, value) in options {
+            let value = value.as_str().ok_or_else(|| invalid("DriverOpts", value))?;
+            args.push(format!("--opt={}={}", key, value));
+        }
+    }
+    if let Some(name) = string_field(body, "Name")?.filter(|n| !n.is_empty()) {
+        args.push("--".to_string());
+        args.push(name);
+    }
+    Ok(args)
+}
+
+/// `POST /containers/{id}/exec` のボディを docker exec の引数に変換する
+fn exec_args(container: &str, body: &Value) -> Result<Vec<String>, String> {
+    let mut args = vec!["exec".to_string()];
+    if bool_field(body, "Privileged")? {
+        args.push("--privileged".to_string());
+    }
+    args.push(container.to_string());
+    args.extend(string_list(body, "Cmd")?);
+    Ok(args)
+}
+
+/// `POST /networks/create` のボディを docker network create の引数に変換する
+fn network_create_args(body: &Value) -> Result<Vec<String>, String> {
+    let mut args = vec!["network".to_string(), "create".to_string()];
+    if let Some(driver) = string_field(body, "Driver")?.filter(|d| !d.is_empty()) {
+        args.push(format!("--driver={}", driver));
+    }
+    if bool_field(body, "Internal")? {
+        args.push("--internal".to_string());
+    }
+    if let Some(options) = field(body, "Options")? {
+        let options = options
+            .as_object()
+            .ok_or_else(|| invalid("Options", options))?;
+        for (key, value) in options {
+            let value = value.as_str().ok_or_else(|| invalid("Options", value))?;
+            args.push(format!("--opt={}={}", key, value));
+        }
+    }
+    if let Some(configs) = nested_field(body, &["IPAM", "Config"])? {
+        let configs = configs
+            .as_array()
+            .ok_or_else(|| invalid("IPAM.Config", configs))?;
+        for config in configs {
+            if let Some(subnet) = string_field(config, "Subnet")?.filter(|s| !s.is_empty()) {
+                args.push(format!("--subnet={}", subnet));
+            }
+        }
+    }
+    let name = string_field(body, "Name")?
+        .filter(|n| !n.is_empty())
+        .ok_or("missing 'Name'")?;
+    args.push("--".to_string());
+    args.push(name);
+    Ok(args)
+}
+
+/// `POST /networks/{id}/connect` のボディを docker network connect の引数に変換する
+fn network_connect_args(network: &str, body: &Value) -> Result<Vec<String>, String> {
+    let container = string_field(body, "Container")?
+        .filter(|c| !c.is_empty())
+        .ok_or("missing 'Container'")?;
+    Ok(vec![
+        "network".to_string(),
+        "connect".to_string(),
+        "--".to_string(),
+        network.to_string(),
+        container,
+    ])
+}
+
+/// `POST /build` のクエリを docker build の引数に変換する。
+/// ビルドコンテキストは tar としてアップロード済みのため、ホストパスは含まない。
+fn build_args(query: &str) -> Result<Vec<String>, String> {
+    let mut args = vec!["build".to_string()];
+    if let Some(build_args) = query_param(query, "buildargs")?.filter(|s| !s.is_empty()) {
+        let map: serde_json::Map<String, Value> =
+            serde_json::from_str(&build_args).map_err(|e| format!("invalid buildargs: {}", e))?;
+        for (key, value) in map {
+            match value {
+                Value::String(v) => args.push(format!("--build-arg={}={}", key, v)),
+                Value::Null => args.push(format!("--build-arg={}", key)),
+                other => return Err(invalid("buildargs", &other)),
+            }
+        }
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_args::{DangerousFlag, DockerSubcommand, parse_docker_args};
+    use serde_json::json;
+
+    fn parse(method: &str, target: &str, body: Value) -> crate::docker_args::DockerCommand {
+        let body = body.to_string();
+        let args = to_docker_args(method, target, Some(body.as_bytes()))
+            .unwrap()
+            .unwrap();
+        let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        parse_docker_args(&args_ref)
+    }
+
+    #[test]
+    fn test_strip_version() {
+        assert_eq!(
+            strip_version("/v1.45/containers/create"),
+            "/containers/create"
+        );
+        assert_eq!(strip_version("/v1/build"), "/build");
+        assert_eq!(strip_version("/containers/json"), "/containers/json");
+        assert_eq!(strip_version("/volumes/create"), "/volumes/create");
+    }
+
+    #[test]
+    fn test_needs_body() {
+        assert!(needs_body("POST", "/v1.45/containers/create?name=web"));
+        assert!(needs_body("POST", "/containers/abc/exec"));
+        assert!(!needs_body("GET", "/containers/json"));
+        assert!(!needs_body("POST", "/build?t=app"));
+        assert!(!needs_body("POST", "/exec/abc/start"));
+    }
+
+    #[test]
+    fn test_container_create_host_config() {
+        let cmd = parse(
+            "POST",
+            "/v1.45/containers/create?name=web",
+            json!({
+                "Image": "ubuntu:24.04",
+                "Cmd": ["sleep", "infinity"],
+                "HostConfig": {
+                    "Binds": ["/etc:/host-etc:ro"],
+                    "Mounts": [
+                        {"Type": "bind", "Source": "/srv", "Target": "/data",
+                         "BindOptions": {"Propagation": "rshared"}},
+                        {"Type": "volume", "Source": "cache", "Target": "/cache"}
+                    ],
+                    "Privileged": true,
+                    "CapAdd": ["SYS_ADMIN"],
+                    "NetworkMode": "host",
+                    "PidMode": "host",
+                    "Devices": [{"PathOnHost": "/dev/sda", "PathInContainer": "/dev/sda", "CgroupPermissions": "rwm"}],
+                    "Sysctls": {"kernel.core_pattern": "|/bin/sh"}
+                }
+            }),
+        );
+        assert_eq!(cmd.subcommand, DockerSubcommand::Create);
+        assert_eq!(cmd.image.as_deref(), Some("ubuntu:24.04"));
+        let hosts: Vec<&str> = cmd
+            .bind_mounts
+            .iter()
+            .map(|m| m.host_path.as_str())
+            .collect();
+        assert_eq!(hosts, vec!["/etc", "/srv"]);
+        assert!(cmd.bind_mounts[0].read_only);
+        for flag in [
+            DangerousFlag::Privileged,
+            DangerousFlag::CapAdd("SYS_ADMIN".to_string()),
+            DangerousFlag::NetworkHost,
+            DangerousFlag::PidHost,
+            DangerousFlag::MountPropagation("rshared".to_string()),
+        ] {
+            assert!(cmd.dangerous_flags.contains(&flag), "missing {:?}", flag);
+        }
+        assert!(
+            cmd.dangerous_flags
+                .iter()
+                .any(|f| matches!(f, DangerousFlag::Device(d) if d.starts_with("/dev/sda")))
+        );
+    }
+
+    #[test]
+    fn test_container_create_defaults() {
+        let cmd = parse(
+            "POST",
+            "/containers/create",
+            json!({"Image": "alpine", "HostConfig": {"NetworkMode": "default", "Binds": null}}),
+        );
+        assert!(cmd.bind_mounts.is_empty());
+        assert!(cmd.dangerous_flags.is_empty());
+        assert_eq!(cmd.image.as_deref(), Some("alpine"));
+    }
+
+    #[test]
+    fn test_container_create_port_bindings() {
+        let cmd = parse(
+            "POST",
+            "/containers/create",
+            json!({"Image": "postgres", "HostConfig": {
+                "PortBindings": {
+                    "5432/tcp": [{"HostIp": "", "HostPort": "5432"}, {"HostIp": "::1", "HostPort": ""}],
+                    "53/udp": null
+                },
+                "PublishAllPorts": true
+            }}),
+        );
+        assert_eq!(cmd.ports, vec![":5432:5432/tcp", "[::1]::5432/tcp"]);
+        assert!(cmd.publish_all);
+        let ports: Vec<_> = cmd
+            .ports
+            .iter()
+            .map(|p| crate::published_ports::parse(p).unwrap().host_ports)
+            .collect();
+        assert_eq!(ports, vec![Some((5432, 5432)), None]);
+    }
+
+    #[test]
+    fn test_container_create_networks() {
+        let cmd = parse(
+            "POST",
+            "/containers/create",
+            json!({"Image": "alpine",
+                "HostConfig": {"NetworkMode": "app", "Dns": ["10.0.0.53"], "Links": ["db:db"]},
+                "NetworkingConfig": {"EndpointsConfig": {"app": {}, "lan": {}}}}),
+        );
+        assert_eq!(cmd.network.networks, vec!["app", "lan"]);
+        assert_eq!(cmd.network.dns, vec!["10.0.0.53"]);
+        assert_eq!(cmd.network.links, vec!["db:db"]);
+    }
+
+    #[test]
+    fn test_network_create_and_connect() {
+        let cmd = parse(
+            "POST",
+            "/v1.45/networks/create",
+            json!({"Name": "lan", "Driver": "macvlan", "Internal": true,
+                "Options": {"parent": "eth0"},
+                "IPAM": {"Config": [{"Subnet": "192.168.1.0/24"}]}}),
+        );
+        let create = cmd.network.create.unwrap();
+        assert_eq!(create.name, "lan");
+        assert_eq!(create.driver, "macvlan");
+        assert!(create.internal);
+        assert_eq!(create.options, vec!["parent=eth0"]);
+        assert_eq!(create.subnets, vec!["192.168.1.0/24"]);
+
+        let cmd = parse(
+            "POST",
+            "/networks/lan/connect",
+            json!({"Container": "web", "EndpointConfig": {}}),
+        );
+        assert_eq!(
+            cmd.subcommand,
+            DockerSubcommand::Other("network-connect".to_string())
+        );
+        assert_eq!(cmd.network.networks, vec!["lan"]);
+        assert_eq!(cmd.network.container.as_deref(), Some("web"));
+        assert!(needs_body("POST", "/v1.45/networks/lan/connect"));
+        assert!(to_docker_args("POST", "/networks/lan/connect", Some(b"{}")).is_err());
+    }
+
+    #[test]
+    fn test_volume_driver_opts_device() {
+        let cmd = parse(
+            "POST",
+            "/v1.45/volumes/create",
+            json!({"Name": "etc", "Driver": "local",
+                "DriverOpts": {"type": "none", "o": "bind", "device": "/etc"}}),
+        );
+        assert_eq!(
+            cmd.subcommand,
+            DockerSubcommand::Other("volume-create".to_string())
+        );
+        let hosts: Vec<&str> = cmd
+            .bind_mounts
+            .iter()
+            .map(|m| m.host_path.as_str())
+            .collect();
+        assert_eq!(hosts, vec!["/etc"]);
+        assert!(needs_body("POST", "/volumes/create"));
+
+        let cmd = parse(
+            "POST",
+            "/containers/create",
+            json!({"Image": "alpine", "HostConfig": {"Mounts": [
+                {"Type": "volume", "Source": "etc", "Target": "/data",
+                 "VolumeOptions": {"DriverConfig": {"Name": "local",
+                     "Options": {"type": "none", "o": "bind,ro", "device": "/etc"}}}},
+                {"Type": "volume", "Source": "cache", "Target": "/cache",
+                 "VolumeOptions": {"NoCopy": true}}
+            ]}}),
+        );
+        assert_eq!(cmd.bind_mounts.len(), 1);
+        assert_eq!(cmd.bind_mounts[0].host_path, "/etc");
+        assert_eq!(cmd.bind_mounts[0].container_path, "/data");
+        assert!(cmd.bind_mounts[0].read_only);
+    }
+
+    #[test]
+    fn test_known_safe_endpoints() {
+        assert!(is_known_safe("GET", "/v1.45/containers/json?all=1"));
+        assert!(is_known_safe("HEAD", "/_ping"));
+        assert!(is_known_safe("POST", "/v1.45/containers/web/start"));
+        assert!(is_known_safe("POST", "/exec/abc/start"));
+        assert!(is_known_safe("POST", "/images/create?fromImage=alpine"));
+        assert!(is_known_safe("POST", "/images/ghcr.io/org/app/push"));
+        assert!(is_known_safe("PUT", "/containers/web/archive?path=/tmp"));
+        assert!(is_known_safe("DELETE", "/v1.45/containers/web?force=1"));
+        assert!(is_known_safe("POST", "/session"));
+        assert!(is_known_safe("POST", "/v1.45/grpc"));
+
+        for (method, target) in [
+            ("POST", "/plugins/pull?remote=evil"),
+            ("POST", "/plugins/evil/set"),
+            ("POST", "/v1.45/services/create"),
+            ("POST", "/services/web/update"),
+            ("POST", "/containers/web/update"),
+            ("POST", "/swarm/init"),
+            ("PUT", "/containers/web/unknown"),
+            ("PATCH", "/containers/web"),
+        ] {
+            assert!(!is_known_safe(method, target), "{} {}", method, target);
+        }
+    }
+
+    #[test]
+    fn test_keys_are_case_insensitive() {
+        // dockerd の encoding/json はフィールド名の大文字・小文字を区別しない
+        let cmd = parse(
+            "POST",
+            "/containers/create",
+            json!({"image": "alpine", "hostconfig": {"privileged": true, "binds": ["/:/host"]}}),
+        );
+        assert!(cmd.dangerous_flags.contains(&DangerousFlag::Privileged));
+        assert_eq!(cmd.bind_mounts[0].host_path, "/");
+
+        let cmd = parse(
+            "POST",
+            "/containers/create",
+            json!({"IMAGE": "alpine", "hostConfig": {"CAPADD": ["SYS_ADMIN"],
+                "ſecurityOpt": ["apparmor=unconfined"],
+                "mounts": [{"type": "volume", "target": "/data",
+                    "volumeoptions": {"driverconfig": {"options": {"device": "/etc"}}}}]}}),
+        );
+        assert!(
+            cmd.dangerous_flags
+                .contains(&DangerousFlag::CapAdd("SYS_ADMIN".to_string()))
+        );
+        assert!(
+            cmd.dangerous_flags
+                .iter()
+                .any(|f| matches!(f, DangerousFlag::SecurityOpt(_)))
+        );
+        assert_eq!(cmd.bind_mounts[0].host_path, "/etc");
+
+        let cmd = parse(
+            "POST",
+            "/volumes/create",
+            json!({"name": "etc", "driveropts": {"type": "none", "o": "bind", "device": "/etc"}}),
+        );
+        assert_eq!(cmd.bind_mounts[0].host_path, "/etc");
+        let cmd = parse("POST", "/containers/web/exec", json!({"PRIVILEGED": true}));
+        assert!(cmd.dangerous_flags.contains(&DangerousFlag::Privileged));
+    }
+
+    #[test]
+    fn test_duplicate_keys_are_errors() {
+        // 綴りの異なる同じフィールドと、同じキーの重複は dockerd で値が重なる
+        for body in [
+            r#"{"Image": "alpine", "HostConfig": {"Privileged": true}, "hostconfig": {}}"#,
+            r#"{"Image": "alpine", "HostConfig": {"Privileged": true}, "HostConfig": {}}"#,
+            r#"{"Image": "alpine", "HostConfig": {"Binds": ["/:/h"], "Binds": []}}"#,
+        ] {
+            assert!(
+                to_docker_args("POST", "/containers/create", Some(body.as_bytes())).is_err(),
+                "{}",
+                body
+            );
+        }
+    }
+
+    #[test]
+    fn test_inline_seccomp_profile_is_not_a_path() {
+        let cmd = parse(
+            "POST",
+            "/containers/create",
+            json!({"Image": "alpine", "HostConfig": {"SecurityOpt": ["seccomp={\"defaultAction\":\"SCMP_ACT_ERRNO\"}"]}}),
+        );
+        assert!(cmd.host_paths.is_empty());
+        assert!(cmd.dangerous_flags.is_empty());
+    }
+
+    #[test]
+    fn test_exec_privileged() {
+        let cmd = parse(
+            "POST",
+            "/v1.45/containers/web/exec",
+            json!({"Privileged": true, "Cmd": ["sh"]}),
+        );
+        assert_eq!(cmd.subcommand, DockerSubcommand::Exec);
+        assert!(cmd.dangerous_flags.contains(&DangerousFlag::Privileged));
+    }
+
+    #[test]
+    fn test_build_query() {
+        let args = to_docker_args(
+            "POST",
+            "/v1.45/build?t=app&buildargs=%7B%22AWS_SECRET_ACCESS_KEY%22%3A%22x%22%7D",
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(args, vec!["build", "--build-arg=AWS_SECRET_ACCESS_KEY=x"]);
+        let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        let cmd = parse_docker_args(&args_ref);
+        assert!(cmd.host_paths.is_empty());
+        assert!(
+            cmd.dangerous_flags
+                .iter()
+                .any(|f| matches!(f, DangerousFlag::BuildArgSecret(_)))
+        );
+    }
+
+    #[test]
+    fn test_other_requests_are_not_inspected() {
+        assert_eq!(
+            to_docker_args("GET", "/v1.45/containers/json", None),
+            Ok(None)
+        );
+        assert_eq!(
+            to_docker_args("POST", "/containers/web/start", None),
+            Ok(None)
+        );
+        assert_eq!(to_docker_args("POST", "/exec/abc/start", None), Ok(None));
+    }
+
+    #[test]
+    fn test_uninterpretable_requests_are_errors() {
+        assert!(to_docker_args("POST", "/containers/create", Some(b"not json")).is_err());
+        assert!(
+            to_docker_args(
+                "POST",
+                "/containers/create",
+                Some(br#"{"Image": "alpine", "HostConfig": {"Binds": "/etc:/etc"}}"#)
+            )
+            .is_err()
+        );
+        assert!(to_docker_args("POST", "/containers/create", Some(b"{}")).is_err());
+        assert!(to_docker_args("POST", "/build?buildargs=%ZZ", None).is_err());
+    }
+}
@@ -23,6 +23,10 @@ pub struct HookInput {
 
     #[serde(default)]
     pub cwd: Option<String>,
+
+    /// PostToolUse のツール実行結果
+    #[serde(default)]
+    pub tool_response: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -32,6 +36,48 @@ pub struct ToolInput {
 
     #[serde(default)]
     pub description: Option<String>,
+
+    /// Write / Edit / MultiEdit ツールの対象ファイル
+    #[serde(default)]
+    pub file_path: Option<String>,
+
+    /// NotebookEdit ツールの対象ファイル
+    #[serde(default)]
+    pub notebook_path: Option<String>,
+
+    /// その他のフィールド (updatedInput で元の入力を引き継ぐため保持)
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// hook イベントの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreToolUse,
+    /// ツール実行成功後
+    PostToolUse,
+    /// ツール実行失敗後
+    PostToolUseFailure,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::PreToolUse => "PreToolUse",
+            HookEvent::PostToolUse => "PostToolUse",
+            HookEvent::PostToolUseFailure => "PostToolUseFailure",
+        }
+    }
+}
+
+/// ツール実行結果 (PostToolUse)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolResult {
+    /// 終了コード (ツール応答に含まれる場合)
+    pub exit_code: Option<i32>,
+    /// "succeeded" / "failed" / "interrupted"
+    pub outcome: &'static str,
+    pub stdout: String,
 }
 
 // --- Output structures ---
@@ -40,6 +86,10 @@ pub struct ToolInput {
 pub struct HookOutput {
     #[serde(rename = "hookSpecificOutput")]
     pub hook_specific_output: HookSpecificOutput,
+
+    /// ユーザーに表示されるメッセージ
+    #[serde(rename = "systemMessage", skip_serializing_if = "Option::is_none")]
+    pub system_message: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -52,6 +102,14 @@ pub struct HookSpecificOutput {
 
     #[serde(rename = "permissionDecisionReason")]
     pub permission_decision_reason: String,
+
+    /// エージェントのコンテキストに追加されるテキスト
+    #[serde(rename = "additionalContext", skip_serializing_if = "Option::is_none")]
+    pub additional_context: Option<String>,
+
+    /// 書き換え後のツール入力
+    #[serde(rename = "updatedInput", skip_serializing_if = "Option::is_none")]
+    pub updated_input: Option<serde_json::Value>,
 }
 
 // --- Decision types ---
@@ -63,10 +121,37 @@ pub enum Decision {
     Ask(String),
 }
 
+/// hook レスポンス (Decision と付加情報)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookResponse {
+    pub decision: Decision,
+    /// Allow を明示的に返す場合の理由 (auto_approve)。None の場合 Allow は何も出力しない
+    pub allow_reason: Option<String>,
+    /// hookSpecificOutput.additionalContext
+    pub additional_context: Option<String>,
+    /// systemMessage
+    pub system_message: Option<String>,
+    /// hookSpecificOutput.updatedInput (コマンド書き換え)
+    pub updated_input: Option<serde_json::Value>,
+}
+
+impl HookResponse {
+    pub fn from_decision(decision: Decision) -> Self {
+        Self {
+            decision,
+            allow_reason: None,
+            additional_context: None,
+            system_message: None,
+            updated_input: None,
+        }
+    }
+}
+
 // --- Functions ---
 
-/// stdin から hook 入力 JSON を読み取る
-pub fn read_input() -> Result<HookInput> {
+/// stdin から hook 入力 JSON を読み取る。
+/// エージェントごとに形式が異なるため、生の JSON として返す (変換は adapter で行う)。
+pub fn read_input() -> Result<serde_json::Value> {
     let mut input = String::with_capacity(256);
     let stdin = io::stdin();
     let mut handle = stdin.lock().take(MAX_INPUT_BYTES as u64 + 1);
@@ -76,8 +161,7 @@ pub fn read_input() -> Result<HookInput> {
         return Err(SafeDockerError::InputTooLarge(input.len()));
     }
 
-    let hook_input: HookInput = serde_json::from_str(&input)?;
-    Ok(hook_input)
+    Ok(serde_json::from_str(&input)?)
 }
 
 /// Bash ツール呼び出しからコマンド文字列を抽出する。
@@ -93,34 +177,134 @@ pub fn extract_command(input: &HookInput) -> Option<&str> {
         .and_then(|ti| ti.command.as_deref())
 }
 
-/// deny 判定結果を stdout に JSON 出力する
-pub fn output_deny(reason: &str) {
-    let output = HookOutput {
-        hook_specific_output: HookSpecificOutput {
-            hook_event_name: "PreToolUse".to_string(),
-            permission_decision: "deny".to_string(),
-            permission_decision_reason: reason.to_string(),
-        },
+/// 入力の hook イベントを判定する。未指定の場合は PreToolUse とみなす (後方互換)。
+pub fn hook_event(input: &HookInput) -> HookEvent {
+    match input.hook_event_name.as_deref() {
+        Some("PostToolUse") => HookEvent::PostToolUse,
+        Some("PostToolUseFailure") => HookEvent::PostToolUseFailure,
+        _ => HookEvent::PreToolUse,
+    }
+}
+
+/// PostToolUse の tool_response から実行結果を抽出する
+pub fn tool_result(input: &HookInput) -> ToolResult {
+    let response = input.tool_response.as_ref();
+    let field = |name: &str| response.and_then(|r| r.get(name));
+
+    let stdout = match response {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        _ => field("stdout")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+    };
+    let exit_code = ["exit_code", "exitCode", "returnCode"]
+        .iter()
+        .find_map(|name| field(name).and_then(|v| v.as_i64()))
+        .map(|c| c as i32);
+    let interrupted = field("interrupted").and_then(|v| v.as_bool()) == Some(true);
+
+    let outcome = if interrupted {
+        "interrupted"
+    } else if hook_event(input) == HookEvent::PostToolUseFailure
+        || exit_code.is_some_and(|c| c != 0)
+    {
+        "failed"
+    } else {
+        "succeeded"
+    };
+
+    ToolResult {
+        exit_code,
+        outcome,
+        stdout,
+    }
+}
+
+/// ファイルを書き換えるツール
+const FILE_WRITE_TOOLS: &[&str] = &["write", "edit", "multiedit", "notebookedit"];
+
+/// Write / Edit 等のファイル書き換えツール呼び出しから対象ファイルパスを抽出する。
+/// 該当しないツールの場合は空を返す。
+pub fn extract_file_paths(input: &HookInput) -> Vec<&str> {
+    let Some(tool_name) = input.tool_name.as_deref() else {
+        return Vec::new();
+    };
+    if !FILE_WRITE_TOOLS
+        .iter()
+        .any(|t| tool_name.eq_ignore_ascii_case(t))
+    {
+        return Vec::new();
+    }
+    let Some(ti) = input.tool_input.as_ref() else {
+        return Vec::new();
     };
+    [ti.file_path.as_deref(), ti.notebook_path.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// HookOutput を stdout に JSON 出力する
+fn write_output(output: &HookOutput) {
     let stdout = io::stdout();
     let mut handle = stdout.lock();
-    let _ = serde_json::to_writer(&mut handle, &output);
+    let _ = serde_json::to_writer(&mut handle, output);
     let _ = writeln!(handle);
 }
 
-/// ask 判定結果を stdout に JSON 出力する
-pub fn output_ask(reason: &str) {
-    let output = HookOutput {
+fn simple_output(permission_decision: &str, reason: &str) -> HookOutput {
+    HookOutput {
         hook_specific_output: HookSpecificOutput {
             hook_event_name: "PreToolUse".to_string(),
-            permission_decision: "ask".to_string(),
+            permission_decision: permission_decision.to_string(),
             permission_decision_reason: reason.to_string(),
+            additional_context: None,
+            updated_input: None,
         },
-    };
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
-    let _ = serde_json::to_writer(&mut handle, &output);
-    let _ = writeln!(handle);
+        system_message: None,
+    }
+}
+
+/// ツール入力を JSON オブジェクトとして復元する
+pub fn tool_input_json(input: &HookInput) -> serde_json::Map<String, serde_json::Value> {
+    let mut map = serde_json::Map::new();
+    if let Some(ti) = input.tool_input.as_ref() {
+        map.extend(ti.extra.clone());
+        let fields = [
+            ("command", &ti.command),
+            ("description", &ti.description),
+            ("file_path", &ti.file_path),
+            ("notebook_path", &ti.notebook_path),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                map.insert(key.to_string(), serde_json::Value::String(value.clone()));
+            }
+        }
+    }
+    map
+}
+
+/// Bash ツールの入力のコマンドを置き換えた updatedInput を構築する
+/// (description, timeout 等の他のフィールドは引き継ぐ)
+pub fn updated_bash_input(input: &HookInput, command: &str) -> serde_json::Value {
+    let mut map = tool_input_json(input);
+    map.insert(
+        "command".to_string(),
+        serde_json::Value::String(command.to_string()),
+    );
+    serde_json::Value::Object(map)
+}
+
+/// deny 判定結果を stdout に JSON 出力する
+pub fn output_deny(reason: &str) {
+    write_output(&simple_output("deny", reason));
+}
+
+/// ask 判定結果を stdout に JSON 出力する
+pub fn output_ask(reason: &str) {
+    write_output(&simple_output("ask", reason));
 }
 
 /// Decision に基づいて出力する。Allow の場合は何も出力しない。
@@ -132,6 +316,27 @@ pub fn output_decision(decision: &Decision) {
     }
 }
 
+/// HookResponse から出力 JSON を構築する。出力しない場合 (暗黙の Allow) は None。
+pub fn build_output(response: &HookResponse) -> Option<HookOutput> {
+    let (permission_decision, reason) = match &response.decision {
+        Decision::Allow => ("allow", response.allow_reason.as_deref()?),
+        Decision::Deny(reason) => ("deny", reason.as_str()),
+        Decision::Ask(reason) => ("ask", reason.as_str()),
+    };
+    let mut output = simple_output(permission_decision, reason);
+    output.hook_specific_output.additional_context = response.additional_context.clone();
+    output.hook_specific_output.updated_input = response.updated_input.clone();
+    output.system_message = response.system_message.clone();
+    Some(output)
+}
+
+/// HookResponse に基づいて出力する
+pub fn output_response(response: &HookResponse) {
+    if let Some(output) = build_output(response) {
+        write_output(&output);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,8 +350,12 @@ mod tests {
             tool_input: Some(ToolInput {
                 command: Some("docker run ubuntu".to_string()),
                 description: None,
+                file_path: None,
+                notebook_path: None,
+                extra: Default::default(),
             }),
             cwd: None,
+            tool_response: None,
         };
         assert_eq!(extract_command(&input), Some("docker run ubuntu"));
     }
@@ -159,6 +368,7 @@ mod tests {
             tool_name: Some("Read".to_string()),
             tool_input: None,
             cwd: None,
+            tool_response: None,
         };
         assert_eq!(extract_command(&input), None);
     }
@@ -172,8 +382,12 @@ mod tests {
             tool_input: Some(ToolInput {
                 command: None,
                 description: None,
+                file_path: None,
+                notebook_path: None,
+                extra: Default::default(),
             }),
             cwd: None,
+            tool_response: None,
         };
         assert_eq!(extract_command(&input), None);
     }
@@ -198,6 +412,122 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extract_file_paths_write_tools() {
+        let json = r#"{
+            "tool_name": "Edit",
+            "tool_input": {"file_path": "/home/user/.claude/settings.json", "old_string": "a", "new_string": "b"}
+        }"#;
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            extract_file_paths(&input),
+            vec!["/home/user/.claude/settings.json"]
+        );
+        assert_eq!(extract_command(&input), None);
+
+        let json =
+            r#"{"tool_name": "NotebookEdit", "tool_input": {"notebook_path": "/tmp/a.ipynb"}}"#;
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        assert_eq!(extract_file_paths(&input), vec!["/tmp/a.ipynb"]);
+    }
+
+    #[test]
+    fn test_extract_file_paths_other_tools() {
+        let json = r#"{"tool_name": "Read", "tool_input": {"file_path": "/tmp/a"}}"#;
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        assert!(extract_file_paths(&input).is_empty());
+    }
+
+    #[test]
+    fn test_build_output_implicit_allow() {
+        let response = HookResponse::from_decision(Decision::Allow);
+        assert!(build_output(&response).is_none());
+    }
+
+    #[test]
+    fn test_build_output_explicit_allow_with_context() {
+        let response = HookResponse {
+            decision: Decision::Allow,
+            allow_reason: Some("auto-approved".to_string()),
+            additional_context: Some("checked".to_string()),
+            system_message: Some("msg".to_string()),
+            updated_input: None,
+        };
+        let json = serde_json::to_value(build_output(&response).unwrap()).unwrap();
+        assert_eq!(json["hookSpecificOutput"]["permissionDecision"], "allow");
+        assert_eq!(
+            json["hookSpecificOutput"]["permissionDecisionReason"],
+            "auto-approved"
+        );
+        assert_eq!(json["hookSpecificOutput"]["additionalContext"], "checked");
+        assert_eq!(json["systemMessage"], "msg");
+    }
+
+    #[test]
+    fn test_build_output_omits_empty_fields() {
+        let response = HookResponse::from_decision(Decision::Deny("no".to_string()));
+        let json = serde_json::to_value(build_output(&response).unwrap()).unwrap();
+        assert_eq!(json["hookSpecificOutput"]["permissionDecision"], "deny");
+        assert!(
+            json["hookSpecificOutput"]
+                .get("additionalContext")
+                .is_none()
+        );
+        assert!(json.get("systemMessage").is_none());
+    }
+
+    #[test]
+    fn test_updated_bash_input_keeps_other_fields() {
+        let json = r#"{
+            "tool_name": "Bash",
+            "tool_input": {"command": "docker run ubuntu", "description": "Run", "timeout": 60000}
+        }"#;
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        let updated = updated_bash_input(&input, "docker run --cap-drop ALL ubuntu");
+        assert_eq!(updated["command"], "docker run --cap-drop ALL ubuntu");
+        assert_eq!(updated["description"], "Run");
+        assert_eq!(updated["timeout"], 60000);
+    }
+
+    #[test]
+    fn test_hook_event() {
+        let input: HookInput =
+            serde_json::from_str(r#"{"hook_event_name": "PostToolUse"}"#).unwrap();
+        assert_eq!(hook_event(&input), HookEvent::PostToolUse);
+        let input: HookInput = serde_json::from_str(r#"{"tool_name": "Bash"}"#).unwrap();
+        assert_eq!(hook_event(&input), HookEvent::PreToolUse);
+    }
+
+    #[test]
+    fn test_tool_result_success() {
+        let json = r#"{
+            "hook_event_name": "PostToolUse",
+            "tool_name": "Bash",
+            "tool_response": {"stdout": "abc\n", "stderr": "", "interrupted": false}
+        }"#;
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        let result = tool_result(&input);
+        assert_eq!(result.outcome, "succeeded");
+        assert_eq!(result.exit_code, None);
+        assert_eq!(result.stdout, "abc\n");
+    }
+
+    #[test]
+    fn test_tool_result_failure() {
+        let json = r#"{
+            "hook_event_name": "PostToolUse",
+            "tool_response": {"stdout": "", "exit_code": 125}
+        }"#;
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        let result = tool_result(&input);
+        assert_eq!(result.outcome, "failed");
+        assert_eq!(result.exit_code, Some(125));
+
+        let json = r#"{"hook_event_name": "PostToolUseFailure", "error": "Exit code 1"}"#;
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        assert_eq!(tool_result(&input).outcome, "failed");
+    }
+
     #[test]
     fn test_decision_deny_output() {
         // Just verify it doesn't panic
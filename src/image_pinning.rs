@@ -0,0 +1,295 @@
+//! イメージのピン留めポリシー (`[image_pinning]`)
+//!
+//! `latest` やタグなしのイメージ、require_digest のレジストリでダイジェストのない
+//! イメージを検出する。対象は docker run / create のイメージ、compose の `image:`、
+//! ビルドする Dockerfile の `FROM` / `COPY --from`、buildx bake のターゲット。
+
+use std::path::Path;
+
+use crate::compose::{self, ComposeAnalysis};
+use crate::config::{ImagePinningConfig, PinningAction};
+use crate::docker_args::{DockerCommand, DockerSubcommand};
+use crate::image_ref::{self, ImageRef};
+use crate::{bake, dockerfile};
+
+/// 判定するイメージ参照と、その参照元
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageUse {
+    /// 書かれているとおりのイメージ参照
+    pub image: String,
+    /// 参照元 (`docker run`, `compose service 'web'`, `./Dockerfile:3 FROM` 等)
+    pub origin: String,
+}
+
+/// ピン留めポリシーの違反
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub action: PinningAction,
+    pub reason: String,
+}
+
+/// docker コマンドが参照するイメージ (run / create のイメージ、build / bake の Dockerfile)
+pub fn command_images(cmd: &DockerCommand, cwd: &str) -> Vec<ImageUse> {
+    let cwd = Path::new(cwd);
+    let mut uses = Vec::new();
+    if matches!(
+        cmd.subcommand,
+        DockerSubcommand::Run | DockerSubcommand::Create
+    ) && let Some(image) = &cmd.image
+    {
+        uses.push(ImageUse {
+            image: image.clone(),
+            origin: format!("docker {}", cmd.subcommand),
+        });
+    }
+
+    let Some(build) = &cmd.build else {
+        return uses;
+    };
+    if build.bake {
+        for file in bake::bake_files(&build.files, cwd) {
+            if file.extension().is_some_and(|e| e == "yml" || e == "yaml") {
+                if let Ok(analysis) = compose::analyze_compose(&file) {
+                    uses.extend(compose_images(&analysis));
+                }
+                continue;
+            }
+            let Ok(targets) = bake::parse_file(&file) else {
+                continue;
+            };
+            for target in targets {
+                let origin = format!("bake target '{}'", target.name);
+                uses.extend(target.image_contexts.iter().map(|image| ImageUse {
+                    image: image.clone(),
+                    origin: format!("{} contexts", origin),
+                }));
+                uses.extend(dockerfile_images(
+                    &target.dockerfile_path(cwd),
+                    &format!("{}, ", origin),
+                ));
+            }
+        }
+    } else if let Some(path) = build.dockerfile_path(cwd) {
+        uses.extend(dockerfile_images(&path, ""));
+    }
+    uses
+}
+
+/// compose のサービスが参照するイメージ (build を持つサービスは Dockerfile の FROM)
+pub fn compose_images(analysis: &ComposeAnalysis) -> Vec<ImageUse> {
+    let mut uses: Vec<ImageUse> = analysis
+        .images
+        .iter()
+        .map(|(service, image)| ImageUse {
+            image: image.clone(),
+            origin: format!("compose service '{}'", service),
+        })
+        .collect();
+    for (service, path) in &analysis.dockerfiles {
+        uses.extend(dockerfile_images(
+            Path::new(path),
+            &format!("compose service '{}', ", service),
+        ));
+    }
+    uses
+}
+
+/// Dockerfile の FROM / COPY --from のイメージ (読めない場合は空)
+fn dockerfile_images(path: &Path, origin_prefix: &str) -> Vec<ImageUse> {
+    let Ok(content) = dockerfile::read(path) else {
+        return Vec::new();
+    };
+    dockerfile::image_references(&dockerfile::parse(&content))
+        .into_iter()
+        .map(|r| ImageUse {
+            image: r.image,
+            origin: format!(
+                "{}{}:{} {}",
+                origin_prefix,
+                path.display(),
+                r.line,
+                r.instruction
+            ),
+        })
+        .collect()
+}
+
+/// ピン留めポリシーでイメージを検査する
+pub fn check(uses: &[ImageUse], config: &ImagePinningConfig) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    for use_ in uses {
+        let Some(finding) = check_one(use_, config) else {
+            continue;
+        };
+        if !findings.contains(&finding) {
+            findings.push(finding);
+        }
+    }
+    findings
+}
+
+fn check_one(use_: &ImageUse, config: &ImagePinningConfig) -> Option<Finding> {
+    let ImageUse { image, origin } = use_;
+    let image_ref = match ImageRef::parse(image) {
+        Ok(r) => r,
+        Err(e) => {
+            // ARG が解決できない等。有効な検査のうち厳しい方の扱いにする
+            let action = config
+                .mutable_tags
+                .max(if config.require_digest.is_empty() {
+                    PinningAction::Allow
+                } else {
+                    config.missing_digest
+                });
+            return (action != PinningAction::Allow).then(|| Finding {
+                action,
+                reason: format!(
+                    "Image reference '{}' ({}) cannot be checked for pinning: {}. Use a literal reference pinned by digest",
+                    image, origin, e
+                ),
+            });
+        }
+    };
+    if image_ref.digest.is_some() {
+        return None;
+    }
+
+    if config.missing_digest != PinningAction::Allow
+        && config
+            .require_digest
+            .iter()
+            .any(|r| image_ref::registry_matches(r, &image_ref.registry))
+    {
+        return Some(Finding {
+            action: config.missing_digest,
+            reason: format!(
+                "Image '{}' ({}) is not pinned by digest, which image_pinning.require_digest requires for {}. Pin it as {}@sha256:<digest>",
+                image, origin, image_ref.registry, image
+            ),
+        });
+    }
+
+    if config.mutable_tags != PinningAction::Allow {
+        let problem = match image_ref.tag.as_deref() {
+            None => "is untagged (resolves to the mutable 'latest' tag)",
+            Some("latest") => "uses the mutable 'latest' tag",
+            Some(_) => return None,
+        };
+        return Some(Finding {
+            action: config.mutable_tags,
+            reason: format!(
+                "Image '{}' ({}) {}. Pin a version tag or digest (e.g. {}:<version>@sha256:<digest>)",
+                image,
+                origin,
+                problem,
+                image.split(':').next().unwrap_or(image)
+            ),
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_args::parse_docker_args;
+
+    const DIGEST: &str = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    fn use_(image: &str) -> ImageUse {
+        ImageUse {
+            image: image.to_string(),
+            origin: "docker run".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_check_mutable_tags() {
+        let config = ImagePinningConfig {
+            mutable_tags: PinningAction::Ask,
+            ..Default::default()
+        };
+        let findings = check(
+            &[
+                use_("ubuntu"),
+                use_("ubuntu:latest"),
+                use_("ubuntu:24.04"),
+                use_(&format!("ubuntu@{}", DIGEST)),
+                use_("ubuntu"),
+            ],
+            &config,
+        );
+        assert_eq!(findings.len(), 2);
+        assert!(
+            findings[0]
+                .reason
+                .contains("'ubuntu' (docker run) is untagged")
+        );
+        assert!(findings[1].reason.contains("mutable 'latest' tag"));
+        assert!(
+            findings[1]
+                .reason
+                .contains("ubuntu:<version>@sha256:<digest>")
+        );
+        assert!(findings.iter().all(|f| f.action == PinningAction::Ask));
+    }
+
+    #[test]
+    fn test_check_require_digest() {
+        let config = ImagePinningConfig {
+            require_digest: vec!["ghcr.io".to_string()],
+            missing_digest: PinningAction::Deny,
+            ..Default::default()
+        };
+        let findings = check(
+            &[
+                use_("ghcr.io/ourorg/app:1.0"),
+                use_(&format!("ghcr.io/ourorg/app:1.0@{}", DIGEST)),
+                use_("ubuntu"),
+                use_("node:$VERSION"),
+            ],
+            &config,
+        );
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].action, PinningAction::Deny);
+        assert!(
+            findings[0]
+                .reason
+                .contains("ghcr.io/ourorg/app:1.0@sha256:<digest>")
+        );
+        assert!(findings[1].reason.contains("cannot be checked for pinning"));
+    }
+
+    #[test]
+    fn test_command_images_from_build_and_bake() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir(root.join("app")).unwrap();
+        std::fs::write(
+            root.join("app/Dockerfile"),
+            "FROM golang:1.22 AS build\nFROM gcr.io/distroless/static\nCOPY --from=build /app /app\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("docker-bake.hcl"),
+            "target \"app\" {\n  context = \"app\"\n  contexts = {\n    tools = \"docker-image://alpine\"\n  }\n}\n",
+        )
+        .unwrap();
+        let cwd = root.to_string_lossy();
+
+        let cmd = parse_docker_args(&["build", "-t", "x", "app"]);
+        let images: Vec<String> = command_images(&cmd, &cwd)
+            .into_iter()
+            .map(|u| u.image)
+            .collect();
+        assert_eq!(images, vec!["golang:1.22", "gcr.io/distroless/static"]);
+
+        let cmd = parse_docker_args(&["buildx", "bake"]);
+        let uses = command_images(&cmd, &cwd);
+        assert_eq!(uses.len(), 3);
+        assert_eq!(uses[0].image, "alpine");
+        assert_eq!(uses[0].origin, "bake target 'app' contexts");
+        assert!(uses[2].origin.starts_with("bake target 'app', "));
+        assert!(uses[2].origin.ends_with("Dockerfile:2 FROM"));
+    }
+}
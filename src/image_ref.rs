@@ -0,0 +1,351 @@
+//! イメージ参照 (`[registry/][namespace/]repo[:tag][@digest]`) の解析と正規化
+//!
+//! - `ubuntu` と `docker.io/library/ubuntu` は同じイメージとして扱う
+//! - 先頭のパス要素が `.` / `:` を含むか `localhost` の場合はレジストリ (`localhost:5000/app`)
+//! - `allowed_images` / `denied_images` のパターンは名前を [`path_pattern`] で比較し、
+//!   タグ (`ubuntu:2*`) やダイジェスト (`app@sha256:...`) も指定できる
+
+use std::fmt;
+
+use crate::path_pattern;
+
+/// レジストリ省略時のレジストリ
+pub const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// Docker Hub の別名 (正規化で DEFAULT_REGISTRY にそろえる)
+const DOCKER_HUB_ALIASES: &[&str] = &["index.docker.io", "registry-1.docker.io"];
+
+/// Docker Hub の公式イメージの名前空間
+const OFFICIAL_NAMESPACE: &str = "library";
+
+/// 正規化したイメージ参照
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRef {
+    /// レジストリ (`docker.io`, `ghcr.io`, `localhost:5000` 等)
+    pub registry: String,
+    /// レジストリとリポジトリ名の間のパス (Docker Hub の公式イメージは `library`、なければ空)
+    pub namespace: String,
+    /// リポジトリ名 (パスの最後の要素)
+    pub repo: String,
+    /// タグ (省略時は None)
+    pub tag: Option<String>,
+    /// ダイジェスト (`sha256:...`)
+    pub digest: Option<String>,
+}
+
+impl ImageRef {
+    /// イメージ参照を解析して正規化する
+    pub fn parse(reference: &str) -> Result<Self, String> {
+        if reference.is_empty() {
+            return Err("empty image reference".to_string());
+        }
+        let (rest, digest) = split_digest(reference);
+        if let Some(digest) = digest {
+            validate_digest(digest)?;
+        }
+        let (name, tag) = split_tag(rest);
+        if let Some(tag) = tag {
+            validate_tag(tag)?;
+        }
+        let (registry, path) = split_registry(name);
+        if registry.is_empty()
+            || !registry
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+        {
+            return Err(format!("invalid registry '{}'", registry));
+        }
+        for component in path.split('/') {
+            validate_path_component(component)?;
+        }
+
+        let registry = normalize_registry(registry);
+        let path = if registry == DEFAULT_REGISTRY && !path.contains('/') {
+            format!("{}/{}", OFFICIAL_NAMESPACE, path)
+        } else {
+            path.to_string()
+        };
+        let (namespace, repo) = match path.rsplit_once('/') {
+            Some((namespace, repo)) => (namespace.to_string(), repo.to_string()),
+            None => (String::new(), path),
+        };
+        Ok(Self {
+            registry,
+            namespace,
+            repo,
+            tag: tag.map(String::from),
+            digest: digest.map(String::from),
+        })
+    }
+
+    /// タグ・ダイジェストを除いた正規化済みの名前 (`docker.io/library/ubuntu`)
+    pub fn name(&self) -> String {
+        if self.namespace.is_empty() {
+            format!("{}/{}", self.registry, self.repo)
+        } else {
+            format!("{}/{}/{}", self.registry, self.namespace, self.repo)
+        }
+    }
+
+    /// 実際に使われるタグ (タグもダイジェストもない場合は `latest`)
+    pub fn effective_tag(&self) -> Option<&str> {
+        match (&self.tag, &self.digest) {
+            (Some(tag), _) => Some(tag),
+            (None, None) => Some("latest"),
+            (None, Some(_)) => None,
+        }
+    }
+}
+
+impl fmt::Display for ImageRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{}", tag)?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{}", digest)?;
+        }
+        Ok(())
+    }
+}
+
+/// allowed_images / denied_images のパターン
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImagePattern {
+    /// 正規化した名前のパターン (`docker.io/library/ubuntu`, `ghcr.io/ourorg/*`)
+    pub name: String,
+    /// タグのパターン (None はすべてのタグ)
+    pub tag: Option<String>,
+    /// ダイジェスト (None はすべてのダイジェスト)
+    pub digest: Option<String>,
+}
+
+impl ImagePattern {
+    /// パターンを解析する。名前はイメージ参照と同じ規則で正規化する
+    /// (`**` で始まるパターンはレジストリを問わないため正規化しない)
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let (rest, digest) = split_digest(pattern);
+        if let Some(digest) = digest {
+            validate_digest(digest)?;
+        }
+        let (name, tag) = split_tag(rest);
+        if tag.is_some_and(str::is_empty) {
+            return Err("empty tag pattern".to_string());
+        }
+        path_pattern::validate(name)?;
+        if name.starts_with('!') {
+            return Err("negation is not supported in image patterns".to_string());
+        }
+
+        let name = if name.starts_with("**") {
+            name.to_string()
+        } else {
+            let (registry, path) = split_registry(name);
+            let registry = normalize_registry(registry);
+            if registry == DEFAULT_REGISTRY && !path.contains('/') {
+                format!("{}/{}/{}", registry, OFFICIAL_NAMESPACE, path)
+            } else {
+                format!("{}/{}", registry, path)
+            }
+        };
+        Ok(Self {
+            name,
+            tag: tag.map(String::from),
+            digest: digest.map(String::from),
+        })
+    }
+
+    /// イメージがパターンに一致するか
+    pub fn matches(&self, image: &ImageRef) -> bool {
+        if !path_pattern::matches_exact(&self.name, &image.name()) {
+            return false;
+        }
+        if let Some(pattern) = &self.tag {
+            match image.effective_tag() {
+                Some(tag) if path_pattern::match_component(pattern, tag) => {}
+                _ => return false,
+            }
+        }
+        match &self.digest {
+            Some(digest) => image.digest.as_ref() == Some(digest),
+            None => true,
+        }
+    }
+}
+
+/// レジストリがパターン (`ghcr.io`, `*.example.com`, `localhost:*`) に一致するか
+pub fn registry_matches(pattern: &str, registry: &str) -> bool {
+    path_pattern::match_component(&normalize_registry(pattern), registry)
+}
+
+/// `@` 以降のダイジェストを分ける
+fn split_digest(reference: &str) -> (&str, Option<&str>) {
+    match reference.split_once('@') {
+        Some((rest, digest)) => (rest, Some(digest)),
+        None => (reference, None),
+    }
+}
+
+/// 最後の `/` より後ろの `:` 以降をタグとして分ける (`localhost:5000/app` のポートはタグではない)
+fn split_tag(name: &str) -> (&str, Option<&str>) {
+    let last_slash = name.rfind('/').map_or(0, |i| i + 1);
+    match name[last_slash..].rfind(':') {
+        Some(i) => (&name[..last_slash + i], Some(&name[last_slash + i + 1..])),
+        None => (name, None),
+    }
+}
+
+/// 先頭のパス要素がレジストリならレジストリとパスに分ける
+fn split_registry(name: &str) -> (&str, &str) {
+    match name.split_once('/') {
+        Some((first, rest))
+            if first.contains('.') || first.contains(':') || first == "localhost" =>
+        {
+            (first, rest)
+        }
+        _ => (DEFAULT_REGISTRY, name),
+    }
+}
+
+fn normalize_registry(registry: &str) -> String {
+    let registry = registry.to_ascii_lowercase();
+    if DOCKER_HUB_ALIASES.contains(&registry.as_str()) {
+        DEFAULT_REGISTRY.to_string()
+    } else {
+        registry
+    }
+}
+
+/// リポジトリのパス要素 (小文字英数字を `.` `_` `-` で区切ったもの)
+fn validate_path_component(component: &str) -> Result<(), String> {
+    let valid = !component.is_empty()
+        && component
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-'))
+        && component.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && component.ends_with(|c: char| c.is_ascii_alphanumeric());
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid repository name component '{}' (use lowercase letters, digits, '.', '_' and '-')",
+            component
+        ))
+    }
+}
+
+fn validate_tag(tag: &str) -> Result<(), String> {
+    let valid = !tag.is_empty()
+        && tag.len() <= 128
+        && !tag.starts_with(['.', '-'])
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("invalid tag '{}'", tag))
+    }
+}
+
+fn validate_digest(digest: &str) -> Result<(), String> {
+    let valid = digest.split_once(':').is_some_and(|(algorithm, hex)| {
+        !algorithm.is_empty()
+            && algorithm.chars().all(|c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '+' | '.' | '_' | '-')
+            })
+            && hex.len() >= 32
+            && hex.chars().all(|c| c.is_ascii_hexdigit())
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("invalid digest '{}'", digest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_parse_and_normalize() {
+        let image = ImageRef::parse("ubuntu").unwrap();
+        assert_eq!(image.name(), "docker.io/library/ubuntu");
+        assert_eq!(image.tag, None);
+        assert_eq!(image.effective_tag(), Some("latest"));
+        assert_eq!(ImageRef::parse("docker.io/library/ubuntu").unwrap(), image);
+        assert_eq!(ImageRef::parse("index.docker.io/ubuntu").unwrap(), image);
+
+        let image = ImageRef::parse("localhost:5000/app:1.0").unwrap();
+        assert_eq!(image.registry, "localhost:5000");
+        assert_eq!(image.namespace, "");
+        assert_eq!(image.repo, "app");
+        assert_eq!(image.tag.as_deref(), Some("1.0"));
+
+        let image = ImageRef::parse(&format!("ghcr.io/org/team/app@{}", DIGEST)).unwrap();
+        assert_eq!(image.registry, "ghcr.io");
+        assert_eq!(image.namespace, "org/team");
+        assert_eq!(image.repo, "app");
+        assert_eq!(image.digest.as_deref(), Some(DIGEST));
+        assert_eq!(image.effective_tag(), None);
+        assert_eq!(
+            image.to_string(),
+            format!("ghcr.io/org/team/app@{}", DIGEST)
+        );
+
+        let image = ImageRef::parse("bitnami/redis:7.2").unwrap();
+        assert_eq!(image.to_string(), "docker.io/bitnami/redis:7.2");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(ImageRef::parse("").is_err());
+        assert!(ImageRef::parse("Ubuntu").is_err());
+        assert!(ImageRef::parse("ubuntu:").is_err());
+        assert!(ImageRef::parse("ubuntu@sha256:xyz").is_err());
+        assert!(ImageRef::parse("org//app").is_err());
+        assert!(ImageRef::parse("-app").is_err());
+    }
+
+    #[test]
+    fn test_pattern_matches() {
+        let image = |s: &str| ImageRef::parse(s).unwrap();
+        let pattern = |s: &str| ImagePattern::parse(s).unwrap();
+
+        assert!(pattern("ubuntu").matches(&image("docker.io/library/ubuntu:22.04")));
+        assert!(!pattern("ubuntu").matches(&image("ghcr.io/ubuntu")));
+        assert!(pattern("ghcr.io/ourorg/*").matches(&image("ghcr.io/ourorg/app:1")));
+        assert!(!pattern("ghcr.io/ourorg/*").matches(&image("ghcr.io/other/app")));
+        assert!(pattern("ghcr.io/**").matches(&image("ghcr.io/a/b/c")));
+        assert!(pattern("**/redis").matches(&image("quay.io/org/redis")));
+        assert!(pattern("localhost:5000/*").matches(&image("localhost:5000/app:1.0")));
+
+        // タグのパターン (タグ省略は latest)
+        assert!(pattern("node:20-*").matches(&image("node:20-alpine")));
+        assert!(!pattern("node:20-*").matches(&image("node:18")));
+        assert!(pattern("node:latest").matches(&image("node")));
+        assert!(!pattern("node:20-*").matches(&image(&format!("node@{}", DIGEST))));
+
+        // ダイジェスト
+        let pinned = pattern(&format!("ghcr.io/ourorg/app@{}", DIGEST));
+        assert!(pinned.matches(&image(&format!("ghcr.io/ourorg/app:1@{}", DIGEST))));
+        assert!(!pinned.matches(&image("ghcr.io/ourorg/app:1")));
+
+        assert!(ImagePattern::parse("ghcr.io/[abc").is_err());
+        assert!(ImagePattern::parse("!ubuntu").is_err());
+        assert!(ImagePattern::parse("node:").is_err());
+    }
+
+    #[test]
+    fn test_registry_matches() {
+        assert!(registry_matches("ghcr.io", "ghcr.io"));
+        assert!(registry_matches("*.example.com", "registry.example.com"));
+        assert!(registry_matches("index.docker.io", "docker.io"));
+        assert!(registry_matches("localhost:*", "localhost:5000"));
+        assert!(!registry_matches("ghcr.io", "docker.io"));
+    }
+}
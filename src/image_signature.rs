@@ -0,0 +1,684 @@
+//! イメージ署名の検証 (`[image_signatures]`)
+//!
+//! docker run / create と compose のサービスのイメージについて、cosign 形式の署名
+//! (simple signing) または DSSE 形式のアテステーション (in-toto) を、設定した公開鍵で
+//! オフラインで検証する。
+//!
+//! - 署名は `cosign save` 等で保存した OCI イメージレイアウト (`image_signatures.layouts`) から読む
+//! - イメージのダイジェストは、参照の `@sha256:` か、docker が実行するローカルのイメージの
+//!   `docker image inspect` の RepoDigests から求める (ネットワークにアクセスしない)。
+//!   レイアウトのタグはローカルのイメージと一致する保証がないため使わない
+//! - 公開鍵は ECDSA P-256 (PEM の `PUBLIC KEY`) のみ。keyless (Fulcio / Rekor) は扱わない
+//! - 暗号処理は `cosign` feature でビルドした場合のみ有効。無効の場合はすべて検証に失敗する (fail-closed)
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::compose::ComposeAnalysis;
+use crate::config::{Config, PinningAction};
+use crate::docker_args::{DockerCommand, DockerSubcommand};
+use crate::image_pinning::{Finding, ImageUse};
+use crate::image_ref::{self, ImageRef};
+use crate::path_validator;
+
+/// cosign の署名レイヤー (ペイロードは simple signing の JSON)
+const SIMPLE_SIGNING_MEDIA_TYPE: &str = "application/vnd.dev.cosign.simplesigning.v1+json";
+/// cosign のアテステーションレイヤー (DSSE エンベロープ)
+const DSSE_MEDIA_TYPE: &str = "application/vnd.dsse.envelope.v1+json";
+/// 署名レイヤーの署名 (base64) を持つアノテーション
+const SIGNATURE_ANNOTATION: &str = "dev.cosignproject.cosign/signature";
+
+/// 読み込む blob の上限サイズ
+const MAX_BLOB_SIZE: u64 = 4 * 1024 * 1024;
+/// 1 つのレイアウトから読み込むマニフェストの上限
+const MAX_MANIFESTS: usize = 1024;
+/// 入れ子のイメージインデックスをたどる深さの上限
+const MAX_INDEX_DEPTH: usize = 4;
+
+/// docker run / create のイメージ
+pub fn command_images(cmd: &DockerCommand) -> Vec<ImageUse> {
+    match (&cmd.subcommand, &cmd.image) {
+        (DockerSubcommand::Run | DockerSubcommand::Create, Some(image)) => vec![ImageUse {
+            image: image.clone(),
+            origin: format!("docker {}", cmd.subcommand),
+        }],
+        _ => Vec::new(),
+    }
+}
+
+/// compose のサービスのイメージ (build を持つサービスは除く)
+pub fn compose_images(analysis: &ComposeAnalysis) -> Vec<ImageUse> {
+    analysis
+        .images
+        .iter()
+        .map(|(service, image)| ImageUse {
+            image: image.clone(),
+            origin: format!("compose service '{}'", service),
+        })
+        .collect()
+}
+
+/// 公開鍵をすべて読み込めるか確かめる (設定の検証用)
+pub fn check_keys(paths: &[String]) -> Result<(), String> {
+    crypto::load_keys(paths).map(|_| ())
+}
+
+/// イメージの署名を検証し、検証できなかったイメージを unsigned の扱いで返す
+pub fn check(uses: &[ImageUse], config: &Config) -> Vec<Finding> {
+    let signatures = &config.image_signatures;
+    let keys = crypto::load_keys(&signatures.keys);
+    let layouts: Vec<Layout> = signatures
+        .layouts
+        .iter()
+        .filter_map(|dir| {
+            Layout::load(Path::new(&path_validator::expand_env(dir)))
+                .map_err(|e| log::warn!("image_signatures.layouts: {}", e))
+                .ok()
+        })
+        .collect();
+
+    let mut findings: Vec<Finding> = Vec::new();
+    for use_ in uses {
+        let Err(detail) = verify_image(&use_.image, keys.as_ref(), &layouts, config) else {
+            continue;
+        };
+        let finding = Finding {
+            action: signatures.unsigned,
+            reason: format!(
+                "Image '{}' ({}) has no verified signature: {}",
+                use_.image, use_.origin, detail
+            ),
+        };
+        if finding.action != PinningAction::Allow && !findings.contains(&finding) {
+            findings.push(finding);
+        }
+    }
+    findings
+}
+
+/// 署名を検証する (exempt_registries のイメージは検証しない)
+fn verify_image(
+    image: &str,
+    keys: Result<&crypto::Keys, &String>,
+    layouts: &[Layout],
+    config: &Config,
+) -> Result<(), String> {
+    let image_ref =
+        ImageRef::parse(image).map_err(|e| format!("invalid image reference ({})", e))?;
+    if config
+        .image_signatures
+        .exempt_registries
+        .iter()
+        .any(|r| image_ref::registry_matches(r, &image_ref.registry))
+    {
+        return Ok(());
+    }
+    let keys = keys.map_err(String::clone)?;
+
+    let digest = resolve_digest(image, &image_ref, config).ok_or_else(|| {
+        "its digest is unknown. Pull the image first or reference it by @sha256:<digest>"
+            .to_string()
+    })?;
+    if layouts
+        .iter()
+        .any(|layout| layout.has_signature(keys, &digest))
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "no signature or attestation for {} made with image_signatures.keys was found in image_signatures.layouts",
+            digest
+        ))
+    }
+}
+
+/// docker が実行するイメージのダイジェストを求める (参照 → docker image inspect)
+fn resolve_digest(image: &str, image_ref: &ImageRef, config: &Config) -> Option<String> {
+    if let Some(digest) = &image_ref.digest {
+        return Some(digest.clone());
+    }
+    repo_digests(image, config)
+        .unwrap_or_else(|e| {
+            log::debug!("docker image inspect {}: {}", image, e);
+            Vec::new()
+        })
+        .iter()
+        .filter_map(|d| ImageRef::parse(d).ok())
+        .find(|r| r.name() == image_ref.name())
+        .and_then(|r| r.digest)
+}
+
+/// `docker image inspect` でローカルのイメージの RepoDigests を取得する
+fn repo_digests(image: &str, config: &Config) -> Result<Vec<String>, String> {
+    let output = crate::wrapper::inspect_output(
+        config,
+        &[
+            "image",
+            "inspect",
+            "--format",
+            "{{json .RepoDigests}}",
+            image,
+        ],
+    )?;
+    let digests: Option<Vec<String>> =
+        serde_json::from_slice(&output).map_err(|e| format!("invalid output: {}", e))?;
+    Ok(digests.unwrap_or_default())
+}
+
+/// OCI イメージレイアウト (`index.json` と `blobs/sha256/`)
+struct Layout {
+    dir: PathBuf,
+    /// イメージマニフェスト (入れ子のインデックスを展開したもの)
+    manifests: Vec<Value>,
+}
+
+impl Layout {
+    fn load(dir: &Path) -> Result<Self, String> {
+        let index_path = dir.join("index.json");
+        let index = read_limited(&index_path)
+            .and_then(|data| serde_json::from_slice::<Value>(&data).map_err(|e| e.to_string()))
+            .map_err(|e| format!("{}: {}", index_path.display(), e))?;
+        let mut layout = Self {
+            dir: dir.to_path_buf(),
+            manifests: Vec::new(),
+        };
+        layout.walk(&index, 0);
+        Ok(layout)
+    }
+
+    /// インデックスの記述子をたどってマニフェストを集める (検証できない blob は読み飛ばす)
+    fn walk(&mut self, index: &Value, depth: usize) {
+        let Some(descriptors) = index.get("manifests").and_then(Value::as_array) else {
+            return;
+        };
+        for descriptor in descriptors {
+            if self.manifests.len() >= MAX_MANIFESTS {
+                return;
+            }
+            let Some(digest) = descriptor.get("digest").and_then(Value::as_str) else {
+                continue;
+            };
+            let Some(document) = self
+                .blob(digest)
+                .ok()
+                .and_then(|data| serde_json::from_slice::<Value>(&data).ok())
+            else {
+                continue;
+            };
+            if document.get("manifests").is_some() {
+                if depth < MAX_INDEX_DEPTH {
+                    self.walk(&document, depth + 1);
+                }
+            } else if document.get("layers").is_some() {
+                self.manifests.push(document);
+            }
+        }
+    }
+
+    /// blob を読み、内容がダイジェストと一致することを確かめる
+    fn blob(&self, digest: &str) -> Result<Vec<u8>, String> {
+        let hex = digest
+            .strip_prefix("sha256:")
+            .filter(|h| h.len() == 64 && h.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| format!("unsupported digest '{}'", digest))?;
+        let data = read_limited(&self.dir.join("blobs").join("sha256").join(hex))?;
+        if crypto::sha256_hex(&data) != hex.to_ascii_lowercase() {
+            return Err(format!("blob {} does not match its digest", digest));
+        }
+        Ok(data)
+    }
+
+    /// digest に対する信頼できる署名またはアテステーションがあるか
+    fn has_signature(&self, keys: &crypto::Keys, digest: &str) -> bool {
+        self.manifests
+            .iter()
+            .filter_map(|m| m.get("layers").and_then(Value::as_array))
+            .flatten()
+            .any(
+                |layer| match layer.get("mediaType").and_then(Value::as_str) {
+                    Some(SIMPLE_SIGNING_MEDIA_TYPE) => {
+                        self.verify_simple_signing(keys, layer, digest)
+                    }
+                    Some(DSSE_MEDIA_TYPE) => self.verify_attestation(keys, layer, digest),
+                    _ => false,
+                },
+            )
+    }
+
+    /// cosign の署名: ペイロードへの署名を検証し、ペイロードのダイジェストを照合する
+    fn verify_simple_signing(&self, keys: &crypto::Keys, layer: &Value, digest: &str) -> bool {
+        let Some(signature) =
+            annotation(layer, SIGNATURE_ANNOTATION).and_then(crypto::decode_base64)
+        else {
+            return false;
+        };
+        let Some(payload) = self.layer_blob(layer) else {
+            return false;
+        };
+        crypto::verify(keys, &payload, &signature)
+            && serde_json::from_slice::<Value>(&payload).is_ok_and(|p| {
+                p.pointer("/critical/image/docker-manifest-digest")
+                    .and_then(Value::as_str)
+                    == Some(digest)
+            })
+    }
+
+    /// DSSE のアテステーション: PAE への署名を検証し、in-toto の subject のダイジェストを照合する
+    fn verify_attestation(&self, keys: &crypto::Keys, layer: &Value, digest: &str) -> bool {
+        let Some(envelope) = self
+            .layer_blob(layer)
+            .and_then(|data| serde_json::from_slice::<Value>(&data).ok())
+        else {
+            return false;
+        };
+        let payload_type = envelope
+            .get("payloadType")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let Some(payload) = envelope
+            .get("payload")
+            .and_then(Value::as_str)
+            .and_then(crypto::decode_base64)
+        else {
+            return false;
+        };
+        let mut pae = format!(
+            "DSSEv1 {} {} {} ",
+            payload_type.len(),
+            payload_type,
+            payload.len()
+        )
+        .into_bytes();
+        pae.extend_from_slice(&payload);
+
+        let signed = envelope
+            .get("signatures")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|s| s.get("sig").and_then(Value::as_str))
+            .filter_map(crypto::decode_base64)
+            .any(|signature| crypto::verify(keys, &pae, &signature));
+        let hex = digest.strip_prefix("sha256:").unwrap_or(digest);
+        signed
+            && serde_json::from_slice::<Value>(&payload).is_ok_and(|statement| {
+                statement
+                    .get("subject")
+                    .and_then(Value::as_array)
+                    .is_some_and(|subjects| {
+                        subjects.iter().any(|s| {
+                            s.pointer("/digest/sha256").and_then(Value::as_str) == Some(hex)
+                        })
+                    })
+            })
+    }
+
+    fn layer_blob(&self, layer: &Value) -> Option<Vec<u8>> {
+        let digest = layer.get("digest").and_then(Value::as_str)?;
+        self.blob(digest).ok()
+    }
+}
+
+fn annotation<'a>(descriptor: &'a Value, key: &str) -> Option<&'a str> {
+    descriptor
+        .get("annotations")
+        .and_then(|a| a.get(key))
+        .and_then(Value::as_str)
+}
+
+/// MAX_BLOB_SIZE までのファイルを読む (超える場合はエラー)
+fn read_limited(path: &Path) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    std::fs::File::open(path)
+        .and_then(|f| f.take(MAX_BLOB_SIZE + 1).read_to_end(&mut data))
+        .map_err(|e| e.to_string())?;
+    if data.len() as u64 > MAX_BLOB_SIZE {
+        return Err(format!("larger than {} bytes", MAX_BLOB_SIZE));
+    }
+    Ok(data)
+}
+
+/// 公開鍵の読み込みと署名の検証
+#[cfg(feature = "cosign")]
+mod crypto {
+    use base64::Engine;
+    use p256::ecdsa::signature::Verifier;
+    use p256::ecdsa::{Signature, VerifyingKey};
+    use p256::pkcs8::DecodePublicKey;
+    use sha2::{Digest, Sha256};
+
+    use crate::path_validator;
+
+    /// 信頼する公開鍵
+    pub struct Keys(Vec<VerifyingKey>);
+
+    pub fn load_keys(paths: &[String]) -> Result<Keys, String> {
+        paths
+            .iter()
+            .map(|path| {
+                let pem = std::fs::read_to_string(path_validator::expand_env(path))
+                    .map_err(|e| format!("cannot read image_signatures.keys '{}': {}", path, e))?;
+                VerifyingKey::from_public_key_pem(&pem).map_err(|e| {
+                    format!(
+                        "image_signatures.keys '{}' is not an ECDSA P-256 public key: {}",
+                        path, e
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Keys)
+    }
+
+    pub fn sha256_hex(data: &[u8]) -> String {
+        Sha256::digest(data)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub fn decode_base64(value: &str) -> Option<Vec<u8>> {
+        base64::engine::general_purpose::STANDARD
+            .decode(value.trim())
+            .ok()
+    }
+
+    /// ASN.1 DER または r||s 形式の署名を検証する (high-S の署名も受け付ける)
+    pub fn verify(keys: &Keys, message: &[u8], signature: &[u8]) -> bool {
+        let Ok(signature) =
+            Signature::from_der(signature).or_else(|_| Signature::from_slice(signature))
+        else {
+            return false;
+        };
+        let signature = signature.normalize_s().unwrap_or(signature);
+        keys.0
+            .iter()
+            .any(|key| key.verify(message, &signature).is_ok())
+    }
+}
+
+/// cosign feature が無効の場合のスタブ (すべての検証に失敗する)
+#[cfg(not(feature = "cosign"))]
+mod crypto {
+    pub struct Keys;
+
+    pub fn load_keys(_paths: &[String]) -> Result<Keys, String> {
+        Err(
+            "safe-docker was built without the 'cosign' feature, so signatures cannot be verified"
+                .to_string(),
+        )
+    }
+
+    pub fn sha256_hex(_data: &[u8]) -> String {
+        String::new()
+    }
+
+    pub fn decode_base64(_value: &str) -> Option<Vec<u8>> {
+        None
+    }
+
+    pub fn verify(_keys: &Keys, _message: &[u8], _signature: &[u8]) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ImageSignaturesConfig;
+
+    fn use_(image: &str) -> ImageUse {
+        ImageUse {
+            image: image.to_string(),
+            origin: "docker run".to_string(),
+        }
+    }
+
+    fn config(keys: Vec<String>, layouts: Vec<String>) -> Config {
+        Config {
+            image_signatures: ImageSignaturesConfig {
+                keys,
+                layouts,
+                exempt_registries: vec!["docker.io".to_string()],
+                unsigned: PinningAction::Deny,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[cfg(not(feature = "cosign"))]
+    #[test]
+    fn test_check_fails_closed_without_cosign_feature() {
+        let config = config(vec!["/nonexistent/cosign.pub".to_string()], Vec::new());
+        let findings = check(
+            &[
+                use_(
+                    "ghcr.io/ourorg/app@sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+                ),
+                use_("ubuntu:24.04"),
+            ],
+            &config,
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].action, PinningAction::Deny);
+        assert!(findings[0].reason.contains("without the 'cosign' feature"));
+    }
+
+    #[cfg(feature = "cosign")]
+    mod cosign {
+        use super::*;
+        use crate::test_utils::{TempEnvVar, env_lock};
+        use base64::Engine;
+        use p256::ecdsa::SigningKey;
+        use p256::ecdsa::signature::Signer;
+        use p256::pkcs8::{EncodePublicKey, LineEnding};
+        use serde_json::json;
+
+        /// blob を書き込み、その記述子を返す
+        fn write_blob(dir: &Path, media_type: &str, data: &[u8]) -> Value {
+            let hex = crypto::sha256_hex(data);
+            std::fs::write(dir.join("blobs/sha256").join(&hex), data).unwrap();
+            json!({"mediaType": media_type, "digest": format!("sha256:{}", hex), "size": data.len()})
+        }
+
+        fn sign(key: &SigningKey, message: &[u8]) -> String {
+            let signature: p256::ecdsa::Signature = key.sign(message);
+            base64::engine::general_purpose::STANDARD.encode(signature.to_der().as_bytes())
+        }
+
+        fn write_public_key(dir: &Path, name: &str, key: &SigningKey) -> String {
+            let path = dir.join(name);
+            let pem = key
+                .verifying_key()
+                .to_public_key_pem(LineEnding::LF)
+                .unwrap();
+            std::fs::write(&path, pem).unwrap();
+            path.to_string_lossy().to_string()
+        }
+
+        /// `docker image inspect` に RepoDigests を返す偽の docker を書き込み、そのパスを返す
+        fn write_fake_docker(dir: &Path, repo_digests: &[String]) -> String {
+            use std::os::unix::fs::PermissionsExt;
+            let path = dir.join("docker");
+            let script = format!(
+                "#!/bin/sh\necho '{}'\n",
+                serde_json::to_string(repo_digests).unwrap()
+            );
+            std::fs::write(&path, script).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path.to_string_lossy().to_string()
+        }
+
+        #[test]
+        fn test_check_signatures_and_attestations() {
+            let lock = env_lock();
+            let _env = TempEnvVar::remove(&lock, "SAFE_DOCKER_DOCKER_PATH");
+            let dir = tempfile::tempdir().unwrap();
+            let root = dir.path();
+            let layout = root.join("layout");
+            std::fs::create_dir_all(layout.join("blobs/sha256")).unwrap();
+            let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+            let other_key = SigningKey::from_slice(&[9u8; 32]).unwrap();
+            let trusted = write_public_key(root, "cosign.pub", &key);
+            let untrusted = write_public_key(root, "other.pub", &other_key);
+
+            const MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+            // 署名するイメージ (タグ付き) とアテステーションだけを持つイメージ
+            let image = write_blob(&layout, MANIFEST, br#"{"schemaVersion":2,"layers":[]}"#);
+            let attested = write_blob(&layout, MANIFEST, br#"{"schemaVersion":2,"layers":[{}]}"#);
+            let image_digest = image["digest"].as_str().unwrap().to_string();
+            let attested_digest = attested["digest"].as_str().unwrap().to_string();
+
+            let payload = json!({"critical": {"identity": {"docker-reference": "ghcr.io/ourorg/app"}, "image": {"docker-manifest-digest": image_digest}, "type": "cosign container image signature"}}).to_string();
+            let mut signature_layer =
+                write_blob(&layout, SIMPLE_SIGNING_MEDIA_TYPE, payload.as_bytes());
+            signature_layer["annotations"] =
+                json!({SIGNATURE_ANNOTATION: sign(&key, payload.as_bytes())});
+
+            let statement = json!({"_type": "https://in-toto.io/Statement/v1", "subject": [{"name": "ghcr.io/ourorg/tool", "digest": {"sha256": attested_digest.trim_start_matches("sha256:")}}], "predicateType": "https://slsa.dev/provenance/v1"}).to_string();
+            let payload_type = "application/vnd.in-toto+json";
+            let pae = format!(
+                "DSSEv1 {} {} {} {}",
+                payload_type.len(),
+                payload_type,
+                statement.len(),
+                statement
+            );
+            let envelope = json!({"payloadType": payload_type, "payload": base64::engine::general_purpose::STANDARD.encode(&statement), "signatures": [{"keyid": "", "sig": sign(&key, pae.as_bytes())}]}).to_string();
+            let attestation_layer = write_blob(&layout, DSSE_MEDIA_TYPE, envelope.as_bytes());
+
+            let signatures = write_blob(
+                &layout,
+                MANIFEST,
+                json!({"schemaVersion": 2, "layers": [signature_layer, attestation_layer]})
+                    .to_string()
+                    .as_bytes(),
+            );
+            let mut tagged = image.clone();
+            tagged["annotations"] =
+                json!({"org.opencontainers.image.ref.name": "ghcr.io/ourorg/app:1.0"});
+            let index = json!({"schemaVersion": 2, "manifests": [tagged, attested, signatures]});
+            std::fs::write(layout.join("index.json"), index.to_string()).unwrap();
+            let layouts = vec![layout.to_string_lossy().to_string()];
+            // タグ付きのイメージのダイジェストはローカルのイメージの RepoDigests から求める
+            let docker = write_fake_docker(root, &[format!("ghcr.io/ourorg/app@{}", image_digest)]);
+            let config = |keys: Vec<String>, layouts: Vec<String>| {
+                let mut config = config(keys, layouts);
+                config.wrapper.docker_path = docker.clone();
+                config
+            };
+
+            let uses = [
+                use_("ghcr.io/ourorg/app:1.0"),
+                use_(&format!("ghcr.io/ourorg/app@{}", image_digest)),
+                use_(&format!("ghcr.io/ourorg/tool@{}", attested_digest)),
+                use_("ubuntu"),
+            ];
+            assert_eq!(
+                check(&uses, &config(vec![trusted.clone()], layouts.clone())),
+                vec![]
+            );
+
+            // 信頼しない鍵では検証できない
+            let findings = check(&uses[..3], &config(vec![untrusted], layouts.clone()));
+            assert_eq!(findings.len(), 3);
+            assert!(findings[0].reason.contains(&format!(
+                "'ghcr.io/ourorg/app:1.0' (docker run) has no verified signature: no signature or attestation for {}",
+                image_digest
+            )));
+
+            // 署名と一致しないダイジェスト
+            let unsigned = format!("ghcr.io/ourorg/app@sha256:{}", "0".repeat(64));
+            let findings = check(&[use_(&unsigned)], &config(vec![trusted.clone()], layouts));
+            assert_eq!(findings.len(), 1);
+            assert_eq!(findings[0].action, PinningAction::Deny);
+
+            // 改ざんされた blob は読み飛ばす
+            let payload_path = layout.join("blobs/sha256").join(
+                signature_layer["digest"]
+                    .as_str()
+                    .unwrap()
+                    .trim_start_matches("sha256:"),
+            );
+            std::fs::write(&payload_path, payload.replace("cosign", "tampered")).unwrap();
+            let layouts = vec![layout.to_string_lossy().to_string()];
+            let findings = check(&uses[..1], &config(vec![trusted], layouts));
+            assert_eq!(findings.len(), 1);
+        }
+
+        #[test]
+        fn test_check_uses_digest_of_local_image_not_layout_tag() {
+            let lock = env_lock();
+            let _env = TempEnvVar::remove(&lock, "SAFE_DOCKER_DOCKER_PATH");
+            let dir = tempfile::tempdir().unwrap();
+            let root = dir.path();
+            let layout = root.join("layout");
+            std::fs::create_dir_all(layout.join("blobs/sha256")).unwrap();
+            let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+            let trusted = write_public_key(root, "cosign.pub", &key);
+
+            // レイアウトには app:latest のタグで署名済みのイメージがある
+            const MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+            let image = write_blob(&layout, MANIFEST, br#"{"schemaVersion":2,"layers":[]}"#);
+            let image_digest = image["digest"].as_str().unwrap().to_string();
+            let payload = json!({"critical": {"identity": {"docker-reference": "ghcr.io/ourorg/app"}, "image": {"docker-manifest-digest": image_digest}, "type": "cosign container image signature"}}).to_string();
+            let mut signature_layer =
+                write_blob(&layout, SIMPLE_SIGNING_MEDIA_TYPE, payload.as_bytes());
+            signature_layer["annotations"] =
+                json!({SIGNATURE_ANNOTATION: sign(&key, payload.as_bytes())});
+            let signatures = write_blob(
+                &layout,
+                MANIFEST,
+                json!({"schemaVersion": 2, "layers": [signature_layer]})
+                    .to_string()
+                    .as_bytes(),
+            );
+            let mut tagged = image.clone();
+            tagged["annotations"] =
+                json!({"org.opencontainers.image.ref.name": "ghcr.io/ourorg/app:latest"});
+            let index = json!({"schemaVersion": 2, "manifests": [tagged, signatures]});
+            std::fs::write(layout.join("index.json"), index.to_string()).unwrap();
+            let layouts = vec![layout.to_string_lossy().to_string()];
+
+            // ローカルの app:latest は別の (署名のない) イメージ
+            let local_digest = format!("sha256:{}", "1".repeat(64));
+            let mut local = config(vec![trusted.clone()], layouts.clone());
+            local.wrapper.docker_path =
+                write_fake_docker(root, &[format!("ghcr.io/ourorg/app@{}", local_digest)]);
+            let findings = check(&[use_("ghcr.io/ourorg/app:latest")], &local);
+            assert_eq!(findings.len(), 1);
+            assert!(
+                findings[0].reason.contains(&local_digest),
+                "{}",
+                findings[0].reason
+            );
+
+            // ローカルのイメージのダイジェストがわからなければレイアウトのタグは使わない
+            let mut unknown = config(vec![trusted], layouts);
+            unknown.wrapper.docker_path = write_fake_docker(root, &[]);
+            let findings = check(&[use_("ghcr.io/ourorg/app:latest")], &unknown);
+            assert_eq!(findings.len(), 1);
+            assert!(
+                findings[0].reason.contains("digest is unknown"),
+                "{}",
+                findings[0].reason
+            );
+        }
+
+        #[test]
+        fn test_check_keys() {
+            let dir = tempfile::tempdir().unwrap();
+            let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+            let path = write_public_key(dir.path(), "cosign.pub", &key);
+            assert!(check_keys(&[path]).is_ok());
+            std::fs::write(dir.path().join("bad.pub"), "not a key").unwrap();
+            let bad = dir.path().join("bad.pub").to_string_lossy().to_string();
+            assert!(
+                check_keys(&[bad])
+                    .unwrap_err()
+                    .contains("not an ECDSA P-256")
+            );
+        }
+    }
+}
@@ -1,13 +1,37 @@
+pub mod adapter;
 pub mod audit;
+pub mod authz_plugin;
+pub mod bake;
+pub mod build_context;
+pub mod cel;
 pub mod compose;
 pub mod config;
 pub mod docker_args;
+pub mod dockerfile;
+pub mod dockerfile_lint;
+pub mod dockerignore;
+pub mod engine_api;
 pub mod error;
 pub mod hook;
+pub mod image_pinning;
+pub mod image_ref;
+pub mod image_signature;
+pub mod mcp_server;
+pub mod mcp_tools;
+pub mod network_policy;
 #[cfg(feature = "otlp")]
 pub mod otlp_types;
+pub mod path_pattern;
 pub mod path_validator;
+pub mod plugin;
 pub mod policy;
+pub mod policy_ext;
+pub mod protection;
+pub mod proxy;
+pub mod published_ports;
+pub mod rego;
+pub mod rewrite;
+pub mod session;
 pub mod setup;
 pub mod shell;
 #[cfg(test)]
@@ -32,6 +56,41 @@ fn main() {
         std::process::exit(setup::run(&args[1..]));
     }
 
+    // mcp サブコマンド: stdio の MCP サーバーとして動作
+    // (argv[0] が docker でなく、追加の引数がない場合のみ。docker mcp プラグインとの衝突を避ける)
+    if args.len() == 2 && args[1] == "mcp" && !is_transparent_wrapper(&args) {
+        let (config, config_source) = load_config_with_warning();
+        std::process::exit(mcp_server::run(config, config_source));
+    }
+
+    // proxy サブコマンド: Docker Engine API のソケットプロキシとして動作
+    if args.len() >= 2 && args[1] == "proxy" && !is_transparent_wrapper(&args) {
+        let (config, config_source) = load_config_with_warning();
+        std::process::exit(proxy::run(&args[2..], config, config_source));
+    }
+
+    // authz-plugin サブコマンド: Docker 認可プラグインとして動作
+    if args.len() >= 2 && args[1] == "authz-plugin" && !is_transparent_wrapper(&args) {
+        let (config, config_source) = load_config_with_warning();
+        std::process::exit(authz_plugin::run(&args[2..], config, config_source));
+    }
+
+    // export-rego サブコマンド: 有効な設定から OPA 用の Rego ポリシーを出力
+    // (設定の読み込みに失敗した場合はデフォルト設定で生成せずにエラーにする)
+    if args.len() >= 2 && args[1] == "export-rego" && !is_transparent_wrapper(&args) {
+        match config::Config::load() {
+            Ok(config) => std::process::exit(rego::run(&args[2..], &config)),
+            Err(e) => {
+                eprintln!(
+                    "[safe-docker] ERROR: Failed to load {}: {}",
+                    config::config_path().display(),
+                    e
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
     // --help / --version (ラッパーモード固有)
     if args.iter().any(|a| a == "--help" || a == "-h") && !is_docker_help_request(&args) {
         print_help();
@@ -42,6 +101,22 @@ fn main() {
         return;
     }
 
+    // --protocol: 他のコーディングエージェントの hook プロトコルで hook モードを実行
+    if !is_transparent_wrapper(&args)
+        && let Some(name) = protocol_option(&args)
+    {
+        match adapter::Protocol::from_name(&name) {
+            Ok(protocol) => {
+                run_hook_mode(protocol);
+                return;
+            }
+            Err(e) => {
+                eprintln!("[safe-docker] --protocol: {}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+
     // --docker-path オプションの抽出
     let docker_path_override = extract_option(&args, "--docker-path");
 
@@ -53,30 +128,7 @@ fn main() {
 
     match mode {
         RunMode::Wrapper(docker_args) => {
-            let cfg_path = config::config_path();
-            let (mut config, config_source) = match config::Config::load() {
-                Ok(config) => {
-                    let source = if cfg_path.exists() {
-                        format!("{}", cfg_path.display())
-                    } else {
-                        "(default)".to_string()
-                    };
-                    (config, source)
-                }
-                Err(e) => {
-                    // ファイルが存在するのにパース失敗 → ユーザーのミスの可能性
-                    eprintln!(
-                        "[safe-docker] WARNING: Failed to load {}: {}",
-                        cfg_path.display(),
-                        e
-                    );
-                    eprintln!(
-                        "[safe-docker] Using default configuration. Run --check-config to diagnose."
-                    );
-                    let source = format!("{} (FAILED, using defaults)", cfg_path.display());
-                    (config::Config::default(), source)
-                }
-            };
+            let (mut config, config_source) = load_config_with_warning();
             // --docker-path CLI オプションで上書き
             if let Some(path) = docker_path_override {
                 config.wrapper.docker_path = path;
@@ -86,7 +138,34 @@ fn main() {
             std::process::exit(wrapper::run(&docker_args, &config, &config_source));
         }
         RunMode::Hook => {
-            run_hook_mode();
+            run_hook_mode(None);
+        }
+    }
+}
+
+/// 設定ファイルを読み込む。読み込みに失敗した場合は警告を表示してデフォルト設定を使う。
+/// 戻り値は (設定, 設定ソースの表示文字列)。
+fn load_config_with_warning() -> (config::Config, String) {
+    let cfg_path = config::config_path();
+    match config::Config::load() {
+        Ok(config) => {
+            let source = if cfg_path.exists() {
+                format!("{}", cfg_path.display())
+            } else {
+                "(default)".to_string()
+            };
+            (config, source)
+        }
+        Err(e) => {
+            // ファイルが存在するのにパース失敗 → ユーザーのミスの可能性
+            eprintln!(
+                "[safe-docker] WARNING: Failed to load {}: {}",
+                cfg_path.display(),
+                e
+            );
+            eprintln!("[safe-docker] Using default configuration. Run --check-config to diagnose.");
+            let source = format!("{} (FAILED, using defaults)", cfg_path.display());
+            (config::Config::default(), source)
         }
     }
 }
@@ -161,13 +240,22 @@ fn print_help() {
     eprintln!("  safe-docker [OPTIONS] <docker-args>...     Wrapper mode");
     eprintln!("  safe-docker setup [--target DIR] [--force] Set up docker symlink");
     eprintln!("  safe-docker --check-config [--config PATH] Check configuration");
+    eprintln!("  safe-docker mcp                            MCP server (stdio)");
+    eprintln!("  safe-docker proxy [--listen P] [--upstream P] Docker API proxy socket");
+    eprintln!("  safe-docker authz-plugin [--listen PATH]   Docker authorization plugin");
+    eprintln!("  safe-docker export-rego [--home DIR]       Generate OPA authz.rego from config");
     eprintln!("  echo '{{...}}' | safe-docker                 Hook mode (Claude Code)");
+    eprintln!("  echo '{{...}}' | safe-docker --protocol NAME Hook mode (other agents)");
     eprintln!();
     eprintln!("OPTIONS:");
     eprintln!("  --dry-run              Show decision without executing docker");
     eprintln!("  --verbose              Show detailed decision reasons");
     eprintln!("  --docker-path PATH     Override docker binary path");
     eprintln!("  --check-config         Validate configuration file");
+    eprintln!(
+        "  --protocol NAME        Hook protocol: {}",
+        adapter::PROTOCOL_NAMES.join(", ")
+    );
     eprintln!("  --help, -h             Show this help message");
     eprintln!("  --version              Show version");
     eprintln!();
@@ -184,6 +272,16 @@ fn print_help() {
     eprintln!("  safe-docker --docker-path /usr/bin/docker run ubuntu");
 }
 
+/// hook モードの `--protocol NAME` / `--protocol=NAME` を取得する
+/// (他の引数がある場合はラッパーモードの docker 引数とみなす)
+fn protocol_option(args: &[String]) -> Option<String> {
+    match args {
+        [_, flag, name] if flag == "--protocol" => Some(name.clone()),
+        [_, flag] => flag.strip_prefix("--protocol=").map(String::from),
+        _ => None,
+    }
+}
+
 /// --key value 形式のオプションを args から抽出する
 fn extract_option(args: &[String], key: &str) -> Option<String> {
     args.windows(2).find(|w| w[0] == key).map(|w| w[1].clone())
@@ -208,20 +306,11 @@ fn remove_option_from_args(args: &[String], key: &str) -> Vec<String> {
 }
 
 /// hook モードの実行（従来のメインロジック）
-fn run_hook_mode() {
-    // パニック時は deny (fail-safe)
-    let default_hook = std::panic::take_hook();
-    std::panic::set_hook(Box::new(move |info| {
-        default_hook(info);
-        hook::output_deny(&format!(
-            "[safe-docker] Internal error (panic). Blocking for safety. Please report this issue: {}",
-            info
-        ));
-    }));
-
+/// protocol が None の場合は入力 JSON から自動判別する。
+fn run_hook_mode(protocol: Option<adapter::Protocol>) {
     // stdin から hook 入力を読み取る
-    let input = match hook::read_input() {
-        Ok(input) => input,
+    let raw = match hook::read_input() {
+        Ok(raw) => raw,
         Err(e) => {
             // 入力エラーは fail-safe (deny)
             // 巨大入力によるバイパスや不正な入力を防ぐ
@@ -233,16 +322,39 @@ fn run_hook_mode() {
                 error::SafeDockerError::Io(_) => "failed to read stdin".to_string(),
                 _ => format!("{}", e),
             };
-            hook::output_deny(&format!("[safe-docker] {}. Blocking for safety.", detail));
+            adapter::output_deny(
+                protocol.unwrap_or(adapter::Protocol::ClaudeCode),
+                &format!("[safe-docker] {}. Blocking for safety.", detail),
+            );
             return;
         }
     };
+    let protocol = protocol.unwrap_or_else(|| adapter::detect(&raw));
 
-    // Bash ツール以外、またはコマンドが無い場合は即 allow
-    let command = match hook::extract_command(&input) {
-        Some(cmd) => cmd.to_string(),
-        None => return, // exit 0, no output = allow
+    // パニック時は deny (fail-safe)
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        adapter::output_deny(
+            protocol,
+            &format!(
+                "[safe-docker] Internal error (panic). Blocking for safety. Please report this issue: {}",
+                info
+            ),
+        );
+    }));
+
+    let input = match adapter::to_hook_input(protocol, raw) {
+        Ok(input) => input,
+        Err(_) => {
+            adapter::output_deny(
+                protocol,
+                "[safe-docker] invalid hook input. Blocking for safety.",
+            );
+            return;
+        }
     };
+    let allow = hook::HookResponse::from_decision(Decision::Allow);
 
     // CWD を取得
     let cwd = input
@@ -257,7 +369,7 @@ fn run_hook_mode() {
 
     // 設定ファイル読み込み
     let cfg_path = config::config_path();
-    let (config, hook_config_source) = match config::Config::load() {
+    let (mut config, hook_config_source) = match config::Config::load() {
         Ok(config) => {
             let source = if cfg_path.exists() {
                 format!("{}", cfg_path.display())
@@ -273,6 +385,35 @@ fn run_hook_mode() {
         }
     };
 
+    // Bash ツール・ファイル書き換えツール・docker を操作する MCP ツール以外、
+    // またはコマンドが無い場合は即 allow
+    let command = hook::extract_command(&input).map(String::from);
+    let file_paths = hook::extract_file_paths(&input);
+    let mcp_call = if command.is_none() && file_paths.is_empty() {
+        mcp_tools::docker_call(&input, &config.hook.mcp_tools)
+    } else {
+        None
+    };
+    if command.is_none() && file_paths.is_empty() && mcp_call.is_none() {
+        adapter::output_response(protocol, &allow);
+        return; // exit 0 (Claude Code は出力なし = allow)
+    }
+
+    // updatedInput に対応しないプロトコルではコマンドを書き換えない
+    if !protocol.supports_updated_input() {
+        config.hook.rewrite.enabled = false;
+    }
+
+    // PostToolUse: 実行結果の記録のみ (判定は出力しない)
+    let event = hook::hook_event(&input);
+    if event != hook::HookEvent::PreToolUse {
+        if let Some(ref command) = command {
+            run_post_tool_use(&input, event, command, &config, &cwd, &hook_config_source);
+        }
+        adapter::output_response(protocol, &allow);
+        return;
+    }
+
     // 監査ログの有効判定
     let audit_enabled = audit::is_enabled(&config.audit);
     let mut collector = if audit_enabled {
@@ -281,22 +422,57 @@ fn run_hook_mode() {
         None
     };
 
-    // コマンドを処理
-    let decision = process_command_with_audit(&command, &config, &cwd, collector.as_mut());
+    // コマンドを処理 (Write/Edit 等は保護対象パスのみチェック)
+    let tool_name = input.tool_name.as_deref().unwrap_or_default();
+    let (command, response) = match (command, mcp_call) {
+        (Some(command), _) => {
+            let response =
+                build_command_response(&input, &command, &config, &cwd, collector.as_mut());
+            (command, response)
+        }
+        (None, Some(call)) => build_mcp_response(
+            tool_name,
+            call,
+            &config,
+            &cwd,
+            input.session_id.as_deref(),
+            collector.as_mut(),
+        ),
+        (None, None) => {
+            let decision = protection::check_file_paths(&file_paths, &config, &cwd);
+            (
+                format!("{} {}", tool_name, file_paths.join(" ")),
+                hook::HookResponse::from_decision(decision),
+            )
+        }
+    };
+    let decision = &response.decision;
 
     // 結果出力 (★ここで stdout に hook レスポンス)
-    hook::output_decision(&decision);
+    adapter::output_response(protocol, &response);
 
     // 監査ログ出力 (★レスポンス後にファイル I/O)
-    if audit_enabled && let Some(ref collector) = collector {
-        let (decision_str, reason) = match &decision {
+    // ファイル書き換えツールは通常の編集が大半のため、allow 以外のみ記録する
+    let is_file_tool = !file_paths.is_empty();
+    if audit_enabled
+        && !(is_file_tool && *decision == Decision::Allow)
+        && let Some(ref collector) = collector
+    {
+        let (decision_str, reason) = match decision {
             hook::Decision::Allow => ("allow", None),
             hook::Decision::Deny(r) => ("deny", Some(r.as_str())),
             hook::Decision::Ask(r) => ("ask", Some(r.as_str())),
         };
 
-        let event = audit::build_event(&audit::AuditContext {
-            command: &command,
+        // 書き換えた場合は実際に実行されるコマンドを記録し、PostToolUse と関連付ける
+        let executed_command = response
+            .updated_input
+            .as_ref()
+            .and_then(|v| v.get("command"))
+            .and_then(|v| v.as_str())
+            .unwrap_or(&command);
+        let mut event = audit::build_event(&audit::AuditContext {
+            command: executed_command,
             decision: decision_str,
             reason,
             collector,
@@ -305,10 +481,106 @@ fn run_hook_mode() {
             mode: "hook",
             config_source: Some(&hook_config_source),
         });
+        if executed_command != command {
+            event.original_command = Some(command.clone());
+        }
+        event.hook_event = Some(hook::HookEvent::PreToolUse.as_str().to_string());
+        event.command_hash = Some(audit::command_hash(executed_command));
         audit::emit(&event, &config.audit);
     }
 }
 
+/// PostToolUse / PostToolUseFailure の処理。
+/// docker コマンドの実行結果を監査ログに記録し、作成されたコンテナをセッションに記録する。
+fn run_post_tool_use(
+    input: &hook::HookInput,
+    event: hook::HookEvent,
+    command: &str,
+    config: &config::Config,
+    cwd: &str,
+    config_source: &str,
+) {
+    let result = hook::tool_result(input);
+
+    let mut collector = audit::AuditCollector::new();
+    // docker run / create の (コンテナ名, イメージ)
+    let mut created = Vec::new();
+    for segment in &shell::split_commands(command) {
+        if !shell::is_docker_command(segment) {
+            continue;
+        }
+        let args = shell::extract_docker_args(segment);
+        let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        let docker_cmd = docker_args::parse_docker_args(&args_ref);
+        collector.record_docker_command(&docker_cmd);
+        if matches!(
+            docker_cmd.subcommand,
+            docker_args::DockerSubcommand::Run | docker_args::DockerSubcommand::Create
+        ) {
+            created.push((container_name(&args), docker_cmd.image.clone()));
+        }
+    }
+
+    // docker コマンドを含まない場合は記録しない
+    if collector.docker_subcommands.is_empty() {
+        return;
+    }
+
+    let container_ids = if created.is_empty() || result.outcome != "succeeded" {
+        Vec::new()
+    } else {
+        session::extract_container_ids(&result.stdout)
+    };
+
+    // セッションごとの作成コンテナを記録
+    if let Some(ref session_id) = input.session_id
+        && !container_ids.is_empty()
+    {
+        // 単一の run / create の場合のみ名前とイメージを関連付ける
+        let (name, image) = match (created.as_slice(), container_ids.len()) {
+            ([(name, image)], 1) => (name.clone(), image.clone()),
+            _ => (None, None),
+        };
+        let records = container_ids
+            .iter()
+            .map(|id| session::ContainerRecord::new(id, name.clone(), image.clone()))
+            .collect();
+        if let Err(e) = session::record_containers(&config.hook.session_dir, session_id, records) {
+            log::warn!("Failed to record session containers: {}", e);
+        }
+    }
+
+    if audit::is_enabled(&config.audit) {
+        let mut audit_event = audit::build_event(&audit::AuditContext {
+            command,
+            decision: "allow",
+            reason: None,
+            collector: &collector,
+            session_id: input.session_id.as_deref(),
+            cwd,
+            mode: "hook",
+            config_source: Some(config_source),
+        });
+        audit_event.hook_event = Some(event.as_str().to_string());
+        audit_event.command_hash = Some(audit::command_hash(command));
+        audit_event.outcome = Some(result.outcome.to_string());
+        audit_event.exit_code = result.exit_code;
+        audit_event.container_ids = container_ids;
+        audit::emit(&audit_event, &config.audit);
+    }
+}
+
+/// docker run / create の --name の値を取得する
+fn container_name(args: &[String]) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == "--name" {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix("--name=").map(String::from)
+        }
+    })
+}
+
 /// --check-config サブコマンドの実行
 fn run_check_config(args: &[String]) -> i32 {
     // --config <path> オプションの処理
@@ -395,6 +667,15 @@ fn run_check_config(args: &[String]) -> i32 {
     if has_errors { 1 } else { 0 }
 }
 
+/// allowed_paths / sensitive_paths をサマリー用に連結する
+fn join_entries(entries: &[config::PathEntry]) -> String {
+    entries
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// 設定のサマリーを stderr に出力
 fn print_config_summary(config: &config::Config) {
     eprintln!("Current configuration:");
@@ -403,13 +684,19 @@ fn print_config_summary(config: &config::Config) {
         if config.allowed_paths.is_empty() {
             "(none)".to_string()
         } else {
-            config.allowed_paths.join(", ")
+            join_entries(&config.allowed_paths)
         }
     );
     eprintln!(
         "  sensitive_paths:      [{}]",
-        config.sensitive_paths.join(", ")
+        join_entries(&config.sensitive_paths)
     );
+    if !config.denied_paths.is_empty() {
+        eprintln!(
+            "  denied_paths:         [{}]",
+            config.denied_paths.join(", ")
+        );
+    }
     eprintln!(
         "  blocked_flags:        [{}]",
         config.blocked_flags.join(", ")
@@ -426,13 +713,112 @@ fn print_config_summary(config: &config::Config) {
             config.allowed_images.join(", ")
         }
     );
+    if !config.denied_images.is_empty() {
+        eprintln!(
+            "  denied_images:        [{}]",
+            config.denied_images.join(", ")
+        );
+    }
+    if !config.allowed_registries.is_empty() {
+        eprintln!(
+            "  allowed_registries:   [{}]",
+            config.allowed_registries.join(", ")
+        );
+    }
+    if config.image_pinning.is_enabled() {
+        eprintln!(
+            "  image_pinning:        mutable_tags={:?}, require_digest=[{}] ({:?})",
+            config.image_pinning.mutable_tags,
+            config.image_pinning.require_digest.join(", "),
+            config.image_pinning.missing_digest
+        );
+    }
+    if config.image_signatures.is_enabled() {
+        eprintln!(
+            "  image_signatures:     keys=[{}], layouts=[{}], unsigned={:?}{}",
+            config.image_signatures.keys.join(", "),
+            config.image_signatures.layouts.join(", "),
+            config.image_signatures.unsigned,
+            if cfg!(feature = "cosign") {
+                ""
+            } else {
+                " (cosign feature disabled: all images are unsigned)"
+            }
+        );
+    }
+    if !config.dockerfile_lint.enabled || !config.dockerfile_lint.ignore_rules.is_empty() {
+        eprintln!(
+            "  dockerfile_lint:      enabled={}, ignore_rules=[{}]",
+            config.dockerfile_lint.enabled,
+            config.dockerfile_lint.ignore_rules.join(", ")
+        );
+    }
+    if config.published_ports.is_enabled() {
+        eprintln!(
+            "  published_ports:      non_loopback={:?}, privileged={:?}, allowed_host_ports=[{}] ({:?})",
+            config.published_ports.non_loopback,
+            config.published_ports.privileged,
+            config.published_ports.allowed_host_ports.join(", "),
+            config.published_ports.outside_allowed
+        );
+    }
+    if config.network_policy.is_enabled() {
+        eprintln!(
+            "  network_policy:       bridging_drivers=[{}] ({:?}), isolated_images={} ({:?})",
+            config.network_policy.bridging_drivers.join(", "),
+            config.network_policy.bridging,
+            config.network_policy.isolated_images.len(),
+            config.network_policy.not_isolated
+        );
+    }
+    eprintln!(
+        "  build_context_scan:   enabled={}, patterns={}, max_file_size={}, max_files={}, timeout_ms={}, incomplete={:?}",
+        config.build_context_scan.enabled,
+        config.build_context_scan.patterns.len(),
+        config.build_context_scan.max_file_size,
+        config.build_context_scan.max_files,
+        config.build_context_scan.timeout_ms,
+        config.build_context_scan.incomplete
+    );
     eprintln!("  block_docker_socket:  {}", config.block_docker_socket);
+    eprintln!("  workspace_root:       {:?}", config.workspace_root);
+    if !config.ask_missing_mount_sources {
+        eprintln!("  ask_missing_mount_sources: false");
+    }
+    if config.wrapper.pin_mount_sources {
+        eprintln!("  wrapper.pin_mount_sources: true");
+    }
     eprintln!("  audit.enabled:        {}", config.audit.enabled);
     if config.audit.enabled {
         eprintln!("  audit.format:         {:?}", config.audit.format);
         eprintln!("  audit.jsonl_path:     {}", config.audit.jsonl_path);
         eprintln!("  audit.otlp_path:      {}", config.audit.otlp_path);
     }
+    eprintln!("  hook.self_protection: {:?}", config.hook.self_protection);
+    eprintln!(
+        "  hook.mcp_tools:       [{}]",
+        config
+            .hook
+            .mcp_tools
+            .iter()
+            .map(|m| m.tool.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    eprintln!(
+        "  policy.rules:         {} inline, {} file(s)",
+        config.policy.rules.len(),
+        config.policy.files.len()
+    );
+    eprintln!(
+        "  plugins:              [{}]",
+        config
+            .plugins
+            .iter()
+            .map(|p| format!("{} ({} ms)", p.name, p.timeout_ms))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
     eprintln!(
         "  wrapper.docker_path:  {}",
         if config.wrapper.docker_path.is_empty() {
@@ -458,31 +844,88 @@ pub fn process_command_with_audit(
     command: &str,
     config: &config::Config,
     cwd: &str,
-    mut collector: Option<&mut audit::AuditCollector>,
+    collector: Option<&mut audit::AuditCollector>,
 ) -> Decision {
+    analyze_command(command, config, cwd, collector).decision
+}
+
+/// コマンド解析の結果
+pub struct CommandAnalysis {
+    /// 最終的な判定
+    pub decision: Decision,
+    /// 評価した docker コマンド
+    pub docker_commands: Vec<docker_args::DockerCommand>,
+    /// 全セグメントが docker コマンドで、リダイレクト等の書き込みもない
+    /// (auto_approve で明示的に allow してよい形のコマンド)
+    pub docker_only: bool,
+}
+
+/// コマンド文字列を解析し、判定と評価内容を返す
+pub fn analyze_command(
+    command: &str,
+    config: &config::Config,
+    cwd: &str,
+    collector: Option<&mut audit::AuditCollector>,
+) -> CommandAnalysis {
+    analyze_command_in_session(command, config, cwd, None, collector)
+}
+
+/// コマンド文字列を解析し、判定と評価内容を返す (セッション ID をポリシー拡張に渡す)
+pub fn analyze_command_in_session(
+    command: &str,
+    config: &config::Config,
+    cwd: &str,
+    session_id: Option<&str>,
+    mut collector: Option<&mut audit::AuditCollector>,
+) -> CommandAnalysis {
     // シェルコマンドをセグメントに分割
     let segments = shell::split_commands(command);
 
     let mut all_deny_reasons = Vec::new();
     let mut all_ask_reasons = Vec::new();
+    let mut docker_commands = Vec::new();
+    let mut docker_only = !segments.is_empty();
+    let session_containers = session_containers(config, session_id);
 
     for segment in &segments {
+        // コマンド置換・プロセス置換は docker 以外のコマンドを実行する
+        if shell::contains_substitution(segment) {
+            docker_only = false;
+        }
+
+        // safe-docker 自身の設定・ラッパーへの書き込み検出 (sed -i, リダイレクト等)
+        let write_targets = shell::extract_write_targets(segment);
+        if !write_targets.is_empty() {
+            docker_only = false;
+        }
+        let protection_reasons = protection::check_write_targets(&write_targets, cwd);
+        if !protection_reasons.is_empty() {
+            match protection::to_decision(protection_reasons, config) {
+                Decision::Deny(reason) => all_deny_reasons.push(reason),
+                Decision::Ask(reason) => all_ask_reasons.push(reason),
+                Decision::Allow => {}
+            }
+        }
+
         // シェル間接実行 (eval, bash -c 等) の検出
         if shell::detect_shell_wrappers(segment) {
             all_deny_reasons.push(
                 "[safe-docker] Shell wrapper detected: indirect docker execution via eval/sh -c/bash -c is not allowed (run docker commands directly instead)".to_string()
             );
+            docker_only = false;
             continue;
         }
 
         // docker コマンドでないセグメントはスキップ
         if !shell::is_docker_command(segment) {
+            docker_only = false;
             continue;
         }
 
         // docker 引数を抽出
         let args = shell::extract_docker_args(segment);
         if args.is_empty() {
+            docker_only = false;
             continue;
         }
 
@@ -495,22 +938,287 @@ pub fn process_command_with_audit(
             c.record_docker_command(&docker_cmd);
         }
 
-        // ポリシー評価
-        match policy::evaluate(&docker_cmd, config, cwd) {
+        // ポリシー評価 (組み込みポリシー → ポリシー拡張)
+        let decision = policy::evaluate(&docker_cmd, config, cwd);
+        let ctx = policy_ext::Context {
+            args: &args,
+            cwd,
+            session_id,
+            session_containers: &session_containers,
+        };
+        match policy_ext::apply(decision, &docker_cmd, config, &ctx) {
             Decision::Allow => {}
             Decision::Deny(reason) => all_deny_reasons.push(reason),
             Decision::Ask(reason) => all_ask_reasons.push(reason),
         }
+        docker_commands.push(docker_cmd);
     }
 
     // 集約: deny > ask > allow
-    if !all_deny_reasons.is_empty() {
+    let decision = if !all_deny_reasons.is_empty() {
         Decision::Deny(all_deny_reasons.join("\n"))
     } else if !all_ask_reasons.is_empty() {
         Decision::Ask(all_ask_reasons.join("\n"))
     } else {
         Decision::Allow
+    };
+
+    CommandAnalysis {
+        decision,
+        docker_commands,
+        docker_only,
+    }
+}
+
+/// セッションで作成したコンテナの参照 (ポリシー拡張の `session.containers`)
+fn session_containers(config: &config::Config, session_id: Option<&str>) -> Vec<String> {
+    match session_id {
+        Some(id) if config.policy.is_enabled() || !config.plugins.is_empty() => {
+            session::load(&config.hook.session_dir, id).container_refs()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Bash コマンドに対する hook レスポンスを構築する。
+/// 書き換えが有効な場合は、より安全なコマンドを updatedInput で提案する。
+fn build_command_response(
+    input: &hook::HookInput,
+    command: &str,
+    config: &config::Config,
+    cwd: &str,
+    collector: Option<&mut audit::AuditCollector>,
+) -> hook::HookResponse {
+    let session_id = input.session_id.as_deref();
+    // 監査ログには実際に実行されるコマンド (書き換えた場合は書き換え後) の解析結果を記録する
+    let mut original_collector = audit::AuditCollector::new();
+    let analysis = analyze_command_in_session(
+        command,
+        config,
+        cwd,
+        session_id,
+        Some(&mut original_collector),
+    );
+
+    if let Some(rewritten) = rewrite::rewrite_command(command, config) {
+        let mut rewritten_collector = audit::AuditCollector::new();
+        let rewritten_analysis = analyze_command_in_session(
+            &rewritten.command,
+            config,
+            cwd,
+            session_id,
+            Some(&mut rewritten_collector),
+        );
+        // 書き換えても deny になる場合は元のコマンドの判定を返す
+        if !matches!(rewritten_analysis.decision, Decision::Deny(_)) {
+            if let Some(collector) = collector {
+                *collector = rewritten_collector;
+            }
+            let mut response = build_rewrite_response(rewritten_analysis, &rewritten, config);
+            response.updated_input = Some(hook::updated_bash_input(input, &rewritten.command));
+            return response;
+        }
+    }
+
+    if let Some(collector) = collector {
+        *collector = original_collector;
+    }
+    build_hook_response(analysis, config)
+}
+
+/// docker を操作する MCP ツール呼び出しに対する hook レスポンスを構築する。
+/// 引数を解釈できない場合は安全側に倒して deny し、対応付けのない引数がある場合は ask にする。
+fn build_mcp_response(
+    tool_name: &str,
+    call: Result<mcp_tools::McpDockerCall, String>,
+    config: &config::Config,
+    cwd: &str,
+    session_id: Option<&str>,
+    collector: Option<&mut audit::AuditCollector>,
+) -> (String, hook::HookResponse) {
+    let call = match call {
+        Ok(call) => call,
+        Err(e) => {
+            let reason = format!(
+                "[safe-docker] Cannot interpret MCP tool call {}: {}. Blocking for safety (check [[hook.mcp_tools]] in config)",
+                tool_name, e
+            );
+            return (
+                tool_name.to_string(),
+                hook::HookResponse::from_decision(Decision::Deny(reason)),
+            );
+        }
+    };
+
+    if let Some(collector) = collector {
+        collector.record_docker_command(&call.docker_cmd);
     }
+    let session_containers = session_containers(config, session_id);
+    let ctx = policy_ext::Context {
+        args: &call.args,
+        cwd,
+        session_id,
+        session_containers: &session_containers,
+    };
+    let decision = policy::evaluate(&call.docker_cmd, config, cwd);
+    let mut decision = policy_ext::apply(decision, &call.docker_cmd, config, &ctx);
+    if !call.unmapped_keys.is_empty() {
+        let reason = format!(
+            "[safe-docker] MCP tool call {} has arguments that are not inspected: {} (map them or add them to ignore_keys in [[hook.mcp_tools]])",
+            tool_name,
+            call.unmapped_keys.join(", ")
+        );
+        decision = policy_ext::merge(decision, Decision::Ask(reason));
+    }
+    let analysis = CommandAnalysis {
+        decision,
+        docker_commands: vec![call.docker_cmd],
+        docker_only: true,
+    };
+    (
+        format!("{}: {}", tool_name, call.command),
+        build_hook_response(analysis, config),
+    )
+}
+
+/// auto_approve で明示的に allow してよいか
+fn is_auto_approvable(analysis: &CommandAnalysis, config: &config::Config) -> bool {
+    let hook_config = &config.hook;
+    hook_config.auto_approve
+        && analysis.decision == Decision::Allow
+        && analysis.docker_only
+        && analysis.docker_commands.iter().all(|cmd| {
+            hook_config.auto_approve_subcommands.is_empty()
+                || hook_config
+                    .auto_approve_subcommands
+                    .contains(&cmd.subcommand.to_string())
+        })
+}
+
+/// 解析結果と設定から hook レスポンスを構築する
+/// (auto_approve による明示的 allow、additionalContext / systemMessage の付与)
+pub fn build_hook_response(
+    analysis: CommandAnalysis,
+    config: &config::Config,
+) -> hook::HookResponse {
+    let auto_approved = is_auto_approvable(&analysis, config);
+
+    // 非 docker コマンドの Allow には何も付与しない (safe-docker の判定対象外)
+    let summary = if analysis.docker_commands.is_empty() {
+        None
+    } else {
+        Some(describe_checks(&analysis))
+    };
+
+    let mut response = hook::HookResponse::from_decision(analysis.decision);
+    if auto_approved {
+        response.allow_reason = Some(
+            "[safe-docker] Auto-approved: all docker commands passed safe-docker policy checks"
+                .to_string(),
+        );
+    }
+    // 何も出力しない Allow には付加情報も付けない
+    if response.decision != Decision::Allow || response.allow_reason.is_some() {
+        attach_summary(&mut response, summary, config);
+    }
+    response
+}
+
+/// 書き換え後のコマンドの解析結果から hook レスポンスを構築する。
+/// 書き換え内容を理由に含め、auto_approve 対象でなければ ask でユーザーに確認させる。
+pub fn build_rewrite_response(
+    analysis: CommandAnalysis,
+    rewritten: &rewrite::RewrittenCommand,
+    config: &config::Config,
+) -> hook::HookResponse {
+    let auto_approved = is_auto_approvable(&analysis, config);
+    let summary = describe_checks(&analysis);
+
+    let changes: Vec<String> = rewritten
+        .changes
+        .iter()
+        .map(|c| format!("  - {}", c))
+        .collect();
+    let diff = format!(
+        "[safe-docker] Command rewritten to a safer form:\n{}\n  Rewritten: {}",
+        changes.join("\n"),
+        rewritten.command
+    );
+
+    let decision = match analysis.decision {
+        Decision::Allow if auto_approved => Decision::Allow,
+        Decision::Allow => Decision::Ask(diff.clone()),
+        Decision::Ask(reason) => Decision::Ask(format!("{}\n{}", diff, reason)),
+        Decision::Deny(reason) => Decision::Deny(reason),
+    };
+    let mut response = hook::HookResponse::from_decision(decision);
+    if auto_approved {
+        response.allow_reason = Some(diff);
+    }
+    attach_summary(&mut response, Some(summary), config);
+    response
+}
+
+/// 設定に応じて additionalContext / systemMessage を付与する
+fn attach_summary(
+    response: &mut hook::HookResponse,
+    summary: Option<String>,
+    config: &config::Config,
+) {
+    if config.hook.additional_context {
+        response.additional_context = summary.clone();
+    }
+    if config.hook.system_message {
+        response.system_message = summary;
+    }
+}
+
+/// 評価した内容の説明文を生成する
+fn describe_checks(analysis: &CommandAnalysis) -> String {
+    let commands: Vec<String> = analysis
+        .docker_commands
+        .iter()
+        .map(|cmd| {
+            let mut details = Vec::new();
+            if let Some(ref image) = cmd.image {
+                details.push(format!("image {}", image));
+            }
+            if !cmd.bind_mounts.is_empty() {
+                let paths: Vec<&str> = cmd
+                    .bind_mounts
+                    .iter()
+                    .map(|m| m.host_path.as_str())
+                    .collect();
+                details.push(format!("bind mounts {}", paths.join(", ")));
+            }
+            if !cmd.host_paths.is_empty() {
+                details.push(format!("host paths {}", cmd.host_paths.join(", ")));
+            }
+            if let Some(ref compose_file) = cmd.compose_file {
+                details.push(format!("compose file {}", compose_file));
+            }
+            if cmd.dangerous_flags.is_empty() {
+                details.push("no dangerous flags".to_string());
+            } else {
+                let flags: Vec<String> =
+                    cmd.dangerous_flags.iter().map(|f| f.to_string()).collect();
+                details.push(format!("flags {}", flags.join(", ")));
+            }
+            format!("docker {} ({})", cmd.subcommand, details.join("; "))
+        })
+        .collect();
+
+    let result = match &analysis.decision {
+        Decision::Allow => "allow",
+        Decision::Deny(_) => "deny",
+        Decision::Ask(_) => "ask",
+    };
+
+    format!(
+        "[safe-docker] Checked bind mount paths, dangerous flags, capabilities, image allowlist and compose settings for: {}. Result: {}",
+        commands.join(", "),
+        result
+    )
 }
 
 #[cfg(test)]
@@ -539,8 +1247,13 @@ mod tests {
 
     #[test]
     fn test_docker_allowed_mount() {
+        // マウント元が存在しない場合の確認はこのテストの対象外
+        let config = config::Config {
+            ask_missing_mount_sources: false,
+            ..default_config()
+        };
         let cmd = format!("docker run -v {}/projects:/app ubuntu", home_dir());
-        let decision = process_command(&cmd, &default_config(), "/tmp");
+        let decision = process_command(&cmd, &config, "/tmp");
         assert_eq!(decision, Decision::Allow);
     }
 
@@ -596,11 +1309,12 @@ mod tests {
 
     #[test]
     fn test_docker_tilde_mount() {
-        let decision = process_command(
-            "docker run -v ~/projects:/app ubuntu",
-            &default_config(),
-            "/tmp",
-        );
+        // マウント元が存在しない場合の確認はこのテストの対象外
+        let config = config::Config {
+            ask_missing_mount_sources: false,
+            ..default_config()
+        };
+        let decision = process_command("docker run -v ~/projects:/app ubuntu", &config, "/tmp");
         assert_eq!(decision, Decision::Allow);
     }
 
@@ -612,7 +1326,9 @@ mod tests {
 
     #[test]
     fn test_docker_build() {
-        let decision = process_command("docker build -t myapp .", &default_config(), "/tmp");
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = dir.path().to_string_lossy();
+        let decision = process_command("docker build -t myapp .", &default_config(), &cwd);
         assert_eq!(decision, Decision::Allow);
     }
 
@@ -626,6 +1342,207 @@ mod tests {
         assert!(matches!(decision, Decision::Deny(_)));
     }
 
+    #[test]
+    fn test_self_protection_sed_config() {
+        let decision = process_command(
+            "sed -i 's/deny/allow/' ~/.claude/settings.json",
+            &default_config(),
+            "/tmp",
+        );
+        assert!(matches!(decision, Decision::Deny(_)));
+    }
+
+    #[test]
+    fn test_self_protection_redirect_ask_mode() {
+        let mut config = default_config();
+        config.hook.self_protection = config::SelfProtection::Ask;
+        let decision = process_command(
+            "echo '' > ~/.local/bin/docker && docker ps",
+            &config,
+            "/tmp",
+        );
+        assert!(matches!(decision, Decision::Ask(_)));
+    }
+
+    #[test]
+    fn test_self_protection_unrelated_write() {
+        let decision = process_command("echo hi > /tmp/out.txt", &default_config(), "/tmp");
+        assert_eq!(decision, Decision::Allow);
+    }
+
+    fn auto_approve_config(subcommands: &[&str]) -> config::Config {
+        let mut config = default_config();
+        config.hook.auto_approve = true;
+        config.hook.auto_approve_subcommands = subcommands.iter().map(|s| s.to_string()).collect();
+        config
+    }
+
+    fn hook_response(command: &str, config: &config::Config) -> hook::HookResponse {
+        build_hook_response(analyze_command(command, config, "/tmp", None), config)
+    }
+
+    #[test]
+    fn test_auto_approve_disabled_by_default() {
+        let response = hook_response("docker ps", &default_config());
+        assert_eq!(response.decision, Decision::Allow);
+        assert!(response.allow_reason.is_none());
+    }
+
+    #[test]
+    fn test_auto_approve_passed_command() {
+        let response = hook_response("docker ps", &auto_approve_config(&[]));
+        assert_eq!(response.decision, Decision::Allow);
+        assert!(response.allow_reason.is_some());
+    }
+
+    #[test]
+    fn test_auto_approve_limited_subcommands() {
+        let config = auto_approve_config(&["ps", "logs"]);
+        assert!(
+            hook_response("docker logs web", &config)
+                .allow_reason
+                .is_some()
+        );
+        assert!(
+            hook_response("docker run ubuntu", &config)
+                .allow_reason
+                .is_none()
+        );
+        assert!(
+            hook_response("docker ps && docker run ubuntu", &config)
+                .allow_reason
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_auto_approve_requires_docker_only() {
+        let config = auto_approve_config(&[]);
+        // 非 docker セグメントやリダイレクトを含む場合は Claude Code の通常の確認に任せる
+        assert!(
+            hook_response("docker ps | grep web", &config)
+                .allow_reason
+                .is_none()
+        );
+        assert!(
+            hook_response("docker ps > /tmp/ps.txt", &config)
+                .allow_reason
+                .is_none()
+        );
+        assert!(hook_response("ls -la", &config).allow_reason.is_none());
+    }
+
+    #[test]
+    fn test_auto_approve_not_for_substitutions() {
+        let config = auto_approve_config(&[]);
+        // 置換の中身は docker 以外のコマンドとして実行される
+        for command in [
+            "docker ps $(rm -rf ~/important)",
+            "docker ps `rm -rf ~/important`",
+            "docker run --env-file <(cat ~/.aws/credentials) ubuntu",
+        ] {
+            let response = hook_response(command, &config);
+            assert!(response.allow_reason.is_none(), "{}", command);
+        }
+    }
+
+    #[test]
+    fn test_auto_approve_not_for_ask_or_deny() {
+        let config = auto_approve_config(&[]);
+        let response = hook_response("docker run --privileged ubuntu", &config);
+        assert!(matches!(response.decision, Decision::Deny(_)));
+        assert!(response.allow_reason.is_none());
+    }
+
+    #[test]
+    fn test_additional_context_and_system_message() {
+        let mut config = auto_approve_config(&[]);
+        config.hook.additional_context = true;
+        config.hook.system_message = true;
+        let response = hook_response("docker run ubuntu echo hi", &config);
+        let context = response.additional_context.unwrap();
+        assert!(context.contains("docker run"), "{}", context);
+        assert!(context.contains("image ubuntu"), "{}", context);
+        assert!(response.system_message.is_some());
+
+        // 暗黙の Allow (出力なし) には付与しない
+        config.hook.auto_approve = false;
+        let response = hook_response("docker ps", &config);
+        assert!(response.additional_context.is_none());
+
+        // deny にも付与する
+        let response = hook_response("docker run -v /etc:/etc ubuntu", &config);
+        assert!(
+            response
+                .additional_context
+                .unwrap()
+                .contains("Result: deny")
+        );
+    }
+
+    fn rewrite_config() -> config::Config {
+        let mut config = default_config();
+        config.hook.rewrite.enabled = true;
+        config
+    }
+
+    fn input_with_command(command: &str) -> hook::HookInput {
+        serde_json::from_value(serde_json::json!({
+            "tool_name": "Bash",
+            "tool_input": {"command": command, "description": "test"}
+        }))
+        .unwrap()
+    }
+
+    fn command_response(command: &str, config: &config::Config) -> hook::HookResponse {
+        build_command_response(&input_with_command(command), command, config, "/tmp", None)
+    }
+
+    #[test]
+    fn test_rewrite_response_asks_with_updated_input() {
+        let response = command_response("docker run -p 8080:80 nginx", &rewrite_config());
+        let Decision::Ask(reason) = &response.decision else {
+            panic!("expected ask: {:?}", response.decision);
+        };
+        assert!(reason.contains("127.0.0.1:8080:80"), "{}", reason);
+        assert!(reason.contains("--cap-drop ALL"), "{}", reason);
+        let updated = response.updated_input.unwrap();
+        assert_eq!(
+            updated["command"],
+            "docker run --security-opt no-new-privileges --cap-drop ALL -p 127.0.0.1:8080:80 nginx"
+        );
+        assert_eq!(updated["description"], "test");
+    }
+
+    #[test]
+    fn test_rewrite_response_auto_approved() {
+        let mut config = rewrite_config();
+        config.hook.auto_approve = true;
+        let response = command_response("docker run -p 8080:80 nginx", &config);
+        assert_eq!(response.decision, Decision::Allow);
+        assert!(response.allow_reason.unwrap().contains("rewritten"));
+        assert!(response.updated_input.is_some());
+    }
+
+    #[test]
+    fn test_rewrite_sensitive_mount_still_asks() {
+        let response =
+            command_response("docker run -v ~/.aws:/root/.aws ubuntu", &rewrite_config());
+        let Decision::Ask(reason) = &response.decision else {
+            panic!("expected ask: {:?}", response.decision);
+        };
+        assert!(reason.contains(":ro"), "{}", reason);
+        assert!(reason.contains("sensitive path"), "{}", reason);
+    }
+
+    #[test]
+    fn test_rewrite_not_applied_when_still_denied() {
+        let response =
+            command_response("docker run --privileged -p 80:80 nginx", &rewrite_config());
+        assert!(matches!(response.decision, Decision::Deny(_)));
+        assert!(response.updated_input.is_none());
+    }
+
     #[test]
     fn test_docker_device() {
         let decision = process_command(
@@ -0,0 +1,819 @@
+//! MCP サーバーモード (`safe-docker mcp`)
+//!
+//! stdio 上の MCP (JSON-RPC 2.0、1 行 1 メッセージ) で、ポリシー検査済みの docker 操作ツールを提供する。
+//! すべてのツール呼び出しは `policy::evaluate` で検査し、許可された場合のみ本物の docker で実行する。
+//! deny (および非対話環境の設定で許可されない ask) は、判定理由を含むツールエラーとして返す。
+
+use serde_json::{Value, json};
+use std::io::{self, BufRead, Read, Write as _};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, McpToolMapping, NonInteractiveAsk};
+use crate::hook::Decision;
+use crate::{audit, mcp_tools, wrapper};
+
+/// 対応する MCP プロトコルバージョン (先頭が最新)
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// docker コマンドの実行タイムアウト
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// ツール結果に含める stdout / stderr の最大サイズ (超えた分は先頭を切り捨てる)
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+// JSON-RPC エラーコード
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// MCP サーバーの状態
+pub struct Server {
+    config: Config,
+    config_source: String,
+    cwd: String,
+}
+
+/// docker コマンドの実行結果
+struct DockerOutput {
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+    timed_out: bool,
+}
+
+/// MCP サーバーモードのエントリポイント。stdin が閉じるまでリクエストを処理する。
+pub fn run(config: Config, config_source: String) -> i32 {
+    let cwd = std::env::current_dir()
+        .ok()
+        .and_then(|p| p.to_str().map(String::from))
+        .unwrap_or_else(|| ".".to_string());
+    let server = Server::new(config, config_source, cwd);
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => server.handle_message(&message),
+            Err(_) => Some(error_response(Value::Null, PARSE_ERROR, "Parse error")),
+        };
+        if let Some(response) = response {
+            let mut handle = stdout.lock();
+            let _ = serde_json::to_writer(&mut handle, &response);
+            let _ = writeln!(handle);
+            let _ = handle.flush();
+        }
+    }
+    0
+}
+
+fn success_response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// ツールの実行結果
+fn tool_result(text: String, is_error: bool, structured: Value) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "structuredContent": structured,
+        "isError": is_error,
+    })
+}
+
+/// 判定理由を個別の指摘事項に分解する
+fn findings(reason: &str) -> Vec<String> {
+    reason
+        .lines()
+        .map(|line| line.trim().trim_start_matches("[safe-docker] "))
+        .map(|line| line.strip_prefix("- ").unwrap_or(line))
+        .filter(|line| !line.is_empty() && *line != "Multiple issues found:")
+        .map(String::from)
+        .collect()
+}
+
+/// ポリシーにより実行しなかった場合のツールエラー
+fn blocked(decision: &str, command: &str, reason: &str) -> Value {
+    tool_result(
+        reason.to_string(),
+        true,
+        json!({ "decision": decision, "command": command, "findings": findings(reason) }),
+    )
+}
+
+impl Server {
+    pub fn new(config: Config, config_source: String, cwd: String) -> Self {
+        Self {
+            config,
+            config_source,
+            cwd,
+        }
+    }
+
+    /// JSON-RPC メッセージを処理する。通知 (id なし) には応答しない。
+    pub fn handle_message(&self, message: &Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            // クライアントからの応答 (result / error) は無視する
+            if message.get("result").is_some() || message.get("error").is_some() {
+                return None;
+            }
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "Invalid Request",
+            ));
+        };
+        let id = id?;
+        let params = message.get("params").cloned().unwrap_or(json!({}));
+
+        let response = match method {
+            "initialize" => success_response(id, initialize_result(&params)),
+            "ping" => success_response(id, json!({})),
+            "tools/list" => success_response(id, json!({ "tools": tool_definitions() })),
+            "tools/call" => match self.call_tool(&params) {
+                Ok(result) => success_response(id, result),
+                Err(e) => error_response(id, INVALID_PARAMS, &e),
+            },
+            _ => error_response(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("Method not found: {}", method),
+            ),
+        };
+        Some(response)
+    }
+
+    /// tools/call の処理。未知のツールは JSON-RPC エラー、それ以外の失敗はツールエラーとして返す。
+    fn call_tool(&self, params: &Value) -> Result<Value, String> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or("missing tool name")?;
+        let args = params.get("arguments").cloned().unwrap_or(json!({}));
+
+        let docker_args = match name {
+            "check_command" => return Ok(self.check_command(&args)),
+            "docker_run" => run_args(&args),
+            "docker_build" => build_args(&args),
+            "compose_up" => compose_up_args(&args),
+            "docker_logs" => logs_args(&args),
+            _ => return Err(format!("Unknown tool: {}", name)),
+        };
+        Ok(match docker_args {
+            Ok(docker_args) => self.execute(&docker_args),
+            Err(e) => tool_result(
+                format!("[safe-docker] Invalid arguments for {}: {}", name, e),
+                true,
+                json!({ "decision": "invalid", "findings": [e] }),
+            ),
+        })
+    }
+
+    /// コマンド文字列をポリシー検査のみ行う (実行しない)
+    fn check_command(&self, args: &Value) -> Value {
+        let Some(command) = args.get("command").and_then(Value::as_str) else {
+            return tool_result(
+                "[safe-docker] Invalid arguments for check_command: 'command' is required"
+                    .to_string(),
+                true,
+                json!({ "decision": "invalid", "findings": ["'command' is required"] }),
+            );
+        };
+        let analysis = crate::analyze_command(command, &self.config, &self.cwd, None);
+        let docker_commands: Vec<String> = analysis
+            .docker_commands
+            .iter()
+            .map(|cmd| cmd.subcommand.to_string())
+            .collect();
+        let (decision, text, reasons) = match &analysis.decision {
+            Decision::Allow => ("allow", "allow".to_string(), Vec::new()),
+            Decision::Deny(reason) => ("deny", reason.clone(), findings(reason)),
+            Decision::Ask(reason) => ("ask", reason.clone(), findings(reason)),
+        };
+        tool_result(
+            text,
+            false,
+            json!({
+                "decision": decision,
+                "findings": reasons,
+                "docker_commands": docker_commands,
+            }),
+        )
+    }
+
+    /// docker 引数をポリシー検査し、許可された場合のみ本物の docker で実行する
+    fn execute(&self, docker_args: &[String]) -> Value {
+        let command = format!("docker {}", shell_words::join(docker_args));
+        let mut collector = audit::AuditCollector::new();
+        let decision = wrapper::evaluate_docker_args(
+            docker_args,
+            &self.config,
+            &self.cwd,
+            Some(&mut collector),
+        );
+        self.emit_audit(&command, &decision, &collector);
+
+        match &decision {
+            Decision::Deny(reason) => return blocked("deny", &command, reason),
+            Decision::Ask(reason)
+                if wrapper::non_interactive_ask(&self.config) != NonInteractiveAsk::Allow =>
+            {
+                return blocked("ask", &command, reason);
+            }
+            _ => {}
+        }
+
+        let docker = match wrapper::find_real_docker_detailed(&self.config) {
+            Ok(res) => res.path,
+            Err(tried) => {
+                return tool_result(
+                    format!(
+                        "[safe-docker] Docker binary not found (tried: {})",
+                        tried.join("; ")
+                    ),
+                    true,
+                    json!({ "decision": "allow", "command": command }),
+                );
+            }
+        };
+
+        match run_docker(&docker, docker_args) {
+            Ok(output) => {
+                let mut text = output.stdout.clone();
+                if !output.stderr.is_empty() {
+                    if !text.is_empty() && !text.ends_with('\n') {
+                        text.push('\n');
+                    }
+                    text.push_str(&output.stderr);
+                }
+                if output.timed_out {
+                    text.push_str(&format!(
+                        "\n[safe-docker] Timed out after {}s; docker was killed",
+                        COMMAND_TIMEOUT.as_secs()
+                    ));
+                }
+                tool_result(
+                    text,
+                    output.timed_out || output.exit_code != Some(0),
+                    json!({
+                        "decision": "allow",
+                        "command": command,
+                        "exit_code": output.exit_code,
+                        "stdout": output.stdout,
+                        "stderr": output.stderr,
+                    }),
+                )
+            }
+            Err(e) => tool_result(
+                format!("[safe-docker] Failed to run {}: {}", docker.display(), e),
+                true,
+                json!({ "decision": "allow", "command": command }),
+            ),
+        }
+    }
+
+    fn emit_audit(&self, command: &str, decision: &Decision, collector: &audit::AuditCollector) {
+        if !audit::is_enabled(&self.config.audit) {
+            return;
+        }
+        let (decision_str, reason) = match decision {
+            Decision::Allow => ("allow", None),
+            Decision::Deny(r) => ("deny", Some(r.as_str())),
+            Decision::Ask(r) => ("ask", Some(r.as_str())),
+        };
+        let event = audit::build_event(&audit::AuditContext {
+            command,
+            decision: decision_str,
+            reason,
+            collector,
+            session_id: None,
+            cwd: &self.cwd,
+            mode: "mcp",
+            config_source: Some(&self.config_source),
+        });
+        audit::emit(&event, &self.config.audit);
+    }
+}
+
+/// initialize の結果。クライアントの要求バージョンに対応していればそれを返す。
+fn initialize_result(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|v| SUPPORTED_PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": { "listChanged": false } },
+        "serverInfo": { "name": "safe-docker", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "Docker operations checked by safe-docker policy. Denied calls return isError with findings.",
+    })
+}
+
+/// 提供するツールの定義
+fn tool_definitions() -> Vec<Value> {
+    let string_array = json!({ "type": "array", "items": { "type": "string" } });
+    vec![
+        json!({
+            "name": "docker_run",
+            "description": "Run a container (detached by default) after safe-docker policy checks.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "image": { "type": "string", "description": "Image reference" },
+                    "command": string_array.clone(),
+                    "name": { "type": "string" },
+                    "volumes": { "type": "array", "items": { "type": "string" }, "description": "SRC:DST[:ro]" },
+                    "ports": { "type": "array", "items": { "type": "string" }, "description": "e.g. 127.0.0.1:8080:80" },
+                    "env": { "type": "object", "additionalProperties": { "type": "string" } },
+                    "network": { "type": "string" },
+                    "workdir": { "type": "string" },
+                    "detach": { "type": "boolean", "default": true },
+                    "rm": { "type": "boolean", "default": false },
+                },
+                "required": ["image"],
+            },
+        }),
+        json!({
+            "name": "docker_build",
+            "description": "Build an image after safe-docker policy checks.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "context": { "type": "string", "description": "Build context directory" },
+                    "file": { "type": "string", "description": "Dockerfile path" },
+                    "tags": string_array.clone(),
+                    "build_args": { "type": "object", "additionalProperties": { "type": "string" } },
+                    "target": { "type": "string" },
+                },
+                "required": ["context"],
+            },
+        }),
+        json!({
+            "name": "compose_up",
+            "description": "Start compose services (detached by default) after safe-docker policy checks.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "files": string_array.clone(),
+                    "project_directory": { "type": "string" },
+                    "services": string_array,
+                    "build": { "type": "boolean", "default": false },
+                    "detach": { "type": "boolean", "default": true },
+                },
+            },
+        }),
+        json!({
+            "name": "docker_logs",
+            "description": "Fetch container logs.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "container": { "type": "string" },
+                    "tail": { "type": "integer", "minimum": 0 },
+                    "since": { "type": "string" },
+                    "timestamps": { "type": "boolean", "default": false },
+                },
+                "required": ["container"],
+            },
+        }),
+        json!({
+            "name": "check_command",
+            "description": "Check a shell command containing docker invocations against safe-docker policy without running it.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "command": { "type": "string" } },
+                "required": ["command"],
+            },
+        }),
+    ]
+}
+
+/// 文字列引数を取得する。フラグとして解釈される値 (`-` 始まり) は拒否する。
+fn string_arg(args: &Value, key: &str) -> Result<Option<String>, String> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if s.starts_with('-') => {
+            Err(format!("'{}' must not start with '-': {}", key, s))
+        }
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(other) => Err(format!("'{}' must be a string: {}", key, other)),
+    }
+}
+
+fn required_string_arg(args: &Value, key: &str) -> Result<String, String> {
+    string_arg(args, key)?.ok_or_else(|| format!("'{}' is required", key))
+}
+
+fn string_array_arg(args: &Value, key: &str) -> Result<Vec<String>, String> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(String::from)
+                    .ok_or_else(|| format!("'{}' must be an array of strings", key))
+            })
+            .collect(),
+        Some(other) => Err(format!("'{}' must be an array of strings: {}", key, other)),
+    }
+}
+
+fn bool_arg(args: &Value, key: &str, default: bool) -> Result<bool, String> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(default),
+        Some(Value::Bool(b)) => Ok(*b),
+        Some(other) => Err(format!("'{}' must be a boolean: {}", key, other)),
+    }
+}
+
+/// docker_run の引数を docker run の引数に変換する
+fn run_args(args: &Value) -> Result<Vec<String>, String> {
+    let image = required_string_arg(args, "image")?;
+    // volumes / ports / env / network は MCP ツールの対応付けと同じ変換を使う
+    let mapping = McpToolMapping {
+        tool: "docker_run".to_string(),
+        subcommand: "run".to_string(),
+        volumes: Some("volumes".to_string()),
+        ports: Some("ports".to_string()),
+        env: Some("env".to_string()),
+        network: Some("network".to_string()),
+        ..Default::default()
+    };
+    let mut docker_args = mcp_tools::to_docker_args(&mapping, args)?;
+
+    let mut options = Vec::new();
+    if bool_arg(args, "detach", true)? {
+        options.push("-d".to_string());
+    }
+    if bool_arg(args, "rm", false)? {
+        options.push("--rm".to_string());
+    }
+    if let Some(name) = string_arg(args, "name")? {
+        options.extend(["--name".to_string(), name]);
+    }
+    if let Some(workdir) = string_arg(args, "workdir")? {
+        options.extend(["-w".to_string(), workdir]);
+    }
+    docker_args.splice(1..1, options);
+    docker_args.push(image);
+    docker_args.extend(string_array_arg(args, "command")?);
+    Ok(docker_args)
+}
+
+/// docker_build の引数を docker build の引数に変換する
+fn build_args(args: &Value) -> Result<Vec<String>, String> {
+    let context = required_string_arg(args, "context")?;
+    let mut docker_args = vec!["build".to_string()];
+    for tag in string_array_arg(args, "tags")? {
+        docker_args.extend(["-t".to_string(), tag]);
+    }
+    if let Some(file) = string_arg(args, "file")? {
+        docker_args.extend(["-f".to_string(), file]);
+    }
+    if let Some(target) = string_arg(args, "target")? {
+        docker_args.extend(["--target".to_string(), target]);
+    }
+    match args.get("build_args") {
+        None | Some(Value::Null) => {}
+        Some(Value::Object(map)) => {
+            for (key, value) in map {
+                let value = value
+                    .as_str()
+                    .ok_or_else(|| format!("'build_args.{}' must be a string", key))?;
+                docker_args.extend(["--build-arg".to_string(), format!("{}={}", key, value)]);
+            }
+        }
+        Some(other) => return Err(format!("'build_args' must be an object: {}", other)),
+    }
+    docker_args.push(context);
+    Ok(docker_args)
+}
+
+/// compose_up の引数を docker compose up の引数に変換する
+fn compose_up_args(args: &Value) -> Result<Vec<String>, String> {
+    let mut docker_args = vec!["compose".to_string()];
+    for file in string_array_arg(args, "files")? {
+        docker_args.extend(["-f".to_string(), file]);
+    }
+    if let Some(dir) = string_arg(args, "project_directory")? {
+        docker_args.extend(["--project-directory".to_string(), dir]);
+    }
+    docker_args.push("up".to_string());
+    if bool_arg(args, "detach", true)? {
+        docker_args.push("-d".to_string());
+    }
+    if bool_arg(args, "build", false)? {
+        docker_args.push("--build".to_string());
+    }
+    for service in string_array_arg(args, "services")? {
+        if service.starts_with('-') {
+            return Err(format!("'services' must not start with '-': {}", service));
+        }
+        docker_args.push(service);
+    }
+    Ok(docker_args)
+}
+
+/// docker_logs の引数を docker logs の引数に変換する
+fn logs_args(args: &Value) -> Result<Vec<String>, String> {
+    let container = required_string_arg(args, "container")?;
+    let mut docker_args = vec!["logs".to_string()];
+    match args.get("tail") {
+        None | Some(Value::Null) => {}
+        Some(tail) => {
+            let tail = tail
+                .as_u64()
+                .ok_or_else(|| format!("'tail' must be a non-negative integer: {}", tail))?;
+            docker_args.extend(["--tail".to_string(), tail.to_string()]);
+        }
+    }
+    if let Some(since) = string_arg(args, "since")? {
+        docker_args.extend(["--since".to_string(), since]);
+    }
+    if bool_arg(args, "timestamps", false)? {
+        docker_args.push("--timestamps".to_string());
+    }
+    docker_args.push(container);
+    Ok(docker_args)
+}
+
+/// 出力を末尾 MAX_OUTPUT_BYTES に切り詰める
+fn truncate_output(output: &[u8]) -> String {
+    let text = String::from_utf8_lossy(output);
+    if text.len() <= MAX_OUTPUT_BYTES {
+        return text.into_owned();
+    }
+    let mut start = text.len() - MAX_OUTPUT_BYTES;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("[safe-docker] (output truncated)\n{}", &text[start..])
+}
+
+fn spawn_reader<R: Read + Send + 'static>(reader: Option<R>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut reader) = reader {
+            let _ = reader.read_to_end(&mut buf);
+        }
+        truncate_output(&buf)
+    })
+}
+
+/// 本物の docker をタイムアウト付きで実行する
+fn run_docker(docker: &Path, args: &[String]) -> io::Result<DockerOutput> {
+    let mut child = Command::new(docker)
+        .args(args)
+        .env("SAFE_DOCKER_ACTIVE", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = spawn_reader(child.stdout.take());
+    let stderr = spawn_reader(child.stderr.take());
+
+    let deadline = Instant::now() + COMMAND_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+
+    Ok(DockerOutput {
+        exit_code: status.and_then(|s| s.code()),
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+        timed_out: status.is_none(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server() -> Server {
+        Server::new(
+            Config::default(),
+            "(default)".to_string(),
+            "/tmp".to_string(),
+        )
+    }
+
+    fn call(server: &Server, name: &str, arguments: Value) -> Value {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": arguments },
+        });
+        server.handle_message(&request).unwrap()["result"].clone()
+    }
+
+    #[test]
+    fn test_initialize() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": { "protocolVersion": "2025-03-26", "capabilities": {} },
+        });
+        let response = server().handle_message(&request).unwrap();
+        assert_eq!(response["id"], 0);
+        assert_eq!(response["result"]["protocolVersion"], "2025-03-26");
+        assert_eq!(response["result"]["serverInfo"]["name"], "safe-docker");
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "protocolVersion": "1999-01-01" },
+        });
+        let response = server().handle_message(&request).unwrap();
+        assert_eq!(
+            response["result"]["protocolVersion"],
+            SUPPORTED_PROTOCOL_VERSIONS[0]
+        );
+    }
+
+    #[test]
+    fn test_notification_has_no_response() {
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(server().handle_message(&notification).is_none());
+    }
+
+    #[test]
+    fn test_tools_list() {
+        let request = json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" });
+        let response = server().handle_message(&request).unwrap();
+        let names: Vec<&str> = response["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "docker_run",
+                "docker_build",
+                "compose_up",
+                "docker_logs",
+                "check_command"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_method_and_tool() {
+        let server = server();
+        let request = json!({ "jsonrpc": "2.0", "id": 3, "method": "resources/list" });
+        let response = server.handle_message(&request).unwrap();
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "method": "tools/call",
+            "params": { "name": "docker_exec", "arguments": {} },
+        });
+        let response = server.handle_message(&request).unwrap();
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_docker_run_denied_without_execution() {
+        let result = call(
+            &server(),
+            "docker_run",
+            json!({ "image": "ubuntu", "volumes": ["/etc:/host-etc", "/var/run/docker.sock:/var/run/docker.sock"] }),
+        );
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["structuredContent"]["decision"], "deny");
+        let findings = result["structuredContent"]["findings"].as_array().unwrap();
+        assert_eq!(findings.len(), 2, "findings: {:?}", findings);
+        assert!(
+            result["structuredContent"]["command"]
+                .as_str()
+                .unwrap()
+                .starts_with("docker run -d -v /etc:/host-etc")
+        );
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let server = server();
+        let result = call(&server, "docker_run", json!({}));
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["structuredContent"]["decision"], "invalid");
+
+        // フラグとして解釈される値は拒否する
+        let result = call(&server, "docker_logs", json!({ "container": "--help" }));
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["structuredContent"]["decision"], "invalid");
+    }
+
+    #[test]
+    fn test_check_command() {
+        let server = server();
+        let result = call(
+            &server,
+            "check_command",
+            json!({ "command": "docker run --privileged ubuntu && docker ps" }),
+        );
+        assert_eq!(result["isError"], false);
+        assert_eq!(result["structuredContent"]["decision"], "deny");
+        assert_eq!(
+            result["structuredContent"]["docker_commands"],
+            json!(["run", "ps"])
+        );
+
+        let result = call(&server, "check_command", json!({ "command": "docker ps" }));
+        assert_eq!(result["structuredContent"]["decision"], "allow");
+    }
+
+    #[test]
+    fn test_run_args() {
+        let args = run_args(&json!({
+            "image": "nginx",
+            "name": "web",
+            "ports": ["127.0.0.1:8080:80"],
+            "env": { "A": "1" },
+            "rm": true,
+            "command": ["nginx", "-g", "daemon off;"],
+        }))
+        .unwrap();
+        assert_eq!(
+            args,
+            vec![
+                "run",
+                "-d",
+                "--rm",
+                "--name",
+                "web",
+                "-p",
+                "127.0.0.1:8080:80",
+                "-e",
+                "A=1",
+                "nginx",
+                "nginx",
+                "-g",
+                "daemon off;"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compose_and_build_and_logs_args() {
+        assert_eq!(
+            compose_up_args(&json!({ "files": ["compose.yml"], "services": ["web"] })).unwrap(),
+            vec!["compose", "-f", "compose.yml", "up", "-d", "web"]
+        );
+        assert_eq!(
+            build_args(&json!({ "context": ".", "tags": ["app:dev"], "build_args": { "V": "1" } }))
+                .unwrap(),
+            vec!["build", "-t", "app:dev", "--build-arg", "V=1", "."]
+        );
+        assert_eq!(
+            logs_args(&json!({ "container": "web", "tail": 100 })).unwrap(),
+            vec!["logs", "--tail", "100", "web"]
+        );
+    }
+
+    #[test]
+    fn test_findings() {
+        assert_eq!(
+            findings("[safe-docker] Multiple issues found:\n  - a\n  - b"),
+            vec!["a", "b"]
+        );
+        assert_eq!(findings("[safe-docker] single"), vec!["single"]);
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        let long = "x".repeat(MAX_OUTPUT_BYTES + 10);
+        let truncated = truncate_output(long.as_bytes());
+        assert!(truncated.starts_with("[safe-docker] (output truncated)"));
+        assert!(truncated.len() < long.len() + 40);
+        assert_eq!(truncate_output(b"short"), "short");
+    }
+}
@@ -0,0 +1,571 @@
+//! MCP ツール呼び出しの docker コマンドへの変換
+//!
+//! MCP サーバー経由の docker 操作 (例: `mcp__docker__run_container`) は Bash を経由しないため、
+//! `[[hook.mcp_tools]]` の対応付けに従ってツール引数から docker CLI 引数を組み立て、
+//! 通常の docker コマンドと同じパーサー・ポリシーで評価する。
+
+use serde_json::Value;
+
+use crate::config::McpToolMapping;
+use crate::docker_args::{self, DockerCommand};
+use crate::hook::{self, HookInput};
+
+/// docker コマンドに変換した MCP ツール呼び出し
+#[derive(Debug, Clone)]
+pub struct McpDockerCall {
+    /// 表示・監査用の等価な docker コマンド
+    pub command: String,
+    /// docker に渡す引数 (サブコマンド以降)
+    pub args: Vec<String>,
+    pub docker_cmd: DockerCommand,
+    /// 対応付けにも ignore_keys にもない引数のキー (検査できないため ask にする)
+    pub unmapped_keys: Vec<String>,
+}
+
+/// ツール名に対応する設定を探す
+pub fn find_mapping<'a>(
+    tool_name: &str,
+    mappings: &'a [McpToolMapping],
+) -> Option<&'a McpToolMapping> {
+    mappings.iter().find(|m| m.matches(tool_name))
+}
+
+/// hook 入力が対応付けされた MCP ツール呼び出しであれば docker コマンドに変換する。
+/// 対応付けの対象外なら None、引数を解釈できない場合は Some(Err)。
+pub fn docker_call(
+    input: &HookInput,
+    mappings: &[McpToolMapping],
+) -> Option<Result<McpDockerCall, String>> {
+    let tool_name = input.tool_name.as_deref()?;
+    let mapping = find_mapping(tool_name, mappings)?;
+    let args = Value::Object(hook::tool_input_json(input));
+    Some(to_docker_args(mapping, &args).map(|docker_args| {
+        let args_ref: Vec<&str> = docker_args.iter().map(|s| s.as_str()).collect();
+        let docker_cmd = docker_args::parse_docker_args(&args_ref);
+        McpDockerCall {
+            command: format!("docker {}", shell_words::join(&docker_args)),
+            args: docker_args,
+            docker_cmd,
+            unmapped_keys: unmapped_keys(mapping, &args),
+        }
+    }))
+}
+
+/// 対応付けにも ignore_keys にもない引数のキーを返す。
+/// 対応付けたキーの親オブジェクト (例: `spec.image` の `spec`) は中身を再帰的に調べる。
+pub fn unmapped_keys(mapping: &McpToolMapping, args: &Value) -> Vec<String> {
+    fn walk(value: &Value, prefix: &str, known: &[&str], out: &mut Vec<String>) {
+        let Value::Object(map) = value else {
+            return;
+        };
+        for (key, value) in map {
+            let path = format!("{}{}", prefix, key);
+            if value.is_null() || known.contains(&path.as_str()) {
+                continue;
+            }
+            let parent = format!("{}.", path);
+            if value.is_object() && known.iter().any(|k| k.starts_with(&parent)) {
+                walk(value, &parent, known, out);
+            } else {
+                out.push(path);
+            }
+        }
+    }
+
+    let known: Vec<&str> = mapping.known_keys().collect();
+    let mut out = Vec::new();
+    walk(args, "", &known, &mut out);
+    out
+}
+
+/// ドット区切りのキーで値を取り出す。null は未指定として扱う。
+fn lookup<'a>(args: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(args, |value, key| value.get(key))
+        .filter(|v| !v.is_null())
+}
+
+/// 対応付けのキーと、それに対応する引数の値
+fn field<'a>(args: &'a Value, key: &'a Option<String>) -> Option<(&'a str, &'a Value)> {
+    let key = key.as_deref()?;
+    Some((key, lookup(args, key)?))
+}
+
+/// スカラー値を文字列にする
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn unsupported(key: &str, value: &Value) -> String {
+    format!("unsupported value for '{}': {}", key, value)
+}
+
+/// 対応付けに従ってツール引数から docker CLI 引数を組み立てる
+pub fn to_docker_args(mapping: &McpToolMapping, args: &Value) -> Result<Vec<String>, String> {
+    let mut docker_args = vec![mapping.subcommand.clone()];
+    if let Some((key, value)) = field(args, &mapping.privileged) {
+        let privileged = match value {
+            Value::Bool(b) => *b,
+            // "1" や "yes" を非特権として扱わない (解釈できない値はエラー)
+            Value::String(s) if s.eq_ignore_ascii_case("true") => true,
+            Value::String(s) if s.eq_ignore_ascii_case("false") => false,
+            _ => return Err(unsupported(key, value)),
+        };
+        if privileged {
+            docker_args.push("--privileged".to_string());
+        }
+    }
+    if let Some((key, value)) = field(args, &mapping.volumes) {
+        for volume in volumes(key, value)? {
+            docker_args.extend(["-v".to_string(), volume]);
+        }
+    }
+    if let Some((key, value)) = field(args, &mapping.ports) {
+        for port in ports(key, value)? {
+            docker_args.extend(["-p".to_string(), port]);
+        }
+    }
+    if let Some((key, value)) = field(args, &mapping.env) {
+        for env in env_vars(key, value)? {
+            docker_args.extend(["-e".to_string(), env]);
+        }
+    }
+    if let Some((key, value)) = field(args, &mapping.network) {
+        let network = scalar(value).ok_or_else(|| unsupported(key, value))?;
+        docker_args.push(format!("--network={}", network));
+    }
+    if let Some((key, value)) = field(args, &mapping.cap_add) {
+        for cap in string_list(key, value)? {
+            docker_args.push(format!("--cap-add={}", cap));
+        }
+    }
+    if let Some((key, value)) = field(args, &mapping.network_mode) {
+        let network = scalar(value).ok_or_else(|| unsupported(key, value))?;
+        docker_args.push(format!("--network={}", network));
+    }
+    if let Some((key, value)) = field(args, &mapping.mounts) {
+        for mount in mounts(key, value)? {
+            docker_args.extend(["--mount".to_string(), mount]);
+        }
+    }
+    if let Some((key, value)) = field(args, &mapping.devices) {
+        for device in string_list(key, value)? {
+            docker_args.push(format!("--device={}", device));
+        }
+    }
+    if let Some((key, value)) = field(args, &mapping.pid_mode) {
+        let pid = scalar(value).ok_or_else(|| unsupported(key, value))?;
+        docker_args.push(format!("--pid={}", pid));
+    }
+    if let Some((key, value)) = field(args, &mapping.security_opt) {
+        for opt in string_list(key, value)? {
+            docker_args.extend(["--security-opt".to_string(), opt]);
+        }
+    }
+    if let Some((key, value)) = field(args, &mapping.image) {
+        docker_args.push(scalar(value).ok_or_else(|| unsupported(key, value))?);
+    }
+    Ok(docker_args)
+}
+
+/// 文字列または文字列の配列
+fn string_list(key: &str, value: &Value) -> Result<Vec<String>, String> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| scalar(item).ok_or_else(|| unsupported(key, item)))
+            .collect(),
+        other => Ok(vec![scalar(other).ok_or_else(|| unsupported(key, other))?]),
+    }
+}
+
+/// ボリューム指定を -v の値 (SRC:DST[:ro]) に変換する
+fn volumes(key: &str, value: &Value) -> Result<Vec<String>, String> {
+    match value {
+        // {"/host": "/container"} / {"/host": {"bind": "/container", "mode": "ro"}}
+        Value::Object(map) => map
+            .iter()
+            .map(|(source, spec)| match spec {
+                Value::String(target) => Ok(format!("{}:{}", source, target)),
+                Value::Object(_) => volume_object(key, spec, Some(source)),
+                other => Err(unsupported(key, other)),
+            })
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s.clone()),
+                Value::Object(_) => volume_object(key, item, None),
+                other => Err(unsupported(key, other)),
+            })
+            .collect(),
+        Value::String(s) => Ok(vec![s.clone()]),
+        other => Err(unsupported(key, other)),
+    }
+}
+
+/// {source, target, read_only} 形式のボリューム指定
+fn volume_object(key: &str, spec: &Value, source: Option<&str>) -> Result<String, String> {
+    let first = |names: &[&str]| names.iter().find_map(|n| spec.get(*n).and_then(scalar));
+    let source = source
+        .map(String::from)
+        .or_else(|| first(&["source", "src", "host_path", "host"]))
+        .ok_or_else(|| unsupported(key, spec))?;
+    let target = first(&["target", "destination", "dst", "container_path", "bind"])
+        .ok_or_else(|| unsupported(key, spec))?;
+    let read_only = ["read_only", "readonly", "ro"]
+        .iter()
+        .any(|n| spec.get(*n).and_then(Value::as_bool) == Some(true))
+        || spec
+            .get("mode")
+            .and_then(Value::as_str)
+            .is_some_and(|mode| mode.split(',').any(|m| m == "ro"));
+    Ok(if read_only {
+        format!("{}:{}:ro", source, target)
+    } else {
+        format!("{}:{}", source, target)
+    })
+}
+
+/// マウント指定を --mount の値に変換する
+fn mounts(key: &str, value: &Value) -> Result<Vec<String>, String> {
+    let items = match value {
+        Value::Array(items) => items.as_slice(),
+        other => std::slice::from_ref(other),
+    };
+    items
+        .iter()
+        .map(|item| match item {
+            Value::String(s) => Ok(s.clone()),
+            Value::Object(_) => mount_object(key, item),
+            other => Err(unsupported(key, other)),
+        })
+        .collect()
+}
+
+/// docker SDK の Mount ({Type, Source, Target, ReadOnly, ...}) 形式のマウント指定。
+/// キーの大文字小文字と `_` は区別しない。解釈できないキーはエラーにする。
+fn mount_object(key: &str, spec: &Value) -> Result<String, String> {
+    let Value::Object(map) = spec else {
+        return Err(unsupported(key, spec));
+    };
+    let mut kind = "volume".to_string();
+    let mut source = None;
+    let mut target = None;
+    let mut read_only = false;
+    let mut propagation = None;
+    for (name, value) in map {
+        if value.is_null() {
+            continue;
+        }
+        match name.replace('_', "").to_ascii_lowercase().as_str() {
+            "type" => kind = scalar(value).ok_or_else(|| unsupported(key, spec))?,
+            "source" => source = Some(scalar(value).ok_or_else(|| unsupported(key, spec))?),
+            "target" => target = Some(scalar(value).ok_or_else(|| unsupported(key, spec))?),
+            "readonly" => read_only = value.as_bool().ok_or_else(|| unsupported(key, spec))?,
+            "propagation" => {
+                propagation = Some(scalar(value).ok_or_else(|| unsupported(key, spec))?)
+            }
+            "bindoptions" => {
+                propagation = value
+                    .as_object()
+                    .ok_or_else(|| unsupported(key, spec))?
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case("propagation"))
+                    .and_then(|(_, v)| scalar(v))
+            }
+            "consistency" => {}
+            _ => return Err(unsupported(key, spec)),
+        }
+    }
+    let target = target.ok_or_else(|| unsupported(key, spec))?;
+    let mut mount = format!("type={}", kind);
+    if let Some(source) = source.filter(|s| !s.is_empty()) {
+        mount.push_str(&format!(",source={}", source));
+    }
+    mount.push_str(&format!(",target={}", target));
+    if read_only {
+        mount.push_str(",readonly");
+    }
+    if let Some(propagation) = propagation {
+        mount.push_str(&format!(",bind-propagation={}", propagation));
+    }
+    Ok(mount)
+}
+
+/// ポート指定を -p の値に変換する
+fn ports(key: &str, value: &Value) -> Result<Vec<String>, String> {
+    let Value::Object(map) = value else {
+        return string_list(key, value);
+    };
+    // {"80/tcp": 8080} / {"80/tcp": ["127.0.0.1", 8080]} / {"80/tcp": null}
+    map.iter()
+        .map(|(container_port, host)| match host {
+            Value::Null => Ok(container_port.clone()),
+            Value::Array(parts) => {
+                let parts: Option<Vec<String>> = parts.iter().map(scalar).collect();
+                let parts = parts.ok_or_else(|| unsupported(key, host))?;
+                Ok(format!("{}:{}", parts.join(":"), container_port))
+            }
+            other => {
+                let host = scalar(other).ok_or_else(|| unsupported(key, other))?;
+                Ok(format!("{}:{}", host, container_port))
+            }
+        })
+        .collect()
+}
+
+/// 環境変数指定を -e の値 (KEY=VALUE) に変換する
+fn env_vars(key: &str, value: &Value) -> Result<Vec<String>, String> {
+    let Value::Object(map) = value else {
+        return string_list(key, value);
+    };
+    map.iter()
+        .map(|(name, v)| {
+            let v = scalar(v).ok_or_else(|| unsupported(key, v))?;
+            Ok(format!("{}={}", name, v))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::docker_args::{DangerousFlag, DockerSubcommand};
+    use serde_json::json;
+
+    fn mcp_input(tool: &str, args: Value) -> HookInput {
+        serde_json::from_value(json!({
+            "hook_event_name": "PreToolUse",
+            "tool_name": tool,
+            "tool_input": args,
+        }))
+        .unwrap()
+    }
+
+    fn call(tool: &str, args: Value) -> Option<Result<McpDockerCall, String>> {
+        docker_call(&mcp_input(tool, args), &Config::default().hook.mcp_tools)
+    }
+
+    #[test]
+    fn test_unmapped_tool() {
+        assert!(call("mcp__github__create_issue", json!({"title": "x"})).is_none());
+        assert!(call("Bash", json!({"command": "docker ps"})).is_none());
+    }
+
+    #[test]
+    fn test_run_container_docker_py_style() {
+        let result = call(
+            "mcp__docker__run_container",
+            json!({
+                "image": "nginx:1.27",
+                "volumes": {"/etc": {"bind": "/host-etc", "mode": "ro"}},
+                "ports": {"80/tcp": 8080},
+                "environment": {"DEBUG": "1"},
+                "privileged": true
+            }),
+        )
+        .unwrap()
+        .unwrap();
+
+        let cmd = &result.docker_cmd;
+        assert_eq!(cmd.subcommand, DockerSubcommand::Run);
+        assert_eq!(cmd.image.as_deref(), Some("nginx:1.27"));
+        assert_eq!(cmd.bind_mounts.len(), 1);
+        assert_eq!(cmd.bind_mounts[0].host_path, "/etc");
+        assert!(cmd.bind_mounts[0].read_only);
+        assert!(cmd.dangerous_flags.contains(&DangerousFlag::Privileged));
+        assert_eq!(
+            result.command,
+            "docker run --privileged -v /etc:/host-etc:ro -p 8080:80/tcp -e 'DEBUG=1' nginx:1.27"
+        );
+    }
+
+    #[test]
+    fn test_create_container_volume_list() {
+        let result = call(
+            "mcp__docker__create_container",
+            json!({
+                "image": "ubuntu",
+                "volumes": ["/var/run/docker.sock:/var/run/docker.sock", {"source": "/srv", "target": "/data", "read_only": true}],
+                "network": "host",
+                "cap_add": ["SYS_ADMIN"]
+            }),
+        )
+        .unwrap()
+        .unwrap();
+
+        let cmd = &result.docker_cmd;
+        assert_eq!(cmd.subcommand, DockerSubcommand::Create);
+        let hosts: Vec<&str> = cmd
+            .bind_mounts
+            .iter()
+            .map(|m| m.host_path.as_str())
+            .collect();
+        assert_eq!(hosts, vec!["/var/run/docker.sock", "/srv"]);
+        assert!(cmd.bind_mounts[1].read_only);
+        assert!(cmd.dangerous_flags.contains(&DangerousFlag::NetworkHost));
+        assert!(
+            cmd.dangerous_flags
+                .contains(&DangerousFlag::CapAdd("SYS_ADMIN".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_run_container_docker_py_names() {
+        let result = call(
+            "mcp__docker__run_container",
+            json!({
+                "image": "ubuntu",
+                "name": "web",
+                "detach": true,
+                "network_mode": "host",
+                "pid_mode": "host",
+                "devices": ["/dev/sda:/dev/xvda:rwm"],
+                "security_opt": ["seccomp=unconfined"],
+                "mounts": [
+                    {"Type": "bind", "Source": "/etc", "Target": "/host-etc", "ReadOnly": true},
+                    {"type": "bind", "source": "/srv", "target": "/srv", "propagation": "rshared"}
+                ]
+            }),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(result.unmapped_keys.is_empty());
+        let cmd = &result.docker_cmd;
+        for flag in [
+            DangerousFlag::NetworkHost,
+            DangerousFlag::PidHost,
+            DangerousFlag::Device("/dev/sda:/dev/xvda:rwm".to_string()),
+            DangerousFlag::SecurityOpt("seccomp=unconfined".to_string()),
+            DangerousFlag::MountPropagation("rshared".to_string()),
+        ] {
+            assert!(cmd.dangerous_flags.contains(&flag), "{:?}", flag);
+        }
+        let hosts: Vec<&str> = cmd
+            .bind_mounts
+            .iter()
+            .map(|m| m.host_path.as_str())
+            .collect();
+        assert_eq!(hosts, vec!["/etc", "/srv"]);
+        assert!(cmd.bind_mounts[0].read_only);
+    }
+
+    #[test]
+    fn test_unsupported_mount_key_is_error() {
+        let result = call(
+            "mcp__docker__run_container",
+            json!({
+                "image": "ubuntu",
+                "mounts": [{"Type": "volume", "Target": "/data", "DriverConfig": {"Options": {"device": "/"}}}]
+            }),
+        )
+        .unwrap();
+        assert!(result.unwrap_err().contains("'mounts'"));
+    }
+
+    #[test]
+    fn test_unmapped_keys() {
+        let result = call(
+            "mcp__docker__run_container",
+            json!({"image": "ubuntu", "name": "web", "userns_mode": "host", "ipc_mode": null}),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(result.unmapped_keys, vec!["userns_mode"]);
+
+        let mapping = McpToolMapping {
+            tool: "mcp__k__run".to_string(),
+            image: Some("spec.image".to_string()),
+            ignore_keys: vec!["spec.name".to_string()],
+            ..Default::default()
+        };
+        let args = json!({
+            "spec": {"image": "alpine", "name": "web", "host_config": {"privileged": true}},
+            "dry_run": false
+        });
+        assert_eq!(
+            unmapped_keys(&mapping, &args),
+            vec!["dry_run", "spec.host_config"]
+        );
+    }
+
+    #[test]
+    fn test_nested_keys() {
+        let mapping = McpToolMapping {
+            tool: "mcp__k__run".to_string(),
+            image: Some("spec.image".to_string()),
+            volumes: Some("spec.host_config.binds".to_string()),
+            privileged: Some("spec.host_config.privileged".to_string()),
+            ..Default::default()
+        };
+        let args = json!({
+            "spec": {
+                "image": "alpine",
+                "host_config": {"binds": ["/:/host"], "privileged": false}
+            }
+        });
+        assert_eq!(
+            to_docker_args(&mapping, &args).unwrap(),
+            vec!["run", "-v", "/:/host", "alpine"]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_value_is_error() {
+        let result = call(
+            "mcp__docker__run_container",
+            json!({"image": "ubuntu", "volumes": 42}),
+        )
+        .unwrap();
+        assert!(result.unwrap_err().contains("'volumes'"));
+
+        let result = call(
+            "mcp__docker__run_container",
+            json!({"image": "ubuntu", "privileged": {"enabled": true}}),
+        )
+        .unwrap();
+        assert!(result.is_err());
+
+        for value in ["1", "yes", "on", ""] {
+            let result = call(
+                "mcp__docker__run_container",
+                json!({"image": "ubuntu", "privileged": value}),
+            )
+            .unwrap();
+            assert!(result.unwrap_err().contains("'privileged'"), "{}", value);
+        }
+        let result = call(
+            "mcp__docker__run_container",
+            json!({"image": "ubuntu", "privileged": "TRUE"}),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(result.command, "docker run --privileged ubuntu");
+        let result = call(
+            "mcp__docker__run_container",
+            json!({"image": "ubuntu", "privileged": "False"}),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(result.command, "docker run ubuntu");
+    }
+
+    #[test]
+    fn test_null_fields_are_ignored() {
+        let result = call(
+            "mcp__docker__run_container",
+            json!({"image": "ubuntu", "volumes": null, "ports": null}),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(result.command, "docker run ubuntu");
+    }
+}
@@ -0,0 +1,498 @@
+//! ネットワークのポリシー (`[network_policy]`)
+//!
+//! - bridging_drivers (macvlan / ipvlan) のネットワークの作成 (`docker network create`、compose の
+//!   `networks:`) と、そのネットワークへの接続 (`--network`、`docker network connect`) を検出する
+//! - isolated_images のコンテナが `--network none` か `--internal` のネットワークだけにつながることを求める
+//!
+//! 作成済みのネットワークのドライバと internal は `docker network inspect` で調べる。
+//! 調べられない場合、ドライバの検査は行わず、分離の検査では分離されていないものとして扱う。
+
+use crate::compose::ComposeAnalysis;
+use crate::config::{Config, NetworkPolicyConfig, PinningAction};
+use crate::docker_args::{DockerCommand, DockerSubcommand, NetworkCreate};
+use crate::image_ref::{ImagePattern, ImageRef};
+
+/// ネットワークのポリシー違反
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub action: PinningAction,
+    pub reason: String,
+}
+
+/// 作成済みのネットワークの情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkInfo {
+    pub driver: String,
+    pub internal: bool,
+}
+
+/// `docker network inspect` でネットワークのドライバと internal を調べる
+pub fn inspect(name: &str, config: &Config) -> Option<NetworkInfo> {
+    let output = crate::wrapper::inspect_output(
+        config,
+        &[
+            "network",
+            "inspect",
+            "--format",
+            "{{.Driver}} {{.Internal}}",
+            "--",
+            name,
+        ],
+    )
+    .map_err(|e| log::debug!("docker network inspect {}: {}", name, e))
+    .ok()?;
+    let output = String::from_utf8_lossy(&output);
+    match output.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [driver, internal @ ("true" | "false")] => Some(NetworkInfo {
+            driver: driver.to_string(),
+            internal: *internal == "true",
+        }),
+        _ => None,
+    }
+}
+
+/// `docker container inspect` でコンテナのイメージを調べる
+pub fn container_image(container: &str, config: &Config) -> Option<String> {
+    let output = crate::wrapper::inspect_output(
+        config,
+        &[
+            "container",
+            "inspect",
+            "--format",
+            "{{.Config.Image}}",
+            "--",
+            container,
+        ],
+    )
+    .map_err(|e| log::debug!("docker container inspect {}: {}", container, e))
+    .ok()?;
+    let image = String::from_utf8_lossy(&output).trim().to_string();
+    (!image.is_empty() && !image.contains(char::is_whitespace)).then_some(image)
+}
+
+/// docker run / create、docker network create / connect を検査する
+///
+/// `lookup` は作成済みのネットワークの情報 (通常は [`inspect`])、
+/// `container_image` は network connect の対象コンテナのイメージ (通常は [`container_image`]) を返す
+pub fn command_findings(
+    cmd: &DockerCommand,
+    config: &NetworkPolicyConfig,
+    lookup: &dyn Fn(&str) -> Option<NetworkInfo>,
+    container_image: &dyn Fn(&str) -> Option<String>,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if let Some(create) = &cmd.network.create {
+        check_definition(create, "docker network create", config, &mut findings);
+    }
+
+    let mut networks = cmd.network.networks.clone();
+    let origin = match &cmd.subcommand {
+        DockerSubcommand::Run | DockerSubcommand::Create => {
+            if networks.is_empty() {
+                // --network を省略すると既定の bridge ネットワークにつながる
+                networks.push("bridge".to_string());
+            }
+            format!("docker {}", cmd.subcommand)
+        }
+        DockerSubcommand::Other(sub)
+            if sub == "network-connect" && cmd.network.container.is_some() =>
+        {
+            "docker network connect".to_string()
+        }
+        _ => {
+            networks.clear();
+            String::new()
+        }
+    };
+    let infos: Vec<(&String, Option<NetworkInfo>)> = networks
+        .iter()
+        .map(|name| (name, lookup_known(name, lookup)))
+        .collect();
+    for (name, info) in &infos {
+        if let Some(info) = info {
+            check_attachment(name, info, &origin, config, &mut findings);
+        }
+    }
+
+    let violations: Vec<String> = infos
+        .iter()
+        .filter_map(|(name, info)| isolation_violation(name, info))
+        .collect();
+    if is_isolated_images_enabled(config) && !violations.is_empty() {
+        // network connect は接続先が分離されていない場合だけコンテナのイメージを調べる
+        let image = match &cmd.network.container {
+            Some(container) => container_image(container).ok_or(container),
+            None => Ok(cmd.image.clone().unwrap_or_default()),
+        };
+        for description in &violations {
+            match &image {
+                Ok(image) if is_isolated(image, config) => {
+                    findings.push(not_isolated(image, &origin, description, config))
+                }
+                Ok(_) => {}
+                Err(container) => findings.push(Finding {
+                    action: config.not_isolated,
+                    reason: format!(
+                        "Container '{}' ({}) could not be inspected to check network_policy.isolated_images; it would be attached to {}",
+                        container, origin, description
+                    ),
+                }),
+            }
+        }
+    }
+    findings.retain(|f| f.action != PinningAction::Allow);
+    findings
+}
+
+/// compose の networks の定義と、サービスの接続先を検査する
+pub fn compose_findings(
+    analysis: &ComposeAnalysis,
+    config: &NetworkPolicyConfig,
+    lookup: &dyn Fn(&str) -> Option<NetworkInfo>,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for network in analysis.networks.iter().filter(|n| !n.external) {
+        check_definition(
+            &network.network,
+            &format!("compose network '{}'", network.key),
+            config,
+            &mut findings,
+        );
+    }
+
+    for service in &analysis.service_networks {
+        let origin = format!("compose service '{}'", service.service);
+        let isolated = service
+            .image
+            .as_deref()
+            .is_some_and(|image| is_isolated(image, config));
+        if let Some(mode) = &service.network_mode {
+            let info = lookup_known(mode, lookup);
+            if isolated && let Some(description) = isolation_violation(mode, &info) {
+                let image = service.image.as_deref().unwrap_or_default();
+                findings.push(not_isolated(image, &origin, &description, config));
+            }
+        }
+        for key in &service.networks {
+            let definition = analysis.networks.iter().find(|n| &n.key == key);
+            let (name, info) = match definition {
+                Some(d) if d.external => (d.network.name.clone(), lookup(&d.network.name)),
+                Some(d) => (
+                    key.clone(),
+                    Some(NetworkInfo {
+                        driver: d.network.driver.clone(),
+                        internal: d.network.internal,
+                    }),
+                ),
+                // 定義のない default は compose が作る bridge ネットワーク
+                None if key == "default" => (
+                    key.clone(),
+                    Some(NetworkInfo {
+                        driver: "bridge".to_string(),
+                        internal: false,
+                    }),
+                ),
+                None => (key.clone(), None),
+            };
+            if let (Some(info), Some(d)) = (&info, definition)
+                && d.external
+            {
+                check_attachment(&name, info, &origin, config, &mut findings);
+            }
+            if isolated && let Some(description) = isolation_violation(&name, &info) {
+                let image = service.image.as_deref().unwrap_or_default();
+                findings.push(not_isolated(image, &origin, &description, config));
+            }
+        }
+    }
+    findings.retain(|f| f.action != PinningAction::Allow);
+    findings
+}
+
+/// 組み込みのネットワークはそのまま、それ以外は lookup で調べる
+fn lookup_known(name: &str, lookup: &dyn Fn(&str) -> Option<NetworkInfo>) -> Option<NetworkInfo> {
+    let builtin = |driver: &str| {
+        Some(NetworkInfo {
+            driver: driver.to_string(),
+            internal: false,
+        })
+    };
+    match name {
+        "bridge" | "default" => builtin("bridge"),
+        "host" => builtin("host"),
+        "none" => builtin("null"),
+        _ if name.contains(':') => None,
+        _ => lookup(name),
+    }
+}
+
+fn is_bridging(driver: &str, config: &NetworkPolicyConfig) -> bool {
+    config.bridging_drivers.iter().any(|d| d == driver)
+}
+
+fn is_isolated_images_enabled(config: &NetworkPolicyConfig) -> bool {
+    !config.isolated_images.is_empty() && config.not_isolated != PinningAction::Allow
+}
+
+/// isolated_images に一致するイメージか
+fn is_isolated(image: &str, config: &NetworkPolicyConfig) -> bool {
+    let Ok(image_ref) = ImageRef::parse(image) else {
+        return false;
+    };
+    config
+        .isolated_images
+        .iter()
+        .any(|p| ImagePattern::parse(p).is_ok_and(|p| p.matches(&image_ref)))
+}
+
+/// ネットワークの作成 (docker network create、compose の networks) を検査する
+fn check_definition(
+    network: &NetworkCreate,
+    origin: &str,
+    config: &NetworkPolicyConfig,
+    findings: &mut Vec<Finding>,
+) {
+    if !is_bridging(&network.driver, config) {
+        return;
+    }
+    let parent = network
+        .options
+        .iter()
+        .find_map(|o| o.strip_prefix("parent="))
+        .map(|p| format!(" (parent={})", p))
+        .unwrap_or_default();
+    findings.push(Finding {
+        action: config.bridging,
+        reason: format!(
+            "Network '{}' ({}) uses the {} driver{}, which attaches containers directly to the host's physical network",
+            network.name, origin, network.driver, parent
+        ),
+    });
+}
+
+/// 作成済みのネットワークへの接続を検査する
+fn check_attachment(
+    name: &str,
+    info: &NetworkInfo,
+    origin: &str,
+    config: &NetworkPolicyConfig,
+    findings: &mut Vec<Finding>,
+) {
+    if is_bridging(&info.driver, config) {
+        findings.push(Finding {
+            action: config.bridging,
+            reason: format!(
+                "Network '{}' ({}) uses the {} driver, which attaches containers directly to the host's physical network",
+                name, origin, info.driver
+            ),
+        });
+    }
+}
+
+/// 分離されていないネットワークの説明 (分離されていれば None)
+fn isolation_violation(name: &str, info: &Option<NetworkInfo>) -> Option<String> {
+    if name == "none" || info.as_ref().is_some_and(|i| i.internal) {
+        return None;
+    }
+    Some(match (name, info) {
+        ("host", _) => "the host network".to_string(),
+        ("bridge" | "default", _) => format!("the {} network", name),
+        (_, Some(_)) => format!("network '{}', which is not --internal", name),
+        (_, None) if name.contains(':') => format!("the network namespace of '{}'", name),
+        (_, None) => format!("network '{}', which could not be inspected", name),
+    })
+}
+
+fn not_isolated(
+    image: &str,
+    origin: &str,
+    description: &str,
+    config: &NetworkPolicyConfig,
+) -> Finding {
+    Finding {
+        action: config.not_isolated,
+        reason: format!(
+            "Image '{}' ({}) must be isolated by network_policy.isolated_images but is attached to {}",
+            image, origin, description
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_args::parse_docker_args;
+
+    fn lookup(name: &str) -> Option<NetworkInfo> {
+        let (driver, internal) = match name {
+            "lan" => ("macvlan", false),
+            "sandbox" => ("bridge", true),
+            "shared" => ("bridge", false),
+            _ => return None,
+        };
+        Some(NetworkInfo {
+            driver: driver.to_string(),
+            internal,
+        })
+    }
+
+    fn findings(args: &[&str], config: &NetworkPolicyConfig) -> Vec<Finding> {
+        command_findings(&parse_docker_args(args), config, &lookup, &|container| {
+            (container == "web").then(|| "ghcr.io/ourorg/scanner:1".to_string())
+        })
+    }
+
+    fn isolation_config() -> NetworkPolicyConfig {
+        NetworkPolicyConfig {
+            isolated_images: vec!["ghcr.io/ourorg/scanner".to_string()],
+            not_isolated: PinningAction::Deny,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_network_create_bridging_driver() {
+        let config = NetworkPolicyConfig::default();
+        let result = findings(
+            &[
+                "network",
+                "create",
+                "-d",
+                "macvlan",
+                "-o",
+                "parent=eth0",
+                "lan",
+            ],
+            &config,
+        );
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].action, PinningAction::Ask);
+        assert_eq!(
+            result[0].reason,
+            "Network 'lan' (docker network create) uses the macvlan driver (parent=eth0), which attaches containers directly to the host's physical network"
+        );
+        assert!(findings(&["network", "create", "--driver=ipvlan", "l3"], &config).len() == 1);
+        assert!(findings(&["network", "create", "--internal", "sandbox"], &config).is_empty());
+    }
+
+    #[test]
+    fn test_attach_to_bridging_network() {
+        let config = NetworkPolicyConfig::default();
+        let result = findings(&["run", "--network", "lan", "alpine"], &config);
+        assert_eq!(result.len(), 1);
+        assert!(
+            result[0]
+                .reason
+                .starts_with("Network 'lan' (docker run) uses the macvlan driver")
+        );
+        let result = findings(&["network", "connect", "lan", "web"], &config);
+        assert_eq!(result.len(), 1);
+        assert!(findings(&["run", "--network=shared", "alpine"], &config).is_empty());
+    }
+
+    #[test]
+    fn test_isolated_images() {
+        let config = isolation_config();
+        for args in [
+            &["run", "--network", "none", "ghcr.io/ourorg/scanner:1"][..],
+            &["run", "--net=sandbox", "ghcr.io/ourorg/scanner:1"],
+            &["run", "ubuntu"],
+        ] {
+            assert!(findings(args, &config).is_empty(), "{:?}", args);
+        }
+
+        let reasons = |args: &[&str]| -> Vec<String> {
+            findings(args, &config)
+                .into_iter()
+                .map(|f| {
+                    assert_eq!(f.action, PinningAction::Deny);
+                    f.reason
+                })
+                .collect()
+        };
+        assert_eq!(
+            reasons(&["run", "ghcr.io/ourorg/scanner:1"]),
+            vec![
+                "Image 'ghcr.io/ourorg/scanner:1' (docker run) must be isolated by network_policy.isolated_images but is attached to the bridge network"
+            ]
+        );
+        assert!(
+            reasons(&["run", "--network", "shared", "ghcr.io/ourorg/scanner:1"])[0]
+                .ends_with("network 'shared', which is not --internal")
+        );
+        assert!(
+            reasons(&["run", "--network", "unknown", "ghcr.io/ourorg/scanner:1"])[0]
+                .ends_with("network 'unknown', which could not be inspected")
+        );
+        assert!(
+            reasons(&["run", "--network", "host", "ghcr.io/ourorg/scanner:1"])
+                .iter()
+                .any(|r| r.ends_with("the host network"))
+        );
+
+        // 分離したコンテナを後から接続する
+        assert_eq!(
+            reasons(&["network", "connect", "shared", "web"]),
+            vec![
+                "Image 'ghcr.io/ourorg/scanner:1' (docker network connect) must be isolated by network_policy.isolated_images but is attached to network 'shared', which is not --internal"
+            ]
+        );
+        assert!(reasons(&["network", "connect", "sandbox", "web"]).is_empty());
+        // イメージを調べられないコンテナは分離が必要なものとして扱う
+        assert_eq!(
+            reasons(&["network", "connect", "--alias", "x", "shared", "other"]),
+            vec![
+                "Container 'other' (docker network connect) could not be inspected to check network_policy.isolated_images; it would be attached to network 'shared', which is not --internal"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compose_findings() {
+        let dir = tempfile::tempdir().unwrap();
+        let compose_path = dir.path().join("compose.yml");
+        std::fs::write(
+            &compose_path,
+            r#"
+services:
+  scanner:
+    image: ghcr.io/ourorg/scanner:1
+    networks: [sandbox]
+  leaky:
+    image: ghcr.io/ourorg/scanner:2
+  offline:
+    image: ghcr.io/ourorg/scanner:3
+    network_mode: none
+  web:
+    image: nginx
+    networks:
+      lan: {}
+      outside: {}
+networks:
+  sandbox:
+    internal: true
+  lan:
+    driver: macvlan
+    driver_opts:
+      parent: eth0
+  outside:
+    external: true
+    name: lan
+"#,
+        )
+        .unwrap();
+        let analysis = crate::compose::analyze_compose(&compose_path).unwrap();
+        let reasons: Vec<String> = compose_findings(&analysis, &isolation_config(), &lookup)
+            .into_iter()
+            .map(|f| f.reason)
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                "Network 'lan' (compose network 'lan') uses the macvlan driver (parent=eth0), which attaches containers directly to the host's physical network",
+                "Image 'ghcr.io/ourorg/scanner:2' (compose service 'leaky') must be isolated by network_policy.isolated_images but is attached to the default network",
+                "Network 'lan' (compose service 'web') uses the macvlan driver, which attaches containers directly to the host's physical network",
+            ]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! gitignore 風のパスパターン (`allowed_paths` / `sensitive_paths` / `denied_paths`)
+//!
+//! - パス要素 (`/` 区切り) 単位で比較する。`.ssh` は `.ssh/id_rsa` に一致し、`.sshfoo` には一致しない
+//! - パターンに一致したディレクトリ配下のパスも一致とみなす
+//! - `*` / `?` / `[abc]` / `[!abc]` は 1 つのパス要素の中だけで一致し、`**` は 0 個以上のパス要素に一致する
+//! - 先頭の `!` は否定 (一覧の後ろで一致したものが優先される)。`\!` で `!` そのものを書ける
+
+/// 先頭の `!` を取り除き、否定パターンかどうかを返す
+pub fn split_negation(pattern: &str) -> (bool, &str) {
+    match pattern.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None if pattern.starts_with("\\!") => (false, &pattern[1..]),
+        None => (false, pattern),
+    }
+}
+
+/// ワイルドカードを含むか (含まない場合はパスそのものとして扱える)
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// パターンの構文を検査する
+pub fn validate(pattern: &str) -> Result<(), String> {
+    let (_, pattern) = split_negation(pattern);
+    if pattern.is_empty() {
+        return Err("empty pattern".to_string());
+    }
+    for component in components(pattern) {
+        if component.contains("**") && component != "**" {
+            return Err(format!(
+                "'**' must be a whole path component (got '{}')",
+                component
+            ));
+        }
+        let chars: Vec<char> = component.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '\\' => i += 2,
+                '[' => match class_end(&chars, i) {
+                    Some(end) => i = end + 1,
+                    None => return Err(format!("unclosed '[' in '{}'", component)),
+                },
+                _ => i += 1,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// パス (またはパターン) を空要素を除いたパス要素に分割する
+fn components(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter(|c| !c.is_empty() && *c != ".")
+        .collect()
+}
+
+/// パターン (否定の `!` は含まない) がパス、またはその親ディレクトリに一致するか
+pub fn matches(pattern: &str, path: &str) -> bool {
+    match_components(&components(pattern), &components(path), true)
+}
+
+/// パターンがパス全体に一致するか (配下のパスは含めない。イメージ名の比較等に使う)
+pub fn matches_exact(pattern: &str, path: &str) -> bool {
+    match_components(&components(pattern), &components(path), false)
+}
+
+fn match_components(pattern: &[&str], path: &[&str], prefix: bool) -> bool {
+    let Some((first, rest)) = pattern.split_first() else {
+        // パターンを使い切った: 残りは一致したディレクトリの配下
+        return prefix || path.is_empty();
+    };
+    if *first == "**" {
+        return (0..=path.len()).any(|skip| match_components(rest, &path[skip..], prefix));
+    }
+    match path.split_first() {
+        Some((head, tail)) => match_component(first, head) && match_components(rest, tail, prefix),
+        None => false,
+    }
+}
+
+/// 1 つのパス要素 (タグ名等の `/` を含まない文字列) をワイルドカードで比較する
+pub fn match_component(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    match_chars(&pattern, &name)
+}
+
+fn match_chars(pattern: &[char], name: &[char]) -> bool {
+    let Some(&first) = pattern.first() else {
+        return name.is_empty();
+    };
+    match first {
+        '*' => (0..=name.len()).any(|skip| match_chars(&pattern[1..], &name[skip..])),
+        '?' => !name.is_empty() && match_chars(&pattern[1..], &name[1..]),
+        '[' => match class_end(pattern, 0) {
+            Some(end) => {
+                !name.is_empty()
+                    && class_matches(&pattern[1..end], name[0])
+                    && match_chars(&pattern[end + 1..], &name[1..])
+            }
+            // 閉じていない '[' は文字として扱う (validate でエラーにする)
+            None => name.first() == Some(&'[') && match_chars(&pattern[1..], &name[1..]),
+        },
+        '\\' if pattern.len() > 1 => {
+            name.first() == Some(&pattern[1]) && match_chars(&pattern[2..], &name[1..])
+        }
+        c => name.first() == Some(&c) && match_chars(&pattern[1..], &name[1..]),
+    }
+}
+
+/// start の '[' に対応する ']' の位置
+fn class_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    if matches!(chars.get(i), Some('!') | Some('^')) {
+        i += 1;
+    }
+    // 先頭の ']' は文字として扱う
+    if chars.get(i) == Some(&']') {
+        i += 1;
+    }
+    while i < chars.len() {
+        if chars[i] == ']' {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// 文字クラス (`[` と `]` の間) に c が含まれるか
+fn class_matches(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.first() {
+        Some('!') | Some('^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            if class[i] <= c && c <= class[i + 2] {
+                found = true;
+            }
+            i += 3;
+        } else {
+            if class[i] == c {
+                found = true;
+            }
+            i += 1;
+        }
+    }
+    found != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_component_boundaries() {
+        assert!(matches(".ssh", ".ssh"));
+        assert!(matches(".ssh", ".ssh/id_rsa"));
+        assert!(!matches(".ssh", ".sshfoo"));
+        assert!(!matches(".ssh", "projects/.ssh"));
+        assert!(matches("/data/projects", "/data/projects/a"));
+        assert!(!matches("/data/projects", "/data/projects-old"));
+        assert!(matches("/data/projects/", "/data/projects"));
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(matches("**/.env", ".env"));
+        assert!(matches("**/.env", "work/app/.env"));
+        assert!(!matches("**/.env", "work/app/.env.example"));
+        assert!(matches("**/*.pem", "certs/server.pem"));
+        assert!(!matches("*.pem", "certs/server.pem"));
+        assert!(matches("projects/*/secrets", "projects/app/secrets/token"));
+        assert!(!matches("projects/*/secrets", "projects/a/b/secrets"));
+        assert!(matches("projects/**/secrets", "projects/a/b/secrets"));
+        assert!(matches("id_?sa", "id_rsa"));
+        assert!(matches("id_[dr]sa", "id_dsa"));
+        assert!(!matches("id_[!dr]sa", "id_rsa"));
+        assert!(matches("key[0-9]", "key7"));
+        assert!(matches(r"a\*b", "a*b"));
+        assert!(!matches(r"a\*b", "axb"));
+    }
+
+    #[test]
+    fn test_matches_exact() {
+        assert!(matches_exact(
+            "docker.io/library/ubuntu",
+            "docker.io/library/ubuntu"
+        ));
+        assert!(!matches_exact(
+            "docker.io/library",
+            "docker.io/library/ubuntu"
+        ));
+        assert!(matches_exact("ghcr.io/ourorg/*", "ghcr.io/ourorg/app"));
+        assert!(!matches_exact(
+            "ghcr.io/ourorg/*",
+            "ghcr.io/ourorg/team/app"
+        ));
+        assert!(matches_exact("ghcr.io/**", "ghcr.io/ourorg/team/app"));
+        assert!(match_component("20-*", "20-alpine"));
+    }
+
+    #[test]
+    fn test_negation_and_validate() {
+        assert_eq!(split_negation("!.ssh/config"), (true, ".ssh/config"));
+        assert_eq!(split_negation(r"\!important"), (false, "!important"));
+        assert!(is_glob("**/*.pem"));
+        assert!(!is_glob("/data/projects"));
+
+        assert!(validate("**/*.pem").is_ok());
+        assert!(validate("!projects/[abc]/x").is_ok());
+        assert!(validate("projects/[abc").is_err());
+        assert!(validate("a**/b").is_err());
+        assert!(validate("!").is_err());
+    }
+}
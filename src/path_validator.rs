@@ -1,6 +1,6 @@
 use std::path::{Path, PathBuf};
 
-use crate::config::Config;
+use crate::config::{AccessMode, Config, WorkspaceMode, WorkspaceRoot};
 
 /// パス判定結果
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -11,10 +11,38 @@ pub enum PathVerdict {
     Sensitive(String),
     /// $HOME 外 — 拒否
     Denied(String),
+    /// $HOME 配下だが workspace_root の外 — ユーザー確認
+    OutsideWorkspace(String),
     /// パスを解決できない（環境変数未展開等） — ユーザー確認
     Unresolvable(String),
 }
 
+/// allowed_paths 等の設定に関係なく常に拒否するホストのパス (配下と、これらを含む親ディレクトリも拒否)
+pub const CRITICAL_PATHS: &[&str] = &[
+    "/proc",
+    "/sys",
+    "/dev",
+    "/boot",
+    "/var/lib/docker",
+    "/var/lib/containerd",
+];
+
+/// コンテナランタイムの API ソケットのファイル名 (docker.sock は block_docker_socket で扱う)
+pub const RUNTIME_SOCKET_NAMES: &[&str] = &[
+    "containerd.sock",
+    "podman.sock",
+    "buildkitd.sock",
+    "crio.sock",
+];
+
+/// コンテナランタイムの API ソケットの既定のパス (これらを含む親ディレクトリも拒否)
+pub const RUNTIME_SOCKET_PATHS: &[&str] = &[
+    "/run/containerd/containerd.sock",
+    "/run/podman/podman.sock",
+    "/run/buildkit/buildkitd.sock",
+    "/run/crio/crio.sock",
+];
+
 /// $HOME ディレクトリを取得
 pub fn home_dir() -> Option<PathBuf> {
     dirs::home_dir()
@@ -66,7 +94,7 @@ fn has_unresolved_vars(path: &str) -> bool {
 
 /// ファイルが存在しなくても動作する論理的なパス正規化。
 /// `..` と `.` を解決し、二重スラッシュを正規化する。
-fn logical_normalize(path: &Path) -> PathBuf {
+pub fn logical_normalize(path: &Path) -> PathBuf {
     use std::path::Component;
     let mut components = Vec::new();
     for component in path.components() {
@@ -95,8 +123,259 @@ fn logical_normalize(path: &Path) -> PathBuf {
     }
 }
 
-/// パスを正規化して判定する
+/// パスを canonicalize し、存在しない場合は存在する部分までシンボリックリンクを解決する
+fn resolve_lenient(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| resolve_partial(path))
+}
+
+/// 存在する親ディレクトリまではシンボリックリンクを解決し、存在しない残りは論理正規化する。
+///
+/// `~/link/new` (link → /etc) のように存在しないパスでも、途中のシンボリックリンクの
+/// 先 (/etc/new) で判定するため。解決済みの部分は実体のディレクトリなので、`..` は
+/// カーネルと同じく解決先の親ディレクトリになる。
+pub fn resolve_partial(path: &Path) -> PathBuf {
+    use std::path::Component;
+    let mut resolved = PathBuf::new();
+    let mut missing = false;
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => resolved.push(component),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                resolved.push(name);
+                if !missing {
+                    match std::fs::canonicalize(&resolved) {
+                        Ok(canonical) => resolved = canonical,
+                        Err(_) => missing = true,
+                    }
+                }
+            }
+        }
+    }
+    if resolved.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        resolved
+    }
+}
+
+/// パスの途中 (最後の要素を除く) にある最初のシンボリックリンクとそのリンク先
+pub fn intermediate_symlink(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let path = logical_normalize(path);
+    let parent = path.parent()?;
+    let mut prefix = PathBuf::new();
+    for component in parent.components() {
+        prefix.push(component);
+        if std::fs::symlink_metadata(&prefix).is_ok_and(|m| m.file_type().is_symlink()) {
+            let target = std::fs::canonicalize(&prefix)
+                .or_else(|_| std::fs::read_link(&prefix))
+                .ok()?;
+            return Some((prefix, target));
+        }
+    }
+    None
+}
+
+/// 判定理由に添える、途中のシンボリックリンクの説明 (なければ空文字列)
+fn symlink_note(path: &Path) -> String {
+    match intermediate_symlink(path) {
+        Some((link, target)) => format!(", via symlink {} -> {}", link.display(), target.display()),
+        None => String::new(),
+    }
+}
+
+/// 存在しないバインドマウント元のうち、差し替えの余地があるものを確認理由として返す。
+///
+/// docker は存在しないソースをデーモン側で作成するため、判定後にエージェントが同じ
+/// 場所へシンボリックリンクを作ると別のパスがマウントされる。最も近い既存の親
+/// ディレクトリが書き込み可能な場合 (リンク切れのシンボリックリンクを含む) に返す。
+pub fn missing_source_reason(raw_path: &str) -> Option<String> {
+    let expanded = expand_env(raw_path);
+    let path = Path::new(&expanded);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().ok()?.join(path)
+    };
+    if std::fs::symlink_metadata(&path).is_ok() {
+        if path.exists() {
+            return None;
+        }
+        return Some(format!(
+            "Mount source is a dangling symlink: {}. Its target could be created after this check; create the target first",
+            raw_path
+        ));
+    }
+    let resolved = resolve_partial(&path);
+    let parent = resolved.ancestors().skip(1).find(|p| p.exists())?;
+    let writable = std::fs::metadata(parent).is_ok_and(|m| !m.permissions().readonly());
+    writable.then(|| {
+        format!(
+            "Mount source does not exist: {} (resolved: {}). {} is writable, so it could be replaced by a symlink before docker creates it; create the directory first",
+            raw_path,
+            resolved.display(),
+            parent.display()
+        )
+    })
+}
+
+/// start から親へ辿って .git (ディレクトリまたは worktree のファイル) を含むディレクトリを探す
+fn find_git_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// workspace_root から $HOME 配下で確認なしにマウントできるディレクトリを求める。
+/// None は $HOME 全体 ("home" モード)。cwd は Hook モードでは hook 入力の cwd。
+pub fn workspace_roots(config: &Config, cwd: &str) -> Option<Vec<PathBuf>> {
+    let cwd = resolve_lenient(Path::new(&expand_env(cwd)));
+    match &config.workspace_root {
+        WorkspaceRoot::Mode(WorkspaceMode::Home) => None,
+        WorkspaceRoot::Mode(WorkspaceMode::Cwd) => Some(vec![cwd]),
+        WorkspaceRoot::Mode(WorkspaceMode::GitRoot) => {
+            Some(vec![find_git_root(&cwd).unwrap_or(cwd)])
+        }
+        WorkspaceRoot::Paths(paths) => Some(
+            paths
+                .iter()
+                .map(|p| resolve_lenient(Path::new(&expand_env(p))))
+                .collect(),
+        ),
+    }
+}
+
+/// 対象とするランタイムソケットのファイル名 (block_docker_socket が有効なら docker.sock を含む)
+fn runtime_socket_names(config: &Config) -> Vec<&'static str> {
+    let mut names = RUNTIME_SOCKET_NAMES.to_vec();
+    if config.block_docker_socket {
+        names.push("docker.sock");
+    }
+    names
+}
+
+/// Unix ソケットかどうか (S_ISSOCK)
+fn is_socket(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path)
+        .map(|m| m.file_type().is_socket())
+        .unwrap_or(false)
+}
+
+/// ホストの疑似ファイルシステムやランタイムの状態ディレクトリ・ソケットへのマウントを判定する。
+/// allowed_paths で上書きできない
+fn check_critical_path(raw_path: &str, path: &Path, config: &Config) -> Option<PathVerdict> {
+    let shown = path.display();
+    for critical in CRITICAL_PATHS {
+        let critical = Path::new(critical);
+        if path.starts_with(critical) {
+            return Some(PathVerdict::Denied(format!(
+                "Host system path cannot be mounted: {} (resolved: {}). {} is never allowed, even via allowed_paths",
+                raw_path,
+                shown,
+                critical.display()
+            )));
+        }
+        if critical.starts_with(path) {
+            return Some(PathVerdict::Denied(format!(
+                "Path contains a host system path: {} (resolved: {}) includes {}, which is never allowed",
+                raw_path,
+                shown,
+                critical.display()
+            )));
+        }
+    }
+
+    let names = runtime_socket_names(config);
+    let socket_paths = RUNTIME_SOCKET_PATHS.iter().map(|p| p.to_string()).chain(
+        config
+            .block_docker_socket
+            .then(|| {
+                [
+                    "/run/docker.sock".to_string(),
+                    "/var/run/docker.sock".to_string(),
+                ]
+            })
+            .into_iter()
+            .flatten(),
+    );
+    let runtime_socket = |reason: String| {
+        Some(PathVerdict::Denied(format!(
+            "Container runtime socket mount is blocked: {} (resolved: {}){}. Access to a runtime API grants control over the host",
+            raw_path, shown, reason
+        )))
+    };
+    if let Some(name) = path.file_name().and_then(|n| n.to_str())
+        && names.contains(&name)
+    {
+        return runtime_socket(String::new());
+    }
+    for socket in socket_paths {
+        if Path::new(&socket).starts_with(path) {
+            return runtime_socket(format!(" includes {}", socket));
+        }
+    }
+    // /run/user/UID 等、既定以外の場所にあるソケットを含むディレクトリ
+    if path.is_dir() {
+        for name in &names {
+            let candidate = path.join(name);
+            if is_socket(&candidate) {
+                return runtime_socket(format!(" includes {}", candidate.display()));
+            }
+        }
+    }
+    None
+}
+
+/// 許可されたパスが Unix ソケットの場合は確認にする (名前を変えたりリンクしたランタイムソケット対策)
+fn check_socket(raw_path: &str, verdict: PathVerdict, config: &Config) -> PathVerdict {
+    if verdict != PathVerdict::Allowed {
+        return verdict;
+    }
+    let Ok(canonical) = std::fs::canonicalize(expand_env(raw_path)) else {
+        return verdict;
+    };
+    if !is_socket(&canonical)
+        || (!config.block_docker_socket
+            && canonical.file_name().is_some_and(|n| n == "docker.sock"))
+    {
+        return verdict;
+    }
+    PathVerdict::Sensitive(format!(
+        "Mounting a Unix socket: {} (resolved: {}). Verify that it does not expose a container runtime or other privileged API",
+        raw_path,
+        canonical.display()
+    ))
+}
+
+/// パスを書き込みを含むアクセスとして正規化して判定する ($HOME 全体をワークスペースとして扱う)
 pub fn validate_path(raw_path: &str, config: &Config) -> PathVerdict {
+    validate_path_in_workspace(raw_path, config, None, AccessMode::Rw)
+}
+
+/// パスを正規化して判定する。workspace が Some の場合、$HOME 配下でもその外は確認にする。
+/// access は allowed_paths / sensitive_paths の mode と照合するアクセスモード
+pub fn validate_path_in_workspace(
+    raw_path: &str,
+    config: &Config,
+    workspace: Option<&[PathBuf]>,
+    access: AccessMode,
+) -> PathVerdict {
+    let verdict = classify_path(raw_path, config, workspace, access);
+    check_socket(raw_path, verdict, config)
+}
+
+/// validate_path_in_workspace の本体 (Unix ソケットの確認を除く)
+fn classify_path(
+    raw_path: &str,
+    config: &Config,
+    workspace: Option<&[PathBuf]>,
+    access: AccessMode,
+) -> PathVerdict {
     // 空パスは拒否
     if raw_path.is_empty() {
         return PathVerdict::Denied("Empty path (specify a host path for the mount)".to_string());
@@ -164,14 +443,38 @@ pub fn validate_path(raw_path: &str, config: &Config) -> PathVerdict {
                     }
                 }
             };
-            logical_normalize(&abs_path)
+            // 途中のシンボリックリンクは解決する (リンク先に作成されるため)
+            resolve_partial(&abs_path)
         }
     };
 
     let canonical_str = canonical.to_string_lossy().to_string();
 
-    // allowed_paths のチェック (最優先)
-    if config.is_path_allowed(&canonical_str) {
+    // ホストの重要パスとランタイムソケットのチェック (denied_paths / allowed_paths より優先)。
+    // シンボリックリンクを解決する前のパスでも照合する
+    let logical = logical_normalize(Path::new(&expanded));
+    if let Some(verdict) = check_critical_path(raw_path, &canonical, config).or_else(|| {
+        logical
+            .is_absolute()
+            .then(|| check_critical_path(raw_path, &logical, config))
+            .flatten()
+    }) {
+        return verdict;
+    }
+
+    // denied_paths のチェック ($HOME 配下や allowed_paths より優先)
+    if let Some(pattern) = config
+        .denied_path_match(&canonical_str)
+        .or_else(|| config.denied_path_match(&logical.to_string_lossy()))
+    {
+        return PathVerdict::Denied(format!(
+            "Path matches denied_paths: {} (resolved: {}, pattern: {})",
+            raw_path, canonical_str, pattern
+        ));
+    }
+
+    // allowed_paths のチェック
+    if config.is_path_allowed(&canonical_str, access) {
         return PathVerdict::Allowed;
     }
 
@@ -196,10 +499,31 @@ pub fn validate_path(raw_path: &str, config: &Config) -> PathVerdict {
             &canonical_str[home_prefix.len()..]
         };
 
-        if config.is_path_sensitive(relative) {
+        if config.is_path_sensitive(relative, access) {
+            let hint = if config.is_path_sensitive(relative, AccessMode::Ro) {
+                ""
+            } else {
+                ". Read-only access is allowed by sensitive_paths (mount with :ro)"
+            };
             return PathVerdict::Sensitive(format!(
-                "Mounting sensitive path {} (resolved: {}) which may contain credentials or keys",
-                raw_path, canonical_str
+                "Mounting sensitive path {} (resolved: {}) which may contain credentials or keys{}",
+                raw_path, canonical_str, hint
+            ));
+        }
+
+        if let Some(roots) = workspace
+            && !roots.iter().any(|root| canonical.starts_with(root))
+        {
+            let roots: Vec<String> = roots
+                .iter()
+                .map(|r| r.to_string_lossy().to_string())
+                .collect();
+            return PathVerdict::OutsideWorkspace(format!(
+                "Path is outside the workspace: {} (resolved: {}{}, workspace: {}). Verify that mounting another $HOME directory is intended (see workspace_root in config)",
+                raw_path,
+                canonical_str,
+                symlink_note(Path::new(&expanded)),
+                roots.join(", ")
             ));
         }
 
@@ -207,9 +531,17 @@ pub fn validate_path(raw_path: &str, config: &Config) -> PathVerdict {
     }
 
     // $HOME 外
+    if access == AccessMode::Rw && config.is_path_allowed(&canonical_str, AccessMode::Ro) {
+        return PathVerdict::Denied(format!(
+            "Write access to {} (resolved: {}) is not permitted: allowed_paths allows it read-only (mount with :ro)",
+            raw_path, canonical_str
+        ));
+    }
     PathVerdict::Denied(format!(
-        "Path is outside $HOME: {} (resolved: {}). Only $HOME paths or allowed_paths are permitted",
-        raw_path, canonical_str
+        "Path is outside $HOME: {} (resolved: {}{}). Only $HOME paths or allowed_paths are permitted",
+        raw_path,
+        canonical_str,
+        symlink_note(Path::new(&expanded))
     ))
 }
 
@@ -381,7 +713,7 @@ mod tests {
     #[test]
     fn test_validate_path_allowed_paths_outside_home() {
         let mut config = Config::default();
-        config.allowed_paths = vec!["/tmp".to_string()];
+        config.allowed_paths = vec!["/tmp".into()];
         let result = validate_path("/tmp/docker-data", &config);
         assert_eq!(
             result,
@@ -422,6 +754,77 @@ mod tests {
 
     // --- 論理正規化テスト ---
 
+    #[test]
+    fn test_validate_path_symlinked_parent_of_missing_source() {
+        use std::os::unix::fs;
+        let dir = tempfile::tempdir().unwrap();
+        let home = home_dir().unwrap();
+        let target = std::fs::canonicalize(dir.path()).unwrap();
+        if target.starts_with(&home) {
+            return;
+        }
+
+        let link_path = home.join(".safe-docker-test-parent-link");
+        let _ = std::fs::remove_file(&link_path);
+        if fs::symlink(&target, &link_path).is_ok() {
+            // 存在しないパスでも途中のシンボリックリンクの先で判定する
+            let raw = link_path.join("new/dir");
+            let result = validate_path(raw.to_str().unwrap(), &Config::default());
+            match result {
+                PathVerdict::Denied(reason) => {
+                    assert!(reason.contains("via symlink"), "{}", reason);
+                    assert!(reason.contains(&target.join("new/dir").to_string_lossy().to_string()));
+                }
+                other => panic!("expected Denied, got {:?}", other),
+            }
+            assert_eq!(
+                intermediate_symlink(&raw),
+                Some((link_path.clone(), target.clone()))
+            );
+            let _ = std::fs::remove_file(&link_path);
+        }
+    }
+
+    #[test]
+    fn test_resolve_partial() {
+        use std::os::unix::fs;
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir_all(root.join("real/sub")).unwrap();
+        fs::symlink(root.join("real/sub"), root.join("link")).unwrap();
+
+        assert_eq!(
+            resolve_partial(&root.join("link/new")),
+            root.join("real/sub/new")
+        );
+        // 解決済みの部分の .. はリンク先の親になる
+        assert_eq!(
+            resolve_partial(&root.join("link/../x")),
+            root.join("real/x")
+        );
+        assert_eq!(
+            resolve_partial(&root.join("new/../other")),
+            root.join("other")
+        );
+        assert_eq!(intermediate_symlink(&root.join("real/sub/x")), None);
+    }
+
+    #[test]
+    fn test_missing_source_reason() {
+        use std::os::unix::fs;
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir(root.join("exists")).unwrap();
+        fs::symlink(root.join("nowhere"), root.join("dangling")).unwrap();
+
+        let path = |name: &str| root.join(name).to_string_lossy().to_string();
+        assert_eq!(missing_source_reason(&path("exists")), None);
+        let reason = missing_source_reason(&path("new/dir")).unwrap();
+        assert!(reason.contains("does not exist"), "{}", reason);
+        let reason = missing_source_reason(&path("dangling")).unwrap();
+        assert!(reason.contains("dangling symlink"), "{}", reason);
+    }
+
     #[test]
     fn test_logical_normalize_parent_dir() {
         let result = logical_normalize(Path::new("/home/user/../../etc"));
@@ -446,4 +849,275 @@ mod tests {
         let result = logical_normalize(Path::new("/a/b/../c/./d/../e"));
         assert_eq!(result, PathBuf::from("/a/c/e"));
     }
+
+    #[test]
+    fn test_validate_path_access_mode() {
+        let config: Config = toml::from_str(
+            r#"
+            allowed_paths = ["/srv/cache", { path = "/data/datasets", mode = "ro" }]
+            sensitive_paths = [".ssh", { path = ".config/git", mode = "ro" }]
+            "#,
+        )
+        .unwrap();
+        let home = home_dir().unwrap().to_string_lossy().to_string();
+        let check = |path: &str, access| validate_path_in_workspace(path, &config, None, access);
+
+        // mode なしの allowed_paths は読み書きとも許可
+        assert_eq!(check("/srv/cache/x", AccessMode::Rw), PathVerdict::Allowed);
+        // mode = "ro" は読み取りのみ許可し、書き込みは理由付きで拒否
+        assert_eq!(
+            check("/data/datasets/a", AccessMode::Ro),
+            PathVerdict::Allowed
+        );
+        assert!(matches!(
+            check("/data/datasets/a", AccessMode::Rw),
+            PathVerdict::Denied(msg) if msg.contains("read-only")
+        ));
+
+        // mode なしの sensitive_paths は読み取りでも確認
+        assert!(matches!(
+            check(&format!("{}/.ssh/known_hosts", home), AccessMode::Ro),
+            PathVerdict::Sensitive(msg) if !msg.contains(":ro")
+        ));
+        // mode = "ro" の sensitive_paths は読み取りなら許可、書き込みは確認
+        assert_eq!(
+            check(&format!("{}/.config/git/config", home), AccessMode::Ro),
+            PathVerdict::Allowed
+        );
+        assert!(matches!(
+            check(&format!("{}/.config/git/config", home), AccessMode::Rw),
+            PathVerdict::Sensitive(msg) if msg.contains("mount with :ro")
+        ));
+    }
+
+    #[test]
+    fn test_validate_path_patterns() {
+        let config: Config = toml::from_str(
+            r#"
+            allowed_paths = ["/srv/data", "!/srv/data/private"]
+            sensitive_paths = [".ssh", "**/*.pem", "projects/*/secrets", "!projects/demo/**/*.pem"]
+            denied_paths = ["~/vault", "**/.env"]
+            "#,
+        )
+        .unwrap();
+        let home = home_dir().unwrap().to_string_lossy().to_string();
+        let check = |path: &str| validate_path(path, &config);
+
+        // パス要素単位の一致: .sshfoo は .ssh に含まれない
+        assert!(matches!(
+            check(&format!("{}/.ssh/id_rsa", home)),
+            PathVerdict::Sensitive(_)
+        ));
+        assert_eq!(check(&format!("{}/.sshfoo", home)), PathVerdict::Allowed);
+
+        // glob と否定
+        assert!(matches!(
+            check(&format!("{}/projects/app/tls/server.pem", home)),
+            PathVerdict::Sensitive(_)
+        ));
+        assert_eq!(
+            check(&format!("{}/projects/demo/tls/server.pem", home)),
+            PathVerdict::Allowed
+        );
+        assert!(matches!(
+            check(&format!("{}/projects/app/secrets/token", home)),
+            PathVerdict::Sensitive(_)
+        ));
+        assert_eq!(check("/srv/data/public"), PathVerdict::Allowed);
+        assert!(
+            matches!(check("/srv/data/private/x"), PathVerdict::Denied(msg) if msg.contains("outside $HOME"))
+        );
+
+        // denied_paths は $HOME 配下でも拒否
+        for path in [
+            format!("{}/vault/keys", home),
+            format!("{}/work/app/.env", home),
+            "/srv/data/.env".to_string(),
+        ] {
+            assert!(
+                matches!(check(&path), PathVerdict::Denied(ref msg) if msg.contains("denied_paths")),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_path_critical_paths() {
+        // allowed_paths に "/" を加えても重要パスは拒否する
+        let config = Config {
+            allowed_paths: vec!["/".into()],
+            ..Default::default()
+        };
+        for path in [
+            "/proc/self/root",
+            "/sys/fs/cgroup",
+            "/dev",
+            "/boot/grub",
+            "/var/lib/docker/volumes",
+            "/",
+            "/var",
+            "/run",
+            "/run/containerd",
+            "/srv/x/../../proc",
+            "/opt/podman.sock",
+        ] {
+            assert!(
+                matches!(validate_path(path, &config), PathVerdict::Denied(_)),
+                "{} should be denied",
+                path
+            );
+        }
+        assert_eq!(validate_path("/opt/data", &config), PathVerdict::Allowed);
+
+        // シンボリックリンク経由
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("proc-link");
+        std::os::unix::fs::symlink("/proc", &link).unwrap();
+        assert!(matches!(
+            validate_path(&link.to_string_lossy(), &config),
+            PathVerdict::Denied(msg) if msg.contains("Host system path")
+        ));
+    }
+
+    #[test]
+    fn test_validate_path_unix_sockets() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dir_str = dir.path().to_string_lossy().to_string();
+        let config = Config {
+            allowed_paths: vec![dir_str.as_str().into()],
+            ..Default::default()
+        };
+        let agent = dir.path().join("agent");
+        std::fs::create_dir(&agent).unwrap();
+        let _agent_sock = UnixListener::bind(agent.join("agent.sock")).unwrap();
+        let user_run = dir.path().join("user-run");
+        std::fs::create_dir(&user_run).unwrap();
+        let _podman = UnixListener::bind(user_run.join("podman.sock")).unwrap();
+
+        // ランタイム以外のソケットは確認
+        assert!(matches!(
+            validate_path(&agent.join("agent.sock").to_string_lossy(), &config),
+            PathVerdict::Sensitive(msg) if msg.contains("Unix socket")
+        ));
+        assert_eq!(
+            validate_path(&agent.to_string_lossy(), &config),
+            PathVerdict::Allowed
+        );
+        // ランタイムソケットとそれを含むディレクトリは拒否
+        assert!(matches!(
+            validate_path(&user_run.join("podman.sock").to_string_lossy(), &config),
+            PathVerdict::Denied(msg) if msg.contains("runtime socket")
+        ));
+        assert!(matches!(
+            validate_path(&user_run.to_string_lossy(), &config),
+            PathVerdict::Denied(msg) if msg.contains("podman.sock")
+        ));
+
+        // block_docker_socket = false なら docker.sock は従来どおり許可
+        let docker_dir = dir.path().join("docker");
+        std::fs::create_dir(&docker_dir).unwrap();
+        let _docker = UnixListener::bind(docker_dir.join("docker.sock")).unwrap();
+        let opt_out = Config {
+            block_docker_socket: false,
+            ..config.clone()
+        };
+        assert_eq!(
+            validate_path(&docker_dir.join("docker.sock").to_string_lossy(), &opt_out),
+            PathVerdict::Allowed
+        );
+        assert!(matches!(
+            validate_path(&docker_dir.to_string_lossy(), &config),
+            PathVerdict::Denied(_)
+        ));
+    }
+
+    #[test]
+    fn test_validate_path_in_workspace() {
+        let config = Config::default();
+        let home = home_dir().unwrap();
+        let workspace = vec![home.join("work/project-a")];
+        let check = |path: PathBuf| {
+            validate_path_in_workspace(
+                &path.to_string_lossy(),
+                &config,
+                Some(&workspace),
+                AccessMode::Rw,
+            )
+        };
+        assert_eq!(check(home.join("work/project-a/src")), PathVerdict::Allowed);
+        assert_eq!(check(home.join("work/project-a")), PathVerdict::Allowed);
+        assert!(matches!(
+            check(home.join("work/project-b")),
+            PathVerdict::OutsideWorkspace(msg) if msg.contains("outside the workspace")
+        ));
+        // 前方一致ではなくパス要素単位で比較する
+        assert!(matches!(
+            check(home.join("work/project-a-old")),
+            PathVerdict::OutsideWorkspace(_)
+        ));
+        assert!(matches!(
+            check(home.join(".ssh/id_rsa")),
+            PathVerdict::Sensitive(_)
+        ));
+        assert!(matches!(
+            check(PathBuf::from("/etc")),
+            PathVerdict::Denied(_)
+        ));
+    }
+
+    #[test]
+    fn test_workspace_roots_modes() {
+        use crate::config::{WorkspaceMode, WorkspaceRoot};
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(repo.join("src/sub")).unwrap();
+        let cwd = repo.join("src/sub").to_string_lossy().to_string();
+
+        let with_mode = |root: WorkspaceRoot| Config {
+            workspace_root: root,
+            ..Default::default()
+        };
+        assert_eq!(workspace_roots(&Config::default(), &cwd), None);
+        assert_eq!(
+            workspace_roots(&with_mode(WorkspaceRoot::Mode(WorkspaceMode::Cwd)), &cwd),
+            Some(vec![repo.join("src/sub")])
+        );
+        assert_eq!(
+            workspace_roots(
+                &with_mode(WorkspaceRoot::Mode(WorkspaceMode::GitRoot)),
+                &cwd
+            ),
+            Some(vec![repo.clone()])
+        );
+        let home = home_dir().unwrap();
+        assert_eq!(
+            workspace_roots(
+                &with_mode(WorkspaceRoot::Paths(vec!["~/work/../code".to_string()])),
+                &cwd
+            ),
+            Some(vec![home.join("code")])
+        );
+    }
+
+    #[test]
+    fn test_git_root_falls_back_to_cwd() {
+        use crate::config::{WorkspaceMode, WorkspaceRoot};
+
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = std::fs::canonicalize(dir.path()).unwrap();
+        let config = Config {
+            workspace_root: WorkspaceRoot::Mode(WorkspaceMode::GitRoot),
+            ..Default::default()
+        };
+        let roots = workspace_roots(&config, &cwd.to_string_lossy()).unwrap();
+        // /tmp 配下にリポジトリが無ければ cwd そのもの
+        if find_git_root(&cwd).is_none() {
+            assert_eq!(roots, vec![cwd]);
+        }
+    }
 }
@@ -0,0 +1,350 @@
+//! 外部ポリシープラグイン (`[[plugins]]`)
+//!
+//! 組み込みポリシーとポリシー拡張の後に、設定したコマンドを docker コマンドごとに実行する。
+//! プラグインは stdin でポリシー拡張と同じ JSON ドキュメントを受け取り、stdout に
+//! `{"findings": [{"decision": "deny" | "ask" | "allow", "message": "..."}]}` を返す。
+//!
+//! - 複数のプラグインは並行して実行し、待ち時間は最も遅いプラグインのタイムアウトで頭打ちになる
+//! - 既に deny の場合はプラグインを実行しない (結果が変わらないため)
+//! - 起動失敗・非ゼロ終了・タイムアウト・不正な出力は deny (fail-safe)
+//! - allow は他の判定を緩めない
+
+use std::io::{Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::{Config, PluginConfig};
+use crate::docker_args::DockerCommand;
+use crate::hook::Decision;
+use crate::{policy, policy_ext};
+
+/// プラグイン出力の上限
+const MAX_OUTPUT_BYTES: u64 = 64 * 1024;
+
+/// プラグインの判定
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FindingDecision {
+    Deny,
+    Ask,
+    Allow,
+}
+
+/// プラグインが返す判定
+#[derive(Debug, Deserialize)]
+struct Finding {
+    decision: FindingDecision,
+    #[serde(default)]
+    message: String,
+}
+
+/// プラグインの出力
+#[derive(Debug, Deserialize)]
+struct PluginOutput {
+    #[serde(default)]
+    findings: Vec<Finding>,
+}
+
+/// 実行中のプラグイン
+struct Running {
+    child: Child,
+    output: mpsc::Receiver<Vec<u8>>,
+    deadline: Instant,
+}
+
+/// プラグインが docker コマンドを対象とするか
+fn applies_to(plugin: &PluginConfig, cmd: &DockerCommand) -> bool {
+    plugin.subcommands.is_empty() || plugin.subcommands.contains(&cmd.subcommand.to_string())
+}
+
+/// プラグインを実行し、判定を返す。decision はそれまでの判定 (プラグインの入力に含める)
+pub fn evaluate(
+    decision: &Decision,
+    cmd: &DockerCommand,
+    config: &Config,
+    ctx: &policy_ext::Context,
+) -> Decision {
+    let plugins: Vec<&PluginConfig> = config
+        .plugins
+        .iter()
+        .filter(|p| applies_to(p, cmd))
+        .collect();
+    if plugins.is_empty() {
+        return Decision::Allow;
+    }
+
+    let mut document = policy_ext::build_document(cmd, ctx, policy_ext::current_time(config));
+    let (result, reason) = match decision {
+        Decision::Allow => ("allow", ""),
+        Decision::Ask(r) => ("ask", r.as_str()),
+        Decision::Deny(r) => ("deny", r.as_str()),
+    };
+    document.insert(
+        "decision".to_string(),
+        serde_json::json!({ "result": result, "reason": reason }),
+    );
+    let input = Value::Object(document).to_string();
+
+    // 全プラグインを起動してから待つ (並行実行)
+    let running: Vec<_> = plugins
+        .iter()
+        .map(|plugin| (plugin, spawn(plugin, input.as_bytes())))
+        .collect();
+
+    let mut deny_reasons = Vec::new();
+    let mut ask_reasons = Vec::new();
+    for (plugin, running) in running {
+        match running.and_then(|r| finish(plugin, r)) {
+            Ok(findings) => {
+                for finding in findings {
+                    let message = if finding.message.is_empty() {
+                        format!("Rejected by plugin '{}'", plugin.name)
+                    } else {
+                        format!("{} (plugin '{}')", finding.message, plugin.name)
+                    };
+                    match finding.decision {
+                        FindingDecision::Deny => deny_reasons.push(message),
+                        FindingDecision::Ask => ask_reasons.push(message),
+                        FindingDecision::Allow => {}
+                    }
+                }
+            }
+            Err(e) => deny_reasons.push(format!(
+                "Plugin '{}' failed: {}. Blocking for safety",
+                plugin.name, e
+            )),
+        }
+    }
+
+    if !deny_reasons.is_empty() {
+        Decision::Deny(policy::format_reasons(&deny_reasons))
+    } else if !ask_reasons.is_empty() {
+        Decision::Ask(policy::format_reasons(&ask_reasons))
+    } else {
+        Decision::Allow
+    }
+}
+
+/// プラグインを起動し、stdin に入力を書き込む
+fn spawn(plugin: &PluginConfig, input: &[u8]) -> Result<Running, String> {
+    let argv =
+        shell_words::split(&plugin.command).map_err(|e| format!("invalid command: {}", e))?;
+    let Some((program, args)) = argv.split_first() else {
+        return Err("empty command".to_string());
+    };
+    let deadline = Instant::now() + Duration::from_millis(plugin.timeout_ms);
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("cannot run {}: {}", program, e))?;
+
+    // 入力を読まずに終了するプラグインもあるため、書き込みエラーは無視する
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = input.to_vec();
+        thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+    }
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stdout.take(MAX_OUTPUT_BYTES + 1).read_to_end(&mut buf);
+            let _ = tx.send(buf);
+        });
+    }
+    Ok(Running {
+        child,
+        output: rx,
+        deadline,
+    })
+}
+
+/// プラグインの終了を待ち、出力を解釈する
+fn finish(plugin: &PluginConfig, mut running: Running) -> Result<Vec<Finding>, String> {
+    let timed_out = || {
+        format!(
+            "timed out after {} ms (plugins[].timeout_ms)",
+            plugin.timeout_ms
+        )
+    };
+    let status = loop {
+        match running.child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+        if Instant::now() >= running.deadline {
+            let _ = running.child.kill();
+            let _ = running.child.wait();
+            return Err(timed_out());
+        }
+        thread::sleep(Duration::from_millis(1));
+    };
+    if !status.success() {
+        return Err(format!("exited with {}", status));
+    }
+
+    // 子プロセスが stdout を開いたままでも、期限を過ぎたら待たない
+    let remaining = running.deadline.saturating_duration_since(Instant::now());
+    let output = running
+        .output
+        .recv_timeout(remaining)
+        .map_err(|_| timed_out())?;
+    if output.len() as u64 > MAX_OUTPUT_BYTES {
+        return Err(format!("output exceeds {} bytes", MAX_OUTPUT_BYTES));
+    }
+    serde_json::from_slice::<PluginOutput>(&output)
+        .map(|o| o.findings)
+        .map_err(|e| format!("invalid output: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker_args::parse_docker_args;
+
+    fn plugin(name: &str, script: &str) -> PluginConfig {
+        PluginConfig {
+            name: name.to_string(),
+            command: shell_words::join(["/bin/sh", "-c", script]),
+            timeout_ms: 5000,
+            ..Default::default()
+        }
+    }
+
+    fn run_plugins(plugins: Vec<PluginConfig>, args: &[&str]) -> Decision {
+        let config = Config {
+            plugins,
+            ..Default::default()
+        };
+        let cmd = parse_docker_args(args);
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let ctx = policy_ext::Context {
+            args: &args,
+            cwd: "/tmp",
+            session_id: Some("s1"),
+            session_containers: &[],
+        };
+        evaluate(&Decision::Allow, &cmd, &config, &ctx)
+    }
+
+    #[test]
+    fn test_findings() {
+        let allow = plugin("allow", r#"cat >/dev/null; echo '{"findings": []}'"#);
+        assert_eq!(
+            run_plugins(vec![allow], &["run", "ubuntu"]),
+            Decision::Allow
+        );
+
+        let ask = plugin(
+            "cmdb",
+            r#"cat >/dev/null; echo '{"findings": [{"decision": "ask", "message": "not in CMDB"}, {"decision": "allow"}]}'"#,
+        );
+        match run_plugins(vec![ask], &["run", "ubuntu"]) {
+            Decision::Ask(reason) => {
+                assert!(reason.contains("not in CMDB (plugin 'cmdb')"), "{}", reason)
+            }
+            other => panic!("expected ask, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_receives_document_on_stdin() {
+        // image と session.id を見て判定するプラグイン
+        let script = r#"input=$(cat); case "$input" in *'"image":"alpine"'*'"id":"s1"'*|*'"id":"s1"'*'"image":"alpine"'*) echo '{"findings": [{"decision": "deny", "message": "alpine seen"}]}' ;; *) echo '{}' ;; esac"#;
+        match run_plugins(vec![plugin("inspect", script)], &["run", "alpine"]) {
+            Decision::Deny(reason) => assert!(reason.contains("alpine seen"), "{}", reason),
+            other => panic!("expected deny, got {:?}", other),
+        }
+        assert_eq!(
+            run_plugins(vec![plugin("inspect", script)], &["run", "ubuntu"]),
+            Decision::Allow
+        );
+    }
+
+    #[test]
+    fn test_failures_fail_closed() {
+        let cases = [
+            (plugin("crash", "exit 3"), "exited with"),
+            (plugin("garbage", "echo not-json"), "invalid output"),
+            (
+                plugin(
+                    "bad-decision",
+                    r#"echo '{"findings": [{"decision": "maybe"}]}'"#,
+                ),
+                "invalid output",
+            ),
+            (
+                PluginConfig {
+                    name: "missing".to_string(),
+                    command: "/nonexistent/safe-docker-plugin".to_string(),
+                    ..Default::default()
+                },
+                "cannot run",
+            ),
+        ];
+        for (plugin, expected) in cases {
+            let name = plugin.name.clone();
+            match run_plugins(vec![plugin], &["run", "ubuntu"]) {
+                Decision::Deny(reason) => {
+                    assert!(reason.contains(expected), "{}: {}", name, reason);
+                    assert!(reason.contains("Blocking for safety"), "{}", reason);
+                }
+                other => panic!("{}: expected deny, got {:?}", name, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_timeout_fails_closed_within_budget() {
+        let slow = PluginConfig {
+            timeout_ms: 100,
+            ..plugin("slow", "sleep 5")
+        };
+        let also_slow = PluginConfig {
+            timeout_ms: 100,
+            ..plugin("also-slow", "sleep 5")
+        };
+        let start = Instant::now();
+        match run_plugins(vec![slow, also_slow], &["run", "ubuntu"]) {
+            Decision::Deny(reason) => {
+                assert!(reason.contains("timed out after 100 ms"), "{}", reason);
+                assert!(reason.contains("also-slow"), "{}", reason);
+            }
+            other => panic!("expected deny, got {:?}", other),
+        }
+        // 並行実行なので 2 つ合わせてもタイムアウト 1 回分程度で返る
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_subcommand_filter() {
+        let deny_all = PluginConfig {
+            subcommands: vec!["run".to_string()],
+            ..plugin("run-only", r#"echo '{"findings": [{"decision": "deny"}]}'"#)
+        };
+        assert_eq!(
+            run_plugins(vec![deny_all.clone()], &["ps"]),
+            Decision::Allow
+        );
+        match run_plugins(vec![deny_all], &["run", "ubuntu"]) {
+            Decision::Deny(reason) => {
+                assert!(
+                    reason.contains("Rejected by plugin 'run-only'"),
+                    "{}",
+                    reason
+                )
+            }
+            other => panic!("expected deny, got {:?}", other),
+        }
+    }
+}
@@ -1,24 +1,40 @@
 use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::Path;
 
-use crate::config::Config;
+use crate::build_context;
+use crate::config::{AccessMode, Config, PinningAction};
 use crate::docker_args::{DangerousFlag, DockerCommand, DockerSubcommand};
+use crate::dockerfile_lint::{self, Severity};
+use crate::dockerignore::DockerIgnore;
 use crate::hook::Decision;
+use crate::image_pinning::{self, ImageUse};
+use crate::image_ref::{self, ImagePattern, ImageRef};
+use crate::image_signature;
+use crate::network_policy::{self, NetworkInfo};
 use crate::path_validator::{self, PathVerdict};
+use crate::published_ports::{self, PortUse};
+
+/// deny する --security-opt のパターン (`export-rego` の Rego ルールもこのリストから生成する)。
+/// = 区切りと : 区切りの両方に対応
+pub(crate) const DANGEROUS_SECURITY_OPTS: &[&str] = &[
+    "apparmor=unconfined",
+    "apparmor:unconfined",
+    "seccomp=unconfined",
+    "seccomp:unconfined",
+    // CIS 5.2: SELinux ラベリングの無効化
+    "label=disable",
+    "label:disable",
+    "no-new-privileges=false",
+    "no-new-privileges:false",
+    "systempaths=unconfined",
+    "systempaths:unconfined",
+];
 
 /// 危険な --security-opt 値かどうか判定
 fn is_dangerous_security_opt(opt: &str) -> bool {
-    // = 区切りと : 区切りの両方に対応
-    opt.contains("apparmor=unconfined")
-        || opt.contains("apparmor:unconfined")
-        || opt.contains("seccomp=unconfined")
-        || opt.contains("seccomp:unconfined")
-        || opt.contains("systempaths=unconfined")
-        || opt.contains("systempaths:unconfined")
-        || opt.contains("no-new-privileges=false")
-        || opt.contains("no-new-privileges:false")
-        // CIS 5.2: SELinux ラベリングの無効化
-        || opt.contains("label=disable")
-        || opt.contains("label:disable")
+    DANGEROUS_SECURITY_OPTS
+        .iter()
+        .any(|pattern| opt.contains(pattern))
 }
 
 /// クラウドメタデータエンドポイントの既知ドメイン名
@@ -110,6 +126,7 @@ fn is_metadata_endpoint(add_host_val: &str) -> bool {
 pub fn evaluate(cmd: &DockerCommand, config: &Config, cwd: &str) -> Decision {
     let mut deny_reasons = Vec::new();
     let mut ask_reasons = Vec::new();
+    let workspace = path_validator::workspace_roots(config, cwd);
 
     // 1. 危険フラグのチェック
     for flag in &cmd.dangerous_flags {
@@ -242,6 +259,12 @@ pub fn evaluate(cmd: &DockerCommand, config: &Config, cwd: &str) -> Decision {
 
     // 2. compose コマンドの場合、compose ファイルを解析
     let mut all_mounts = cmd.bind_mounts.clone();
+    let mut image_uses: Vec<ImageUse> = Vec::new();
+    let mut signed_uses: Vec<ImageUse> = Vec::new();
+    let mut port_uses: Vec<PortUse> = Vec::new();
+    let mut network_findings: Vec<network_policy::Finding> = Vec::new();
+    let network_lookup =
+        |name: &str| -> Option<NetworkInfo> { network_policy::inspect(name, config) };
     let mut all_flags: Vec<DangerousFlag> = Vec::new();
     if matches!(
         cmd.subcommand,
@@ -251,13 +274,34 @@ pub fn evaluate(cmd: &DockerCommand, config: &Config, cwd: &str) -> Decision {
     ) {
         match resolve_compose_analysis(cmd, cwd) {
             Ok(analysis) => {
+                if config.image_pinning.is_enabled() {
+                    image_uses.extend(image_pinning::compose_images(&analysis));
+                }
+                if config.image_signatures.is_enabled() {
+                    signed_uses.extend(image_signature::compose_images(&analysis));
+                }
+                if config.published_ports.is_enabled() {
+                    port_uses.extend(published_ports::compose_ports(&analysis));
+                }
+                if config.network_policy.is_enabled() {
+                    network_findings.extend(network_policy::compose_findings(
+                        &analysis,
+                        &config.network_policy,
+                        &network_lookup,
+                    ));
+                }
                 all_mounts.extend(analysis.bind_mounts);
                 all_flags.extend(analysis.dangerous_flags);
                 // env_file ディレクティブのパスを検証（--env-file と同様、$HOME 外は deny）
                 for path in &analysis.env_file_paths {
-                    match path_validator::validate_path(path, config) {
+                    match path_validator::validate_path_in_workspace(
+                        path,
+                        config,
+                        workspace.as_deref(),
+                        AccessMode::Ro,
+                    ) {
                         PathVerdict::Allowed => {}
-                        PathVerdict::Sensitive(reason) => {
+                        PathVerdict::Sensitive(reason) | PathVerdict::OutsideWorkspace(reason) => {
                             ask_reasons.push(reason);
                         }
                         PathVerdict::Denied(reason) => {
@@ -271,9 +315,14 @@ pub fn evaluate(cmd: &DockerCommand, config: &Config, cwd: &str) -> Decision {
                 }
                 // include ディレクティブのホストパスを検証
                 for path in &analysis.host_paths {
-                    match path_validator::validate_path(path, config) {
+                    match path_validator::validate_path_in_workspace(
+                        path,
+                        config,
+                        workspace.as_deref(),
+                        AccessMode::Ro,
+                    ) {
                         PathVerdict::Allowed => {}
-                        PathVerdict::Sensitive(reason) => {
+                        PathVerdict::Sensitive(reason) | PathVerdict::OutsideWorkspace(reason) => {
                             ask_reasons.push(reason);
                         }
                         PathVerdict::Denied(reason) => {
@@ -432,9 +481,25 @@ pub fn evaluate(cmd: &DockerCommand, config: &Config, cwd: &str) -> Decision {
 
     // 3. バインドマウントのパス検証
     for mount in &all_mounts {
-        match path_validator::validate_path(&mount.host_path, config) {
-            PathVerdict::Allowed => {}
-            PathVerdict::Sensitive(reason) => {
+        let access = if mount.read_only {
+            AccessMode::Ro
+        } else {
+            AccessMode::Rw
+        };
+        match path_validator::validate_path_in_workspace(
+            &mount.host_path,
+            config,
+            workspace.as_deref(),
+            access,
+        ) {
+            PathVerdict::Allowed => {
+                if config.ask_missing_mount_sources
+                    && let Some(reason) = path_validator::missing_source_reason(&mount.host_path)
+                {
+                    ask_reasons.push(reason);
+                }
+            }
+            PathVerdict::Sensitive(reason) | PathVerdict::OutsideWorkspace(reason) => {
                 ask_reasons.push(reason);
             }
             PathVerdict::Denied(reason) => {
@@ -446,11 +511,21 @@ pub fn evaluate(cmd: &DockerCommand, config: &Config, cwd: &str) -> Decision {
         }
     }
 
-    // 4. ホストパスの検証 (docker cp, docker build)
-    for host_path in &cmd.host_paths {
-        match path_validator::validate_path(host_path, config) {
+    // 4. ホストパスの検証 (docker cp, docker build)。読み取りと書き込みを区別する
+    let host_accesses = cmd
+        .host_paths
+        .iter()
+        .map(|p| (p, AccessMode::Ro))
+        .chain(cmd.host_write_paths.iter().map(|p| (p, AccessMode::Rw)));
+    for (host_path, access) in host_accesses {
+        match path_validator::validate_path_in_workspace(
+            host_path,
+            config,
+            workspace.as_deref(),
+            access,
+        ) {
             PathVerdict::Allowed => {}
-            PathVerdict::Sensitive(reason) => {
+            PathVerdict::Sensitive(reason) | PathVerdict::OutsideWorkspace(reason) => {
                 ask_reasons.push(reason);
             }
             PathVerdict::Denied(reason) => {
@@ -462,24 +537,110 @@ pub fn evaluate(cmd: &DockerCommand, config: &Config, cwd: &str) -> Decision {
         }
     }
 
-    // 5. イメージホワイトリスト
-    if !config.allowed_images.is_empty()
-        && let Some(image) = &cmd.image
+    // 5. Dockerfile の静的解析 (docker build)
+    if config.dockerfile_lint.enabled
+        && let Some(build) = &cmd.build
+        && let Some(dockerfile) = build.dockerfile_path(Path::new(cwd))
     {
-        let image_name = image.split(':').next().unwrap_or(image);
-        if !config
-            .allowed_images
-            .iter()
-            .any(|allowed| image_name == allowed)
-        {
-            ask_reasons.push(format!(
-                "Image '{}' is not in allowed_images (to allow, add it to config)",
-                image
-            ));
+        let context = build.context.as_ref().map(|c| Path::new(cwd).join(c));
+        for finding in dockerfile_lint::lint_file(&dockerfile, context.as_deref()) {
+            if config
+                .dockerfile_lint
+                .ignore_rules
+                .iter()
+                .any(|r| r == finding.rule)
+            {
+                continue;
+            }
+            match finding.severity {
+                Severity::Deny => deny_reasons.push(finding.reason(&dockerfile)),
+                Severity::Ask => ask_reasons.push(finding.reason(&dockerfile)),
+            }
         }
     }
 
-    // 6. 結果集約: deny が一つでもあれば deny、ask があれば ask、それ以外は allow
+    // 6. ビルドコンテキストの機密ファイル・巨大ファイル (既に deny の場合は走査しない)
+    if config.build_context_scan.enabled
+        && deny_reasons.is_empty()
+        && let Some(build) = cmd.build.as_ref().filter(|b| !b.bake)
+        && let Some(context) = &build.context
+    {
+        let context = path_validator::logical_normalize(&Path::new(cwd).join(context));
+        if context.is_dir() {
+            let dockerfile = build.dockerfile_path(Path::new(cwd));
+            let ignore = DockerIgnore::load(&context, dockerfile.as_deref());
+            let result = build_context::scan(&context, &ignore, &config.build_context_scan);
+            ask_reasons.extend(result.reasons(&context));
+            if let Some(reason) = result.incomplete_reason(&context) {
+                match config.build_context_scan.incomplete {
+                    PinningAction::Deny => deny_reasons.push(reason),
+                    PinningAction::Ask => ask_reasons.push(reason),
+                    PinningAction::Allow => log::warn!("{}", reason),
+                }
+            }
+        }
+    }
+
+    // 7. 公開ポート (-p / -P、compose の ports)
+    if config.published_ports.is_enabled() {
+        port_uses.extend(published_ports::command_ports(cmd));
+        for finding in published_ports::check(&port_uses, &config.published_ports) {
+            match finding.action {
+                PinningAction::Deny => deny_reasons.push(finding.reason),
+                PinningAction::Ask => ask_reasons.push(finding.reason),
+                PinningAction::Allow => {}
+            }
+        }
+    }
+
+    // 8. ネットワーク (macvlan / ipvlan、isolated_images の分離)
+    if config.network_policy.is_enabled() {
+        let container_image = |container: &str| network_policy::container_image(container, config);
+        network_findings.extend(network_policy::command_findings(
+            cmd,
+            &config.network_policy,
+            &network_lookup,
+            &container_image,
+        ));
+        for finding in network_findings {
+            match finding.action {
+                PinningAction::Deny => deny_reasons.push(finding.reason),
+                PinningAction::Ask => ask_reasons.push(finding.reason),
+                PinningAction::Allow => {}
+            }
+        }
+    }
+
+    // 9. イメージの許可・拒否リスト
+    if let Some(image) = &cmd.image {
+        check_image_lists(image, config, &mut deny_reasons, &mut ask_reasons);
+    }
+
+    // 10. イメージのピン留め (run / create、compose の image、Dockerfile の FROM、bake)
+    if config.image_pinning.is_enabled() {
+        image_uses.extend(image_pinning::command_images(cmd, cwd));
+        for finding in image_pinning::check(&image_uses, &config.image_pinning) {
+            match finding.action {
+                PinningAction::Deny => deny_reasons.push(finding.reason),
+                PinningAction::Ask => ask_reasons.push(finding.reason),
+                PinningAction::Allow => {}
+            }
+        }
+    }
+
+    // 11. イメージ署名の検証 (既に deny の場合は結果が変わらないため検証しない)
+    if config.image_signatures.is_enabled() && deny_reasons.is_empty() {
+        signed_uses.extend(image_signature::command_images(cmd));
+        for finding in image_signature::check(&signed_uses, config) {
+            match finding.action {
+                PinningAction::Deny => deny_reasons.push(finding.reason),
+                PinningAction::Ask => ask_reasons.push(finding.reason),
+                PinningAction::Allow => {}
+            }
+        }
+    }
+
+    // 12. 結果集約: deny が一つでもあれば deny、ask があれば ask、それ以外は allow
     if !deny_reasons.is_empty() {
         Decision::Deny(format_reasons(&deny_reasons))
     } else if !ask_reasons.is_empty() {
@@ -489,8 +650,60 @@ pub fn evaluate(cmd: &DockerCommand, config: &Config, cwd: &str) -> Decision {
     }
 }
 
+/// denied_images (deny) と allowed_registries / allowed_images (ask) でイメージを検査する
+fn check_image_lists(
+    image: &str,
+    config: &Config,
+    deny_reasons: &mut Vec<String>,
+    ask_reasons: &mut Vec<String>,
+) {
+    if config.allowed_images.is_empty()
+        && config.denied_images.is_empty()
+        && config.allowed_registries.is_empty()
+    {
+        return;
+    }
+    let image_ref = match ImageRef::parse(image) {
+        Ok(r) => r,
+        Err(e) => {
+            ask_reasons.push(format!(
+                "Cannot parse image reference '{}': {} (image lists cannot be checked)",
+                image, e
+            ));
+            return;
+        }
+    };
+    let matches =
+        |pattern: &String| ImagePattern::parse(pattern).is_ok_and(|p| p.matches(&image_ref));
+
+    if let Some(pattern) = config.denied_images.iter().find(|p| matches(p)) {
+        deny_reasons.push(format!(
+            "Image '{}' matches denied_images (resolved: {}, pattern: {})",
+            image, image_ref, pattern
+        ));
+        return;
+    }
+    if !config.allowed_registries.is_empty()
+        && !config
+            .allowed_registries
+            .iter()
+            .any(|r| image_ref::registry_matches(r, &image_ref.registry))
+    {
+        ask_reasons.push(format!(
+            "Image '{}' is from registry '{}', which is not in allowed_registries",
+            image, image_ref.registry
+        ));
+    }
+    if !config.allowed_images.is_empty() && !config.allowed_images.iter().any(matches) {
+        ask_reasons.push(format!(
+            "Image '{}' is not in allowed_images (to allow, add it to config)",
+            image
+        ));
+    }
+}
+
 /// compose ファイルを解析してマウントと危険設定を返す
-fn resolve_compose_analysis(
+pub fn resolve_compose_analysis(
     cmd: &DockerCommand,
     cwd: &str,
 ) -> std::result::Result<crate::compose::ComposeAnalysis, String> {
@@ -517,7 +730,7 @@ fn resolve_compose_analysis(
 }
 
 /// 理由リストを整形して一つの文字列にする
-fn format_reasons(reasons: &[String]) -> String {
+pub fn format_reasons(reasons: &[String]) -> String {
     if reasons.len() == 1 {
         format!("[safe-docker] {}", reasons[0])
     } else {
@@ -529,7 +742,7 @@ fn format_reasons(reasons: &[String]) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::docker_args::{BindMount, MountSource};
+    use crate::docker_args::{BindMount, MountSource, parse_docker_args};
 
     fn home_path(suffix: &str) -> String {
         let home = dirs::home_dir().unwrap().to_string_lossy().to_string();
@@ -538,7 +751,11 @@ mod tests {
 
     #[test]
     fn test_evaluate_allowed() {
-        let config = Config::default();
+        // マウント元が存在しない場合の確認はこのテストの対象外
+        let config = Config {
+            ask_missing_mount_sources: false,
+            ..Config::default()
+        };
         let cmd = DockerCommand {
             subcommand: DockerSubcommand::Run,
             bind_mounts: vec![BindMount {
@@ -551,6 +768,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         assert_eq!(evaluate(&cmd, &config, "/tmp"), Decision::Allow);
     }
@@ -570,6 +792,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(matches!(decision, Decision::Deny(_)));
@@ -585,6 +812,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(matches!(decision, Decision::Deny(_)));
@@ -605,6 +837,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(matches!(decision, Decision::Ask(_)));
@@ -620,6 +857,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(matches!(decision, Decision::Deny(_)));
@@ -640,6 +882,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         match decision {
@@ -660,6 +907,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         assert_eq!(evaluate(&cmd, &config, "/tmp"), Decision::Allow);
     }
@@ -691,6 +943,11 @@ mod tests {
             compose_file: None,
             image: Some("nginx".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -711,6 +968,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert_eq!(decision, Decision::Allow);
@@ -727,6 +989,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu:22.04".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert_eq!(
@@ -736,6 +1003,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_evaluate_image_lists() {
+        let config = Config {
+            allowed_images: vec![
+                "ubuntu".to_string(),
+                "ghcr.io/ourorg/*".to_string(),
+                "localhost:5000/app:1.*".to_string(),
+            ],
+            denied_images: vec!["ghcr.io/ourorg/legacy".to_string()],
+            allowed_registries: vec!["docker.io".to_string(), "ghcr.io".to_string()],
+            ..Default::default()
+        };
+        let run = |image: &str| {
+            let cmd = DockerCommand {
+                subcommand: DockerSubcommand::Run,
+                bind_mounts: vec![],
+                dangerous_flags: vec![],
+                compose_file: None,
+                image: Some(image.to_string()),
+                host_paths: vec![],
+                host_write_paths: vec![],
+                ports: vec![],
+                publish_all: false,
+                build: None,
+                network: Default::default(),
+            };
+            evaluate(&cmd, &config, "/tmp")
+        };
+
+        assert_eq!(run("docker.io/library/ubuntu:24.04"), Decision::Allow);
+        assert_eq!(
+            run(
+                "ghcr.io/ourorg/app@sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"
+            ),
+            Decision::Allow
+        );
+        match run("ghcr.io/ourorg/legacy:1") {
+            Decision::Deny(reason) => assert!(reason.contains("denied_images"), "{}", reason),
+            other => panic!("expected Deny, got {:?}", other),
+        }
+        // localhost:5000 は allowed_images に一致するが allowed_registries にない
+        match run("localhost:5000/app:1.0") {
+            Decision::Ask(reason) => {
+                assert!(reason.contains("allowed_registries"), "{}", reason);
+                assert!(!reason.contains("not in allowed_images"), "{}", reason);
+            }
+            other => panic!("expected Ask, got {:?}", other),
+        }
+        match run("ghcr.io/other/app") {
+            Decision::Ask(reason) => assert!(reason.contains("not in allowed_images")),
+            other => panic!("expected Ask, got {:?}", other),
+        }
+        match run("Ubuntu") {
+            Decision::Ask(reason) => assert!(reason.contains("Cannot parse image reference")),
+            other => panic!("expected Ask, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_evaluate_security_opt_apparmor_unconfined() {
         let config = Config::default();
@@ -748,6 +1073,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(matches!(decision, Decision::Deny(_)));
@@ -763,6 +1093,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(matches!(decision, Decision::Deny(_)));
@@ -780,6 +1115,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(matches!(decision, Decision::Deny(_)));
@@ -797,6 +1137,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         // no-new-privileges=false はセキュリティ制限の無効化なので deny
@@ -818,6 +1163,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(matches!(decision, Decision::Deny(_)));
@@ -835,6 +1185,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(matches!(decision, Decision::Deny(_)));
@@ -852,6 +1207,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(matches!(decision, Decision::Deny(_)));
@@ -867,6 +1227,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert_eq!(
@@ -886,6 +1251,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(matches!(decision, Decision::Deny(_)));
@@ -902,6 +1272,11 @@ mod tests {
             compose_file: None,
             image: None,
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, dir.path().to_str().unwrap());
         assert!(
@@ -924,6 +1299,11 @@ mod tests {
             compose_file: None,
             image: None,
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, dir.path().to_str().unwrap());
         assert!(
@@ -956,6 +1336,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         // deny (/etc) が ask (.ssh) より優先
@@ -977,6 +1362,11 @@ mod tests {
             compose_file: None,
             image: None,
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         // ComposeExec は compose ファイル解析対象外
         let decision = evaluate(&cmd, &config, dir.path().to_str().unwrap());
@@ -990,7 +1380,9 @@ mod tests {
     #[test]
     fn test_evaluate_allowed_paths_tmp() {
         let mut config = Config::default();
-        config.allowed_paths = vec!["/tmp".to_string()];
+        config.allowed_paths = vec!["/tmp".into()];
+        // マウント元が存在しない場合の確認はこのテストの対象外
+        config.ask_missing_mount_sources = false;
         let cmd = DockerCommand {
             subcommand: DockerSubcommand::Run,
             bind_mounts: vec![BindMount {
@@ -1003,6 +1395,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert_eq!(
@@ -1012,6 +1409,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_evaluate_read_only_allowed_paths() {
+        let config: Config = toml::from_str(
+            r#"
+allowed_paths = [{ path = "/tmp/datasets", mode = "ro" }]
+ask_missing_mount_sources = false
+"#,
+        )
+        .unwrap();
+        let eval = |args: &[&str]| evaluate(&parse_docker_args(args), &config, "/tmp");
+
+        // 読み取り: :ro マウント、docker cp のコピー元
+        assert_eq!(
+            eval(&["run", "-v", "/tmp/datasets:/data:ro", "ubuntu"]),
+            Decision::Allow
+        );
+        assert_eq!(
+            eval(&["cp", "/tmp/datasets/a.csv", "web:/data/"]),
+            Decision::Allow
+        );
+
+        // 書き込み: :ro なしのマウント、docker cp のコピー先、build --output
+        for args in [
+            &["run", "-v", "/tmp/datasets:/data", "ubuntu"][..],
+            &["cp", "web:/out", "/tmp/datasets/out"],
+            &["build", "-o", "/tmp/datasets/out", "."],
+        ] {
+            match eval(args) {
+                Decision::Deny(reason) => {
+                    assert!(reason.contains("read-only"), "{:?}: {}", args, reason)
+                }
+                other => panic!("{:?}: expected deny, got {:?}", args, other),
+            }
+        }
+    }
+
     #[test]
     fn test_evaluate_uts_host() {
         let config = Config::default();
@@ -1022,6 +1455,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1041,6 +1479,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec!["/etc/secrets.env".to_string()],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1060,6 +1503,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![home_path("projects/.env")],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert_eq!(
@@ -1079,6 +1527,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1098,6 +1551,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1117,6 +1575,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1140,6 +1603,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1159,6 +1627,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1178,6 +1651,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert_eq!(
@@ -1202,6 +1680,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1221,6 +1704,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert_eq!(
@@ -1243,6 +1731,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1264,6 +1757,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1283,6 +1781,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1306,6 +1809,11 @@ mod tests {
             compose_file: None,
             image: None,
             host_paths: vec![home_path("project")],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1325,6 +1833,11 @@ mod tests {
             compose_file: None,
             image: None,
             host_paths: vec![home_path("project"), "/etc/secrets/db.env".to_string()],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, "/tmp");
         assert!(
@@ -1349,6 +1862,11 @@ mod tests {
             compose_file: None,
             image: None,
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, dir.path().to_str().unwrap());
         assert!(
@@ -1382,6 +1900,11 @@ mod tests {
             compose_file: None,
             image: None,
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
         let decision = evaluate(&cmd, &config, dir.path().to_str().unwrap());
         assert_eq!(
@@ -1490,6 +2013,11 @@ mod tests {
             compose_file: None,
             image: None,
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
 
         // compose ファイルを作成
@@ -1518,6 +2046,11 @@ mod tests {
             compose_file: None,
             image: None,
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
 
         let dir = tempfile::tempdir().unwrap();
@@ -1535,6 +2068,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_evaluate_compose_published_ports() {
+        let mut config = Config::default();
+        config.published_ports.non_loopback = PinningAction::Deny;
+        let cmd = DockerCommand {
+            subcommand: DockerSubcommand::ComposeUp,
+            bind_mounts: vec![],
+            dangerous_flags: vec![],
+            compose_file: None,
+            image: None,
+            host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let compose = dir.path().join("compose.yml");
+        std::fs::write(
+            &compose,
+            "services:\n  db:\n    image: postgres\n    ports:\n      - \"5432:5432\"\n",
+        )
+        .unwrap();
+        let decision = evaluate(&cmd, &config, dir.path().to_str().unwrap());
+        assert!(
+            matches!(&decision, Decision::Deny(r) if r.contains("compose service 'db'")),
+            "Compose port on all interfaces should be denied: {:?}",
+            decision
+        );
+
+        std::fs::write(
+            &compose,
+            "services:\n  db:\n    image: postgres\n    ports:\n      - \"127.0.0.1:5432:5432\"\n",
+        )
+        .unwrap();
+        let decision = evaluate(&cmd, &config, dir.path().to_str().unwrap());
+        assert_eq!(decision, Decision::Allow);
+    }
+
+    #[test]
+    fn test_evaluate_network_policy() {
+        let mut config = Config::default();
+        let cmd = parse_docker_args(&[
+            "network",
+            "create",
+            "-d",
+            "macvlan",
+            "-o",
+            "parent=eth0",
+            "lan",
+        ]);
+        let decision = evaluate(&cmd, &config, "/tmp");
+        assert!(
+            matches!(&decision, Decision::Ask(r) if r.contains("uses the macvlan driver")),
+            "macvlan network should ask: {:?}",
+            decision
+        );
+        let cmd = parse_docker_args(&["network", "create", "app"]);
+        assert_eq!(evaluate(&cmd, &config, "/tmp"), Decision::Allow);
+
+        config.network_policy.isolated_images = vec!["busybox".to_string()];
+        config.network_policy.not_isolated = PinningAction::Deny;
+        let cmd = parse_docker_args(&["run", "--rm", "busybox"]);
+        let decision = evaluate(&cmd, &config, "/tmp");
+        assert!(
+            matches!(&decision, Decision::Deny(r) if r.contains("attached to the bridge network")),
+            "isolated image on the default bridge should be denied: {:?}",
+            decision
+        );
+        let cmd = parse_docker_args(&["run", "--rm", "--network", "none", "busybox"]);
+        assert_eq!(evaluate(&cmd, &config, "/tmp"), Decision::Allow);
+        let cmd = parse_docker_args(&["run", "--rm", "alpine"]);
+        assert_eq!(evaluate(&cmd, &config, "/tmp"), Decision::Allow);
+    }
+
     #[test]
     fn test_evaluate_cgroup_parent_ask() {
         let config = Config::default();
@@ -1545,6 +2155,11 @@ mod tests {
             compose_file: None,
             image: Some("ubuntu".to_string()),
             host_paths: vec![],
+            host_write_paths: vec![],
+            ports: vec![],
+            publish_all: false,
+            build: None,
+            network: Default::default(),
         };
 
         let decision = evaluate(&cmd, &config, "/tmp");
@@ -0,0 +1,454 @@
+//! ポリシー拡張 (`[policy]` セクション)
+//!
+//! 固定の設定項目では表せない組織固有のルール (例: 「`ghcr.io/ourorg/*` のイメージだけ
+//! `NET_ADMIN` を許可」「18 時以降は `0.0.0.0` への公開を禁止」) を CEL 式で記述し、
+//! 組み込みポリシー (`policy::evaluate`) の後に評価する。
+//!
+//! ルールは deny / ask を追加するだけで、組み込みポリシーの判定を緩めることはできない。
+//! 式を評価できない場合は安全側に倒して deny する。ルールの後に外部プラグイン
+//! (`[[plugins]]`, `plugin.rs`) を同じドキュメントで実行する。
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{Map, Value, json};
+
+use crate::cel;
+use crate::config::{Config, PolicyRuleDecision};
+use crate::docker_args::{BindMount, DangerousFlag, DockerCommand, DockerSubcommand, MountSource};
+use crate::hook::Decision;
+use crate::image_ref::ImageRef;
+use crate::{plugin, policy};
+
+/// ルールに渡す評価対象の情報
+pub struct Context<'a> {
+    /// docker に渡す引数 (サブコマンド以降)
+    pub args: &'a [String],
+    pub cwd: &'a str,
+    /// エージェントのセッション ID (Hook モードのみ)
+    pub session_id: Option<&'a str>,
+    /// セッションで作成したコンテナの ID・短縮 ID・名前 (Hook モードのみ、`session.rs`)
+    pub session_containers: &'a [String],
+}
+
+/// 組み込みポリシーの判定にポリシー拡張 (ルール → 外部プラグイン) の判定を合成する
+pub fn apply(decision: Decision, cmd: &DockerCommand, config: &Config, ctx: &Context) -> Decision {
+    let mut decision = decision;
+    if config.policy.is_enabled() {
+        decision = merge(decision, evaluate(cmd, config, ctx));
+    }
+    // 既に deny なら結果は変わらないため、プラグインの起動コストを払わない
+    if !config.plugins.is_empty() && !matches!(decision, Decision::Deny(_)) {
+        let findings = plugin::evaluate(&decision, cmd, config, ctx);
+        decision = merge(decision, findings);
+    }
+    decision
+}
+
+/// ポリシー拡張のルールを評価する
+pub fn evaluate(cmd: &DockerCommand, config: &Config, ctx: &Context) -> Decision {
+    let rules = match config.policy.load_rules() {
+        Ok(rules) => rules,
+        Err(e) => {
+            return Decision::Deny(format!(
+                "[safe-docker] Policy extension error: {}. Blocking for safety",
+                e
+            ));
+        }
+    };
+    if rules.is_empty() {
+        return Decision::Allow;
+    }
+
+    let document = build_document(cmd, ctx, current_time(config));
+
+    let mut deny_reasons = Vec::new();
+    let mut ask_reasons = Vec::new();
+    for rule in &rules {
+        match cel::parse(&rule.when).and_then(|expr| cel::evaluate_bool(&expr, &document)) {
+            Ok(true) => {
+                let message = if rule.message.is_empty() {
+                    format!("Matched policy rule '{}'", rule.name)
+                } else {
+                    format!("{} (policy rule '{}')", rule.message, rule.name)
+                };
+                match rule.decision {
+                    PolicyRuleDecision::Deny => deny_reasons.push(message),
+                    PolicyRuleDecision::Ask => ask_reasons.push(message),
+                }
+            }
+            Ok(false) => {}
+            Err(e) => deny_reasons.push(format!(
+                "Policy rule '{}' could not be evaluated: {}. Blocking for safety",
+                rule.name, e
+            )),
+        }
+    }
+
+    if !deny_reasons.is_empty() {
+        Decision::Deny(policy::format_reasons(&deny_reasons))
+    } else if !ask_reasons.is_empty() {
+        Decision::Ask(policy::format_reasons(&ask_reasons))
+    } else {
+        Decision::Allow
+    }
+}
+
+/// 二つの判定を合成する (deny > ask > allow、同じ強さなら理由を連結)
+pub fn merge(base: Decision, extra: Decision) -> Decision {
+    match (base, extra) {
+        (Decision::Deny(a), Decision::Deny(b)) => Decision::Deny(format!("{}\n{}", a, b)),
+        (Decision::Deny(a), _) => Decision::Deny(a),
+        (_, Decision::Deny(b)) => Decision::Deny(b),
+        (Decision::Ask(a), Decision::Ask(b)) => Decision::Ask(format!("{}\n{}", a, b)),
+        (Decision::Ask(a), _) => Decision::Ask(a),
+        (_, extra) => extra,
+    }
+}
+
+/// ルールに渡す JSON ドキュメントを組み立てる
+pub fn build_document(cmd: &DockerCommand, ctx: &Context, time: Value) -> Map<String, Value> {
+    let (flags, cap_add) = flags_json(&cmd.dangerous_flags);
+    let is_compose = matches!(
+        cmd.subcommand,
+        DockerSubcommand::ComposeUp
+            | DockerSubcommand::ComposeRun
+            | DockerSubcommand::ComposeCreate
+            | DockerSubcommand::ComposeExec
+    );
+    // compose ファイルを解析できない場合は組み込みポリシーが deny するため、ここでは空とする
+    let analysis = if is_compose {
+        policy::resolve_compose_analysis(cmd, ctx.cwd).ok()
+    } else {
+        None
+    };
+    let compose = match analysis {
+        Some(a) => {
+            let (compose_flags, compose_caps) = flags_json(&a.dangerous_flags);
+            json!({
+                "bind_mounts": mounts_json(&a.bind_mounts),
+                "dangerous_flags": compose_flags,
+                "cap_add": compose_caps,
+                "host_paths": a.host_paths,
+                "env_file_paths": a.env_file_paths,
+            })
+        }
+        None => json!({
+            "bind_mounts": [],
+            "dangerous_flags": [],
+            "cap_add": [],
+            "host_paths": [],
+            "env_file_paths": [],
+        }),
+    };
+
+    let document = json!({
+        "command": {
+            "subcommand": cmd.subcommand.to_string(),
+            "args": ctx.args,
+            "image": cmd.image.clone().unwrap_or_default(),
+            "image_ref": image_ref_json(cmd.image.as_deref()),
+            "bind_mounts": mounts_json(&cmd.bind_mounts),
+            "dangerous_flags": flags,
+            "cap_add": cap_add,
+            "host_paths": cmd.host_paths,
+            "host_write_paths": cmd.host_write_paths,
+            "ports": cmd.ports,
+            "publish_all": cmd.publish_all,
+            "networks": cmd.network.networks,
+            "dns": cmd.network.dns,
+            "links": cmd.network.links,
+            "compose_file": cmd.compose_file.clone().unwrap_or_default(),
+        },
+        "compose": compose,
+        "cwd": ctx.cwd,
+        "session": {
+            "id": ctx.session_id.unwrap_or_default(),
+            "containers": ctx.session_containers,
+        },
+        "time": time,
+    });
+    match document {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+/// 正規化したイメージ参照 (イメージがない・解析できない場合は null)
+fn image_ref_json(image: Option<&str>) -> Value {
+    match image.map(ImageRef::parse) {
+        Some(Ok(r)) => json!({
+            "name": r.name(),
+            "registry": r.registry,
+            "namespace": r.namespace,
+            "repo": r.repo,
+            "tag": r.tag.unwrap_or_default(),
+            "digest": r.digest.unwrap_or_default(),
+        }),
+        _ => Value::Null,
+    }
+}
+
+fn mounts_json(mounts: &[BindMount]) -> Vec<Value> {
+    mounts
+        .iter()
+        .map(|m| {
+            json!({
+                "host_path": m.host_path,
+                "container_path": m.container_path,
+                "source": match m.source {
+                    MountSource::VolumeFlag => "volume",
+                    MountSource::MountFlag => "mount",
+                    MountSource::ComposeVolumes => "compose",
+                    MountSource::VolumeOpt => "volume-opt",
+                },
+                "read_only": m.read_only,
+            })
+        })
+        .collect()
+}
+
+/// 危険フラグの表示文字列と、追加される capability (大文字) を返す
+fn flags_json(flags: &[DangerousFlag]) -> (Vec<String>, Vec<String>) {
+    let strings = flags.iter().map(|f| f.to_string()).collect();
+    let caps = flags
+        .iter()
+        .filter_map(|f| match f {
+            DangerousFlag::CapAdd(cap) => Some(cap.to_uppercase()),
+            _ => None,
+        })
+        .collect();
+    (strings, caps)
+}
+
+/// 現在時刻の time フィールド ([policy] utc_offset のタイムゾーン)
+pub fn current_time(config: &Config) -> Value {
+    let unix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    time_fields(unix, config.policy.utc_offset_minutes().unwrap_or(0))
+}
+
+/// UNIX 時刻とオフセット (分) から time フィールドを作る。weekday は 0=日曜
+pub fn time_fields(unix: i64, offset_minutes: i64) -> Value {
+    let local = unix + offset_minutes * 60;
+    let days = local.div_euclid(86_400);
+    let seconds = local.rem_euclid(86_400);
+    json!({
+        "unix": unix,
+        "hour": seconds / 3600,
+        "minute": seconds % 3600 / 60,
+        // 1970-01-01 は木曜日
+        "weekday": (days + 4).rem_euclid(7),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{PolicyConfig, PolicyRule};
+    use crate::docker_args::parse_docker_args;
+
+    fn rule(name: &str, when: &str, decision: PolicyRuleDecision) -> PolicyRule {
+        PolicyRule {
+            name: name.to_string(),
+            when: when.to_string(),
+            decision,
+            message: String::new(),
+        }
+    }
+
+    fn config_with(rules: Vec<PolicyRule>) -> Config {
+        Config {
+            policy: PolicyConfig {
+                rules,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn run(args: &[&str], config: &Config) -> Decision {
+        let cmd = parse_docker_args(args);
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let ctx = Context {
+            args: &args,
+            cwd: "/tmp",
+            session_id: Some("session-1"),
+            session_containers: &[],
+        };
+        apply(policy::evaluate(&cmd, config, "/tmp"), &cmd, config, &ctx)
+    }
+
+    #[test]
+    fn test_no_rules_keeps_decision() {
+        let config = Config::default();
+        assert_eq!(run(&["run", "ubuntu"], &config), Decision::Allow);
+        assert!(matches!(
+            run(&["run", "--privileged", "ubuntu"], &config),
+            Decision::Deny(_)
+        ));
+    }
+
+    #[test]
+    fn test_capability_scoped_to_image() {
+        // NET_ADMIN は組み込みポリシーから外し、特定のイメージだけに許可する
+        let mut config = config_with(vec![PolicyRule {
+            message: "NET_ADMIN is only allowed for ghcr.io/ourorg images".to_string(),
+            ..rule(
+                "net-admin-ourorg-only",
+                r#""NET_ADMIN" in command.cap_add && !command.image.startsWith("ghcr.io/ourorg/")"#,
+                PolicyRuleDecision::Deny,
+            )
+        }]);
+        config.blocked_capabilities.retain(|c| c != "NET_ADMIN");
+
+        assert_eq!(
+            run(
+                &["run", "--cap-add", "net_admin", "ghcr.io/ourorg/router"],
+                &config
+            ),
+            Decision::Allow
+        );
+        match run(&["run", "--cap-add=NET_ADMIN", "alpine"], &config) {
+            Decision::Deny(reason) => {
+                assert!(
+                    reason.contains("only allowed for ghcr.io/ourorg"),
+                    "{}",
+                    reason
+                );
+                assert!(reason.contains("net-admin-ourorg-only"), "{}", reason);
+            }
+            other => panic!("expected deny, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ask_rule_and_merge_with_builtin() {
+        let config = config_with(vec![rule(
+            "public-port",
+            "command.args.exists(a, a.startsWith('0.0.0.0:'))",
+            PolicyRuleDecision::Ask,
+        )]);
+        match run(&["run", "-p", "0.0.0.0:80:80", "nginx"], &config) {
+            Decision::Ask(reason) => assert!(reason.contains("public-port"), "{}", reason),
+            other => panic!("expected ask, got {:?}", other),
+        }
+        // 組み込みポリシーの deny が優先される
+        match run(
+            &["run", "--privileged", "-p", "0.0.0.0:80:80", "nginx"],
+            &config,
+        ) {
+            Decision::Deny(reason) => assert!(!reason.contains("public-port"), "{}", reason),
+            other => panic!("expected deny, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_document_fields() {
+        let args: Vec<String> = [
+            "run",
+            "-v",
+            "/data:/data:ro",
+            "--cap-add",
+            "sys_time",
+            "-p",
+            "127.0.0.1:8080:80",
+            "--network",
+            "app",
+            "alpine",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        let cmd = parse_docker_args(&args_ref);
+        let ctx = Context {
+            args: &args,
+            cwd: "/work",
+            session_id: None,
+            session_containers: &[],
+        };
+        let doc = Value::Object(build_document(&cmd, &ctx, time_fields(0, 0)));
+        assert_eq!(doc["command"]["subcommand"], "run");
+        assert_eq!(doc["command"]["image"], "alpine");
+        assert_eq!(
+            doc["command"]["image_ref"]["name"],
+            "docker.io/library/alpine"
+        );
+        assert_eq!(doc["command"]["image_ref"]["tag"], "");
+        assert_eq!(doc["command"]["cap_add"], json!(["SYS_TIME"]));
+        assert_eq!(doc["command"]["bind_mounts"][0]["host_path"], "/data");
+        assert_eq!(doc["command"]["bind_mounts"][0]["source"], "volume");
+        assert_eq!(doc["command"]["bind_mounts"][0]["read_only"], true);
+        assert_eq!(doc["command"]["ports"], json!(["127.0.0.1:8080:80"]));
+        assert_eq!(doc["command"]["publish_all"], false);
+        assert_eq!(doc["command"]["networks"], json!(["app"]));
+        assert_eq!(doc["command"]["dns"], json!([]));
+        assert_eq!(doc["compose"]["bind_mounts"], json!([]));
+        assert_eq!(doc["cwd"], "/work");
+        assert_eq!(doc["session"]["id"], "");
+    }
+
+    #[test]
+    fn test_time_fields() {
+        // 2024-01-01T18:30:00Z (月曜日)
+        let t = time_fields(1_704_133_800, 0);
+        assert_eq!(
+            (t["hour"].clone(), t["minute"].clone()),
+            (json!(18), json!(30))
+        );
+        assert_eq!(t["weekday"], 1);
+        // +09:00 では 2024-01-02 (火曜日) 03:30
+        let t = time_fields(1_704_133_800, 540);
+        assert_eq!(
+            (t["hour"].clone(), t["weekday"].clone()),
+            (json!(3), json!(2))
+        );
+        // 負のオフセットで日付をまたぐ
+        let t = time_fields(0, -60);
+        assert_eq!(
+            (t["hour"].clone(), t["weekday"].clone()),
+            (json!(23), json!(3))
+        );
+    }
+
+    #[test]
+    fn test_evaluation_error_fails_closed() {
+        let config = config_with(vec![rule(
+            "typo",
+            "command.imag == 'x'",
+            PolicyRuleDecision::Ask,
+        )]);
+        match run(&["run", "ubuntu"], &config) {
+            Decision::Deny(reason) => {
+                assert!(reason.contains("could not be evaluated"), "{}", reason)
+            }
+            other => panic!("expected deny, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_rule_file_fails_closed() {
+        let config = Config {
+            policy: PolicyConfig {
+                files: vec!["/nonexistent/safe-docker-policy.toml".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(matches!(run(&["ps"], &config), Decision::Deny(_)));
+    }
+
+    #[test]
+    fn test_merge() {
+        let deny = |s: &str| Decision::Deny(s.to_string());
+        let ask = |s: &str| Decision::Ask(s.to_string());
+        assert_eq!(merge(Decision::Allow, Decision::Allow), Decision::Allow);
+        assert_eq!(merge(Decision::Allow, ask("b")), ask("b"));
+        assert_eq!(merge(ask("a"), ask("b")), ask("a\nb"));
+        assert_eq!(merge(ask("a"), deny("b")), deny("b"));
+        assert_eq!(merge(deny("a"), ask("b")), deny("a"));
+        assert_eq!(merge(deny("a"), deny("b")), deny("a\nb"));
+    }
+}
@@ -0,0 +1,310 @@
+//! safe-docker 自身の保護 (agent による設定改ざんの防止)
+//!
+//! Hook モードでは、エージェントが Write/Edit ツールや `sed -i` 等で
+//! safe-docker の設定、Claude Code の hook 設定、`setup` が作成した docker ラッパーを
+//! 書き換えることで保護を無効化できてしまう。ここではそれらのパスへの書き込みを検出する。
+
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, SelfProtection};
+use crate::hook::Decision;
+use crate::path_validator;
+use crate::shell::{WriteKind, WriteTarget};
+
+/// 保護対象パス
+#[derive(Debug, Clone)]
+pub struct ProtectedPath {
+    pub path: PathBuf,
+    /// 理由メッセージ用の説明
+    pub description: &'static str,
+    /// ディレクトリ全体を保護する場合 true
+    pub is_dir: bool,
+}
+
+impl ProtectedPath {
+    fn file(path: PathBuf, description: &'static str) -> Self {
+        Self {
+            path,
+            description,
+            is_dir: false,
+        }
+    }
+
+    fn dir(path: PathBuf, description: &'static str) -> Self {
+        Self {
+            path,
+            description,
+            is_dir: true,
+        }
+    }
+}
+
+/// 保護対象パスの一覧を返す
+///
+/// - safe-docker の設定ファイル (`SAFE_DOCKER_CONFIG` 含む) と設定ディレクトリ
+/// - Claude Code の settings ファイル (ユーザー / プロジェクト / managed)
+/// - `setup` が作成する docker ラッパー、および PATH 上で safe-docker を指す docker
+/// - safe-docker 実行ファイル自身
+pub fn protected_paths(cwd: &str) -> Vec<ProtectedPath> {
+    let mut paths = vec![ProtectedPath::file(
+        crate::config::config_path(),
+        "safe-docker config",
+    )];
+
+    if let Some(config_dir) = dirs::config_dir() {
+        paths.push(ProtectedPath::dir(
+            config_dir.join("safe-docker"),
+            "safe-docker config directory",
+        ));
+    }
+
+    let home = path_validator::home_dir();
+    if let Some(ref home) = home {
+        for name in ["settings.json", "settings.local.json"] {
+            paths.push(ProtectedPath::file(
+                home.join(".claude").join(name),
+                "Claude Code settings",
+            ));
+        }
+    }
+    for name in ["settings.json", "settings.local.json"] {
+        paths.push(ProtectedPath::file(
+            Path::new(cwd).join(".claude").join(name),
+            "Claude Code project settings",
+        ));
+    }
+    paths.push(ProtectedPath::file(
+        PathBuf::from("/etc/claude-code/managed-settings.json"),
+        "Claude Code managed settings",
+    ));
+    paths.push(ProtectedPath::file(
+        PathBuf::from("/Library/Application Support/ClaudeCode/managed-settings.json"),
+        "Claude Code managed settings",
+    ));
+
+    if let Some(ref home) = home {
+        paths.push(ProtectedPath::file(
+            home.join(".local/bin/docker"),
+            "safe-docker wrapper symlink",
+        ));
+    }
+
+    // PATH 上で safe-docker 自身を指している docker (setup --target で作成されたもの)
+    let self_exe = std::env::current_exe()
+        .ok()
+        .and_then(|p| std::fs::canonicalize(p).ok());
+    if let Some(ref self_exe) = self_exe {
+        let path_env = std::env::var("PATH").unwrap_or_default();
+        for dir in path_env.split(':').filter(|d| !d.is_empty()) {
+            let candidate = Path::new(dir).join("docker");
+            if std::fs::canonicalize(&candidate).is_ok_and(|c| &c == self_exe) {
+                paths.push(ProtectedPath::file(
+                    candidate,
+                    "safe-docker wrapper symlink",
+                ));
+            }
+        }
+        paths.push(ProtectedPath::file(
+            self_exe.clone(),
+            "safe-docker executable",
+        ));
+    }
+
+    paths
+}
+
+/// パスを絶対パス化して正規化する。
+/// 最終要素のシンボリックリンクは解決しない (ラッパーのシンボリックリンク自体を比較するため)。
+fn normalize(raw: &str, cwd: &str) -> PathBuf {
+    let expanded = path_validator::expand_env(raw);
+    let path = Path::new(&expanded);
+    let abs = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        Path::new(cwd).join(path)
+    };
+    let logical = path_validator::logical_normalize(&abs);
+
+    // 親ディレクトリのシンボリックリンクを解決 (~/.config -> dotfiles 等)
+    match (logical.parent(), logical.file_name()) {
+        (Some(parent), Some(name)) => match std::fs::canonicalize(parent) {
+            Ok(parent) => parent.join(name),
+            Err(_) => logical,
+        },
+        _ => logical,
+    }
+}
+
+/// 書き込み対象が保護対象パスに該当する場合、そのパスを返す
+fn find_protected<'a>(
+    target: &WriteTarget,
+    protected: &'a [ProtectedPath],
+    cwd: &str,
+) -> Option<&'a ProtectedPath> {
+    let target_path = normalize(&target.path, cwd);
+    protected.iter().find(|p| {
+        let candidates = [p.path.clone(), normalize(&p.path.to_string_lossy(), cwd)];
+        candidates.iter().any(|protected_path| {
+            if target_path == *protected_path {
+                return true;
+            }
+            if p.is_dir && target_path.starts_with(protected_path) {
+                return true;
+            }
+            // 親ディレクトリの削除・移動は保護対象の削除と同じ
+            target.kind == WriteKind::Remove && protected_path.starts_with(&target_path)
+        })
+    })
+}
+
+/// 書き込み対象のリストを検査し、保護対象への書き込みがあれば理由のリストを返す
+pub fn check_write_targets(targets: &[WriteTarget], cwd: &str) -> Vec<String> {
+    if targets.is_empty() {
+        return Vec::new();
+    }
+    let protected = protected_paths(cwd);
+    let mut reasons = Vec::new();
+    for target in targets {
+        if let Some(p) = find_protected(target, &protected, cwd) {
+            let reason = format!(
+                "Modifying {} ({}) is not allowed from an agent session: it controls safe-docker's own protection. Edit it manually instead",
+                p.description,
+                p.path.display()
+            );
+            if !reasons.contains(&reason) {
+                reasons.push(reason);
+            }
+        }
+    }
+    reasons
+}
+
+/// Write/Edit 系ツールのファイルパスを検査して Decision を返す
+pub fn check_file_paths(file_paths: &[&str], config: &Config, cwd: &str) -> Decision {
+    let targets: Vec<WriteTarget> = file_paths
+        .iter()
+        .map(|p| WriteTarget {
+            path: p.to_string(),
+            kind: WriteKind::Write,
+        })
+        .collect();
+    let reasons = check_write_targets(&targets, cwd);
+    to_decision(reasons, config)
+}
+
+/// 理由リストを self_protection 設定に従って Decision に変換する
+pub fn to_decision(reasons: Vec<String>, config: &Config) -> Decision {
+    if reasons.is_empty() {
+        return Decision::Allow;
+    }
+    let reason = if reasons.len() == 1 {
+        format!("[safe-docker] {}", reasons[0])
+    } else {
+        let items: Vec<String> = reasons.iter().map(|r| format!("  - {}", r)).collect();
+        format!("[safe-docker] Multiple issues found:\n{}", items.join("\n"))
+    };
+    match config.hook.self_protection {
+        SelfProtection::Deny => Decision::Deny(reason),
+        SelfProtection::Ask => Decision::Ask(reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home() -> String {
+        dirs::home_dir().unwrap().to_string_lossy().to_string()
+    }
+
+    fn write(path: &str) -> WriteTarget {
+        WriteTarget {
+            path: path.to_string(),
+            kind: WriteKind::Write,
+        }
+    }
+
+    fn remove(path: &str) -> WriteTarget {
+        WriteTarget {
+            path: path.to_string(),
+            kind: WriteKind::Remove,
+        }
+    }
+
+    #[test]
+    fn test_claude_settings_protected() {
+        let path = format!("{}/.claude/settings.json", home());
+        assert_eq!(check_write_targets(&[write(&path)], "/tmp").len(), 1);
+        assert_eq!(
+            check_write_targets(&[write("~/.claude/settings.local.json")], "/tmp").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_project_settings_relative_to_cwd() {
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = dir.path().to_str().unwrap();
+        let reasons = check_write_targets(&[write(".claude/settings.json")], cwd);
+        assert_eq!(reasons.len(), 1);
+        assert!(reasons[0].contains("Claude Code project settings"));
+    }
+
+    #[test]
+    fn test_safe_docker_config_dir_protected() {
+        let config_dir = dirs::config_dir().unwrap().join("safe-docker");
+        let path = config_dir.join("other.toml");
+        assert_eq!(
+            check_write_targets(&[write(path.to_str().unwrap())], "/tmp").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_wrapper_symlink_protected() {
+        let reasons = check_write_targets(&[write("~/.local/bin/docker")], "/tmp");
+        assert_eq!(reasons.len(), 1);
+        assert!(reasons[0].contains("wrapper symlink"));
+    }
+
+    #[test]
+    fn test_unrelated_file_not_protected() {
+        let reasons = check_write_targets(
+            &[write("~/projects/app/settings.json"), write("/tmp/x")],
+            "/tmp",
+        );
+        assert!(reasons.is_empty(), "{:?}", reasons);
+    }
+
+    #[test]
+    fn test_remove_parent_dir_protected() {
+        assert_eq!(check_write_targets(&[remove("~/.claude")], "/tmp").len(), 1);
+        // 書き込みの場合は親ディレクトリへの書き込みは対象外 (ディレクトリ自体は変更されない)
+        assert!(check_write_targets(&[write("~/.claude")], "/tmp").is_empty());
+    }
+
+    #[test]
+    fn test_dot_dot_traversal_protected() {
+        let path = format!("{}/projects/../.claude/settings.json", home());
+        assert_eq!(check_write_targets(&[write(&path)], "/tmp").len(), 1);
+    }
+
+    #[test]
+    fn test_check_file_paths_decision() {
+        let mut config = Config::default();
+        let path = format!("{}/.claude/settings.json", home());
+        assert!(matches!(
+            check_file_paths(&[&path], &config, "/tmp"),
+            Decision::Deny(_)
+        ));
+        config.hook.self_protection = SelfProtection::Ask;
+        assert!(matches!(
+            check_file_paths(&[&path], &config, "/tmp"),
+            Decision::Ask(_)
+        ));
+        assert_eq!(
+            check_file_paths(&["/tmp/notes.txt"], &config, "/tmp"),
+            Decision::Allow
+        );
+    }
+}
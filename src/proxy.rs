@@ -0,0 +1,690 @@
+//! Docker Engine API プロキシモード (`safe-docker proxy`)
+//!
+//! docker ソケットの手前に検査用の Unix ソケットを置き、API リクエストを
+//! `engine_api` で docker コマンドに変換して `policy::evaluate` で評価する。
+//! 許可されたリクエストのみ上流ソケットに転送し、それ以外は Docker 形式のエラー
+//! (`{"message": ...}`、403) を返す。クライアントは `DOCKER_HOST=unix://...` で利用する。
+//!
+//! 1 接続 1 リクエストで処理する (上流には `Connection: close` を付けて転送する)。
+//! keep-alive 接続で後続のリクエストが検査を経ずに転送されることを防ぐため。
+//! 接続の切り替え (Upgrade) は attach / exec start と BuildKit の session / grpc だけに許し、上流が 101 を返した後に限って
+//! クライアントからの入力を中継する。
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use crate::config::Config;
+use crate::engine_api;
+
+const DEFAULT_LISTEN: &str = "~/.safe-docker/docker.sock";
+const DEFAULT_UPSTREAM: &str = "/var/run/docker.sock";
+
+/// リクエストヘッダーの最大サイズ
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+
+/// 検査のためにメモリに読み込むリクエストボディの最大サイズ
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// プロキシの状態
+struct Proxy {
+    inspector: engine_api::Inspector,
+    upstream: PathBuf,
+}
+
+/// HTTP リクエストのヘッダー部分
+#[derive(Debug)]
+pub struct RequestHead {
+    pub method: String,
+    pub target: String,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl RequestHead {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// ボディの区切り方
+    pub fn body_framing(&self) -> Result<BodyFraming, String> {
+        if let Some(te) = self.header("Transfer-Encoding") {
+            if te.eq_ignore_ascii_case("chunked") {
+                return Ok(BodyFraming::Chunked);
+            }
+            return Err(format!("unsupported Transfer-Encoding: {}", te));
+        }
+        match self.header("Content-Length") {
+            Some(len) => len
+                .trim()
+                .parse()
+                .map(BodyFraming::Length)
+                .map_err(|_| format!("invalid Content-Length: {}", len)),
+            None => Ok(BodyFraming::Length(0)),
+        }
+    }
+
+    /// 接続の乗っ取り (attach / exec start の双方向ストリーム、BuildKit の h2c) を伴うか。
+    /// それ以外のエンドポイントの Upgrade は上流に渡さない
+    fn is_upgrade(&self) -> bool {
+        if self.header("Upgrade").is_none() || !self.method.eq_ignore_ascii_case("POST") {
+            return false;
+        }
+        let (path, _) = engine_api::split_target(&self.target);
+        let segments: Vec<&str> = engine_api::strip_version(path)
+            .trim_matches('/')
+            .split('/')
+            .collect();
+        matches!(
+            segments.as_slice(),
+            ["containers", _, "attach"] | ["exec", _, "start"] | ["session"] | ["grpc"]
+        )
+    }
+}
+
+/// リクエストボディの区切り方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFraming {
+    Length(usize),
+    Chunked,
+}
+
+/// proxy サブコマンドのエントリポイント。args は "proxy" 以降の引数。
+pub fn run(args: &[String], config: Config, config_source: String) -> i32 {
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        print_proxy_help();
+        return 0;
+    }
+    let listen = match option_value(args, "--listen") {
+        Ok(v) => expand_tilde(v.as_deref().unwrap_or(DEFAULT_LISTEN)),
+        Err(e) => {
+            eprintln!("[safe-docker] ERROR: {}", e);
+            return 1;
+        }
+    };
+    let upstream = match option_value(args, "--upstream") {
+        Ok(v) => expand_tilde(v.as_deref().unwrap_or(DEFAULT_UPSTREAM)),
+        Err(e) => {
+            eprintln!("[safe-docker] ERROR: {}", e);
+            return 1;
+        }
+    };
+    if listen == upstream {
+        eprintln!("[safe-docker] ERROR: --listen and --upstream must be different sockets");
+        return 1;
+    }
+
+    let listener = match bind(&listen) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!(
+                "[safe-docker] ERROR: Cannot listen on {}: {}",
+                listen.display(),
+                e
+            );
+            return 1;
+        }
+    };
+    eprintln!(
+        "[safe-docker] Proxy listening on {} (upstream: {})",
+        listen.display(),
+        upstream.display()
+    );
+    eprintln!(
+        "[safe-docker] Use: export DOCKER_HOST=unix://{}",
+        listen.display()
+    );
+
+    let proxy = Arc::new(Proxy {
+        inspector: engine_api::Inspector::new(config, config_source, "proxy"),
+        upstream,
+    });
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let proxy = Arc::clone(&proxy);
+                thread::spawn(move || {
+                    if let Err(e) = proxy.handle_connection(stream) {
+                        log::debug!("proxy connection error: {}", e);
+                    }
+                });
+            }
+            Err(e) => log::warn!("proxy accept error: {}", e),
+        }
+    }
+    0
+}
+
+fn print_proxy_help() {
+    eprintln!("USAGE:");
+    eprintln!("  safe-docker proxy [--listen PATH] [--upstream PATH]");
+    eprintln!();
+    eprintln!("OPTIONS:");
+    eprintln!(
+        "  --listen PATH     Socket to listen on (default: {})",
+        DEFAULT_LISTEN
+    );
+    eprintln!(
+        "  --upstream PATH   Docker daemon socket (default: {})",
+        DEFAULT_UPSTREAM
+    );
+}
+
+/// `--key value` / `--key=value` の値を取得する
+pub fn option_value(args: &[String], key: &str) -> Result<Option<String>, String> {
+    let prefix = format!("{}=", key);
+    for (i, arg) in args.iter().enumerate() {
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Ok(Some(value.to_string()));
+        }
+        if arg == key {
+            return match args.get(i + 1) {
+                Some(value) if !value.starts_with('-') => Ok(Some(value.clone())),
+                _ => Err(format!("{} requires a socket path", key)),
+            };
+        }
+    }
+    Ok(None)
+}
+
+/// `~/` をホームディレクトリに展開する
+pub fn expand_tilde(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(rest);
+    }
+    PathBuf::from(path)
+}
+
+/// 待ち受けソケットを作成する。古いソケットファイルは置き換え、所有者のみアクセス可能にする。
+pub fn bind(path: &Path) -> io::Result<UnixListener> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+        && !parent.exists()
+    {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(parent)?;
+    }
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "path exists and is not a socket",
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+impl Proxy {
+    fn handle_connection(&self, client: UnixStream) -> io::Result<()> {
+        let mut writer = client.try_clone()?;
+        let mut reader = BufReader::new(client);
+
+        let head = match read_head(&mut reader) {
+            Ok(Some(head)) => head,
+            Ok(None) => return Ok(()),
+            Err(e) => return write_error(&mut writer, 400, &format!("[safe-docker] {}", e)),
+        };
+        let framing = match head.body_framing() {
+            Ok(f) => f,
+            Err(e) => return write_error(&mut writer, 400, &format!("[safe-docker] {}", e)),
+        };
+
+        // 検査にボディが必要なリクエストと、後続のデータを確認する Upgrade リクエストは全体を読み込む
+        let body = if engine_api::needs_body(&head.method, &head.target) || head.is_upgrade() {
+            match read_body(&mut reader, framing, MAX_BODY_BYTES) {
+                Ok(body) => Some(body),
+                Err(e) => {
+                    return write_error(&mut writer, 400, &format!("[safe-docker] {}", e));
+                }
+            }
+        } else {
+            None
+        };
+
+        if let Some(reason) = self
+            .inspector
+            .check(&head.method, &head.target, body.as_deref())
+        {
+            return write_error(&mut writer, 403, &reason);
+        }
+
+        // Upgrade リクエストに続けて送られたデータは、検査していない後続のリクエストの可能性がある
+        if head.is_upgrade() && !reader.buffer().is_empty() {
+            return write_error(
+                &mut writer,
+                400,
+                "[safe-docker] unexpected data after an upgrade request",
+            );
+        }
+
+        let upstream = match UnixStream::connect(&self.upstream) {
+            Ok(s) => s,
+            Err(e) => {
+                return write_error(
+                    &mut writer,
+                    502,
+                    &format!(
+                        "[safe-docker] Cannot connect to upstream {}: {}",
+                        self.upstream.display(),
+                        e
+                    ),
+                );
+            }
+        };
+        let mut upstream_writer = upstream.try_clone()?;
+        upstream_writer.write_all(&forward_head(&head, body.as_ref().map(Vec::len)))?;
+        match &body {
+            Some(body) => upstream_writer.write_all(body)?,
+            None => copy_body(&mut reader, &mut upstream_writer, framing)?,
+        }
+        upstream_writer.flush()?;
+
+        let mut upstream_reader = BufReader::new(upstream);
+        // attach / exec start / BuildKit: 上流が 101 を返して接続を切り替えた後だけ、
+        // クライアントからの入力をそのまま中継する (上流はもう HTTP として解釈しない)
+        if head.is_upgrade() {
+            let response = read_response_head(&mut upstream_reader)?;
+            writer.write_all(&response)?;
+            if response_status(&response) == Some(101) {
+                thread::spawn(move || {
+                    let _ = io::copy(&mut reader, &mut upstream_writer);
+                    let _ = upstream_writer.shutdown(std::net::Shutdown::Write);
+                });
+            }
+        }
+        io::copy(&mut upstream_reader, &mut writer)?;
+        writer.shutdown(std::net::Shutdown::Both)
+    }
+}
+
+/// リクエストヘッダーを読み込む。ヘッダー前に接続が閉じられた場合は None。
+pub fn read_head<R: BufRead>(reader: &mut R) -> Result<Option<RequestHead>, String> {
+    let mut lines = Vec::new();
+    let mut total = 0;
+    loop {
+        let mut line = Vec::new();
+        let n = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| format!("failed to read request: {}", e))?;
+        if n == 0 {
+            if lines.is_empty() {
+                return Ok(None);
+            }
+            return Err("connection closed in request header".to_string());
+        }
+        total += n;
+        if total > MAX_HEAD_BYTES {
+            return Err("request header too large".to_string());
+        }
+        let line = String::from_utf8(line).map_err(|_| "invalid request header".to_string())?;
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            if lines.is_empty() {
+                continue;
+            }
+            break;
+        }
+        lines.push(line.to_string());
+    }
+
+    let mut request_line = lines[0].split(' ');
+    let (Some(method), Some(target), Some(version), None) = (
+        request_line.next(),
+        request_line.next(),
+        request_line.next(),
+        request_line.next(),
+    ) else {
+        return Err(format!("invalid request line: {}", lines[0]));
+    };
+    let headers = lines[1..]
+        .iter()
+        .map(|line| {
+            line.split_once(':')
+                .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                .ok_or_else(|| format!("invalid header line: {}", line))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Some(RequestHead {
+        method: method.to_string(),
+        target: target.to_string(),
+        version: version.to_string(),
+        headers,
+    }))
+}
+
+/// 上流の応答ヘッダーを (そのまま転送できるよう) バイト列で読み込む
+fn read_response_head<R: BufRead>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    loop {
+        let start = head.len();
+        if reader.read_until(b'\n', &mut head)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "upstream closed in response header",
+            ));
+        }
+        if head.len() > MAX_HEAD_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "upstream response header too large",
+            ));
+        }
+        if matches!(&head[start..], b"\r\n" | b"\n") {
+            return Ok(head);
+        }
+    }
+}
+
+/// 応答ヘッダーのステータスコード
+fn response_status(head: &[u8]) -> Option<u16> {
+    let line = head.split(|&b| b == b'\n').next()?;
+    std::str::from_utf8(line)
+        .ok()?
+        .split(' ')
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+/// チャンクサイズ行を読む
+fn read_chunk_size<R: BufRead>(reader: &mut R, line: &mut String) -> io::Result<usize> {
+    line.clear();
+    if reader.read_line(line)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed in chunked body",
+        ));
+    }
+    let size = line.trim_end().split(';').next().unwrap_or("").trim();
+    usize::from_str_radix(size, 16)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid chunk size"))
+}
+
+/// チャンク末尾のトレーラーを読み飛ばす (空行まで)
+fn skip_trailers<R: BufRead>(reader: &mut R, mut out: Option<&mut dyn Write>) -> io::Result<()> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if let Some(out) = out.as_deref_mut() {
+            out.write_all(line.as_bytes())?;
+        }
+        if line.trim_end().is_empty() {
+            return Ok(());
+        }
+    }
+}
+
+/// ボディ全体を読み込む (chunked はデコードする)
+pub fn read_body<R: BufRead>(
+    reader: &mut R,
+    framing: BodyFraming,
+    limit: usize,
+) -> Result<Vec<u8>, String> {
+    let too_large = || format!("request body exceeds {} bytes", limit);
+    let mut body = Vec::new();
+    match framing {
+        BodyFraming::Length(len) => {
+            if len > limit {
+                return Err(too_large());
+            }
+            body.resize(len, 0);
+            reader
+                .read_exact(&mut body)
+                .map_err(|e| format!("failed to read request body: {}", e))?;
+        }
+        BodyFraming::Chunked => {
+            let mut line = String::new();
+            loop {
+                let size = read_chunk_size(reader, &mut line).map_err(|e| e.to_string())?;
+                if size == 0 {
+                    skip_trailers(reader, None).map_err(|e| e.to_string())?;
+                    break;
+                }
+                if body.len() + size > limit {
+                    return Err(too_large());
+                }
+                let start = body.len();
+                body.resize(start + size, 0);
+                reader
+                    .read_exact(&mut body[start..])
+                    .map_err(|e| format!("failed to read request body: {}", e))?;
+                let mut crlf = [0u8; 2];
+                reader
+                    .read_exact(&mut crlf)
+                    .map_err(|e| format!("failed to read request body: {}", e))?;
+            }
+        }
+    }
+    Ok(body)
+}
+
+/// ボディをそのままの形式で転送する。ボディの終わりで止まり、後続のデータは読まない。
+pub fn copy_body<R: BufRead, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    framing: BodyFraming,
+) -> io::Result<()> {
+    match framing {
+        BodyFraming::Length(len) => {
+            let copied = io::copy(&mut reader.take(len as u64), writer)?;
+            if copied < len as u64 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "connection closed in request body",
+                ));
+            }
+        }
+        BodyFraming::Chunked => {
+            let mut line = String::new();
+            loop {
+                let size = read_chunk_size(reader, &mut line)?;
+                writer.write_all(line.as_bytes())?;
+                if size == 0 {
+                    skip_trailers(reader, Some(writer))?;
+                    break;
+                }
+                // チャンク本体 + CRLF
+                let chunk = size as u64 + 2;
+                if io::copy(&mut reader.take(chunk), writer)? < chunk {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "connection closed in chunked body",
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 上流に送るリクエストヘッダーを組み立てる。
+/// 接続の再利用を止め (`Connection: close`)、読み込み済みのボディは Content-Length で送る。
+pub fn forward_head(head: &RequestHead, body_len: Option<usize>) -> Vec<u8> {
+    let upgrade = head.is_upgrade();
+    let mut out = format!("{} {} {}\r\n", head.method, head.target, head.version);
+    for (name, value) in &head.headers {
+        let hop_by_hop = name.eq_ignore_ascii_case("Keep-Alive")
+            || ((name.eq_ignore_ascii_case("Connection") || name.eq_ignore_ascii_case("Upgrade"))
+                && !upgrade);
+        let reframed = body_len.is_some()
+            && (name.eq_ignore_ascii_case("Content-Length")
+                || name.eq_ignore_ascii_case("Transfer-Encoding"));
+        if hop_by_hop || reframed {
+            continue;
+        }
+        out.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if let Some(len) = body_len {
+        out.push_str(&format!("Content-Length: {}\r\n", len));
+    }
+    if !upgrade {
+        out.push_str("Connection: close\r\n");
+    }
+    out.push_str("\r\n");
+    out.into_bytes()
+}
+
+/// Docker Engine 形式のエラー応答を返して接続を閉じる
+fn write_error(writer: &mut UnixStream, status: u16, message: &str) -> io::Result<()> {
+    let reason = match status {
+        400 => "Bad Request",
+        403 => "Forbidden",
+        _ => "Bad Gateway",
+    };
+    let body = serde_json::json!({ "message": message }).to_string();
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    writer.flush()?;
+    writer.shutdown(std::net::Shutdown::Both)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head(raw: &str) -> RequestHead {
+        read_head(&mut raw.as_bytes()).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_read_head() {
+        let h = head(
+            "POST /v1.45/containers/create?name=web HTTP/1.1\r\nHost: docker\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}",
+        );
+        assert_eq!(h.method, "POST");
+        assert_eq!(h.target, "/v1.45/containers/create?name=web");
+        assert_eq!(h.header("content-length"), Some("2"));
+        assert_eq!(h.body_framing(), Ok(BodyFraming::Length(2)));
+    }
+
+    #[test]
+    fn test_read_head_errors() {
+        assert!(read_head(&mut "".as_bytes()).unwrap().is_none());
+        assert!(read_head(&mut "GET /\r\n\r\n".as_bytes()).is_err());
+        assert!(read_head(&mut "GET / HTTP/1.1\r\nbroken\r\n\r\n".as_bytes()).is_err());
+        let h = head("POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n");
+        assert!(h.body_framing().is_err());
+    }
+
+    #[test]
+    fn test_chunked_body() {
+        let raw = "4\r\n{\"Im\r\n3;ext=1\r\nage\r\n0\r\nX-Trailer: 1\r\n\r\nGET /next HTTP/1.1\r\n";
+
+        let mut reader = raw.as_bytes();
+        let body = read_body(&mut reader, BodyFraming::Chunked, 1024).unwrap();
+        assert_eq!(body, b"{\"Image");
+        // ボディの後ろ (次のリクエスト) は読まない
+        assert_eq!(reader, b"GET /next HTTP/1.1\r\n");
+
+        let mut reader = raw.as_bytes();
+        let mut out = Vec::new();
+        copy_body(&mut reader, &mut out, BodyFraming::Chunked).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "4\r\n{\"Im\r\n3;ext=1\r\nage\r\n0\r\nX-Trailer: 1\r\n\r\n"
+        );
+        assert_eq!(reader, b"GET /next HTTP/1.1\r\n");
+    }
+
+    #[test]
+    fn test_body_limit() {
+        let mut reader = "abcdef".as_bytes();
+        assert!(read_body(&mut reader, BodyFraming::Length(6), 4).is_err());
+        let mut reader = "6\r\nabcdef\r\n0\r\n\r\n".as_bytes();
+        assert!(read_body(&mut reader, BodyFraming::Chunked, 4).is_err());
+    }
+
+    #[test]
+    fn test_forward_head_reframes_body() {
+        let h = head(
+            "POST /containers/create HTTP/1.1\r\nHost: docker\r\nTransfer-Encoding: chunked\r\nConnection: keep-alive\r\n\r\n",
+        );
+        let out = String::from_utf8(forward_head(&h, Some(15))).unwrap();
+        assert_eq!(
+            out,
+            "POST /containers/create HTTP/1.1\r\nHost: docker\r\nContent-Length: 15\r\nConnection: close\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_forward_head_keeps_upgrade() {
+        let h = head(
+            "POST /exec/abc/start HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: tcp\r\nContent-Length: 2\r\n\r\n",
+        );
+        let out = String::from_utf8(forward_head(&h, None)).unwrap();
+        assert!(out.contains("Connection: Upgrade\r\n"));
+        assert!(out.contains("Content-Length: 2\r\n"));
+        assert!(!out.contains("Connection: close"));
+    }
+
+    #[test]
+    fn test_forward_head_drops_upgrade_of_other_endpoints() {
+        let h = head("GET /_ping HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: tcp\r\n\r\n");
+        assert!(!h.is_upgrade());
+        let out = String::from_utf8(forward_head(&h, None)).unwrap();
+        assert_eq!(out, "GET /_ping HTTP/1.1\r\nConnection: close\r\n\r\n");
+        assert!(
+            head("POST /v1.45/containers/web/attach?stream=1 HTTP/1.1\r\nUpgrade: tcp\r\n\r\n")
+                .is_upgrade()
+        );
+        assert!(
+            head("POST /session HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: h2c\r\n\r\n")
+                .is_upgrade()
+        );
+        assert!(
+            head("POST /v1.45/grpc HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: h2c\r\n\r\n")
+                .is_upgrade()
+        );
+    }
+
+    #[test]
+    fn test_read_response_head() {
+        let mut reader = "HTTP/1.1 101 UPGRADED\r\nUpgrade: tcp\r\n\r\nraw".as_bytes();
+        let response = read_response_head(&mut reader).unwrap();
+        assert_eq!(response, b"HTTP/1.1 101 UPGRADED\r\nUpgrade: tcp\r\n\r\n");
+        assert_eq!(response_status(&response), Some(101));
+        assert_eq!(reader, b"raw");
+        assert!(read_response_head(&mut "HTTP/1.1 200 OK\r\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_option_value() {
+        let args: Vec<String> = ["--listen", "/tmp/a.sock", "--upstream=/tmp/b.sock"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            option_value(&args, "--listen").unwrap().as_deref(),
+            Some("/tmp/a.sock")
+        );
+        assert_eq!(
+            option_value(&args, "--upstream").unwrap().as_deref(),
+            Some("/tmp/b.sock")
+        );
+        let missing = vec!["--listen".to_string()];
+        assert!(option_value(&missing, "--listen").is_err());
+    }
+}
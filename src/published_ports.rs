@@ -0,0 +1,377 @@
+//! 公開ポートのポリシー (`[published_ports]`)
+//!
+//! `-p` / `--publish`、`-P` / `--publish-all`、compose の `ports:` で公開されるホストポートを検査する。
+//!
+//! - アドレスを省略した公開は全インターフェース (0.0.0.0 / [::]) で待ち受ける
+//! - ホストポートを省略した公開 (`-p 80`、`-p 127.0.0.1::80`、`-P`) は Docker がエフェメラルポートを選ぶ
+
+use std::net::IpAddr;
+
+use crate::compose::ComposeAnalysis;
+use crate::config::{PinningAction, PublishedPortsConfig};
+use crate::docker_args::{DockerCommand, DockerSubcommand};
+
+/// 特権ポートの上限 (これ未満のポートは root 権限が必要)
+const PRIVILEGED_PORT_LIMIT: u16 = 1024;
+
+/// 判定する公開ポートと、その指定元
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortUse {
+    /// 書かれているとおりの指定 (`-P` は `--publish-all`)
+    pub spec: String,
+    /// 指定元 (`docker run`, `compose service 'db'` 等)
+    pub origin: String,
+    /// 解析結果 (解析できない場合は理由)
+    pub port: Result<PublishedPort, String>,
+}
+
+/// 公開ポートの解析結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishedPort {
+    /// 待ち受けるアドレス (None は全インターフェース)
+    pub host_ip: Option<IpAddr>,
+    /// ホストポートの範囲 (None は Docker が選ぶエフェメラルポート)
+    pub host_ports: Option<(u16, u16)>,
+}
+
+/// 公開ポートのポリシー違反
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub action: PinningAction,
+    pub reason: String,
+}
+
+/// docker run / create と docker compose run で公開するポート
+pub fn command_ports(cmd: &DockerCommand) -> Vec<PortUse> {
+    let origin = format!("docker {}", cmd.subcommand);
+    let mut uses: Vec<PortUse> = cmd
+        .ports
+        .iter()
+        .map(|spec| PortUse {
+            spec: spec.clone(),
+            origin: origin.clone(),
+            port: parse(spec),
+        })
+        .collect();
+    if cmd.publish_all
+        && matches!(
+            cmd.subcommand,
+            DockerSubcommand::Run | DockerSubcommand::Create
+        )
+    {
+        uses.push(PortUse {
+            spec: "--publish-all".to_string(),
+            origin,
+            port: Ok(PublishedPort {
+                host_ip: None,
+                host_ports: None,
+            }),
+        });
+    }
+    uses
+}
+
+/// compose のサービスの ports
+pub fn compose_ports(analysis: &ComposeAnalysis) -> Vec<PortUse> {
+    analysis
+        .ports
+        .iter()
+        .map(|(service, spec)| PortUse {
+            spec: spec.clone(),
+            origin: format!("compose service '{}'", service),
+            port: parse(spec),
+        })
+        .collect()
+}
+
+/// `-p` の値を解析する (`[ip:][hostPort:]containerPort[/protocol]`、ポートは範囲も可)
+pub fn parse(spec: &str) -> Result<PublishedPort, String> {
+    let (rest, protocol) = spec.rsplit_once('/').unwrap_or((spec, "tcp"));
+    if !matches!(protocol, "tcp" | "udp" | "sctp") {
+        return Err(format!("unknown protocol '{}'", protocol));
+    }
+
+    // docker と同じく、末尾の 2 要素をポート、残りをアドレスとして扱う (IPv6 は [] 省略可)
+    let parts: Vec<&str> = rest.split(':').collect();
+    let (ip, host, container) = match parts.as_slice() {
+        [container] => ("", "", *container),
+        [host, container] => ("", *host, *container),
+        [ip @ .., host, container] => (&rest[..ip.join(":").len()], *host, *container),
+        [] => unreachable!("split returns at least one element"),
+    };
+
+    parse_port_range(container)?;
+    let host_ip = match ip.trim_start_matches('[').trim_end_matches(']') {
+        "" => None,
+        ip => Some(
+            ip.parse::<IpAddr>()
+                .map_err(|_| format!("invalid host address '{}'", ip))?,
+        ),
+    };
+    let host_ports = match host {
+        "" | "0" => None,
+        host => Some(parse_port_range(host)?),
+    };
+    Ok(PublishedPort {
+        host_ip,
+        host_ports,
+    })
+}
+
+/// ポート番号 (`8080`) または範囲 (`8000-8999`) を解析する
+pub fn parse_port_range(value: &str) -> Result<(u16, u16), String> {
+    let (start, end) = value.split_once('-').unwrap_or((value, value));
+    let port = |s: &str| s.parse::<u16>().ok().filter(|&p| p > 0);
+    match (port(start), port(end)) {
+        (Some(start), Some(end)) if start <= end => Ok((start, end)),
+        _ => Err(format!("'{}' is not a port or port range", value)),
+    }
+}
+
+/// 公開ポートのポリシーで検査する
+pub fn check(uses: &[PortUse], config: &PublishedPortsConfig) -> Vec<Finding> {
+    let allowed: Vec<(u16, u16)> = config
+        .allowed_host_ports
+        .iter()
+        .filter_map(|range| parse_port_range(range).ok())
+        .collect();
+    let mut findings = Vec::new();
+    for use_ in uses {
+        let port = match &use_.port {
+            Ok(port) => port,
+            Err(e) => {
+                findings.push(Finding {
+                    action: PinningAction::Ask,
+                    reason: format!(
+                        "Published port {} ({}) cannot be checked: {}",
+                        use_.spec, use_.origin, e
+                    ),
+                });
+                continue;
+            }
+        };
+
+        match port.host_ip {
+            None => findings.push(Finding {
+                action: config.non_loopback,
+                reason: format!(
+                    "Published port {} ({}) is bound on all interfaces",
+                    use_.spec, use_.origin
+                ),
+            }),
+            Some(ip) if !ip.is_loopback() => findings.push(Finding {
+                action: config.non_loopback,
+                reason: format!(
+                    "Published port {} ({}) is bound on {}, which is not a loopback address",
+                    use_.spec, use_.origin, ip
+                ),
+            }),
+            Some(_) => {}
+        }
+
+        if let Some((start, end)) = port.host_ports
+            && start < PRIVILEGED_PORT_LIMIT
+        {
+            findings.push(Finding {
+                action: config.privileged,
+                reason: format!(
+                    "Published port {} ({}) uses privileged host port {}",
+                    use_.spec,
+                    use_.origin,
+                    format_range(start, end.min(PRIVILEGED_PORT_LIMIT - 1))
+                ),
+            });
+        }
+
+        if !config.allowed_host_ports.is_empty() {
+            match port.host_ports {
+                None => findings.push(Finding {
+                    action: config.outside_allowed,
+                    reason: format!(
+                        "Published port {} ({}) uses a host port chosen by Docker, which may be outside published_ports.allowed_host_ports",
+                        use_.spec, use_.origin
+                    ),
+                }),
+                Some((start, end))
+                    if !allowed.iter().any(|&(lo, hi)| lo <= start && end <= hi) =>
+                {
+                    findings.push(Finding {
+                        action: config.outside_allowed,
+                        reason: format!(
+                            "Published port {} ({}) uses host port {} outside published_ports.allowed_host_ports",
+                            use_.spec,
+                            use_.origin,
+                            format_range(start, end)
+                        ),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    findings.retain(|f| f.action != PinningAction::Allow);
+    findings
+}
+
+fn format_range(start: u16, end: u16) -> String {
+    if start == end {
+        start.to_string()
+    } else {
+        format!("{}-{}", start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port(ip: Option<&str>, host_ports: Option<(u16, u16)>) -> PublishedPort {
+        PublishedPort {
+            host_ip: ip.map(|ip| ip.parse().unwrap()),
+            host_ports,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("80"), Ok(port(None, None)));
+        assert_eq!(parse("8080:80"), Ok(port(None, Some((8080, 8080)))));
+        assert_eq!(parse("5432:5432/tcp"), Ok(port(None, Some((5432, 5432)))));
+        assert_eq!(
+            parse("127.0.0.1:8080:80/udp"),
+            Ok(port(Some("127.0.0.1"), Some((8080, 8080))))
+        );
+        assert_eq!(parse("127.0.0.1::80"), Ok(port(Some("127.0.0.1"), None)));
+        assert_eq!(
+            parse("8000-8010:8000-8010"),
+            Ok(port(None, Some((8000, 8010))))
+        );
+        assert_eq!(parse("0:80"), Ok(port(None, None)));
+        assert_eq!(
+            parse("[::1]:8080:80"),
+            Ok(port(Some("::1"), Some((8080, 8080))))
+        );
+        assert_eq!(
+            parse("::1:8080:80"),
+            Ok(port(Some("::1"), Some((8080, 8080))))
+        );
+        assert_eq!(parse("[::]::80/sctp"), Ok(port(Some("::"), None)));
+
+        assert!(parse("8080:80/icmp").is_err());
+        assert!(parse("localhost:8080:80").is_err());
+        assert!(parse("8080:").is_err());
+        assert!(parse("70000:80").is_err());
+        assert!(parse("8010-8000:80").is_err());
+        assert!(parse("${PORT}:80").is_err());
+    }
+
+    fn check_specs(specs: &[&str], config: &PublishedPortsConfig) -> Vec<Finding> {
+        let uses: Vec<PortUse> = specs
+            .iter()
+            .map(|spec| PortUse {
+                spec: spec.to_string(),
+                origin: "docker run".to_string(),
+                port: parse(spec),
+            })
+            .collect();
+        check(&uses, config)
+    }
+
+    #[test]
+    fn test_check_non_loopback() {
+        let config = PublishedPortsConfig {
+            non_loopback: PinningAction::Deny,
+            ..Default::default()
+        };
+        let findings = check_specs(
+            &[
+                "5432:5432",
+                "192.168.1.10:8080:80",
+                "127.0.0.1:8080:80",
+                "[::1]::80",
+            ],
+            &config,
+        );
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].action, PinningAction::Deny);
+        assert_eq!(
+            findings[0].reason,
+            "Published port 5432:5432 (docker run) is bound on all interfaces"
+        );
+        assert!(
+            findings[1]
+                .reason
+                .contains("192.168.1.10, which is not a loopback")
+        );
+
+        // 既定ではどれも検出しない
+        assert!(check_specs(&["5432:5432", "80:80"], &PublishedPortsConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_check_privileged_and_ranges() {
+        let config = PublishedPortsConfig {
+            privileged: PinningAction::Deny,
+            allowed_host_ports: vec!["8000-8999".to_string(), "3000".to_string()],
+            ..Default::default()
+        };
+        let findings = check_specs(
+            &[
+                "127.0.0.1:80:80",
+                "127.0.0.1:1000-1030:1000-1030",
+                "127.0.0.1:8080:80",
+                "127.0.0.1:3000:3000",
+                "127.0.0.1:8990-9010:80",
+                "127.0.0.1::80",
+                "bogus",
+            ],
+            &config,
+        );
+        let reasons: Vec<(PinningAction, &str)> = findings
+            .iter()
+            .map(|f| (f.action, f.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (
+                    PinningAction::Deny,
+                    "Published port 127.0.0.1:80:80 (docker run) uses privileged host port 80"
+                ),
+                (
+                    PinningAction::Ask,
+                    "Published port 127.0.0.1:80:80 (docker run) uses host port 80 outside published_ports.allowed_host_ports"
+                ),
+                (
+                    PinningAction::Deny,
+                    "Published port 127.0.0.1:1000-1030:1000-1030 (docker run) uses privileged host port 1000-1023"
+                ),
+                (
+                    PinningAction::Ask,
+                    "Published port 127.0.0.1:1000-1030:1000-1030 (docker run) uses host port 1000-1030 outside published_ports.allowed_host_ports"
+                ),
+                (
+                    PinningAction::Ask,
+                    "Published port 127.0.0.1:8990-9010:80 (docker run) uses host port 8990-9010 outside published_ports.allowed_host_ports"
+                ),
+                (
+                    PinningAction::Ask,
+                    "Published port 127.0.0.1::80 (docker run) uses a host port chosen by Docker, which may be outside published_ports.allowed_host_ports"
+                ),
+                (
+                    PinningAction::Ask,
+                    "Published port bogus (docker run) cannot be checked: 'bogus' is not a port or port range"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_command_ports_publish_all() {
+        let cmd = crate::docker_args::parse_docker_args(&["run", "-dP", "-p", "8080:80", "nginx"]);
+        let uses = command_ports(&cmd);
+        assert_eq!(uses.len(), 2);
+        assert_eq!(uses[1].spec, "--publish-all");
+        assert_eq!(uses[1].port, Ok(port(None, None)));
+    }
+}
@@ -0,0 +1,778 @@
+//! OPA Docker AuthZ 用 Rego ポリシーの生成 (`safe-docker export-rego`)
+//!
+//! 有効な `Config` から `authz.rego` を組み立てる。capability や許可パスを手で同期する代わりに、
+//! 設定を変更したら再生成する。OPA は確認 (ask) を求められないため、safe-docker で ask になる
+//! ルール (機密パス、イメージホワイトリスト等) は deny として出力する。
+
+use std::fmt::Write as _;
+
+use crate::config::{AccessMode, Config};
+use crate::policy::DANGEROUS_SECURITY_OPTS;
+use crate::{path_pattern, path_validator};
+
+/// リポジトリ同梱の `opa/authz.rego` で使うホームディレクトリのプレースホルダー
+pub const HOME_PLACEHOLDER: &str = "/home/username";
+
+/// safe-docker が deny する名前空間の共有 (`--pid=host` 等)
+const HOST_NAMESPACE_FIELDS: &[&str] = &[
+    "PidMode",
+    "NetworkMode",
+    "IpcMode",
+    "UTSMode",
+    "CgroupnsMode",
+    "UsernsMode",
+];
+
+/// safe-docker が deny するコンテナ間の名前空間共有 (`--network=container:NAME` 等)
+const CONTAINER_NAMESPACE_FIELDS: &[&str] = &["PidMode", "NetworkMode", "IpcMode"];
+
+/// Rego の文字列リテラル
+fn quote(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// 複数行の集合リテラル (インデント付き)
+fn set_literal(items: &[String], indent: &str) -> String {
+    let mut out = String::from("{\n");
+    for item in items {
+        let _ = writeln!(out, "{}    {},", indent, quote(item));
+    }
+    out.push_str(indent);
+    out.push('}');
+    out
+}
+
+/// パターン先頭の `~` / `$HOME` / `${HOME}` を home に置き換える
+fn expand_home(pattern: &str, home: &str) -> String {
+    if pattern == "~" {
+        return home.to_string();
+    }
+    for prefix in ["~/", "$HOME/", "${HOME}/"] {
+        if let Some(rest) = pattern.strip_prefix(prefix) {
+            return format!("{}/{}", home, rest);
+        }
+    }
+    pattern.to_string()
+}
+
+/// パスパターン (配下のパスも一致) を OPA の glob.match 用のパターンに展開する。
+/// glob.match の `**` は 0 個のパス要素に一致しないため、`/**/` を `/` にしたものも加える
+fn glob_variants(pattern: &str) -> Vec<String> {
+    let base = pattern.trim_end_matches('/');
+    let mut variants = Vec::new();
+    for base in [base.to_string(), base.replace("/**/", "/")] {
+        for variant in [base.clone(), format!("{}/**", base)] {
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+    }
+    variants
+}
+
+/// 設定から Rego ポリシーを生成する。home はバインドマウントを許可するホームディレクトリ。
+pub fn render(config: &Config, home: &str) -> String {
+    let home = home.trim_end_matches('/');
+    let home_prefix = format!("{}/", home);
+    let mut out = String::new();
+
+    out.push_str(
+        "# safe-docker export-rego で生成したポリシー (手で編集せず、設定を変更して再生成すること)\n",
+    );
+    let _ = writeln!(
+        out,
+        "# 再生成: safe-docker export-rego --home {} > authz.rego",
+        home
+    );
+    out.push_str(
+        r#"
+package docker.authz
+
+import rego.v1
+
+default allow := false
+
+# docker plugin 操作は常に許可（ロックアウト防止）
+allow if {
+    input.Path == "/Plugin.Disable"
+}
+
+allow if {
+    input.Path == "/Plugin.Enable"
+}
+
+# deny ルールに引っかからなければ許可
+allow if {
+    not deny
+}
+
+# --privileged をブロック
+deny if {
+    input.Body.HostConfig.Privileged == true
+}
+"#,
+    );
+
+    if !config.blocked_capabilities.is_empty() {
+        let mut caps: Vec<String> = config
+            .blocked_capabilities
+            .iter()
+            .map(|c| c.to_uppercase())
+            .collect();
+        caps.dedup();
+        let _ = write!(
+            out,
+            r#"
+# 危険な capability をブロック (blocked_capabilities)
+deny if {{
+    cap := upper(input.Body.HostConfig.CapAdd[_])
+    cap in {}
+}}
+"#,
+            set_literal(&caps, "    ")
+        );
+    }
+
+    out.push_str("\n# ホストの名前空間へのアクセスをブロック\n");
+    for field in HOST_NAMESPACE_FIELDS {
+        let _ = write!(
+            out,
+            "deny if {{\n    input.Body.HostConfig.{} == \"host\"\n}}\n\n",
+            field
+        );
+    }
+    out.push_str("# 他のコンテナとの名前空間の共有をブロック\n");
+    for field in CONTAINER_NAMESPACE_FIELDS {
+        let _ = write!(
+            out,
+            "deny if {{\n    startswith(input.Body.HostConfig.{}, \"container:\")\n}}\n\n",
+            field
+        );
+    }
+
+    out.push_str(
+        r#"# デバイスアクセスをブロック
+deny if {
+    count(input.Body.HostConfig.Devices) > 0
+}
+
+# マウント伝搬 (shared / rshared) をブロック
+deny if {
+    mount := input.Body.HostConfig.Mounts[_]
+    mount.BindOptions.Propagation in {"shared", "rshared"}
+}
+
+# ボリュームのドライバオプション (device=) によるホストパスのマウントをブロック
+# (safe-docker はバインドマウントと同じくパスを検証する。OPA では一律に拒否する)
+deny if {
+    input.Body.DriverOpts.device
+}
+
+deny if {
+    mount := input.Body.HostConfig.Mounts[_]
+    mount.VolumeOptions.DriverConfig.Options.device
+}
+
+# 危険な security-opt をブロック
+"#,
+    );
+    for opt in DANGEROUS_SECURITY_OPTS {
+        let _ = write!(
+            out,
+            "deny if {{\n    opt := input.Body.HostConfig.SecurityOpt[_]\n    contains(opt, {})\n}}\n\n",
+            quote(opt)
+        );
+    }
+
+    out.push_str(
+        r#"# カーネルパラメータ (kernel.*) の変更をブロック
+deny if {
+    some key, _ in input.Body.HostConfig.Sysctls
+    startswith(key, "kernel.")
+}
+
+# 以下は safe-docker では確認 (ask) になる操作。OPA では確認できないため拒否する
+deny if {
+    some key, _ in input.Body.HostConfig.Sysctls
+    startswith(key, "net.")
+}
+
+deny if {
+    count(input.Body.HostConfig.VolumesFrom) > 0
+}
+
+deny if {
+    input.Body.HostConfig.CgroupParent != ""
+}
+
+deny if {
+    entry := input.Body.HostConfig.ExtraHosts[_]
+    contains(entry, "169.254.169.254")
+}
+"#,
+    );
+
+    // バインドマウント: $HOME 配下と allowed_paths のみ許可。
+    // OPA では一覧の順序による否定パターン (!) を再現しないため、安全側に倒す:
+    // allowed_paths の否定は拒否として扱い、sensitive_paths / denied_paths の否定は無視する
+    let mut prefixes = vec![home_prefix.clone()];
+    let mut globs = Vec::new();
+    let mut readonly_prefixes = Vec::new();
+    let mut readonly_globs = Vec::new();
+    let mut denied_globs = Vec::new();
+    for entry in &config.allowed_paths {
+        let (negated, pattern) = path_pattern::split_negation(entry.path());
+        let pattern = expand_home(pattern, home);
+        if negated {
+            denied_globs.extend(glob_variants(&pattern));
+            continue;
+        }
+        let readonly = entry.mode() == Some(AccessMode::Ro);
+        match (readonly, path_pattern::is_glob(&pattern)) {
+            (false, false) => prefixes.push(pattern),
+            (false, true) => globs.extend(glob_variants(&pattern)),
+            (true, false) => readonly_prefixes.push(pattern),
+            (true, true) => readonly_globs.extend(glob_variants(&pattern)),
+        }
+    }
+    // mode = "ro" の allowed_paths は読み取り専用のマウントだけ許可する
+    let readonly_condition = if readonly_prefixes.is_empty() && readonly_globs.is_empty() {
+        ""
+    } else {
+        "    not bind_readonly_allowed(bm)\n"
+    };
+    let _ = write!(
+        out,
+        r#"
+# バインドマウントを $HOME 配下と allowed_paths に制限
+allowed_bind_prefixes := {}
+
+bind_allowed(path) if {{
+    path == {}
+}}
+
+bind_allowed(path) if {{
+    some prefix in allowed_bind_prefixes
+    startswith(path, prefix)
+}}
+"#,
+        set_literal(&prefixes, ""),
+        quote(home),
+    );
+    if !globs.is_empty() {
+        let _ = write!(
+            out,
+            r#"
+allowed_bind_globs := {}
+
+bind_allowed(path) if {{
+    some pattern in allowed_bind_globs
+    glob.match(pattern, ["/"], path)
+}}
+"#,
+            set_literal(&globs, ""),
+        );
+    }
+    if !readonly_prefixes.is_empty() {
+        let _ = write!(
+            out,
+            r#"
+# 読み取り専用のマウントだけ許可するパス (allowed_paths の mode = "ro")
+readonly_bind_prefixes := {}
+
+bind_readonly_allowed(bm) if {{
+    bm.ReadOnly == true
+    some prefix in readonly_bind_prefixes
+    startswith(bm.Resolved, prefix)
+}}
+"#,
+            set_literal(&readonly_prefixes, ""),
+        );
+    }
+    if !readonly_globs.is_empty() {
+        let _ = write!(
+            out,
+            r#"
+readonly_bind_globs := {}
+
+bind_readonly_allowed(bm) if {{
+    bm.ReadOnly == true
+    some pattern in readonly_bind_globs
+    glob.match(pattern, ["/"], bm.Resolved)
+}}
+"#,
+            set_literal(&readonly_globs, ""),
+        );
+    }
+    let _ = write!(
+        out,
+        r#"
+deny if {{
+    bm := input.BindMounts[_]
+    resolved := bm.Resolved
+    resolved != ""
+    not bind_allowed(resolved)
+{}}}
+
+# Source が $HOME 配下でもリンク先が異なる場合を検出（パストラバーサル防止）
+deny if {{
+    bm := input.BindMounts[_]
+    startswith(bm.Source, {})
+    bm.Resolved != ""
+    not bind_allowed(bm.Resolved)
+{}}}
+"#,
+        readonly_condition,
+        quote(&home_prefix),
+        readonly_condition,
+    );
+
+    let mut sensitive = Vec::new();
+    let mut sensitive_globs = Vec::new();
+    let mut readonly_sensitive = Vec::new();
+    let mut readonly_sensitive_globs = Vec::new();
+    for entry in &config.sensitive_paths {
+        let (negated, pattern) = path_pattern::split_negation(entry.path());
+        if negated {
+            continue;
+        }
+        let path = format!("{}{}", home_prefix, pattern.trim_start_matches('/'));
+        let readonly = entry.mode() == Some(AccessMode::Ro);
+        match (readonly, path_pattern::is_glob(&path)) {
+            (false, false) => sensitive.push(path),
+            (false, true) => sensitive_globs.extend(glob_variants(&path)),
+            (true, false) => readonly_sensitive.push(path),
+            (true, true) => readonly_sensitive_globs.extend(glob_variants(&path)),
+        }
+    }
+    if !sensitive.is_empty() {
+        let _ = write!(
+            out,
+            r#"
+# 機密パス (sensitive_paths) のマウントを拒否
+sensitive_paths := {}
+
+deny if {{
+    bm := input.BindMounts[_]
+    some path in sensitive_paths
+    startswith(bm.Resolved, path)
+}}
+"#,
+            set_literal(&sensitive, "")
+        );
+    }
+    if !sensitive_globs.is_empty() {
+        let _ = write!(
+            out,
+            r#"
+sensitive_path_globs := {}
+
+deny if {{
+    bm := input.BindMounts[_]
+    some pattern in sensitive_path_globs
+    glob.match(pattern, ["/"], bm.Resolved)
+}}
+"#,
+            set_literal(&sensitive_globs, "")
+        );
+    }
+    if !readonly_sensitive.is_empty() {
+        let _ = write!(
+            out,
+            r#"
+# 読み取り専用なら許可する機密パス (sensitive_paths の mode = "ro")
+readonly_sensitive_paths := {}
+
+deny if {{
+    bm := input.BindMounts[_]
+    some path in readonly_sensitive_paths
+    startswith(bm.Resolved, path)
+    not bm.ReadOnly
+}}
+"#,
+            set_literal(&readonly_sensitive, "")
+        );
+    }
+    if !readonly_sensitive_globs.is_empty() {
+        let _ = write!(
+            out,
+            r#"
+readonly_sensitive_path_globs := {}
+
+deny if {{
+    bm := input.BindMounts[_]
+    some pattern in readonly_sensitive_path_globs
+    glob.match(pattern, ["/"], bm.Resolved)
+    not bm.ReadOnly
+}}
+"#,
+            set_literal(&readonly_sensitive_globs, "")
+        );
+    }
+
+    for raw in &config.denied_paths {
+        let (negated, pattern) = path_pattern::split_negation(raw);
+        if !negated {
+            denied_globs.extend(glob_variants(&expand_home(pattern, home)));
+        }
+    }
+    if !denied_globs.is_empty() {
+        let _ = write!(
+            out,
+            r#"
+# 拒否するパス (denied_paths と allowed_paths の否定パターン)
+denied_path_globs := {}
+
+deny if {{
+    bm := input.BindMounts[_]
+    some pattern in denied_path_globs
+    glob.match(pattern, ["/"], bm.Resolved)
+}}
+"#,
+            set_literal(&denied_globs, "")
+        );
+    }
+
+    if config.block_docker_socket {
+        out.push_str(
+            r#"
+# Docker ソケットのマウントを禁止 (block_docker_socket)
+deny if {
+    bm := input.BindMounts[_]
+    bm.Resolved in {"/var/run/docker.sock", "/run/docker.sock"}
+}
+
+deny if {
+    bm := input.BindMounts[_]
+    endswith(bm.Resolved, "/docker.sock")
+}
+"#,
+        );
+    }
+
+    // ホストの重要パスとランタイムソケット (設定で上書きできない)
+    let critical: Vec<String> = path_validator::CRITICAL_PATHS
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+    let socket_names: Vec<String> = path_validator::RUNTIME_SOCKET_NAMES
+        .iter()
+        .map(|name| format!("/{}", name))
+        .collect();
+    let socket_paths: Vec<String> = path_validator::RUNTIME_SOCKET_PATHS
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+    let _ = write!(
+        out,
+        r#"
+# ホストの重要パス (/proc, /sys 等) とコンテナランタイムのソケットを禁止 (allowed_paths でも許可しない)
+critical_paths := {}
+
+runtime_socket_suffixes := {}
+
+runtime_socket_paths := {}
+
+protected_paths := critical_paths | runtime_socket_paths
+
+path_within(path, base) if {{
+    path == base
+}}
+
+path_within(path, base) if {{
+    startswith(path, concat("", [trim_suffix(base, "/"), "/"]))
+}}
+
+deny if {{
+    bm := input.BindMounts[_]
+    some critical in critical_paths
+    path_within(bm.Resolved, critical)
+}}
+
+# 重要パスやランタイムソケットを含む親ディレクトリ (/, /var, /run 等)
+deny if {{
+    bm := input.BindMounts[_]
+    bm.Resolved != ""
+    some protected in protected_paths
+    path_within(protected, bm.Resolved)
+}}
+
+deny if {{
+    bm := input.BindMounts[_]
+    some suffix in runtime_socket_suffixes
+    endswith(bm.Resolved, suffix)
+}}
+"#,
+        set_literal(&critical, ""),
+        set_literal(&socket_names, ""),
+        set_literal(&socket_paths, ""),
+    );
+
+    if !config.allowed_images.is_empty()
+        || !config.denied_images.is_empty()
+        || !config.allowed_registries.is_empty()
+    {
+        out.push_str(
+            r#"
+# イメージ参照の正規化 (ubuntu → docker.io/library/ubuntu)。パターンにも同じ正規化を適用する
+image_digest(ref) := split(ref, "@")[1] if {
+    contains(ref, "@")
+} else := ""
+
+image_name(ref) := regex.replace(split(ref, "@")[0], ":[^:/]*$", "")
+
+image_tag(ref) := substring(split(ref, "@")[0], count(image_name(ref)) + 1, -1) if {
+    split(ref, "@")[0] != image_name(ref)
+} else := ""
+
+image_effective_tag(ref) := image_tag(ref) if {
+    image_tag(ref) != ""
+} else := "latest" if {
+    image_digest(ref) == ""
+}
+
+image_full_name(ref) := name if {
+    name := image_name(ref)
+    startswith(name, "**")
+} else := name if {
+    name := image_name(ref)
+    parts := split(name, "/")
+    count(parts) > 1
+    regex.match(`[.:]|^localhost$`, parts[0])
+} else := concat("/", ["docker.io", image_name(ref)]) if {
+    contains(image_name(ref), "/")
+} else := concat("/", ["docker.io/library", image_name(ref)])
+
+image_matches(pattern, ref) if {
+    glob.match(image_full_name(pattern), ["/"], image_full_name(ref))
+    image_tag_matches(image_tag(pattern), ref)
+    image_digest(pattern) in {"", image_digest(ref)}
+}
+
+image_tag_matches(pattern, _) if pattern == ""
+
+image_tag_matches(pattern, ref) if glob.match(pattern, null, image_effective_tag(ref))
+"#,
+        );
+    }
+
+    if !config.allowed_images.is_empty() {
+        let _ = write!(
+            out,
+            r#"
+# イメージホワイトリスト (allowed_images)
+allowed_images := {}
+
+deny if {{
+    ref := input.Body.Image
+    not image_allowed(ref)
+}}
+
+image_allowed(ref) if {{
+    some pattern in allowed_images
+    image_matches(pattern, ref)
+}}
+"#,
+            set_literal(&config.allowed_images, "")
+        );
+    }
+
+    if !config.denied_images.is_empty() {
+        let _ = write!(
+            out,
+            r#"
+# 拒否するイメージ (denied_images)
+denied_images := {}
+
+deny if {{
+    some pattern in denied_images
+    image_matches(pattern, input.Body.Image)
+}}
+"#,
+            set_literal(&config.denied_images, "")
+        );
+    }
+
+    if !config.allowed_registries.is_empty() {
+        let _ = write!(
+            out,
+            r#"
+# イメージを取得してよいレジストリ (allowed_registries)
+allowed_registries := {}
+
+deny if {{
+    registry := split(image_full_name(input.Body.Image), "/")[0]
+    not registry_allowed(registry)
+}}
+
+registry_allowed(registry) if {{
+    some pattern in allowed_registries
+    glob.match(pattern, null, registry)
+}}
+"#,
+            set_literal(&config.allowed_registries, "")
+        );
+    }
+
+    out
+}
+
+/// export-rego サブコマンドのエントリポイント。args は "export-rego" 以降の引数。
+pub fn run(args: &[String], config: &Config) -> i32 {
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        eprintln!("USAGE:");
+        eprintln!("  safe-docker export-rego [--home DIR] [--output FILE]");
+        eprintln!();
+        eprintln!("OPTIONS:");
+        eprintln!("  --home DIR      Home directory allowed for bind mounts (default: $HOME)");
+        eprintln!("  --output FILE   Write the policy to FILE instead of stdout");
+        return 0;
+    }
+    let (home, output) = match (
+        crate::proxy::option_value(args, "--home"),
+        crate::proxy::option_value(args, "--output"),
+    ) {
+        (Ok(home), Ok(output)) => (home, output),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("[safe-docker] ERROR: {}", e);
+            return 1;
+        }
+    };
+    let Some(home) = home.or_else(|| dirs::home_dir().map(|h| h.to_string_lossy().to_string()))
+    else {
+        eprintln!("[safe-docker] ERROR: Could not determine home directory (use --home DIR)");
+        return 1;
+    };
+
+    let rego = render(config, &home);
+    match output {
+        Some(path) => match std::fs::write(&path, rego) {
+            Ok(()) => {
+                eprintln!("[safe-docker] Wrote {}", path);
+                0
+            }
+            Err(e) => {
+                eprintln!("[safe-docker] ERROR: Cannot write {}: {}", path, e);
+                1
+            }
+        },
+        None => {
+            print!("{}", rego);
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_reflects_config() {
+        let config = Config {
+            blocked_capabilities: vec!["sys_admin".to_string(), "NET_RAW".to_string()],
+            allowed_paths: vec!["/data/projects".into()],
+            sensitive_paths: vec![".ssh".into()],
+            allowed_images: vec!["ubuntu".to_string()],
+            ..Default::default()
+        };
+        let rego = render(&config, "/home/alice/");
+        assert!(rego.contains("        \"SYS_ADMIN\",\n        \"NET_RAW\",\n"));
+        assert!(rego.contains("    \"/home/alice/\",\n    \"/data/projects\",\n"));
+        assert!(rego.contains("path == \"/home/alice\""));
+        assert!(rego.contains("\"/home/alice/.ssh\""));
+        assert!(rego.contains("allowed_images := {\n    \"ubuntu\",\n}"));
+        assert!(rego.contains("/var/run/docker.sock"));
+    }
+
+    #[test]
+    fn test_render_image_lists() {
+        let config = Config {
+            denied_images: vec!["ghcr.io/ourorg/legacy".to_string()],
+            allowed_registries: vec!["ghcr.io".to_string()],
+            ..Default::default()
+        };
+        let rego = render(&config, "/home/alice");
+        assert!(rego.contains("image_full_name(ref) := name if {"));
+        assert!(rego.contains("denied_images := {\n    \"ghcr.io/ourorg/legacy\",\n}"));
+        assert!(rego.contains("allowed_registries := {\n    \"ghcr.io\",\n}"));
+        assert!(!rego.contains("allowed_images :="));
+    }
+
+    #[test]
+    fn test_render_omits_disabled_rules() {
+        let config = Config {
+            blocked_capabilities: vec![],
+            sensitive_paths: vec![],
+            block_docker_socket: false,
+            ..Default::default()
+        };
+        let rego = render(&config, HOME_PLACEHOLDER);
+        assert!(!rego.contains("CapAdd"));
+        assert!(!rego.contains("sensitive_paths :="));
+        assert!(!rego.contains("docker.sock"));
+        assert!(!rego.contains("allowed_images :="));
+        assert!(!rego.contains("image_full_name"));
+        // ホストの重要パスとランタイムソケットは設定に関係なく出力する
+        assert!(rego.contains("critical_paths := {\n    \"/proc\",\n"));
+        assert!(rego.contains("    \"/containerd.sock\",\n"));
+    }
+
+    #[test]
+    fn test_render_read_only_entries() {
+        let config: Config = toml::from_str(
+            r#"
+            allowed_paths = ["/srv", { path = "/data/datasets", mode = "ro" }]
+            sensitive_paths = [".ssh", { path = ".config/git", mode = "ro" }]
+            "#,
+        )
+        .unwrap();
+        let rego = render(&config, "/home/alice");
+        assert!(
+            rego.contains("allowed_bind_prefixes := {\n    \"/home/alice/\",\n    \"/srv\",\n}")
+        );
+        assert!(rego.contains("readonly_bind_prefixes := {\n    \"/data/datasets\",\n}"));
+        assert!(
+            rego.contains("    not bind_allowed(resolved)\n    not bind_readonly_allowed(bm)\n}")
+        );
+        assert!(rego.contains("sensitive_paths := {\n    \"/home/alice/.ssh\",\n}"));
+        assert!(
+            rego.contains("readonly_sensitive_paths := {\n    \"/home/alice/.config/git\",\n}")
+        );
+        assert!(rego.contains("    not bm.ReadOnly\n"));
+
+        // mode なしのみなら従来の出力と同じ
+        let rego = render(&Config::default(), HOME_PLACEHOLDER);
+        assert!(!rego.contains("readonly"));
+    }
+
+    #[test]
+    fn test_render_path_patterns() {
+        let config: Config = toml::from_str(
+            r#"
+            allowed_paths = ["/srv/*/data", "!/srv/private"]
+            sensitive_paths = [".ssh", "**/*.pem", "!projects/demo"]
+            denied_paths = ["~/vault", "/data/**/secrets", "!/data/demo"]
+            "#,
+        )
+        .unwrap();
+        let rego = render(&config, "/home/alice");
+        assert!(
+            rego.contains(
+                "allowed_bind_globs := {\n    \"/srv/*/data\",\n    \"/srv/*/data/**\",\n}"
+            )
+        );
+        assert!(rego.contains("glob.match(pattern, [\"/\"], path)"));
+        assert!(rego.contains("    \"/home/alice/**/*.pem\",\n    \"/home/alice/**/*.pem/**\",\n"));
+        // 否定パターン: allowed_paths は拒否に、sensitive_paths / denied_paths は無視
+        assert!(rego.contains("    \"/srv/private\",\n"));
+        assert!(rego.contains("    \"/home/alice/vault/**\",\n"));
+        assert!(rego.contains("    \"/data/secrets\",\n"));
+        assert!(!rego.contains("demo"));
+    }
+
+    #[test]
+    fn test_quote_escapes() {
+        assert_eq!(quote("/data/\"x\""), r#""/data/\"x\"""#);
+    }
+}
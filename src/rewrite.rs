@@ -0,0 +1,671 @@
+//! hook の updatedInput による安全なコマンドへの書き換え
+//!
+//! deny / ask にする代わりに、同等でより安全なコマンドを提案する。
+//! 書き換えは単一の docker コマンドのみを対象とし、書き換え後のコマンドも
+//! 通常どおりポリシー評価される。
+
+use std::path::Path;
+
+use crate::config::{Config, RewriteRule};
+use crate::docker_args;
+use crate::path_validator::{self, PathVerdict};
+use crate::shell;
+
+/// 書き換え結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewrittenCommand {
+    /// 書き換え後のコマンド文字列
+    pub command: String,
+    /// 変更内容 (理由メッセージに表示)
+    pub changes: Vec<String>,
+}
+
+/// docker 引数の書き換え結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArgsRewrite {
+    /// 書き換え後の docker 引数
+    pub args: Vec<String>,
+    /// コマンドの前に付与する環境変数 (--build-arg → --secret の値の受け渡し)
+    pub env: Vec<(String, String)>,
+    /// 変更内容
+    pub changes: Vec<String>,
+}
+
+/// docker グローバルオプションのうち値を取るもの
+const GLOBAL_VALUE_FLAGS: &[&str] = &[
+    "-H",
+    "--host",
+    "-c",
+    "--context",
+    "--config",
+    "-l",
+    "--log-level",
+    "--tlscacert",
+    "--tlscert",
+    "--tlskey",
+];
+
+/// docker run / create の値を取るフラグのうち VALUE_FLAGS で個別に扱われているもの
+const RUN_VALUE_FLAGS: &[&str] = &[
+    "-v",
+    "--volume",
+    "--mount",
+    "--security-opt",
+    "--cap-add",
+    "--cap-drop",
+    "--device",
+];
+
+/// コマンド文字列を書き換える。書き換え対象がない場合は None。
+///
+/// 単一セグメントの docker コマンド (パイプ・チェーン・リダイレクトなし) のみを対象とする。
+pub fn rewrite_command(command: &str, config: &Config) -> Option<RewrittenCommand> {
+    if !config.hook.rewrite.enabled {
+        return None;
+    }
+
+    let segments = shell::split_commands(command);
+    let [segment] = segments.as_slice() else {
+        return None;
+    };
+    if shell::detect_shell_wrappers(segment) || !shell::extract_write_targets(segment).is_empty() {
+        return None;
+    }
+    let head = shell::docker_command_head(segment)?;
+
+    let args = shell::extract_docker_args(segment);
+    let rewrite = rewrite_docker_args(&args, config)?;
+
+    let mut command = head.to_string();
+    for (key, value) in &rewrite.env {
+        command.push_str(&format!("{}={} ", key, quote_arg(value)));
+    }
+    command.push_str("docker ");
+    let quoted: Vec<String> = rewrite.args.iter().map(|a| quote_arg(a)).collect();
+    command.push_str(&quoted.join(" "));
+
+    Some(RewrittenCommand {
+        command,
+        changes: rewrite.changes,
+    })
+}
+
+/// シェル引数としてクォートする。
+/// `~` や `$HOME` の展開を維持するため、安全な文字だけの引数はそのまま、
+/// 変数参照を含む引数はダブルクォートで囲む。
+fn quote_arg(arg: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./~-".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        return arg.to_string();
+    }
+    let has_var = arg.contains('$');
+    let needs_single = arg.contains(['"', '\\', '`']) || arg.contains("$(") || arg.starts_with('~');
+    if has_var && !needs_single {
+        return format!("\"{}\"", arg);
+    }
+    shell_words::quote(arg).into_owned()
+}
+
+/// グローバルオプションをスキップしたサブコマンドの位置
+fn subcommand_index(args: &[String]) -> usize {
+    let mut i = 0;
+    while i < args.len() && args[i].starts_with('-') {
+        if GLOBAL_VALUE_FLAGS.contains(&args[i].as_str()) {
+            i += 1;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// docker 引数を書き換える。書き換え対象がない場合は None。
+pub fn rewrite_docker_args(args: &[String], config: &Config) -> Option<ArgsRewrite> {
+    let sub_idx = subcommand_index(args);
+    let subcommand = args.get(sub_idx)?.as_str();
+
+    let mut rewrite = match subcommand {
+        "run" | "create" => rewrite_run_args(args, sub_idx, config),
+        "build" => rewrite_build_args(args, sub_idx + 1, config),
+        "buildx" if args.get(sub_idx + 1).is_some_and(|a| a == "build") => {
+            rewrite_build_args(args, sub_idx + 2, config)
+        }
+        _ => return None,
+    };
+
+    if rewrite.changes.is_empty() {
+        return None;
+    }
+    rewrite.changes.dedup();
+    Some(rewrite)
+}
+
+/// docker run / create のバインドマウント元を解決済みのパスに固定する (wrapper.pin_mount_sources)。
+/// 書き換え対象がない場合は None。
+///
+/// 判定後に途中のディレクトリがシンボリックリンクへ差し替えられても、docker には
+/// 判定したときの実体のパスが渡る。
+pub fn pin_mount_sources(args: &[String]) -> Option<ArgsRewrite> {
+    let sub_idx = subcommand_index(args);
+    if !matches!(args.get(sub_idx)?.as_str(), "run" | "create") {
+        return None;
+    }
+    let rewrite = map_run_values(args, sub_idx, |flag, value| match flag {
+        "-v" | "--volume" => pinned_volume(value),
+        "--mount" => pinned_mount(value),
+        _ => None,
+    });
+    (!rewrite.changes.is_empty()).then_some(rewrite)
+}
+
+/// docker run / create のオプションの値を f で書き換える (None はそのまま)
+fn map_run_values(
+    args: &[String],
+    sub_idx: usize,
+    mut f: impl FnMut(&str, &str) -> Option<String>,
+) -> ArgsRewrite {
+    let mut out: Vec<String> = args[..=sub_idx].to_vec();
+    let mut changes = Vec::new();
+
+    let mut i = sub_idx + 1;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        // イメージ名以降はコンテナのコマンド
+        if !arg.starts_with('-') || arg == "--" {
+            break;
+        }
+
+        // --flag=value 形式を分解
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f, Some(v)),
+            _ => (arg, None),
+        };
+        let takes_value = docker_args::is_flag_with_value(flag) || RUN_VALUE_FLAGS.contains(&flag);
+        if !takes_value {
+            out.push(arg.to_string());
+            i += 1;
+            continue;
+        }
+        let value = match inline_value {
+            Some(v) => v,
+            None => match args.get(i + 1) {
+                Some(v) => v.as_str(),
+                None => {
+                    out.push(arg.to_string());
+                    i += 1;
+                    break;
+                }
+            },
+        };
+        let consumed = if inline_value.is_some() { 1 } else { 2 };
+
+        match f(flag, value) {
+            Some(new_value) => {
+                changes.push(format!("{} {} → {} {}", flag, value, flag, new_value));
+                out.push(flag.to_string());
+                out.push(new_value);
+            }
+            None => {
+                out.extend(args[i..i + consumed].iter().cloned());
+            }
+        }
+        i += consumed;
+    }
+    out.extend(args[i.min(args.len())..].iter().cloned());
+
+    ArgsRewrite {
+        args: out,
+        env: Vec::new(),
+        changes,
+    }
+}
+
+/// docker run / create の書き換え
+fn rewrite_run_args(args: &[String], sub_idx: usize, config: &Config) -> ArgsRewrite {
+    let rules = &config.hook.rewrite;
+    let mut has_no_new_privileges = false;
+    let mut has_cap_drop_all = false;
+
+    let mut rewrite = map_run_values(args, sub_idx, |flag, value| match flag {
+        "-v" | "--volume" if rules.has_rule(RewriteRule::ReadonlySensitiveMounts) => {
+            readonly_volume(value, config)
+        }
+        "--mount" if rules.has_rule(RewriteRule::ReadonlySensitiveMounts) => {
+            readonly_mount(value, config)
+        }
+        "-p" | "--publish" if rules.has_rule(RewriteRule::LoopbackPorts) => loopback_port(value),
+        "--security-opt" => {
+            if value == "no-new-privileges" || value == "no-new-privileges:true" {
+                has_no_new_privileges = true;
+            }
+            None
+        }
+        "--cap-drop" => {
+            if value.eq_ignore_ascii_case("ALL") {
+                has_cap_drop_all = true;
+            }
+            None
+        }
+        _ => None,
+    });
+
+    // サブコマンド直後にハードニングフラグを挿入
+    let mut inserted = Vec::new();
+    if rules.has_rule(RewriteRule::NoNewPrivileges) && !has_no_new_privileges {
+        inserted.push("--security-opt".to_string());
+        inserted.push("no-new-privileges".to_string());
+        rewrite
+            .changes
+            .push("added --security-opt no-new-privileges".to_string());
+    }
+    if rules.has_rule(RewriteRule::CapDropAll) && !has_cap_drop_all {
+        inserted.push("--cap-drop".to_string());
+        inserted.push("ALL".to_string());
+        rewrite.changes.push("added --cap-drop ALL".to_string());
+    }
+    rewrite.args.splice(sub_idx + 1..sub_idx + 1, inserted);
+    rewrite
+}
+
+/// docker build の書き換え (機密情報を含む --build-arg → --secret)
+fn rewrite_build_args(args: &[String], start: usize, config: &Config) -> ArgsRewrite {
+    let mut rewrite = ArgsRewrite {
+        args: args[..start.min(args.len())].to_vec(),
+        ..Default::default()
+    };
+    if !config.hook.rewrite.has_rule(RewriteRule::BuildSecrets) {
+        rewrite.args = args.to_vec();
+        return rewrite;
+    }
+
+    let mut i = start;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        if arg == "--" {
+            rewrite.args.extend(args[i..].iter().cloned());
+            break;
+        }
+        let (value, consumed) = if arg == "--build-arg" {
+            match args.get(i + 1) {
+                Some(v) => (Some(v.as_str()), 2),
+                None => (None, 1),
+            }
+        } else if let Some(v) = arg.strip_prefix("--build-arg=") {
+            (Some(v), 1)
+        } else {
+            (None, 1)
+        };
+
+        match value {
+            Some(value) if docker_args::is_secret_build_arg(value) => {
+                let (key, secret) = match value.split_once('=') {
+                    Some((k, v)) => (k, Some(v)),
+                    None => (value, None),
+                };
+                // 値が指定されている場合は環境変数経由で渡す (イメージ履歴には残らない)
+                if let Some(secret) = secret {
+                    rewrite.env.push((key.to_string(), secret.to_string()));
+                }
+                rewrite.args.push("--secret".to_string());
+                rewrite.args.push(format!("id={},env={}", key, key));
+                rewrite.changes.push(format!(
+                    "--build-arg {} → --secret id={},env={} (consume it with RUN --mount=type=secret,id={})",
+                    key, key, key, key
+                ));
+            }
+            _ => rewrite.args.extend(args[i..i + consumed].iter().cloned()),
+        }
+        i += consumed;
+    }
+    rewrite
+}
+
+/// ホストパスが機密パスかどうか
+fn is_sensitive_source(source: &str, config: &Config) -> bool {
+    // 名前付きボリュームは対象外
+    if !(source.starts_with('/')
+        || source.starts_with('~')
+        || source.starts_with('$')
+        || source.starts_with('.'))
+    {
+        return false;
+    }
+    matches!(
+        path_validator::validate_path(source, config),
+        PathVerdict::Sensitive(_)
+    )
+}
+
+/// -v SRC:DST[:OPTS] の機密パスを読み取り専用にする
+fn readonly_volume(value: &str, config: &Config) -> Option<String> {
+    let parts: Vec<&str> = value.splitn(3, ':').collect();
+    if parts.len() < 2 || !is_sensitive_source(parts[0], config) {
+        return None;
+    }
+    match parts.get(2) {
+        None => Some(format!("{}:ro", value)),
+        Some(opts) => {
+            let mut opts: Vec<&str> = opts.split(',').filter(|o| !o.is_empty()).collect();
+            if opts.contains(&"ro") {
+                return None;
+            }
+            opts.retain(|o| *o != "rw");
+            opts.push("ro");
+            Some(format!("{}:{}:{}", parts[0], parts[1], opts.join(",")))
+        }
+    }
+}
+
+/// --mount type=bind,source=SRC,... の機密パスを読み取り専用にする
+fn readonly_mount(value: &str, config: &Config) -> Option<String> {
+    let fields: Vec<&str> = value.split(',').collect();
+    let is_bind = fields.contains(&"type=bind");
+    let source = fields
+        .iter()
+        .find_map(|f| f.strip_prefix("source=").or_else(|| f.strip_prefix("src=")))?;
+    let readonly = fields.iter().any(|f| {
+        matches!(
+            *f,
+            "readonly" | "ro" | "readonly=true" | "ro=true" | "readonly=1" | "ro=1"
+        )
+    });
+    if !is_bind || readonly || !is_sensitive_source(source, config) {
+        return None;
+    }
+    let mut fields: Vec<&str> = fields
+        .into_iter()
+        .filter(|f| !f.starts_with("readonly=") && !f.starts_with("ro="))
+        .collect();
+    fields.push("readonly");
+    Some(fields.join(","))
+}
+
+/// ホストパスを解決済みの絶対パスにする (名前付きボリューム・変化がない場合は None)
+fn pinned_source(source: &str) -> Option<String> {
+    if !(source.starts_with('/')
+        || source.starts_with('~')
+        || source.starts_with('$')
+        || source.starts_with('.'))
+    {
+        return None;
+    }
+    let expanded = path_validator::expand_env(source);
+    let path = Path::new(&expanded);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().ok()?.join(path)
+    };
+    let resolved = path_validator::resolve_partial(&path)
+        .to_string_lossy()
+        .to_string();
+    // ':' や ',' を含むパスは -v / --mount の区切りと衝突するため書き換えない
+    (resolved != source && !resolved.contains([':', ','])).then_some(resolved)
+}
+
+/// -v SRC:DST[:OPTS] のホストパスを解決済みのパスにする
+fn pinned_volume(value: &str) -> Option<String> {
+    let (source, rest) = value.split_once(':')?;
+    pinned_source(source).map(|resolved| format!("{}:{}", resolved, rest))
+}
+
+/// --mount type=bind,source=SRC,... のホストパスを解決済みのパスにする
+fn pinned_mount(value: &str) -> Option<String> {
+    let fields: Vec<&str> = value.split(',').collect();
+    if !fields.contains(&"type=bind") {
+        return None;
+    }
+    let mut changed = false;
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|field| {
+            let pinned = field.split_once('=').and_then(|(key, source)| {
+                matches!(key, "source" | "src")
+                    .then(|| pinned_source(source))
+                    .flatten()
+                    .map(|resolved| format!("{}={}", key, resolved))
+            });
+            match pinned {
+                Some(pinned) => {
+                    changed = true;
+                    pinned
+                }
+                None => field.to_string(),
+            }
+        })
+        .collect();
+    changed.then(|| fields.join(","))
+}
+
+/// -p の公開先をループバックに限定する
+///
+/// ホスト IP 未指定・ワイルドカード (0.0.0.0, ::) の場合のみ 127.0.0.1 に置き換える。
+fn loopback_port(value: &str) -> Option<String> {
+    // [::]:8080:80 形式
+    if let Some(rest) = value.strip_prefix('[') {
+        let (ip, after) = rest.split_once(']')?;
+        if ip == "::" || ip.is_empty() {
+            return Some(format!("127.0.0.1{}", after));
+        }
+        return None;
+    }
+    let parts: Vec<&str> = value.split(':').collect();
+    match parts.len() {
+        // 80 → 127.0.0.1::80 (ホストポートはランダム)
+        1 => Some(format!("127.0.0.1::{}", value)),
+        // 8080:80 → 127.0.0.1:8080:80
+        2 => Some(format!("127.0.0.1:{}", value)),
+        // IP:8080:80
+        3 if parts[0].is_empty() || parts[0] == "0.0.0.0" => {
+            Some(format!("127.0.0.1:{}:{}", parts[1], parts[2]))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RewriteConfig;
+
+    fn config_with(rules: &[RewriteRule]) -> Config {
+        let mut config = Config::default();
+        config.hook.rewrite = RewriteConfig {
+            enabled: true,
+            rules: rules.to_vec(),
+        };
+        config
+    }
+
+    fn args(s: &str) -> Vec<String> {
+        shell_words::split(s).unwrap()
+    }
+
+    #[test]
+    fn test_quote_arg() {
+        assert_eq!(quote_arg("~/.aws:/root/.aws:ro"), "~/.aws:/root/.aws:ro");
+        assert_eq!(quote_arg("id=KEY,env=KEY"), "id=KEY,env=KEY");
+        assert_eq!(quote_arg("$HOME/.kube:/kube"), "\"$HOME/.kube:/kube\"");
+        assert_eq!(quote_arg("echo hi"), "'echo hi'");
+        assert_eq!(quote_arg("$(id)"), "'$(id)'");
+    }
+
+    #[test]
+    fn test_rewrite_disabled_by_default() {
+        assert_eq!(
+            rewrite_command("docker run -p 8080:80 nginx", &Config::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rewrite_sensitive_volume_readonly() {
+        let config = config_with(&[RewriteRule::ReadonlySensitiveMounts]);
+        let rw = rewrite_command("docker run -v ~/.aws:/root/.aws ubuntu", &config).unwrap();
+        assert_eq!(rw.command, "docker run -v ~/.aws:/root/.aws:ro ubuntu");
+        assert_eq!(rw.changes.len(), 1);
+
+        let rw = rewrite_command("docker run -v ~/.ssh:/keys:rw,z ubuntu", &config).unwrap();
+        assert_eq!(rw.command, "docker run -v ~/.ssh:/keys:z,ro ubuntu");
+
+        // 既に ro / 機密パスでない場合は書き換えない
+        assert_eq!(
+            rewrite_command("docker run -v ~/.aws:/root/.aws:ro ubuntu", &config),
+            None
+        );
+        assert_eq!(
+            rewrite_command("docker run -v ~/projects:/app ubuntu", &config),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rewrite_sensitive_mount_readonly() {
+        let config = config_with(&[RewriteRule::ReadonlySensitiveMounts]);
+        let rw = rewrite_command(
+            "docker run --mount type=bind,source=$HOME/.kube,target=/kube ubuntu",
+            &config,
+        )
+        .unwrap();
+        assert!(
+            rw.command
+                .contains("type=bind,source=$HOME/.kube,target=/kube,readonly"),
+            "{}",
+            rw.command
+        );
+    }
+
+    #[test]
+    fn test_rewrite_hardening_flags() {
+        let config = config_with(&[RewriteRule::NoNewPrivileges, RewriteRule::CapDropAll]);
+        let rw = rewrite_command("docker run --rm ubuntu echo hi", &config).unwrap();
+        assert_eq!(
+            rw.command,
+            "docker run --security-opt no-new-privileges --cap-drop ALL --rm ubuntu echo hi"
+        );
+
+        // 既に指定されている場合は追加しない
+        assert_eq!(
+            rewrite_command(
+                "docker run --security-opt=no-new-privileges --cap-drop all ubuntu",
+                &config
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rewrite_loopback_ports() {
+        let config = config_with(&[RewriteRule::LoopbackPorts]);
+        let rw = rewrite_command("docker run -p 8080:80 -p 443 nginx", &config).unwrap();
+        assert_eq!(
+            rw.command,
+            "docker run -p 127.0.0.1:8080:80 -p 127.0.0.1::443 nginx"
+        );
+        let rw = rewrite_command("docker run --publish=0.0.0.0:53:53/udp dns", &config).unwrap();
+        assert_eq!(rw.command, "docker run --publish 127.0.0.1:53:53/udp dns");
+        assert_eq!(
+            rewrite_command("docker run -p 127.0.0.1:8080:80 nginx", &config),
+            None
+        );
+    }
+
+    #[test]
+    fn test_loopback_port_ipv6_wildcard() {
+        assert_eq!(
+            loopback_port("[::]:8080:80"),
+            Some("127.0.0.1:8080:80".to_string())
+        );
+        assert_eq!(loopback_port("[::1]:8080:80"), None);
+        assert_eq!(loopback_port("192.168.1.10:8080:80"), None);
+    }
+
+    #[test]
+    fn test_pin_mount_sources() {
+        use std::os::unix::fs;
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(root.join("real")).unwrap();
+        fs::symlink(root.join("real"), root.join("link")).unwrap();
+        let root = root.to_string_lossy();
+
+        let rw = pin_mount_sources(&args(&format!(
+            "run -v {root}/link/data:/data:ro --mount type=bind,src={root}/link,dst=/x ubuntu ls -v /tmp"
+        )))
+        .unwrap();
+        assert_eq!(
+            rw.args,
+            args(&format!(
+                "run -v {root}/real/data:/data:ro --mount type=bind,src={root}/real,dst=/x ubuntu ls -v /tmp"
+            ))
+        );
+        assert_eq!(rw.changes.len(), 2);
+
+        // 解決済みのパス・名前付きボリューム・run / create 以外は書き換えない
+        assert_eq!(
+            pin_mount_sources(&args(&format!(
+                "run -v {root}/real:/data -v cache:/c ubuntu"
+            ))),
+            None
+        );
+        assert_eq!(
+            pin_mount_sources(&args(&format!("cp {root}/link/f container:/f"))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rewrite_container_command_untouched() {
+        let config = config_with(&[RewriteRule::LoopbackPorts]);
+        let rw = rewrite_command("docker run -p 80 nginx nginx -p 8080:80", &config).unwrap();
+        assert_eq!(
+            rw.command,
+            "docker run -p 127.0.0.1::80 nginx nginx -p 8080:80"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_build_arg_secret() {
+        let config = config_with(&[RewriteRule::BuildSecrets]);
+        let rw = rewrite_command(
+            "docker build --build-arg NPM_TOKEN=abc --build-arg VERSION=1 -t app .",
+            &config,
+        )
+        .unwrap();
+        assert_eq!(
+            rw.command,
+            "NPM_TOKEN=abc docker build --secret id=NPM_TOKEN,env=NPM_TOKEN --build-arg VERSION=1 -t app ."
+        );
+
+        let rewrite =
+            rewrite_docker_args(&args("buildx build --build-arg=API_KEY ."), &config).unwrap();
+        assert_eq!(
+            rewrite.args,
+            args("buildx build --secret id=API_KEY,env=API_KEY .")
+        );
+        assert!(rewrite.env.is_empty());
+    }
+
+    #[test]
+    fn test_rewrite_keeps_sudo_prefix() {
+        let config = config_with(&[RewriteRule::BuildSecrets]);
+        let rw = rewrite_command("sudo docker build --build-arg DB_PASSWORD=x .", &config).unwrap();
+        assert_eq!(
+            rw.command,
+            "sudo DB_PASSWORD=x docker build --secret id=DB_PASSWORD,env=DB_PASSWORD ."
+        );
+    }
+
+    #[test]
+    fn test_rewrite_skips_compound_commands() {
+        let config = config_with(&[RewriteRule::LoopbackPorts]);
+        assert_eq!(
+            rewrite_command("docker run -p 80:80 nginx && echo ok", &config),
+            None
+        );
+        assert_eq!(
+            rewrite_command("docker run -p 80:80 nginx > out.log", &config),
+            None
+        );
+        assert_eq!(rewrite_command("docker ps", &config), None);
+    }
+}
@@ -0,0 +1,340 @@
+//! セッションごとの作成コンテナの記録
+//!
+//! PostToolUse で `docker run -d` / `docker create` が出力したコンテナ ID を
+//! セッション ID ごとに記録する。記録はポリシー拡張のドキュメントに `session.containers`
+//! として渡され、exec / rm 等の対象がそのセッションで作成されたコンテナかどうかを
+//! ルールで判定できる。
+//!
+//! 同じセッションの hook は並行して実行されうるため、更新はロックファイルで排他し、
+//! 一時ファイルからの rename で置き換える。更新されないまま [`MAX_AGE`] を過ぎた
+//! セッションの記録は記録時に削除する。
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 最後の更新からこの期間を過ぎたセッションの記録は削除する
+pub const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// ロックの取得を待つ最大時間
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// この期間を過ぎたロックファイルは異常終了したプロセスの残骸とみなす
+const STALE_LOCK_AGE: Duration = Duration::from_secs(10);
+
+/// セッションの記録
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SessionRecord {
+    pub session_id: String,
+    pub containers: Vec<ContainerRecord>,
+}
+
+impl SessionRecord {
+    /// ルールで照合するためのコンテナの参照 (ID、12 桁の短縮 ID、名前)
+    pub fn container_refs(&self) -> Vec<String> {
+        let mut refs = Vec::new();
+        for container in &self.containers {
+            refs.push(container.id.clone());
+            if let Some(short) = container.id.get(..12) {
+                refs.push(short.to_string());
+            }
+            refs.extend(container.name.clone());
+        }
+        refs
+    }
+}
+
+/// セッションで作成されたコンテナ
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContainerRecord {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    pub created_at_unix: u64,
+}
+
+impl ContainerRecord {
+    pub fn new(id: &str, name: Option<String>, image: Option<String>) -> Self {
+        let created_at_unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self {
+            id: id.to_string(),
+            name,
+            image,
+            created_at_unix,
+        }
+    }
+}
+
+/// docker run -d / docker create の出力からコンテナ ID (64 桁の16進数) を抽出する
+pub fn extract_container_ids(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.len() == 64 && line.bytes().all(|b| b.is_ascii_hexdigit()))
+        .map(|line| line.to_ascii_lowercase())
+        .collect()
+}
+
+/// セッション記録ファイルのパス
+fn session_path(dir: &str, session_id: &str) -> PathBuf {
+    let dir = crate::path_validator::expand_env(dir);
+    // ファイル名に使えない文字を含む session_id はハッシュに置き換える
+    let file_stem = if !session_id.is_empty()
+        && session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        session_id.to_string()
+    } else {
+        crate::audit::command_hash(session_id)
+    };
+    PathBuf::from(dir).join(format!("{}.json", file_stem))
+}
+
+/// セッション記録を読み込む。存在しない・壊れている場合は空の記録を返す。
+pub fn load(dir: &str, session_id: &str) -> SessionRecord {
+    let path = session_path(dir, session_id);
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| SessionRecord {
+            session_id: session_id.to_string(),
+            containers: Vec::new(),
+        })
+}
+
+/// 作成されたコンテナをセッション記録に追加する
+pub fn record_containers(
+    dir: &str,
+    session_id: &str,
+    containers: Vec<ContainerRecord>,
+) -> io::Result<()> {
+    if containers.is_empty() {
+        return Ok(());
+    }
+    let path = session_path(dir, session_id);
+    let Some(parent) = path.parent() else {
+        return Err(io::Error::other("invalid session directory"));
+    };
+    fs::create_dir_all(parent)?;
+
+    with_lock(&path, || {
+        let mut record = load(dir, session_id);
+        for container in containers {
+            if !record.containers.iter().any(|c| c.id == container.id) {
+                record.containers.push(container);
+            }
+        }
+        let json = serde_json::to_string_pretty(&record).map_err(io::Error::other)?;
+        // 読み込み側がロックなしで途中までの内容を読まないよう rename で置き換える
+        let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &path)
+    })?;
+
+    remove_expired(parent, MAX_AGE);
+    Ok(())
+}
+
+/// セッション記録のロックファイル (`<session>.json.lock`) を作成している間だけ f を実行する
+fn with_lock<T>(path: &Path, f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+    let lock_path = path.with_extension("json.lock");
+    let started = SystemTime::now();
+    loop {
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+        {
+            Ok(_) => break,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                if is_older_than(&lock_path, STALE_LOCK_AGE) {
+                    let _ = fs::remove_file(&lock_path);
+                    continue;
+                }
+                if started.elapsed().unwrap_or_default() > LOCK_TIMEOUT {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!("session record is locked: {}", lock_path.display()),
+                    ));
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    let result = f();
+    let _ = fs::remove_file(&lock_path);
+    result
+}
+
+/// 最終更新から max_age を過ぎているか (更新時刻を取得できない場合は false)
+fn is_older_than(path: &Path, max_age: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > max_age)
+}
+
+/// 更新されないまま max_age を過ぎたセッション記録を削除する (失敗は無視する)
+fn remove_expired(dir: &Path, max_age: Duration) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") && is_older_than(&path, max_age) {
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID_A: &str = "4f1c2b8a9d0e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3f2a";
+    const ID_B: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_extract_container_ids() {
+        let stdout = format!("Unable to find image 'nginx:latest' locally\n{}\n", ID_A);
+        assert_eq!(extract_container_ids(&stdout), vec![ID_A]);
+        assert!(extract_container_ids("hello world\nabc123\n").is_empty());
+    }
+
+    #[test]
+    fn test_record_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().to_str().unwrap();
+
+        record_containers(
+            dir,
+            "sess-1",
+            vec![ContainerRecord::new(
+                ID_A,
+                Some("web".to_string()),
+                Some("nginx".to_string()),
+            )],
+        )
+        .unwrap();
+        // 同じ ID は重複して記録しない
+        record_containers(
+            dir,
+            "sess-1",
+            vec![
+                ContainerRecord::new(ID_A, None, None),
+                ContainerRecord::new(ID_B, None, None),
+            ],
+        )
+        .unwrap();
+
+        let record = load(dir, "sess-1");
+        assert_eq!(record.session_id, "sess-1");
+        let ids: Vec<&str> = record.containers.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec![ID_A, ID_B]);
+        assert_eq!(record.containers[0].name.as_deref(), Some("web"));
+
+        // 別セッションは独立
+        assert!(load(dir, "sess-2").containers.is_empty());
+    }
+
+    #[test]
+    fn test_container_refs() {
+        let record = SessionRecord {
+            session_id: "sess-1".to_string(),
+            containers: vec![
+                ContainerRecord::new(ID_A, Some("web".to_string()), None),
+                ContainerRecord::new(ID_B, None, None),
+            ],
+        };
+        assert_eq!(
+            record.container_refs(),
+            vec![ID_A, &ID_A[..12], "web", ID_B, &ID_B[..12]]
+        );
+    }
+
+    #[test]
+    fn test_concurrent_records_are_not_lost() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().to_str().unwrap().to_string();
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let dir = dir.clone();
+                thread::spawn(move || {
+                    let id = format!("{:064x}", i);
+                    record_containers(&dir, "sess-1", vec![ContainerRecord::new(&id, None, None)])
+                        .unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(load(&dir, "sess-1").containers.len(), 8);
+        assert!(!Path::new(&dir).join("sess-1.json.lock").exists());
+    }
+
+    #[test]
+    fn test_stale_lock_is_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = dir.path().join("sess-1.json.lock");
+        fs::write(&lock, "").unwrap();
+        let old = SystemTime::now() - STALE_LOCK_AGE * 2;
+        fs::File::options()
+            .write(true)
+            .open(&lock)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let dir = dir.path().to_str().unwrap();
+        record_containers(dir, "sess-1", vec![ContainerRecord::new(ID_A, None, None)]).unwrap();
+        assert_eq!(load(dir, "sess-1").containers.len(), 1);
+    }
+
+    #[test]
+    fn test_expired_records_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let expired = dir.path().join("old-session.json");
+        fs::write(&expired, "{}").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&expired)
+            .unwrap()
+            .set_modified(SystemTime::now() - MAX_AGE - Duration::from_secs(60))
+            .unwrap();
+        let unrelated = dir.path().join("notes.txt");
+        fs::write(&unrelated, "").unwrap();
+
+        let dir_str = dir.path().to_str().unwrap();
+        record_containers(
+            dir_str,
+            "sess-1",
+            vec![ContainerRecord::new(ID_A, None, None)],
+        )
+        .unwrap();
+        assert!(!expired.exists());
+        assert!(unrelated.exists());
+        assert!(dir.path().join("sess-1.json").exists());
+    }
+
+    #[test]
+    fn test_session_path_sanitized() {
+        let path = session_path("/tmp/sessions", "../../etc/passwd");
+        assert_eq!(
+            path.parent().unwrap(),
+            std::path::Path::new("/tmp/sessions")
+        );
+        assert!(!path.to_string_lossy().contains(".."));
+    }
+}
@@ -61,6 +61,13 @@ pub fn split_commands(command: &str) -> Vec<String> {
             continue;
         }
 
+        // `>|`, `>&`, `&>` はリダイレクト演算子の一部なので分割しない
+        let after_redirect = current.ends_with('>') && !current.ends_with("\\>");
+        if (ch == '|' || ch == '&') && after_redirect || ch == '&' && chars.peek() == Some(&'>') {
+            current.push(ch);
+            continue;
+        }
+
         // セパレータ: |, &&, ||, ;
         if ch == '|' {
             if chars.peek() == Some(&'|') {
@@ -152,6 +159,30 @@ fn push_segment(segments: &mut Vec<String>, segment: &str) {
     }
 }
 
+/// コマンド置換 (`$(...)`, バッククォート) またはプロセス置換 (`<(...)`, `>(...)`) を含むか。
+/// シングルクォート内は展開されないため対象外。ダブルクォート内ではコマンド置換のみ展開される。
+pub fn contains_substitution(segment: &str) -> bool {
+    let mut chars = segment.chars().peekable();
+    let mut in_single_quote = false;
+    let mut in_double_quote = false;
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if !in_single_quote => {
+                chars.next();
+            }
+            '\'' if !in_double_quote => in_single_quote = !in_single_quote,
+            '"' if !in_single_quote => in_double_quote = !in_double_quote,
+            _ if in_single_quote => {}
+            '`' => return true,
+            '$' if chars.peek() == Some(&'(') => return true,
+            '<' | '>' if !in_double_quote && chars.peek() == Some(&'(') => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
 /// シェル間接実行 (eval, bash -c, sh -c, xargs docker) を検出する。
 /// docker コマンドが間接的に実行されようとしている場合 true を返す。
 pub fn detect_shell_wrappers(segment: &str) -> bool {
@@ -385,6 +416,353 @@ pub fn extract_docker_args(segment: &str) -> Vec<String> {
     }
 }
 
+/// docker コマンドセグメントの `docker` より前の部分 (環境変数設定・sudo) を返す。
+/// docker-compose や docker コマンドでない場合は None。
+pub fn docker_command_head(segment: &str) -> Option<&str> {
+    let trimmed = segment.trim();
+    let cmd_part = skip_env_assignments(trimmed);
+    let docker_part = cmd_part
+        .strip_prefix("sudo ")
+        .or_else(|| cmd_part.strip_prefix("sudo\t"))
+        .map(|s| s.trim_start())
+        .unwrap_or(cmd_part);
+    let rest = docker_part.strip_prefix("docker")?;
+    if !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    Some(&trimmed[..trimmed.len() - docker_part.len()])
+}
+
+/// ファイル変更コマンドの書き込み先の種類
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteKind {
+    /// 上書き・追記・作成 (リダイレクト、sed -i、cp の宛先等)
+    Write,
+    /// 削除・移動元 (rm, mv の移動元等)。親ディレクトリの削除も保護対象に影響する
+    Remove,
+}
+
+/// ファイル変更コマンドの書き込み先パス
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteTarget {
+    pub path: String,
+    pub kind: WriteKind,
+}
+
+impl WriteTarget {
+    fn write(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            kind: WriteKind::Write,
+        }
+    }
+
+    fn remove(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            kind: WriteKind::Remove,
+        }
+    }
+}
+
+/// コマンドセグメントからファイルを変更する操作の対象パスを抽出する。
+///
+/// リダイレクト (`>`, `>>`, `&>` 等) と、代表的なファイル変更コマンド
+/// (sed -i, perl -i, tee, cp, mv, install, ln, rm, truncate, dd of= 等) を対象とする。
+/// 変数展開やスクリプト経由の書き込みは検出できない (best-effort)。
+pub fn extract_write_targets(segment: &str) -> Vec<WriteTarget> {
+    let cmd_part = skip_env_assignments(segment.trim());
+    let cmd_part = cmd_part
+        .strip_prefix("sudo ")
+        .or_else(|| cmd_part.strip_prefix("sudo\t"))
+        .map(|s| s.trim_start())
+        .unwrap_or(cmd_part);
+
+    // リダイレクトを取り除きつつ書き込み先を収集
+    let (words, redirects) = split_redirects(cmd_part);
+    let mut targets: Vec<WriteTarget> = redirects.iter().map(|p| WriteTarget::write(p)).collect();
+    let args: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    let Some((&program, rest)) = args.split_first() else {
+        return targets;
+    };
+    let program = program.rsplit('/').next().unwrap_or(program);
+
+    match program {
+        "sed"
+            if rest
+                .iter()
+                .any(|a| *a == "--in-place" || a.starts_with("--in-place="))
+                || rest.iter().any(|a| is_short_flag_with(a, 'i')) =>
+        {
+            targets.extend(sed_like_files(rest).iter().map(|p| WriteTarget::write(p)));
+        }
+        "perl" if rest.iter().any(|a| is_short_flag_with(a, 'i')) => {
+            targets.extend(sed_like_files(rest).iter().map(|p| WriteTarget::write(p)));
+        }
+        "tee" => {
+            targets.extend(positional(rest).into_iter().map(|p| WriteTarget::write(&p)));
+        }
+        "cp" | "install" | "rsync" | "ln" | "mv" => {
+            let mut pos = positional(rest);
+            // -t DIR / --target-directory=DIR 形式
+            let target_dir = rest
+                .windows(2)
+                .find(|w| w[0] == "-t" || w[0] == "--target-directory")
+                .map(|w| w[1].to_string())
+                .or_else(|| {
+                    rest.iter()
+                        .find_map(|a| a.strip_prefix("--target-directory="))
+                        .map(String::from)
+                });
+            let dest = match target_dir {
+                Some(dir) => {
+                    pos.retain(|p| *p != dir);
+                    Some(dir)
+                }
+                None => pos.pop(),
+            };
+            if let Some(dest) = dest {
+                targets.push(WriteTarget::write(&dest));
+                // 宛先がディレクトリの場合、ファイルは dest/basename(src) に配置される
+                for src in &pos {
+                    if let Some(name) = std::path::Path::new(src).file_name() {
+                        let joined = std::path::Path::new(&dest).join(name);
+                        targets.push(WriteTarget::write(&joined.to_string_lossy()));
+                    }
+                }
+            }
+            if program == "mv" {
+                targets.extend(pos.iter().map(|p| WriteTarget::remove(p)));
+            }
+        }
+        "rm" | "rmdir" | "unlink" | "shred" => {
+            targets.extend(
+                positional(rest)
+                    .into_iter()
+                    .map(|p| WriteTarget::remove(&p)),
+            );
+        }
+        "truncate" | "touch" | "chmod" | "chown" | "chattr" => {
+            // chmod/chown の最初の位置引数はモード/所有者
+            let mut pos = positional(rest);
+            if matches!(program, "chmod" | "chown" | "chattr") && !pos.is_empty() {
+                pos.remove(0);
+            }
+            targets.extend(pos.into_iter().map(|p| WriteTarget::write(&p)));
+        }
+        "dd" => {
+            targets.extend(
+                rest.iter()
+                    .filter_map(|a| a.strip_prefix("of="))
+                    .map(WriteTarget::write),
+            );
+        }
+        _ => {}
+    }
+
+    targets
+}
+
+/// コマンドをクォートを除去した単語に分割し、リダイレクトの書き込み先を別に返す。
+///
+/// 単語の途中にあるクォート外の `>`, `>>`, `>|`, `&>`, `&>>`, `N>` も演算子として扱う
+/// (`echo x>file` は単語 `echo`, `x` と書き込み先 `file` になる)。
+/// `>&N` や `>&-` は fd の複製/クローズとして除外し、`>&file` はファイルへの書き込みとして扱う。
+fn split_redirects(cmd: &str) -> (Vec<String>, Vec<String>) {
+    let mut words = Vec::new();
+    let mut redirects = Vec::new();
+    let mut current = String::new();
+    // 現在の単語がクォートやエスケープを含むか (`"2">x` の 2 は fd 番号ではない)
+    let mut quoted = false;
+    let mut in_word = false;
+    let mut chars = cmd.chars().peekable();
+
+    // 単語を 1 つ読み取る (クォート外の空白・リダイレクト・`<` で終わる)
+    fn read_word(
+        chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+        word: &mut String,
+        quoted: &mut bool,
+    ) {
+        while let Some(&ch) = chars.peek() {
+            match ch {
+                c if c.is_whitespace() || c == '>' || c == '<' => return,
+                '&' if {
+                    let mut ahead = chars.clone();
+                    ahead.next();
+                    ahead.peek() == Some(&'>')
+                } =>
+                {
+                    return;
+                }
+                '\\' => {
+                    chars.next();
+                    *quoted = true;
+                    if let Some(c) = chars.next() {
+                        word.push(c);
+                    }
+                }
+                '\'' => {
+                    chars.next();
+                    *quoted = true;
+                    for c in chars.by_ref() {
+                        if c == '\'' {
+                            break;
+                        }
+                        word.push(c);
+                    }
+                }
+                '"' => {
+                    chars.next();
+                    *quoted = true;
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '"' => break,
+                            '\\' if matches!(chars.peek(), Some('$' | '`' | '"' | '\\' | '\n')) => {
+                                word.extend(chars.next());
+                            }
+                            _ => word.push(c),
+                        }
+                    }
+                }
+                _ => {
+                    chars.next();
+                    word.push(ch);
+                }
+            }
+        }
+    }
+
+    loop {
+        // 単語の先頭の # 以降はコメント
+        if !in_word && chars.peek() == Some(&'#') {
+            break;
+        }
+        read_word(&mut chars, &mut current, &mut quoted);
+        in_word |= !current.is_empty() || quoted;
+        let Some(ch) = chars.next() else {
+            break;
+        };
+        if ch.is_whitespace() || ch == '<' {
+            if in_word {
+                words.push(std::mem::take(&mut current));
+            }
+            if ch == '<' {
+                current.push(ch);
+                in_word = true;
+            } else {
+                in_word = false;
+            }
+            quoted = false;
+            continue;
+        }
+
+        // リダイレクト演算子: 直前の数字だけの単語は fd 番号
+        let is_fd = !quoted && !current.is_empty() && current.chars().all(|c| c.is_ascii_digit());
+        if in_word && !is_fd {
+            words.push(std::mem::take(&mut current));
+        }
+        current.clear();
+        in_word = false;
+        quoted = false;
+        if ch == '&' {
+            chars.next(); // `&>` の `>`
+        }
+        let mut dup = false;
+        match chars.peek() {
+            Some('>') | Some('|') => {
+                chars.next();
+            }
+            Some('&') if ch == '>' => {
+                chars.next();
+                dup = true;
+            }
+            _ => {}
+        }
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        let mut target = String::new();
+        let mut target_quoted = false;
+        read_word(&mut chars, &mut target, &mut target_quoted);
+        // `>&1`, `>&-` は fd の複製/クローズ
+        let fd_dup = dup
+            && !target_quoted
+            && (target == "-"
+                || (!target.is_empty() && target.chars().all(|c| c.is_ascii_digit())));
+        if !fd_dup && (!target.is_empty() || target_quoted) {
+            redirects.push(target);
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+
+    (words, redirects)
+}
+
+/// `-i`, `-pi`, `-i.bak` のような短縮フラグに指定の文字が含まれるか
+fn is_short_flag_with(arg: &str, flag: char) -> bool {
+    match arg.strip_prefix('-') {
+        Some(rest) if !rest.starts_with('-') => {
+            // -i.bak のようなサフィックス部分は除外する
+            rest.split('.').next().unwrap_or("").contains(flag)
+        }
+        _ => false,
+    }
+}
+
+/// sed / perl の引数からスクリプトを除いた対象ファイルを抽出する
+fn sed_like_files(args: &[&str]) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut has_script_flag = false;
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i];
+        if matches!(arg, "-e" | "-f" | "--expression" | "--file") {
+            has_script_flag = true;
+            i += 2;
+            continue;
+        }
+        if arg.starts_with("--expression=") || arg.starts_with("--file=") {
+            has_script_flag = true;
+        }
+        if arg.starts_with('-') && arg.len() > 1 {
+            // perl -pie 's/a/b/' のように e がフラグ群の末尾にある場合は次がスクリプト
+            if arg.ends_with('e') && !arg.starts_with("--") {
+                has_script_flag = true;
+                i += 2;
+                continue;
+            }
+            i += 1;
+            continue;
+        }
+        files.push(arg.to_string());
+        i += 1;
+    }
+    // -e / -f がない場合、最初の位置引数はスクリプト
+    if !has_script_flag && !files.is_empty() {
+        files.remove(0);
+    }
+    files
+}
+
+/// オプションを除いた位置引数を返す (`--` 以降はすべて位置引数)
+fn positional(args: &[&str]) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut after_double_dash = false;
+    for arg in args {
+        if after_double_dash {
+            result.push(arg.to_string());
+        } else if *arg == "--" {
+            after_double_dash = true;
+        } else if !arg.starts_with('-') || *arg == "-" {
+            result.push(arg.to_string());
+        }
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -781,4 +1159,136 @@ mod tests {
         let result = split_commands(r#"echo `echo "hello"` && docker ps"#);
         assert_eq!(result, vec![r#"echo `echo "hello"`"#, "docker ps"]);
     }
+
+    // --- contains_substitution ---
+
+    #[test]
+    fn test_contains_substitution() {
+        assert!(contains_substitution("docker ps $(rm -rf ~/important)"));
+        assert!(contains_substitution("docker ps `rm -rf ~/important`"));
+        assert!(contains_substitution(r#"docker run -e "X=$(id)" ubuntu"#));
+        assert!(contains_substitution("docker run --env-file <(env) ubuntu"));
+        assert!(contains_substitution("docker ps >(tee log)"));
+        assert!(!contains_substitution("docker ps"));
+        assert!(!contains_substitution("docker run -e 'X=$(id)' ubuntu"));
+        assert!(!contains_substitution(r#"docker run -e "X=<(a)" ubuntu"#));
+        assert!(!contains_substitution(r"docker run -e X=\$(id) ubuntu"));
+        assert!(!contains_substitution("docker run -e HOME=$HOME ubuntu"));
+    }
+
+    // --- extract_write_targets ---
+
+    fn write_paths(segment: &str) -> Vec<String> {
+        extract_write_targets(segment)
+            .into_iter()
+            .filter(|t| t.kind == WriteKind::Write)
+            .map(|t| t.path)
+            .collect()
+    }
+
+    #[test]
+    fn test_write_targets_redirect() {
+        assert_eq!(write_paths("echo x > a.txt"), vec!["a.txt"]);
+        assert_eq!(write_paths("echo x >> a.txt"), vec!["a.txt"]);
+        assert_eq!(write_paths("echo x 2>err.log"), vec!["err.log"]);
+        assert!(write_paths("echo x 2>&1").is_empty());
+        assert!(write_paths("echo x >&2 2>&-").is_empty());
+        assert!(write_paths("echo 'a > b' \"c>d\"").is_empty());
+    }
+
+    #[test]
+    fn test_write_targets_redirect_without_space() {
+        assert_eq!(
+            write_paths("echo x>~/.claude/settings.json"),
+            vec!["~/.claude/settings.json"]
+        );
+        assert_eq!(
+            write_paths("cat a>>~/.config/safe-docker/config.toml"),
+            vec!["~/.config/safe-docker/config.toml"]
+        );
+        assert_eq!(write_paths("echo x>|a.txt"), vec!["a.txt"]);
+        assert_eq!(write_paths("echo x 2>>err.log"), vec!["err.log"]);
+        // クォートした数字は fd 番号ではない
+        assert_eq!(write_paths("echo \"2\">a.txt"), vec!["a.txt"]);
+        assert_eq!(write_paths("echo x > \"my file\""), vec!["my file"]);
+    }
+
+    #[test]
+    fn test_write_targets_redirect_both_streams() {
+        assert_eq!(write_paths("echo x &>a.txt"), vec!["a.txt"]);
+        assert_eq!(write_paths("echo x&>>a.txt"), vec!["a.txt"]);
+        assert_eq!(write_paths("echo x &> a.txt"), vec!["a.txt"]);
+        assert_eq!(write_paths("echo x >&a.txt"), vec!["a.txt"]);
+        assert_eq!(write_paths("echo x 1>&a.txt"), vec!["a.txt"]);
+    }
+
+    #[test]
+    fn test_split_commands_keeps_redirect_operators() {
+        assert_eq!(split_commands("echo x &>a.txt"), vec!["echo x &>a.txt"]);
+        assert_eq!(split_commands("echo x >|a.txt"), vec!["echo x >|a.txt"]);
+        assert_eq!(split_commands("echo x >&a.txt"), vec!["echo x >&a.txt"]);
+        assert_eq!(
+            split_commands("echo x 2>&1 | cat"),
+            vec!["echo x 2>&1", "cat"]
+        );
+        assert_eq!(
+            split_commands("sleep 1 & echo x"),
+            vec!["sleep 1", "echo x"]
+        );
+    }
+
+    #[test]
+    fn test_write_targets_sed_in_place() {
+        assert_eq!(write_paths("sed -i 's/a/b/' conf.toml"), vec!["conf.toml"]);
+        assert_eq!(
+            write_paths("sed -i.bak -e 's/a/b/' conf.toml"),
+            vec!["conf.toml"]
+        );
+        assert!(write_paths("sed 's/a/b/' conf.toml").is_empty());
+        assert_eq!(write_paths("perl -pi -e 's/a/b/' x.json"), vec!["x.json"]);
+    }
+
+    #[test]
+    fn test_write_targets_cp_mv_rm() {
+        assert_eq!(
+            write_paths("cp evil.json ~/.claude/settings.json"),
+            vec![
+                "~/.claude/settings.json",
+                "~/.claude/settings.json/evil.json"
+            ]
+        );
+        let targets = extract_write_targets("mv a.toml b.toml");
+        assert!(targets.contains(&WriteTarget::remove("a.toml")));
+        assert!(targets.contains(&WriteTarget::write("b.toml")));
+        assert_eq!(
+            extract_write_targets("rm -rf ~/.claude"),
+            vec![WriteTarget::remove("~/.claude")]
+        );
+    }
+
+    #[test]
+    fn test_write_targets_tee_dd_sudo() {
+        assert_eq!(write_paths("sudo tee -a /etc/hosts"), vec!["/etc/hosts"]);
+        assert_eq!(write_paths("dd if=/dev/zero of=disk.img"), vec!["disk.img"]);
+        assert_eq!(write_paths("chmod 777 run.sh"), vec!["run.sh"]);
+    }
+
+    #[test]
+    fn test_write_targets_read_only_commands() {
+        assert!(extract_write_targets("cat ~/.claude/settings.json").is_empty());
+        assert!(extract_write_targets("docker ps").is_empty());
+    }
+
+    // --- docker_command_head ---
+
+    #[test]
+    fn test_docker_command_head() {
+        assert_eq!(docker_command_head("docker run ubuntu"), Some(""));
+        assert_eq!(
+            docker_command_head("FOO=1 sudo docker run ubuntu"),
+            Some("FOO=1 sudo ")
+        );
+        assert_eq!(docker_command_head("docker-compose up"), None);
+        assert_eq!(docker_command_head("ls -la"), None);
+    }
 }
@@ -1,9 +1,18 @@
-use std::io::IsTerminal;
+use std::io::{IsTerminal, Read};
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use crate::config::{Config, NonInteractiveAsk};
 use crate::hook::Decision;
-use crate::{audit, docker_args, policy};
+use crate::{audit, docker_args, policy, policy_ext, rewrite};
+
+/// `docker ... inspect` のタイムアウト
+const INSPECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// `docker ... inspect` の出力の上限
+const MAX_INSPECT_OUTPUT: u64 = 64 * 1024;
 
 /// Docker バイナリの検索結果
 pub struct DockerResolution {
@@ -55,12 +64,24 @@ pub fn run(args: &[String], config: &Config, config_source: &str) -> i32 {
 
     // safe-docker 固有オプションを除去して docker に渡す引数を構築
     // (--docker-path は main.rs で事前に除去済み)
-    let docker_args: Vec<String> = args
+    let mut docker_args: Vec<String> = args
         .iter()
         .filter(|a| *a != "--dry-run" && *a != "--verbose")
         .cloned()
         .collect();
 
+    // バインドマウント元を解決済みのパスに固定する (評価と実行で同じパスを使う)
+    if config.wrapper.pin_mount_sources
+        && let Some(pinned) = rewrite::pin_mount_sources(&docker_args)
+    {
+        if verbose {
+            for change in &pinned.changes {
+                eprintln!("[safe-docker] Pinned mount source: {}", change);
+            }
+        }
+        docker_args = pinned.args;
+    }
+
     // verbose: 設定ソースと docker 解決結果を表示
     if verbose {
         eprintln!("[safe-docker] Config: {}", config_source);
@@ -173,7 +194,26 @@ pub fn evaluate_docker_args(
         c.record_docker_command(&docker_cmd);
     }
 
-    policy::evaluate(&docker_cmd, config, cwd)
+    let decision = policy::evaluate(&docker_cmd, config, cwd);
+    let ctx = policy_ext::Context {
+        args,
+        cwd,
+        session_id: None,
+        session_containers: &[],
+    };
+    policy_ext::apply(decision, &docker_cmd, config, &ctx)
+}
+
+/// 非対話環境での ask の扱い (環境変数 SAFE_DOCKER_ASK が設定より優先)
+pub fn non_interactive_ask(config: &Config) -> NonInteractiveAsk {
+    std::env::var("SAFE_DOCKER_ASK")
+        .ok()
+        .and_then(|v| match v.as_str() {
+            "allow" => Some(NonInteractiveAsk::Allow),
+            "deny" => Some(NonInteractiveAsk::Deny),
+            _ => None,
+        })
+        .unwrap_or_else(|| config.wrapper.non_interactive_ask.clone())
 }
 
 /// Ask 判定時の対話的確認
@@ -186,16 +226,7 @@ fn handle_ask(reason: &str, docker_args: &[String], config: &Config, verbose: bo
 
     if !is_tty {
         // 非対話環境: 環境変数 or 設定に従う
-        let ask_policy = std::env::var("SAFE_DOCKER_ASK")
-            .ok()
-            .and_then(|v| match v.as_str() {
-                "allow" => Some(NonInteractiveAsk::Allow),
-                "deny" => Some(NonInteractiveAsk::Deny),
-                _ => None,
-            })
-            .unwrap_or_else(|| config.wrapper.non_interactive_ask.clone());
-
-        match ask_policy {
+        match non_interactive_ask(config) {
             NonInteractiveAsk::Allow => {
                 eprintln!("[safe-docker] Non-interactive: proceeding (SAFE_DOCKER_ASK=allow)");
                 match find_real_docker_detailed(config) {
@@ -301,6 +332,47 @@ pub fn find_real_docker_detailed(config: &Config) -> Result<DockerResolution, Ve
     Err(tried)
 }
 
+/// 本物の docker で `inspect` 等の読み取り専用のコマンドを実行し、標準出力を返す
+///
+/// ポリシー評価の途中で呼ぶため、INSPECT_TIMEOUT で打ち切り、出力は MAX_INSPECT_OUTPUT までしか読まない
+pub fn inspect_output(config: &Config, args: &[&str]) -> Result<Vec<u8>, String> {
+    let docker = find_real_docker(config).ok_or_else(|| "docker binary not found".to_string())?;
+    let mut child = Command::new(docker)
+        .args(args)
+        .env("SAFE_DOCKER_ACTIVE", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stdout.take(MAX_INSPECT_OUTPUT).read_to_end(&mut buf);
+            let _ = tx.send(buf);
+        });
+    }
+    let deadline = Instant::now() + INSPECT_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("timed out".to_string());
+        }
+        thread::sleep(Duration::from_millis(1));
+    };
+    if !status.success() {
+        return Err(format!("exited with {}", status));
+    }
+    rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .map_err(|_| "timed out".to_string())
+}
+
 /// Docker バイナリが見つからなかった場合の詳細エラーを表示
 fn print_docker_not_found(tried: &[String]) {
     eprintln!("[safe-docker] Error: could not find the real docker binary");
@@ -346,6 +418,30 @@ fn generate_tips(reason: &str) -> Vec<String> {
                 .to_string(),
         );
     }
+    if reason.contains("system path") || reason.contains("runtime socket mount is blocked") {
+        tips.push(
+            "Host system paths (/proc, /sys, /dev, ...) and container runtime sockets cannot be allowed via config; copy the needed files into your project instead"
+                .to_string(),
+        );
+    }
+    if reason.contains("denied_paths") {
+        tips.push(
+            "This path is listed in denied_paths in config.toml (denied_paths take precedence over allowed_paths)"
+                .to_string(),
+        );
+    }
+    if reason.contains("via symlink") {
+        tips.push(
+            "A directory in the mount source is a symlink that points elsewhere; mount the symlink target directly if it is intended"
+                .to_string(),
+        );
+    }
+    if reason.contains("Mount source does not exist") || reason.contains("dangling symlink") {
+        tips.push(
+            "Create the mount source before running docker, or set ask_missing_mount_sources = false in config.toml"
+                .to_string(),
+        );
+    }
     if reason.contains("Docker socket mount is blocked") {
         tips.push(
             "To allow Docker socket access, set block_docker_socket = false in config.toml"
@@ -464,6 +560,80 @@ fn generate_tips(reason: &str) -> Vec<String> {
                 .to_string(),
         );
     }
+    if reason.contains("matches denied_images") {
+        tips.push(
+            "This image is listed in denied_images in config.toml (denied_images take precedence over allowed_images)"
+                .to_string(),
+        );
+    }
+    if reason.contains("not in allowed_registries") {
+        tips.push(
+            "Use an image from an allowed registry, or add the registry to allowed_registries in config.toml"
+                .to_string(),
+        );
+    }
+
+    if reason.contains("is not pinned by digest") {
+        tips.push(
+            "Look up the digest with 'docker buildx imagetools inspect <image>' and append it as @sha256:<digest>"
+                .to_string(),
+        );
+    }
+    if reason.contains("mutable 'latest' tag") || reason.contains("cannot be checked for pinning") {
+        tips.push(
+            "Mutable tags can change without notice. Pin images to reproduce builds, or relax image_pinning in config.toml"
+                .to_string(),
+        );
+    }
+
+    if reason.contains("has no verified signature") {
+        tips.push(
+            "Sign the image with a key in image_signatures.keys and save the signature with 'cosign save --dir' into image_signatures.layouts, or add its registry to exempt_registries"
+                .to_string(),
+        );
+    }
+
+    if reason.contains("Dockerfile rule ") {
+        tips.push(
+            "Fix the Dockerfile, or add the rule ID to dockerfile_lint.ignore_rules in config.toml if the construct is intended"
+                .to_string(),
+        );
+    }
+
+    if reason.contains("not excluded by .dockerignore")
+        || reason.contains("larger than build_context_scan.max_file_size")
+    {
+        tips.push(
+            "Add the files to .dockerignore so they are not sent to the Docker daemon, or adjust build_context_scan in config.toml"
+                .to_string(),
+        );
+    }
+
+    if reason.contains("is bound on all interfaces") || reason.contains("not a loopback address") {
+        tips.push(
+            "Bind the port to loopback, e.g. -p 127.0.0.1:5432:5432 (compose: \"127.0.0.1:5432:5432\"), or relax published_ports.non_loopback in config.toml"
+                .to_string(),
+        );
+    }
+    if reason.contains("privileged host port") || reason.contains("allowed_host_ports") {
+        tips.push(
+            "Publish the service on a host port in published_ports.allowed_host_ports (1024 or higher), e.g. -p 127.0.0.1:8080:80"
+                .to_string(),
+        );
+    }
+
+    if reason.contains("directly to the host's physical network") {
+        tips.push(
+            "Use a bridge network (docker network create app) instead of macvlan / ipvlan, or adjust network_policy.bridging in config.toml"
+                .to_string(),
+        );
+    }
+    if reason.contains("network_policy.isolated_images") {
+        tips.push(
+            "Run the image with --network none, or on a network created with docker network create --internal"
+                .to_string(),
+        );
+    }
 
     // フォールバック: 何もマッチしなかった場合
     if tips.is_empty() {
@@ -552,7 +722,11 @@ mod tests {
             format!("{}/projects:/app", home_dir()),
             "ubuntu".to_string(),
         ];
-        let config = default_config();
+        // マウント元が存在しない場合の確認はこのテストの対象外
+        let config = Config {
+            ask_missing_mount_sources: false,
+            ..default_config()
+        };
         let decision = evaluate_docker_args(&args, &config, "/tmp", None);
         assert_eq!(decision, Decision::Allow);
     }
@@ -726,6 +900,39 @@ mod tests {
         assert!(tips.iter().any(|t| t.contains("allowed_paths")));
     }
 
+    #[test]
+    fn test_tips_critical_paths() {
+        let tips = generate_tips(
+            "[safe-docker] Path contains a host system path: / (resolved: /) includes /proc, which is never allowed",
+        );
+        assert!(
+            tips.iter()
+                .any(|t| t.contains("cannot be allowed via config"))
+        );
+    }
+
+    #[test]
+    fn test_tips_denied_paths() {
+        let tips = generate_tips(
+            "[safe-docker] Path matches denied_paths: ~/secrets (resolved: /home/u/secrets, pattern: ~/secrets)",
+        );
+        assert!(tips.iter().any(|t| t.contains("denied_paths")));
+        assert!(!tips.iter().any(|t| t.contains("add it to allowed_paths")));
+    }
+
+    #[test]
+    fn test_tips_symlinked_and_missing_sources() {
+        let tips = generate_tips(
+            "[safe-docker] Path is outside $HOME: ~/x/new (resolved: /etc/new, via symlink /home/u/x -> /etc). Only $HOME paths or allowed_paths are permitted",
+        );
+        assert!(tips.iter().any(|t| t.contains("symlink")));
+
+        let tips = generate_tips(
+            "[safe-docker] Mount source does not exist: ~/new (resolved: /home/u/new). /home/u is writable, so it could be replaced by a symlink before docker creates it; create the directory first",
+        );
+        assert!(tips.iter().any(|t| t.contains("ask_missing_mount_sources")));
+    }
+
     #[test]
     fn test_tips_docker_socket() {
         let tips =
@@ -776,6 +983,80 @@ mod tests {
         assert!(tips.iter().any(|t| t.contains("compose.yml")));
     }
 
+    #[test]
+    fn test_tips_image_lists() {
+        let tips = generate_tips(
+            "Image 'ghcr.io/ourorg/legacy' matches denied_images (resolved: ghcr.io/ourorg/legacy, pattern: ghcr.io/ourorg/legacy)",
+        );
+        assert!(tips.iter().any(|t| t.contains("denied_images")));
+        let tips = generate_tips(
+            "Image 'quay.io/x/y' is from registry 'quay.io', which is not in allowed_registries",
+        );
+        assert!(tips.iter().any(|t| t.contains("allowed_registries")));
+    }
+
+    #[test]
+    fn test_tips_image_pinning() {
+        let tips = generate_tips(
+            "Image 'ghcr.io/ourorg/app:1.0' (docker run) is not pinned by digest, which image_pinning.require_digest requires for ghcr.io. Pin it as ghcr.io/ourorg/app:1.0@sha256:<digest>",
+        );
+        assert!(tips.iter().any(|t| t.contains("imagetools inspect")));
+        let tips = generate_tips(
+            "Image 'ubuntu' (docker run) is untagged (resolves to the mutable 'latest' tag). Pin a version tag or digest (e.g. ubuntu:<version>@sha256:<digest>)",
+        );
+        assert!(tips.iter().any(|t| t.contains("image_pinning")));
+    }
+
+    #[test]
+    fn test_tips_image_signatures() {
+        let tips = generate_tips(
+            "Image 'ghcr.io/ourorg/app:1.0' (docker run) has no verified signature: its digest is unknown. Pull the image first or reference it by @sha256:<digest>",
+        );
+        assert!(tips.iter().any(|t| t.contains("cosign save")));
+    }
+
+    #[test]
+    fn test_tips_dockerfile_lint() {
+        let tips = generate_tips(
+            "Dockerfile rule DF003 (/w/Dockerfile:4): RUN --network=host gives the build step access to the host network",
+        );
+        assert!(
+            tips.iter()
+                .any(|t| t.contains("dockerfile_lint.ignore_rules"))
+        );
+    }
+
+    #[test]
+    fn test_tips_build_context_scan() {
+        let tips = generate_tips(
+            "Build context /w/app contains files that look like secrets and are not excluded by .dockerignore: .env. They are sent to the Docker daemon and can end up in the image via COPY",
+        );
+        assert!(tips.iter().any(|t| t.contains(".dockerignore")));
+    }
+
+    #[test]
+    fn test_tips_published_ports() {
+        let tips =
+            generate_tips("Published port 5432:5432 (docker run) is bound on all interfaces");
+        assert!(tips.iter().any(|t| t.contains("127.0.0.1:5432:5432")));
+        let tips = generate_tips(
+            "Published port 127.0.0.1:80:80 (docker run) uses privileged host port 80",
+        );
+        assert!(tips.iter().any(|t| t.contains("allowed_host_ports")));
+    }
+
+    #[test]
+    fn test_tips_network_policy() {
+        let tips = generate_tips(
+            "Network 'lan' (docker network create) uses the macvlan driver, which attaches containers directly to the host's physical network",
+        );
+        assert!(tips.iter().any(|t| t.contains("network_policy.bridging")));
+        let tips = generate_tips(
+            "Image 'busybox' (docker run) must be isolated by network_policy.isolated_images but is attached to the bridge network",
+        );
+        assert!(tips.iter().any(|t| t.contains("--network none")));
+    }
+
     #[test]
     fn test_tips_image_not_allowed() {
         let tips = generate_tips("Image 'nginx' is not in allowed_images");
@@ -0,0 +1,187 @@
+//! authz-plugin モードの E2E テスト
+//!
+//! Docker デーモンの代わりにプラグインソケットへ認可リクエストを送り、応答を確認する。
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+struct PluginProcess {
+    child: Child,
+    socket: PathBuf,
+    _dir: tempfile::TempDir,
+}
+
+impl Drop for PluginProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn start_plugin() -> PluginProcess {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    std::fs::write(&config_path, "[audit]\nenabled = false\n").unwrap();
+    let socket = dir.path().join("plugins/safe-docker.sock");
+
+    let child = Command::new(env!("CARGO_BIN_EXE_safe-docker"))
+        .arg("authz-plugin")
+        .arg("--listen")
+        .arg(&socket)
+        .env("SAFE_DOCKER_CONFIG", &config_path)
+        .env_remove("SAFE_DOCKER_ASK")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to spawn safe-docker authz-plugin");
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while UnixStream::connect(&socket).is_err() {
+        assert!(Instant::now() < deadline, "plugin did not start");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    PluginProcess {
+        child,
+        socket,
+        _dir: dir,
+    }
+}
+
+fn encode_base64(data: &[u8]) -> String {
+    const TABLE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let mut buf = [0u8; 3];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        let n = u32::from_be_bytes([0, buf[0], buf[1], buf[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// 同じ接続でプラグイン API を呼び出し、応答ボディを返す
+fn call(
+    reader: &mut BufReader<UnixStream>,
+    path: &str,
+    body: &serde_json::Value,
+) -> serde_json::Value {
+    let body = body.to_string();
+    write!(
+        reader.get_mut(),
+        "POST {} HTTP/1.1\r\nHost: plugin\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        path,
+        body.len(),
+        body
+    )
+    .unwrap();
+
+    let mut content_length = 0;
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line).unwrap();
+    assert!(status_line.starts_with("HTTP/1.1 200"), "{}", status_line);
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+            content_length = len.trim().parse().unwrap();
+        }
+        if line == "\r\n" {
+            break;
+        }
+    }
+    let mut response = vec![0; content_length];
+    reader.read_exact(&mut response).unwrap();
+    serde_json::from_slice(&response).unwrap()
+}
+
+fn authz_req(method: &str, uri: &str, body: Option<serde_json::Value>) -> serde_json::Value {
+    let mut request = serde_json::json!({
+        "User": "",
+        "UserAuthNMethod": "",
+        "RequestMethod": method,
+        "RequestURI": uri,
+        "RequestHeaders": {"Content-Type": "application/json"},
+    });
+    if let Some(body) = body {
+        request["RequestBody"] = encode_base64(body.to_string().as_bytes()).into();
+    }
+    request
+}
+
+#[test]
+fn test_plugin_protocol_on_persistent_connection() {
+    let plugin = start_plugin();
+    let mut conn = BufReader::new(UnixStream::connect(&plugin.socket).unwrap());
+
+    let activate = call(&mut conn, "/Plugin.Activate", &serde_json::json!({}));
+    assert_eq!(activate, serde_json::json!({"Implements": ["authz"]}));
+
+    let allowed = call(
+        &mut conn,
+        "/AuthZPlugin.AuthZReq",
+        &authz_req(
+            "POST",
+            "/v1.45/containers/create?name=web",
+            Some(serde_json::json!({"Image": "ubuntu", "HostConfig": {"Binds": ["cache:/data"]}})),
+        ),
+    );
+    assert_eq!(allowed, serde_json::json!({"Allow": true}));
+
+    let denied = call(
+        &mut conn,
+        "/AuthZPlugin.AuthZReq",
+        &authz_req(
+            "POST",
+            "/v1.45/containers/create",
+            Some(
+                serde_json::json!({"Image": "ubuntu", "HostConfig": {"Binds": ["/etc:/host-etc"]}}),
+            ),
+        ),
+    );
+    assert_eq!(denied["Allow"], false);
+    assert!(
+        denied["Msg"].as_str().unwrap().contains("outside $HOME"),
+        "{}",
+        denied
+    );
+
+    let listed = call(
+        &mut conn,
+        "/AuthZPlugin.AuthZReq",
+        &authz_req("GET", "/v1.45/containers/json", None),
+    );
+    assert_eq!(listed["Allow"], true);
+
+    let response = call(
+        &mut conn,
+        "/AuthZPlugin.AuthZRes",
+        &authz_req("GET", "/v1.45/containers/json", None),
+    );
+    assert_eq!(response["Allow"], true);
+}
+
+#[test]
+fn test_plugin_denies_privileged_exec() {
+    let plugin = start_plugin();
+    let mut conn = BufReader::new(UnixStream::connect(&plugin.socket).unwrap());
+    let denied = call(
+        &mut conn,
+        "/AuthZPlugin.AuthZReq",
+        &authz_req(
+            "POST",
+            "/v1.45/containers/web/exec",
+            Some(serde_json::json!({"Privileged": true, "Cmd": ["sh"]})),
+        ),
+    );
+    assert_eq!(denied["Allow"], false);
+}
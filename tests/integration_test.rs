@@ -23,6 +23,10 @@ fn run_hook(input_json: &str) -> (String, i32) {
 }
 
 fn make_bash_input(command: &str) -> String {
+    make_bash_input_in(command, "/tmp")
+}
+
+fn make_bash_input_in(command: &str, cwd: &str) -> String {
     serde_json::json!({
         "session_id": "test-session",
         "hook_event_name": "PreToolUse",
@@ -31,7 +35,7 @@ fn make_bash_input(command: &str) -> String {
             "command": command,
             "description": "test"
         },
-        "cwd": "/tmp"
+        "cwd": cwd
     })
     .to_string()
 }
@@ -40,6 +44,14 @@ fn home_dir() -> String {
     dirs::home_dir().unwrap().to_string_lossy().to_string()
 }
 
+/// $HOME 配下に一時ディレクトリを作る (存在しないマウント元は確認になるため)
+fn home_tempdir() -> tempfile::TempDir {
+    tempfile::Builder::new()
+        .prefix(".safe-docker-test-")
+        .tempdir_in(home_dir())
+        .unwrap()
+}
+
 // --- 非 docker コマンド: allow (exit 0, no output) ---
 
 #[test]
@@ -69,6 +81,311 @@ fn test_non_bash_tool() {
     assert!(stdout.trim().is_empty());
 }
 
+// --- 自己保護: safe-docker / Claude Code 設定の書き換え ---
+
+#[test]
+fn test_write_tool_to_claude_settings_denied() {
+    let input = serde_json::json!({
+        "session_id": "test-session",
+        "hook_event_name": "PreToolUse",
+        "tool_name": "Write",
+        "tool_input": {
+            "file_path": format!("{}/.claude/settings.json", home_dir()),
+            "content": "{}"
+        },
+        "cwd": "/tmp"
+    })
+    .to_string();
+
+    let (stdout, exit_code) = run_hook(&input);
+    assert_eq!(exit_code, 0);
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("deny")
+    );
+    assert!(
+        output["hookSpecificOutput"]["permissionDecisionReason"]
+            .as_str()
+            .unwrap()
+            .contains("Claude Code settings")
+    );
+}
+
+#[test]
+fn test_edit_tool_other_file_allowed() {
+    let input = serde_json::json!({
+        "session_id": "test-session",
+        "hook_event_name": "PreToolUse",
+        "tool_name": "Edit",
+        "tool_input": {
+            "file_path": "/tmp/project/src/main.rs",
+            "old_string": "a",
+            "new_string": "b"
+        },
+        "cwd": "/tmp/project"
+    })
+    .to_string();
+
+    let (stdout, exit_code) = run_hook(&input);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.trim().is_empty());
+}
+
+#[test]
+fn test_bash_sed_safe_docker_config_denied() {
+    let (stdout, exit_code) = run_hook(&make_bash_input(
+        "sed -i 's/block_docker_socket = true/block_docker_socket = false/' ~/.config/safe-docker/config.toml",
+    ));
+    assert_eq!(exit_code, 0);
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("deny")
+    );
+}
+
+#[test]
+fn test_bash_redirect_without_space_to_claude_settings_denied() {
+    for command in [
+        "echo {}>~/.claude/settings.json",
+        "echo {} &>~/.claude/settings.json",
+        "echo {} >&~/.claude/settings.json",
+    ] {
+        let (stdout, exit_code) = run_hook(&make_bash_input(command));
+        assert_eq!(exit_code, 0);
+        let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+        assert_eq!(
+            output["hookSpecificOutput"]["permissionDecision"].as_str(),
+            Some("deny"),
+            "{}",
+            command
+        );
+    }
+}
+
+// --- auto_approve: 明示的 allow ---
+
+fn run_hook_with_config(input_json: &str, config_toml: &str) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    run_hook_with_config_in(dir.path(), input_json, config_toml)
+}
+
+fn run_hook_with_config_in(dir: &std::path::Path, input_json: &str, config_toml: &str) -> String {
+    let config_path = dir.join("config.toml");
+    std::fs::write(&config_path, config_toml).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_safe-docker"))
+        .env("SAFE_DOCKER_CONFIG", &config_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn safe-docker");
+    child
+        .stdin
+        .as_mut()
+        .unwrap()
+        .write_all(input_json.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_auto_approve_emits_allow_with_context() {
+    let stdout = run_hook_with_config(
+        &make_bash_input("docker logs web"),
+        "[hook]\nauto_approve = true\nauto_approve_subcommands = [\"ps\", \"logs\"]\nadditional_context = true\n",
+    );
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("allow")
+    );
+    assert!(
+        output["hookSpecificOutput"]["additionalContext"]
+            .as_str()
+            .unwrap()
+            .contains("docker logs")
+    );
+    assert!(output.get("systemMessage").is_none());
+}
+
+#[test]
+fn test_auto_approve_subcommand_not_listed() {
+    let stdout = run_hook_with_config(
+        &make_bash_input("docker run ubuntu"),
+        "[hook]\nauto_approve = true\nauto_approve_subcommands = [\"ps\"]\n",
+    );
+    assert!(stdout.trim().is_empty());
+}
+
+// --- rewrite: updatedInput によるコマンド書き換え ---
+
+#[test]
+fn test_rewrite_emits_updated_input() {
+    let stdout = run_hook_with_config(
+        &make_bash_input("docker run -p 8080:80 nginx"),
+        "[hook.rewrite]\nenabled = true\nrules = [\"loopback-ports\"]\n",
+    );
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    let hso = &output["hookSpecificOutput"];
+    assert_eq!(hso["permissionDecision"].as_str(), Some("ask"));
+    assert_eq!(
+        hso["updatedInput"]["command"].as_str(),
+        Some("docker run -p 127.0.0.1:8080:80 nginx")
+    );
+    assert_eq!(hso["updatedInput"]["description"].as_str(), Some("test"));
+    assert!(
+        hso["permissionDecisionReason"]
+            .as_str()
+            .unwrap()
+            .contains("-p 8080:80 → -p 127.0.0.1:8080:80")
+    );
+}
+
+#[test]
+fn test_rewrite_audits_rewritten_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let audit_path = dir.path().join("audit.jsonl");
+    let config = format!(
+        "[audit]\nenabled = true\njsonl_path = \"{}\"\n\n[hook.rewrite]\nenabled = true\nrules = [\"build-secrets\"]\n",
+        audit_path.display()
+    );
+    let command = "docker build --build-arg NPM_TOKEN=abc .";
+    let stdout = run_hook_with_config_in(dir.path(), &make_bash_input(command), &config);
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    let rewritten = output["hookSpecificOutput"]["updatedInput"]["command"]
+        .as_str()
+        .unwrap();
+
+    let content = std::fs::read_to_string(&audit_path).unwrap();
+    let event: serde_json::Value = serde_json::from_str(content.trim()).unwrap();
+    assert_eq!(event["command"], rewritten);
+    assert_eq!(event["original_command"], command);
+    // 書き換え後のコマンドの解析結果 (--build-arg のシークレットは含まれない)
+    assert_eq!(event["docker_subcommand"], "build");
+    assert!(
+        event["dangerous_flags"].as_array().unwrap().is_empty(),
+        "{}",
+        event
+    );
+}
+
+// --- PostToolUse: 実行結果の記録 ---
+
+#[test]
+fn test_post_tool_use_records_outcome_and_containers() {
+    let dir = tempfile::tempdir().unwrap();
+    let audit_path = dir.path().join("audit.jsonl");
+    let session_dir = dir.path().join("sessions");
+    let config = format!(
+        "[audit]\nenabled = true\njsonl_path = \"{}\"\n\n[hook]\nsession_dir = \"{}\"\n",
+        audit_path.display(),
+        session_dir.display()
+    );
+    let command = "docker run -d --name web nginx";
+    let container_id = "4f1c2b8a9d0e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3f2a";
+
+    // PreToolUse
+    let stdout = run_hook_with_config_in(dir.path(), &make_bash_input(command), &config);
+    assert!(stdout.trim().is_empty());
+
+    // PostToolUse: 出力なし
+    let post_input = serde_json::json!({
+        "session_id": "test-session",
+        "hook_event_name": "PostToolUse",
+        "tool_name": "Bash",
+        "tool_input": {"command": command},
+        "tool_response": {"stdout": format!("{}\n", container_id), "stderr": "", "interrupted": false},
+        "cwd": "/tmp"
+    })
+    .to_string();
+    let stdout = run_hook_with_config_in(dir.path(), &post_input, &config);
+    assert!(stdout.trim().is_empty());
+
+    let content = std::fs::read_to_string(&audit_path).unwrap();
+    let events: Vec<serde_json::Value> = content
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["hook_event"], "PreToolUse");
+    assert_eq!(events[1]["hook_event"], "PostToolUse");
+    assert_eq!(events[0]["command_hash"], events[1]["command_hash"]);
+    assert_eq!(events[1]["outcome"], "succeeded");
+    assert_eq!(events[1]["container_ids"][0], container_id);
+
+    let session: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(session_dir.join("test-session.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(session["containers"][0]["id"], container_id);
+    assert_eq!(session["containers"][0]["name"], "web");
+    assert_eq!(session["containers"][0]["image"], "nginx");
+}
+
+#[test]
+fn test_policy_rule_sees_session_containers() {
+    let dir = tempfile::tempdir().unwrap();
+    let session_dir = dir.path().join("sessions");
+    let config = format!(
+        "[hook]\nsession_dir = \"{}\"\n\n[[policy.rules]]\nname = \"own-containers-only\"\nwhen = 'command.args[0] == \"exec\" && !command.args.exists(a, a in session.containers)'\ndecision = \"ask\"\n",
+        session_dir.display()
+    );
+    let post_input = serde_json::json!({
+        "session_id": "test-session",
+        "hook_event_name": "PostToolUse",
+        "tool_name": "Bash",
+        "tool_input": {"command": "docker run -d --name web nginx"},
+        "tool_response": {"stdout": "4f1c2b8a9d0e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3f2a\n", "stderr": "", "interrupted": false},
+        "cwd": "/tmp"
+    })
+    .to_string();
+    run_hook_with_config_in(dir.path(), &post_input, &config);
+
+    // セッションで作成したコンテナ (名前・短縮 ID) への exec はルールに一致しない
+    for command in ["docker exec web ls", "docker exec 4f1c2b8a9d0e ls"] {
+        let stdout = run_hook_with_config_in(dir.path(), &make_bash_input(command), &config);
+        assert!(stdout.trim().is_empty(), "{}: {}", command, stdout);
+    }
+
+    let stdout =
+        run_hook_with_config_in(dir.path(), &make_bash_input("docker exec db ls"), &config);
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("ask")
+    );
+}
+
+#[test]
+fn test_post_tool_use_failure_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let audit_path = dir.path().join("audit.jsonl");
+    let config = format!(
+        "[audit]\nenabled = true\njsonl_path = \"{}\"\n",
+        audit_path.display()
+    );
+    let post_input = serde_json::json!({
+        "session_id": "test-session",
+        "hook_event_name": "PostToolUseFailure",
+        "tool_name": "Bash",
+        "tool_input": {"command": "docker run -d nginx"},
+        "error": "Exit code 125",
+        "cwd": "/tmp"
+    })
+    .to_string();
+    run_hook_with_config_in(dir.path(), &post_input, &config);
+
+    let content = std::fs::read_to_string(&audit_path).unwrap();
+    let event: serde_json::Value = serde_json::from_str(content.trim()).unwrap();
+    assert_eq!(event["hook_event"], "PostToolUseFailure");
+    assert_eq!(event["outcome"], "failed");
+    assert!(event.get("container_ids").is_none());
+}
+
 #[test]
 fn test_invalid_json() {
     let (stdout, exit_code) = run_hook("not json at all");
@@ -83,6 +400,127 @@ fn test_invalid_json() {
     );
 }
 
+// --- MCP ツール経由の docker 操作 ---
+
+fn make_mcp_input(tool_name: &str, args: serde_json::Value) -> String {
+    serde_json::json!({
+        "session_id": "test-session",
+        "hook_event_name": "PreToolUse",
+        "tool_name": tool_name,
+        "tool_input": args,
+        "cwd": "/tmp"
+    })
+    .to_string()
+}
+
+#[test]
+fn test_mcp_run_container_privileged_denied() {
+    let (stdout, exit_code) = run_hook(&make_mcp_input(
+        "mcp__docker__run_container",
+        serde_json::json!({"image": "ubuntu", "privileged": true}),
+    ));
+    assert_eq!(exit_code, 0);
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("deny")
+    );
+    assert!(
+        output["hookSpecificOutput"]["permissionDecisionReason"]
+            .as_str()
+            .unwrap()
+            .contains("--privileged")
+    );
+}
+
+#[test]
+fn test_mcp_run_container_mount_outside_home_denied() {
+    let (stdout, _) = run_hook(&make_mcp_input(
+        "mcp__docker__run_container",
+        serde_json::json!({"image": "ubuntu", "volumes": {"/etc": {"bind": "/data", "mode": "rw"}}}),
+    ));
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("deny")
+    );
+}
+
+#[test]
+fn test_mcp_run_container_safe_allowed() {
+    let (stdout, exit_code) = run_hook(&make_mcp_input(
+        "mcp__docker__run_container",
+        serde_json::json!({"image": "ubuntu", "ports": {"80/tcp": 8080}}),
+    ));
+    assert_eq!(exit_code, 0);
+    assert!(stdout.trim().is_empty());
+}
+
+#[test]
+fn test_mcp_unmapped_argument_asks() {
+    let (stdout, exit_code) = run_hook(&make_mcp_input(
+        "mcp__docker__run_container",
+        serde_json::json!({"image": "ubuntu", "userns_mode": "host"}),
+    ));
+    assert_eq!(exit_code, 0);
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("ask")
+    );
+    assert!(
+        output["hookSpecificOutput"]["permissionDecisionReason"]
+            .as_str()
+            .unwrap()
+            .contains("userns_mode")
+    );
+}
+
+#[test]
+fn test_mcp_custom_mapping() {
+    let stdout = run_hook_with_config(
+        &make_mcp_input(
+            "mcp__containers__start",
+            serde_json::json!({"spec": {"image": "ubuntu", "binds": ["/var/run/docker.sock:/var/run/docker.sock"]}}),
+        ),
+        "[[hook.mcp_tools]]\ntool = \"mcp__containers__*\"\nimage = \"spec.image\"\nvolumes = \"spec.binds\"\n",
+    );
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("deny")
+    );
+    assert!(
+        output["hookSpecificOutput"]["permissionDecisionReason"]
+            .as_str()
+            .unwrap()
+            .contains("docker.sock")
+    );
+}
+
+#[test]
+fn test_mcp_uninterpretable_args_denied() {
+    let (stdout, _) = run_hook(&make_mcp_input(
+        "mcp__docker__run_container",
+        serde_json::json!({"image": "ubuntu", "volumes": 42}),
+    ));
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("deny")
+    );
+}
+
+#[test]
+fn test_mcp_unmapped_tool_allowed() {
+    let (stdout, exit_code) = run_hook(&make_mcp_input(
+        "mcp__github__create_issue",
+        serde_json::json!({"title": "privileged: true"}),
+    ));
+    assert_eq!(exit_code, 0);
+    assert!(stdout.trim().is_empty());
+}
+
 // --- docker run: mount outside $HOME → deny ---
 
 #[test]
@@ -117,7 +555,8 @@ fn test_deny_mount_root() {
 
 #[test]
 fn test_allow_mount_home() {
-    let cmd = format!("docker run -v {}/projects:/app ubuntu", home_dir());
+    let dir = home_tempdir();
+    let cmd = format!("docker run -v {}:/app ubuntu", dir.path().display());
     let (stdout, exit_code) = run_hook(&make_bash_input(&cmd));
     assert_eq!(exit_code, 0);
     assert!(
@@ -128,11 +567,38 @@ fn test_allow_mount_home() {
 
 #[test]
 fn test_allow_tilde_mount() {
-    let (stdout, exit_code) = run_hook(&make_bash_input("docker run -v ~/projects:/app ubuntu"));
+    let dir = home_tempdir();
+    let name = dir.path().file_name().unwrap().to_str().unwrap();
+    let (stdout, exit_code) = run_hook(&make_bash_input(&format!(
+        "docker run -v ~/{}:/app ubuntu",
+        name
+    )));
     assert_eq!(exit_code, 0);
     assert!(stdout.trim().is_empty());
 }
 
+#[test]
+fn test_ask_missing_mount_source_by_default() {
+    let dir = home_tempdir();
+    let cmd = format!(
+        "docker run -v {}/not-created-yet:/app ubuntu",
+        dir.path().display()
+    );
+    let (stdout, exit_code) = run_hook(&make_bash_input(&cmd));
+    assert_eq!(exit_code, 0);
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("ask")
+    );
+
+    let stdout = run_hook_with_config(
+        &make_bash_input(&cmd),
+        "ask_missing_mount_sources = false\n",
+    );
+    assert!(stdout.trim().is_empty(), "{}", stdout);
+}
+
 // --- docker run: sensitive path → ask ---
 
 #[test]
@@ -246,7 +712,12 @@ fn test_deny_piped_command() {
 
 #[test]
 fn test_allow_docker_build() {
-    let (stdout, exit_code) = run_hook(&make_bash_input("docker build -t myapp ."));
+    // ビルドコンテキストの走査が /tmp の内容に左右されないよう、空のディレクトリで実行する
+    let dir = tempfile::tempdir().unwrap();
+    let (stdout, exit_code) = run_hook(&make_bash_input_in(
+        "docker build -t myapp .",
+        &dir.path().to_string_lossy(),
+    ));
     assert_eq!(exit_code, 0);
     assert!(stdout.trim().is_empty());
 }
@@ -460,7 +931,12 @@ fn test_allow_docker_compose_exec() {
 
 #[test]
 fn test_allow_docker_buildx_build() {
-    let (stdout, exit_code) = run_hook(&make_bash_input("docker buildx build -t myapp ."));
+    // ビルドコンテキストの走査が /tmp の内容に左右されないよう、空のディレクトリで実行する
+    let dir = tempfile::tempdir().unwrap();
+    let (stdout, exit_code) = run_hook(&make_bash_input_in(
+        "docker buildx build -t myapp .",
+        &dir.path().to_string_lossy(),
+    ));
     assert_eq!(exit_code, 0);
     assert!(
         stdout.trim().is_empty(),
@@ -623,3 +1099,208 @@ fn test_check_config_broken_toml() {
     assert_eq!(exit_code, 1, "Broken TOML should fail: {}", stderr);
     assert!(stderr.contains("Error"));
 }
+
+// --- [policy]: ポリシー拡張ルール ---
+
+const POLICY_CONFIG: &str = r#"
+[[policy.rules]]
+name = "public-port"
+when = "command.args.exists(a, a.startsWith('0.0.0.0:'))"
+decision = "ask"
+message = "Publishing on 0.0.0.0 needs approval"
+
+[[policy.rules]]
+name = "session-image"
+when = "session.id == 'test-session' && command.image.startsWith('internal/')"
+message = "internal images are not allowed in agent sessions"
+"#;
+
+#[test]
+fn test_policy_rule_asks_for_bash_command() {
+    let stdout = run_hook_with_config(
+        &make_bash_input("docker run -p 0.0.0.0:80:80 nginx"),
+        POLICY_CONFIG,
+    );
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("ask")
+    );
+    assert!(
+        output["hookSpecificOutput"]["permissionDecisionReason"]
+            .as_str()
+            .unwrap()
+            .contains("policy rule 'public-port'")
+    );
+
+    let stdout = run_hook_with_config(
+        &make_bash_input("docker run -p 127.0.0.1:80:80 nginx"),
+        POLICY_CONFIG,
+    );
+    assert!(stdout.trim().is_empty());
+}
+
+#[test]
+fn test_policy_rule_sees_session_for_mcp_call() {
+    let stdout = run_hook_with_config(
+        &make_mcp_input(
+            "mcp__docker__run_container",
+            serde_json::json!({"image": "internal/tools"}),
+        ),
+        POLICY_CONFIG,
+    );
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("deny")
+    );
+    assert!(
+        output["hookSpecificOutput"]["permissionDecisionReason"]
+            .as_str()
+            .unwrap()
+            .contains("not allowed in agent sessions")
+    );
+}
+
+// --- [[plugins]]: 外部ポリシープラグイン ---
+
+#[test]
+fn test_plugin_findings_and_failures() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("registry-check.sh");
+    std::fs::write(
+        &script,
+        r#"#!/bin/sh
+if grep -q '"image":"ubuntu"'; then
+  echo '{}'
+else
+  echo '{"findings": [{"decision": "ask", "message": "unknown image"}]}'
+fi
+"#,
+    )
+    .unwrap();
+    let config = format!(
+        "[[plugins]]\nname = \"registry\"\ncommand = \"/bin/sh {}\"\ntimeout_ms = 5000\n",
+        script.display()
+    );
+
+    let stdout =
+        run_hook_with_config_in(dir.path(), &make_bash_input("docker run ubuntu"), &config);
+    assert!(stdout.trim().is_empty(), "{}", stdout);
+
+    let stdout =
+        run_hook_with_config_in(dir.path(), &make_bash_input("docker run alpine"), &config);
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("ask")
+    );
+    assert!(
+        output["hookSpecificOutput"]["permissionDecisionReason"]
+            .as_str()
+            .unwrap()
+            .contains("unknown image (plugin 'registry')")
+    );
+
+    // 非 docker コマンドではプラグインを起動しない
+    let stdout = run_hook_with_config_in(dir.path(), &make_bash_input("ls -la"), &config);
+    assert!(stdout.trim().is_empty());
+
+    let crashing = "[[plugins]]\nname = \"crashing\"\ncommand = \"/bin/false\"\n";
+    let stdout = run_hook_with_config(&make_bash_input("docker run ubuntu"), crashing);
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("deny")
+    );
+}
+
+// --- workspace_root: $HOME 配下のマウント範囲 ---
+
+#[test]
+fn test_workspace_root_cwd_uses_hook_cwd() {
+    let home = home_dir();
+    let input = |command: &str| {
+        serde_json::json!({
+            "session_id": "test-session",
+            "hook_event_name": "PreToolUse",
+            "tool_name": "Bash",
+            "tool_input": {"command": command},
+            "cwd": format!("{}/work/project-a", home),
+        })
+        .to_string()
+    };
+    // マウント元が存在しない場合の確認はこのテストの対象外
+    let config = "workspace_root = \"cwd\"\nask_missing_mount_sources = false\n";
+
+    let stdout = run_hook_with_config(
+        &input(&format!(
+            "docker run -v {}/work/project-a/src:/src ubuntu",
+            home
+        )),
+        config,
+    );
+    assert!(stdout.trim().is_empty(), "{}", stdout);
+
+    let stdout = run_hook_with_config(
+        &input(&format!("docker run -v {}/work/project-b:/b ubuntu", home)),
+        config,
+    );
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        output["hookSpecificOutput"]["permissionDecision"].as_str(),
+        Some("ask")
+    );
+    assert!(
+        output["hookSpecificOutput"]["permissionDecisionReason"]
+            .as_str()
+            .unwrap()
+            .contains("outside the workspace")
+    );
+
+    // デフォルト ("home") では $HOME 全体を許可する
+    let stdout = run_hook_with_config(
+        &input(&format!("docker run -v {}/work/project-b:/b ubuntu", home)),
+        "ask_missing_mount_sources = false\n",
+    );
+    assert!(stdout.trim().is_empty(), "{}", stdout);
+}
+
+#[test]
+fn test_denied_paths_and_sensitive_globs() {
+    let home = home_dir();
+    let config = r#"
+sensitive_paths = [".ssh", "**/*.pem"]
+denied_paths = ["~/vault"]
+ask_missing_mount_sources = false
+"#;
+    let decision = |command: &str| {
+        let stdout = run_hook_with_config(&make_bash_input(command), config);
+        if stdout.trim().is_empty() {
+            return ("allow".to_string(), String::new());
+        }
+        let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+        let hook = &output["hookSpecificOutput"];
+        (
+            hook["permissionDecision"].as_str().unwrap().to_string(),
+            hook["permissionDecisionReason"]
+                .as_str()
+                .unwrap()
+                .to_string(),
+        )
+    };
+
+    // denied_paths は $HOME 配下でも拒否
+    let (result, reason) = decision(&format!("docker run -v {}/vault:/v ubuntu", home));
+    assert_eq!(result, "deny");
+    assert!(reason.contains("denied_paths"), "{}", reason);
+
+    // glob の機密パスは確認、パス要素の境界を越えては一致しない
+    let (result, _) = decision(&format!(
+        "docker run -v {}/work/app/tls/server.pem:/cert ubuntu",
+        home
+    ));
+    assert_eq!(result, "ask");
+    let (result, _) = decision(&format!("docker run -v {}/.sshfoo:/x ubuntu", home));
+    assert_eq!(result, "allow");
+}
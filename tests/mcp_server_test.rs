@@ -0,0 +1,163 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// MCP サーバーモードで safe-docker を起動し、JSON-RPC メッセージを順に送って応答を返す
+fn run_mcp(messages: &[serde_json::Value], env_vars: &[(&str, &str)]) -> Vec<serde_json::Value> {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    std::fs::write(&config_path, "[audit]\nenabled = false\n").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_safe-docker"));
+    cmd.arg("mcp")
+        .current_dir(dir.path())
+        .env("SAFE_DOCKER_CONFIG", &config_path)
+        // モック docker として /bin/echo を使用
+        .env("SAFE_DOCKER_DOCKER_PATH", "/bin/echo")
+        .env_remove("SAFE_DOCKER_ACTIVE")
+        .env_remove("SAFE_DOCKER_ASK");
+    for (key, value) in env_vars {
+        cmd.env(key, value);
+    }
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn safe-docker");
+
+    {
+        let stdin = child.stdin.as_mut().unwrap();
+        for message in messages {
+            writeln!(stdin, "{}", message).unwrap();
+        }
+    }
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn tool_call(id: u64, name: &str, arguments: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "tools/call",
+        "params": { "name": name, "arguments": arguments },
+    })
+}
+
+#[test]
+fn test_mcp_session() {
+    let responses = run_mcp(
+        &[
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "clientInfo": { "name": "test", "version": "0" }
+                }
+            }),
+            serde_json::json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            serde_json::json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+        ],
+        &[],
+    );
+    // 通知には応答しない
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["result"]["protocolVersion"], "2025-06-18");
+    assert_eq!(responses[1]["id"], 2);
+    assert_eq!(responses[1]["result"]["tools"].as_array().unwrap().len(), 5);
+}
+
+#[test]
+fn test_mcp_docker_run_allowed_is_executed() {
+    let responses = run_mcp(
+        &[tool_call(
+            1,
+            "docker_run",
+            serde_json::json!({ "image": "ubuntu", "name": "web", "command": ["echo", "hi"] }),
+        )],
+        &[],
+    );
+    let result = &responses[0]["result"];
+    assert_eq!(result["isError"], false, "result: {}", result);
+    // /bin/echo がモック docker として実行され、引数がそのまま出力される
+    assert_eq!(
+        result["structuredContent"]["stdout"]
+            .as_str()
+            .unwrap()
+            .trim(),
+        "run -d --name web ubuntu echo hi"
+    );
+    assert_eq!(result["structuredContent"]["exit_code"], 0);
+}
+
+#[test]
+fn test_mcp_docker_run_denied_is_not_executed() {
+    let responses = run_mcp(
+        &[tool_call(
+            1,
+            "docker_run",
+            serde_json::json!({ "image": "ubuntu", "volumes": ["/etc:/data"] }),
+        )],
+        &[],
+    );
+    let result = &responses[0]["result"];
+    assert_eq!(result["isError"], true);
+    assert_eq!(result["structuredContent"]["decision"], "deny");
+    assert!(result["structuredContent"].get("stdout").is_none());
+    assert!(
+        result["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("outside $HOME")
+    );
+}
+
+#[test]
+fn test_mcp_ask_follows_non_interactive_setting() {
+    // 機密パスのマウントは ask。非対話環境のデフォルト (deny) ではエラーとして返す
+    let home = dirs::home_dir().unwrap().to_string_lossy().to_string();
+    let call = tool_call(
+        1,
+        "docker_run",
+        serde_json::json!({ "image": "ubuntu", "volumes": [format!("{}/.ssh:/keys:ro", home)] }),
+    );
+    let responses = run_mcp(std::slice::from_ref(&call), &[]);
+    assert_eq!(responses[0]["result"]["isError"], true);
+    assert_eq!(
+        responses[0]["result"]["structuredContent"]["decision"],
+        "ask"
+    );
+
+    let responses = run_mcp(&[call], &[("SAFE_DOCKER_ASK", "allow")]);
+    assert_eq!(responses[0]["result"]["isError"], false);
+}
+
+#[test]
+fn test_mcp_invalid_json_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_safe-docker"))
+        .arg("mcp")
+        .env("SAFE_DOCKER_CONFIG", dir.path().join("missing.toml"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .as_mut()
+        .unwrap()
+        .write_all(b"not json\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let response: serde_json::Value =
+        serde_json::from_str(String::from_utf8_lossy(&output.stdout).trim()).unwrap();
+    assert_eq!(response["error"]["code"], -32700);
+}
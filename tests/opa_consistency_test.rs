@@ -1,9 +1,29 @@
 //! OPA authz.rego と safe-docker のデフォルトポリシーの一貫性検証テスト
 //!
 //! safe-docker のポリシーを拡張した際に、OPA 側の authz.rego への反映漏れを検出する。
+//! opa/authz.rego はデフォルト設定に対する `safe-docker export-rego` の出力と一致すること。
 //! `cargo test` の一部として自動実行される。
 
 use std::collections::HashSet;
+use std::process::Command;
+
+/// `safe-docker export-rego` を実行して生成された Rego を返す
+fn export_rego(config: Option<&str>, home: &str) -> (String, i32) {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    if let Some(content) = config {
+        std::fs::write(&config_path, content).unwrap();
+    }
+    let output = Command::new(env!("CARGO_BIN_EXE_safe-docker"))
+        .args(["export-rego", "--home", home])
+        .env("SAFE_DOCKER_CONFIG", &config_path)
+        .output()
+        .expect("Failed to run safe-docker export-rego");
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        output.status.code().unwrap_or(-1),
+    )
+}
 
 /// opa/authz.rego の内容を読み込む
 fn read_rego() -> String {
@@ -88,26 +108,56 @@ fn extract_rego_namespace_fields(rego: &str) -> HashSet<String> {
     fields
 }
 
-/// OPA authz.rego から security-opt deny ルールのパターンを抽出する
+/// OPA authz.rego から security-opt deny ルールのパターン (`contains(opt, "...")`) を抽出する
 fn extract_rego_security_opts(rego: &str) -> HashSet<String> {
-    let mut opts = HashSet::new();
-    let patterns = [
-        "apparmor=unconfined",
-        "seccomp=unconfined",
-        "label=disable",
-        "label:disable",
-        "no-new-privileges=false",
-        "systempaths=unconfined",
-    ];
+    rego.lines()
+        .filter_map(|line| {
+            line.trim()
+                .strip_prefix("contains(opt, \"")?
+                .strip_suffix("\")")
+                .map(String::from)
+        })
+        .collect()
+}
 
-    for pattern in &patterns {
-        let search = format!("contains(opt, \"{}\")", pattern);
-        if rego.contains(&search) {
-            opts.insert(pattern.to_string());
-        }
+/// hook モードで docker コマンドを評価し、permissionDecision を返す (allow は "allow")
+fn hook_decision(command: &str) -> String {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    std::fs::write(&config_path, "[audit]\nenabled = false\n").unwrap();
+    let input = serde_json::json!({
+        "session_id": "test-session",
+        "hook_event_name": "PreToolUse",
+        "tool_name": "Bash",
+        "tool_input": {"command": command},
+        "cwd": "/tmp",
+    });
+    let mut child = Command::new(env!("CARGO_BIN_EXE_safe-docker"))
+        .env("SAFE_DOCKER_CONFIG", &config_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to spawn safe-docker");
+    child
+        .stdin
+        .as_mut()
+        .unwrap()
+        .write_all(input.to_string().as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        return "allow".to_string();
     }
-
-    opts
+    let output: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    output["hookSpecificOutput"]["permissionDecision"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string()
 }
 
 // =============================================================================
@@ -242,6 +292,26 @@ fn test_rego_contains_all_security_opt_deny_rules() {
     );
 }
 
+#[test]
+fn test_rego_security_opts_match_builtin_policy() {
+    // Rego のルールと組み込みポリシーは同じリストから作られるため、
+    // Rego が deny するパターンはすべて safe-docker でも deny される
+    let found = extract_rego_security_opts(&read_rego());
+    assert!(!found.is_empty());
+    for opt in &found {
+        assert_eq!(
+            hook_decision(&format!("docker run --security-opt {} ubuntu", opt)),
+            "deny",
+            "--security-opt {} is denied by opa/authz.rego but not by safe-docker",
+            opt
+        );
+    }
+    assert_eq!(
+        hook_decision("docker run --security-opt no-new-privileges=true ubuntu"),
+        "allow"
+    );
+}
+
 #[test]
 fn test_rego_contains_docker_socket_deny() {
     let rego = read_rego();
@@ -266,6 +336,17 @@ fn test_rego_contains_bind_mount_path_restriction() {
     );
 }
 
+#[test]
+fn test_rego_contains_volume_device_deny() {
+    let rego = read_rego();
+    // docker volume create --opt device=... と --mount の volume-opt=device=...
+    assert!(
+        rego.contains("input.Body.DriverOpts.device")
+            && rego.contains("VolumeOptions.DriverConfig.Options.device"),
+        "opa/authz.rego must deny volume driver options that mount a host path (device=)"
+    );
+}
+
 #[test]
 fn test_rego_contains_plugin_lockout_prevention() {
     let rego = read_rego();
@@ -312,3 +393,60 @@ fn test_rego_capabilities_match_config_defaults() {
         expected.difference(&rego_caps).collect::<Vec<_>>()
     );
 }
+
+#[test]
+fn test_rego_matches_export_rego_output() {
+    // opa/authz.rego はデフォルト設定から生成したものと一致すること
+    let (generated, code) = export_rego(None, "/home/username");
+    assert_eq!(code, 0);
+    assert!(
+        read_rego() == generated,
+        "opa/authz.rego is out of date with the default config.\n\
+         Hint: SAFE_DOCKER_CONFIG=/nonexistent cargo run -- export-rego --home /home/username > opa/authz.rego"
+    );
+}
+
+#[test]
+fn test_export_rego_reflects_config() {
+    let (rego, code) = export_rego(
+        Some(
+            r#"
+allowed_paths = ["/data/projects"]
+sensitive_paths = [".ssh"]
+blocked_capabilities = ["SYS_ADMIN", "net_raw"]
+allowed_images = ["ubuntu", "node"]
+block_docker_socket = false
+"#,
+        ),
+        "/home/alice",
+    );
+    assert_eq!(code, 0);
+
+    let caps = extract_rego_capabilities(&rego);
+    let expected: HashSet<String> = ["SYS_ADMIN", "NET_RAW"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(caps, expected);
+
+    assert!(rego.contains("\"/home/alice/\",\n    \"/data/projects\","));
+    assert!(rego.contains("\"/home/alice/.ssh\","));
+    assert!(!rego.contains("/home/alice/.aws"));
+    assert!(rego.contains("\"ubuntu\",\n    \"node\","));
+    assert!(!rego.contains("/var/run/docker.sock"));
+    assert!(!rego.contains("/home/username"));
+    // 設定に関わらず常に含まれるルール
+    assert!(rego.contains("HostConfig.Privileged == true"));
+    assert_eq!(
+        extract_rego_security_opts(&rego),
+        extract_rego_security_opts(&read_rego()),
+        "all security-opt rules must be present"
+    );
+}
+
+#[test]
+fn test_export_rego_invalid_config_fails() {
+    let (stdout, code) = export_rego(Some("blocked_capabilities = 42"), "/home/alice");
+    assert_eq!(code, 1);
+    assert!(stdout.is_empty());
+}
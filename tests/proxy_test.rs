@@ -0,0 +1,390 @@
+//! proxy モードの E2E テスト
+//!
+//! 偽の上流 docker ソケットを立て、safe-docker proxy 経由で Engine API リクエストを送り、
+//! 許可されたリクエストだけが上流に届くことを確認する。
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const UPSTREAM_RESPONSE: &str = "HTTP/1.1 201 Created\r\nContent-Type: application/json\r\nContent-Length: 15\r\nConnection: close\r\n\r\n{\"Id\":\"abc123\"}";
+
+/// 偽の上流 docker デーモン。受け取ったリクエスト (ヘッダー + ボディ) を記録する。
+fn spawn_upstream(path: &Path) -> Arc<Mutex<Vec<String>>> {
+    let listener = UnixListener::bind(path).unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&received);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { break };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = len.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8_lossy(&body));
+            log.lock().unwrap().push(request);
+            let mut stream = stream;
+            stream.write_all(UPSTREAM_RESPONSE.as_bytes()).unwrap();
+        }
+    });
+    received
+}
+
+/// 偽の上流 docker デーモン (BuildKit session 用)。Upgrade に 101 を返し、以降の入力をそのまま返す。
+fn spawn_upgrading_upstream(path: &Path) -> Arc<Mutex<Vec<String>>> {
+    let listener = UnixListener::bind(path).unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&received);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                    break;
+                }
+                request.push_str(&line);
+            }
+            log.lock().unwrap().push(request);
+            stream
+                .write_all(b"HTTP/1.1 101 Switching Protocols\r\nConnection: Upgrade\r\nUpgrade: h2c\r\n\r\n")
+                .unwrap();
+            let mut buf = [0; 64];
+            while let Ok(n) = reader.read(&mut buf) {
+                if n == 0 || stream.write_all(&buf[..n]).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    received
+}
+
+struct ProxyProcess {
+    child: Child,
+    socket: PathBuf,
+    received: Arc<Mutex<Vec<String>>>,
+    _dir: tempfile::TempDir,
+}
+
+impl Drop for ProxyProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn start_proxy(env_vars: &[(&str, &str)]) -> ProxyProcess {
+    start_proxy_with(env_vars, spawn_upstream)
+}
+
+fn start_proxy_with(
+    env_vars: &[(&str, &str)],
+    upstream_fn: fn(&Path) -> Arc<Mutex<Vec<String>>>,
+) -> ProxyProcess {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    std::fs::write(&config_path, "[audit]\nenabled = false\n").unwrap();
+    let upstream = dir.path().join("upstream.sock");
+    let socket = dir.path().join("proxy/docker.sock");
+    let received = upstream_fn(&upstream);
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_safe-docker"));
+    cmd.arg("proxy")
+        .arg("--listen")
+        .arg(&socket)
+        .arg("--upstream")
+        .arg(&upstream)
+        .env("SAFE_DOCKER_CONFIG", &config_path)
+        .env_remove("SAFE_DOCKER_ASK")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    for (key, value) in env_vars {
+        cmd.env(key, value);
+    }
+    let child = cmd.spawn().expect("Failed to spawn safe-docker proxy");
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while UnixStream::connect(&socket).is_err() {
+        assert!(Instant::now() < deadline, "proxy did not start");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    ProxyProcess {
+        child,
+        socket,
+        received,
+        _dir: dir,
+    }
+}
+
+impl ProxyProcess {
+    /// リクエストを送り、(ステータスコード, ボディ) を返す
+    fn send(&self, request: &str) -> (u16, String) {
+        let mut stream = UnixStream::connect(&self.socket).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response
+            .split(' ')
+            .nth(1)
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_else(|| panic!("invalid response: {:?}", response));
+        let body = response
+            .split_once("\r\n\r\n")
+            .map(|(_, b)| b.to_string())
+            .unwrap_or_default();
+        (status, body)
+    }
+
+    fn received(&self) -> Vec<String> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+fn post_json(target: &str, body: &serde_json::Value) -> String {
+    let body = body.to_string();
+    format!(
+        "POST {} HTTP/1.1\r\nHost: docker\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        target,
+        body.len(),
+        body
+    )
+}
+
+fn message(body: &str) -> String {
+    let json: serde_json::Value = serde_json::from_str(body).unwrap();
+    json["message"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_allowed_create_is_forwarded() {
+    let proxy = start_proxy(&[]);
+    let (status, body) = proxy.send(&post_json(
+        "/v1.45/containers/create?name=web",
+        &serde_json::json!({"Image": "ubuntu", "HostConfig": {"Binds": ["cache:/data"]}}),
+    ));
+    assert_eq!(status, 201);
+    assert_eq!(body, "{\"Id\":\"abc123\"}");
+
+    let received = proxy.received();
+    assert_eq!(received.len(), 1);
+    assert!(received[0].starts_with("POST /v1.45/containers/create?name=web HTTP/1.1\r\n"));
+    assert!(received[0].contains("Connection: close\r\n"));
+    assert!(received[0].ends_with("\"Image\":\"ubuntu\"}"));
+}
+
+#[test]
+fn test_dangerous_create_is_rejected() {
+    let proxy = start_proxy(&[]);
+    let (status, body) = proxy.send(&post_json(
+        "/v1.45/containers/create",
+        &serde_json::json!({"Image": "ubuntu", "HostConfig": {"Privileged": true}}),
+    ));
+    assert_eq!(status, 403);
+    assert!(message(&body).contains("--privileged"), "{}", body);
+
+    let (status, body) = proxy.send(&post_json(
+        "/containers/create",
+        &serde_json::json!({"Image": "ubuntu", "HostConfig": {"Binds": ["/etc:/host-etc"]}}),
+    ));
+    assert_eq!(status, 403);
+    assert!(message(&body).contains("outside $HOME"), "{}", body);
+    assert!(proxy.received().is_empty());
+}
+
+#[test]
+fn test_lowercase_keys_are_inspected() {
+    let proxy = start_proxy(&[]);
+    let (status, body) = proxy.send(&post_json(
+        "/containers/create",
+        &serde_json::json!({"image": "ubuntu", "hostconfig": {"privileged": true, "binds": ["/:/host"]}}),
+    ));
+    assert_eq!(status, 403);
+    assert!(message(&body).contains("--privileged"), "{}", body);
+    assert!(proxy.received().is_empty());
+}
+
+#[test]
+fn test_chunked_create_body_is_inspected() {
+    let proxy = start_proxy(&[]);
+    let body = r#"{"Image":"ubuntu","HostConfig":{"Binds":["/etc:/x"]}}"#;
+    let (first, rest) = body.split_at(10);
+    let request = format!(
+        "POST /containers/create HTTP/1.1\r\nHost: docker\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+        first.len(),
+        first,
+        rest.len(),
+        rest
+    );
+    let (status, _) = proxy.send(&request);
+    assert_eq!(status, 403);
+    assert!(proxy.received().is_empty());
+}
+
+#[test]
+fn test_other_requests_are_passed_through() {
+    let proxy = start_proxy(&[]);
+    let (status, _) = proxy.send("GET /v1.45/containers/json HTTP/1.1\r\nHost: docker\r\n\r\n");
+    assert_eq!(status, 201);
+    assert!(proxy.received()[0].starts_with("GET /v1.45/containers/json HTTP/1.1\r\n"));
+}
+
+#[test]
+fn test_build_and_exec_are_inspected() {
+    let proxy = start_proxy(&[]);
+    // 機密情報らしい build-arg は ask → 非対話のデフォルト (deny) でブロック
+    let (status, body) = proxy.send(
+        "POST /v1.45/build?t=app&buildargs=%7B%22GITHUB_TOKEN%22%3A%22x%22%7D HTTP/1.1\r\nHost: docker\r\nContent-Length: 0\r\n\r\n",
+    );
+    assert_eq!(status, 403);
+    assert!(message(&body).contains("GITHUB_TOKEN"), "{}", body);
+
+    let (status, _) = proxy.send(&post_json(
+        "/v1.45/containers/web/exec",
+        &serde_json::json!({"Privileged": true, "Cmd": ["sh"]}),
+    ));
+    assert_eq!(status, 403);
+    assert!(proxy.received().is_empty());
+
+    let (status, _) = proxy
+        .send("POST /v1.45/build?t=app HTTP/1.1\r\nHost: docker\r\nContent-Length: 4\r\n\r\ntar!");
+    assert_eq!(status, 201);
+    assert!(proxy.received()[0].ends_with("\r\n\r\ntar!"));
+}
+
+#[test]
+fn test_unknown_mutating_requests_are_blocked() {
+    let proxy = start_proxy(&[]);
+    for request in [
+        "POST /v1.45/plugins/pull?remote=evil/plugin HTTP/1.1\r\nHost: docker\r\nContent-Length: 2\r\n\r\n[]",
+        "POST /v1.45/containers/web/update HTTP/1.1\r\nHost: docker\r\nContent-Length: 2\r\n\r\n{}",
+        "POST /services/create HTTP/1.1\r\nHost: docker\r\nContent-Length: 2\r\n\r\n{}",
+    ] {
+        let (status, body) = proxy.send(request);
+        assert_eq!(status, 403, "{}", request);
+        assert!(message(&body).contains("is not inspected"), "{}", body);
+    }
+    assert!(proxy.received().is_empty());
+
+    let (status, _) =
+        proxy.send("POST /v1.45/containers/web/start HTTP/1.1\r\nHost: docker\r\n\r\n");
+    assert_eq!(status, 201);
+    assert_eq!(proxy.received().len(), 1);
+}
+
+#[test]
+fn test_ask_follows_non_interactive_setting() {
+    let proxy = start_proxy(&[("SAFE_DOCKER_ASK", "allow")]);
+    let (status, _) = proxy.send(
+        "POST /build?buildargs=%7B%22GITHUB_TOKEN%22%3A%22x%22%7D HTTP/1.1\r\nHost: docker\r\nContent-Length: 0\r\n\r\n",
+    );
+    assert_eq!(status, 201);
+}
+
+#[test]
+fn test_invalid_body_is_rejected() {
+    let proxy = start_proxy(&[]);
+    let (status, body) = proxy.send(
+        "POST /containers/create HTTP/1.1\r\nHost: docker\r\nContent-Length: 8\r\n\r\nnot json",
+    );
+    assert_eq!(status, 403);
+    assert!(message(&body).contains("Cannot interpret Engine API request"));
+    assert!(proxy.received().is_empty());
+}
+
+#[test]
+fn test_request_pipelined_after_upgrade_is_not_forwarded() {
+    let proxy = start_proxy(&[]);
+    let create = post_json(
+        "/containers/create",
+        &serde_json::json!({"Image": "ubuntu", "HostConfig": {"Privileged": true}}),
+    );
+
+    // attach / exec 以外の Upgrade は通常のリクエストとして転送し、後続のデータは転送しない
+    let (status, _) = proxy.send(&format!(
+        "GET /_ping HTTP/1.1\r\nHost: docker\r\nConnection: Upgrade\r\nUpgrade: tcp\r\n\r\n{}",
+        create
+    ));
+    assert_eq!(status, 201);
+    let received = proxy.received();
+    assert_eq!(received.len(), 1);
+    assert!(received[0].starts_with("GET /_ping HTTP/1.1\r\n"));
+    assert!(received[0].contains("Connection: close\r\n"));
+    assert!(!received[0].contains("Upgrade"));
+    assert!(!received[0].contains("Privileged"));
+
+    // exec start の Upgrade に続けて送られたデータは拒否する
+    let (status, body) = proxy.send(&format!(
+        "POST /v1.45/exec/abc/start HTTP/1.1\r\nHost: docker\r\nConnection: Upgrade\r\nUpgrade: tcp\r\nContent-Length: 2\r\n\r\n{{}}{}",
+        create
+    ));
+    assert_eq!(status, 400);
+    assert!(
+        message(&body).contains("after an upgrade request"),
+        "{}",
+        body
+    );
+    assert_eq!(proxy.received().len(), 1);
+
+    // ボディだけなら転送する (上流が 101 を返さないため中継はしない)
+    let (status, _) = proxy.send(
+        "POST /v1.45/exec/abc/start HTTP/1.1\r\nHost: docker\r\nConnection: Upgrade\r\nUpgrade: tcp\r\nContent-Length: 16\r\n\r\n{\"Detach\":false}",
+    );
+    assert_eq!(status, 201);
+    let received = proxy.received();
+    assert_eq!(received.len(), 2);
+    assert!(received[1].contains("Upgrade: tcp\r\n"));
+    assert!(received[1].ends_with("{\"Detach\":false}"));
+}
+
+#[test]
+fn test_buildkit_session_is_relayed() {
+    let proxy = start_proxy_with(&[], spawn_upgrading_upstream);
+    let mut stream = UnixStream::connect(&proxy.socket).unwrap();
+    stream
+        .write_all(b"POST /v1.45/session HTTP/1.1\r\nHost: docker\r\nConnection: Upgrade\r\nUpgrade: h2c\r\nX-Docker-Expose-Session-Uuid: abc\r\n\r\n")
+        .unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut status = String::new();
+    reader.read_line(&mut status).unwrap();
+    assert!(status.starts_with("HTTP/1.1 101 "), "{}", status);
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line == "\r\n" {
+            break;
+        }
+    }
+
+    // 101 の後は h2c のフレームとしてそのまま中継される
+    stream.write_all(b"PRI * HTTP/2.0").unwrap();
+    let mut echoed = [0; 14];
+    reader.read_exact(&mut echoed).unwrap();
+    assert_eq!(&echoed, b"PRI * HTTP/2.0");
+
+    let received = proxy.received();
+    assert_eq!(received.len(), 1);
+    assert!(received[0].starts_with("POST /v1.45/session HTTP/1.1\r\n"));
+    assert!(received[0].contains("Upgrade: h2c\r\n"));
+    assert!(received[0].contains("X-Docker-Expose-Session-Uuid: abc\r\n"));
+}
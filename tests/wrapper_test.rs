@@ -4,6 +4,23 @@ fn home_dir() -> String {
     dirs::home_dir().unwrap().to_string_lossy().to_string()
 }
 
+/// $HOME 配下に一時ディレクトリを作り、`~/` からの相対名とともに返す
+/// (存在しないマウント元は確認になるため)
+fn home_tempdir() -> (tempfile::TempDir, String) {
+    let dir = tempfile::Builder::new()
+        .prefix(".safe-docker-test-")
+        .tempdir_in(home_dir())
+        .unwrap();
+    let name = dir
+        .path()
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    (dir, name)
+}
+
 /// ラッパーモードで safe-docker を実行するヘルパー
 fn run_wrapper(args: &[&str]) -> (String, String, i32) {
     run_wrapper_with_env(args, &[])
@@ -60,7 +77,8 @@ fn test_wrapper_allow_ps() {
 
 #[test]
 fn test_wrapper_allow_home_mount() {
-    let mount_arg = format!("{}/projects:/app", home_dir());
+    let (_dir, name) = home_tempdir();
+    let mount_arg = format!("{}/{}:/app", home_dir(), name);
     let (stdout, _stderr, exit_code) =
         run_wrapper(&["run", "-v", &mount_arg, "ubuntu", "echo", "hello"]);
     assert_eq!(exit_code, 0);
@@ -85,6 +103,41 @@ fn test_wrapper_deny_mount_etc() {
     );
 }
 
+#[test]
+fn test_wrapper_deny_volume_device_etc() {
+    // docker volume create の device= で作ったボリュームは /etc のバインドマウントと同じ
+    let (_stdout, stderr, exit_code) = run_wrapper(&[
+        "volume",
+        "create",
+        "--opt",
+        "type=none",
+        "--opt",
+        "o=bind",
+        "--opt",
+        "device=/etc",
+        "etc",
+    ]);
+    assert_eq!(exit_code, 1);
+    assert!(
+        stderr.contains("outside $HOME"),
+        "Expected path denial reason, got: {}",
+        stderr
+    );
+
+    let (_stdout, stderr, exit_code) = run_wrapper(&[
+        "run",
+        "--mount",
+        "type=volume,src=etc,dst=/data,volume-opt=type=none,volume-opt=o=bind,volume-opt=device=/etc",
+        "ubuntu",
+    ]);
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("outside $HOME"), "stderr: {}", stderr);
+
+    let (stdout, _stderr, exit_code) = run_wrapper(&["volume", "create", "cache"]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.contains("volume create cache"));
+}
+
 #[test]
 fn test_wrapper_deny_privileged() {
     let (_stdout, stderr, exit_code) = run_wrapper(&["run", "--privileged", "ubuntu"]);
@@ -346,7 +399,9 @@ fn test_wrapper_deny_mount_type_bind() {
 
 #[test]
 fn test_wrapper_allow_tilde_mount() {
-    let (stdout, _stderr, exit_code) = run_wrapper(&["run", "-v", "~/projects:/app", "ubuntu"]);
+    let (_dir, name) = home_tempdir();
+    let mount_arg = format!("~/{}:/app", name);
+    let (stdout, _stderr, exit_code) = run_wrapper(&["run", "-v", &mount_arg, "ubuntu"]);
     assert_eq!(exit_code, 0);
     assert!(stdout.contains("run"));
 }
@@ -415,8 +470,10 @@ fn test_wrapper_verbose_deny_network_host() {
 
 #[test]
 fn test_wrapper_v_flag_is_docker_volume() {
-    // -v ~/projects:/app は docker の volume フラグ、safe-docker の --verbose ではない
-    let (stdout, _stderr, exit_code) = run_wrapper(&["run", "-v", "~/projects:/app", "ubuntu"]);
+    // -v ~/dir:/app は docker の volume フラグ、safe-docker の --verbose ではない
+    let (_dir, name) = home_tempdir();
+    let mount_arg = format!("~/{}:/app", name);
+    let (stdout, _stderr, exit_code) = run_wrapper(&["run", "-v", &mount_arg, "ubuntu"]);
     assert_eq!(exit_code, 0);
     // -v がフィルタされずに docker に渡されることを確認
     assert!(
@@ -1401,3 +1458,301 @@ fn test_wrapper_non_interactive_default_is_deny() {
         stderr
     );
 }
+
+// --- マウント元の TOCTOU 対策 ---
+
+#[test]
+fn test_wrapper_pin_mount_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    std::fs::create_dir(root.join("real")).unwrap();
+    std::os::unix::fs::symlink(root.join("real"), root.join("link")).unwrap();
+    let config_path = root.join("config.toml");
+    std::fs::write(
+        &config_path,
+        format!(
+            "allowed_paths = [\"{}\"]\n[wrapper]\npin_mount_sources = true\n",
+            root.display()
+        ),
+    )
+    .unwrap();
+
+    let mount_arg = format!("{}/link:/data", root.display());
+    let (stdout, stderr, exit_code) =
+        run_wrapper_with_config(&["run", "-v", &mount_arg, "ubuntu"], &config_path);
+    assert_eq!(exit_code, 0, "stderr: {}", stderr);
+    // docker (echo) には解決済みのパスが渡る
+    assert!(
+        stdout.contains(&format!("{}/real:/data", root.display())),
+        "Expected pinned source, got stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_wrapper_ask_missing_mount_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let config_path = root.join("config.toml");
+    std::fs::write(
+        &config_path,
+        format!(
+            "allowed_paths = [\"{}\"]\nask_missing_mount_sources = true\n",
+            root.display()
+        ),
+    )
+    .unwrap();
+
+    let mount_arg = format!("{}/new:/data", root.display());
+    let (_, stderr, exit_code) =
+        run_wrapper_with_config(&["run", "-v", &mount_arg, "ubuntu"], &config_path);
+    assert_eq!(exit_code, 1, "stderr: {}", stderr);
+    assert!(
+        stderr.contains("Mount source does not exist"),
+        "Expected missing source reason, got stderr: {}",
+        stderr
+    );
+
+    std::fs::create_dir(root.join("new")).unwrap();
+    let (_, stderr, exit_code) =
+        run_wrapper_with_config(&["run", "-v", &mount_arg, "ubuntu"], &config_path);
+    assert_eq!(exit_code, 0, "stderr: {}", stderr);
+}
+
+#[test]
+fn test_wrapper_image_pinning() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let config_path = root.join("config.toml");
+    std::fs::write(
+        &config_path,
+        format!(
+            "allowed_paths = [\"{}\"]\n[image_pinning]\nmutable_tags = \"ask\"\nrequire_digest = [\"ghcr.io\"]\nmissing_digest = \"deny\"\n",
+            root.display()
+        ),
+    )
+    .unwrap();
+
+    let (_, stderr, exit_code) = run_wrapper_with_config(&["run", "ubuntu"], &config_path);
+    assert_eq!(exit_code, 1, "stderr: {}", stderr);
+    assert!(
+        stderr.contains("'ubuntu' (docker run) is untagged"),
+        "Expected mutable tag reason, got stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, exit_code) = run_wrapper_with_config(&["run", "ubuntu:24.04"], &config_path);
+    assert_eq!(exit_code, 0, "stderr: {}", stderr);
+
+    std::fs::write(
+        root.join("Dockerfile"),
+        "FROM ghcr.io/ourorg/base:1.0\nRUN true\n",
+    )
+    .unwrap();
+    let context = root.to_string_lossy();
+    let (_, stderr, exit_code) =
+        run_wrapper_with_config(&["build", "-t", "app", &context], &config_path);
+    assert_eq!(exit_code, 1, "stderr: {}", stderr);
+    assert!(
+        stderr.contains("Dockerfile:1 FROM) is not pinned by digest"),
+        "Expected digest reason, got stderr: {}",
+        stderr
+    );
+    assert!(stderr.contains("ghcr.io/ourorg/base:1.0@sha256:<digest>"));
+}
+
+#[test]
+fn test_wrapper_image_signatures_fail_closed() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let config_path = root.join("config.toml");
+    // 読めない鍵 (cosign feature なしのビルドでも検証に失敗する)
+    std::fs::write(
+        &config_path,
+        format!(
+            "[image_signatures]\nkeys = [\"{}/missing.pub\"]\nexempt_registries = [\"docker.io\"]\nunsigned = \"deny\"\n",
+            root.display()
+        ),
+    )
+    .unwrap();
+
+    let image = format!("ghcr.io/ourorg/app@sha256:{}", "a".repeat(64));
+    let (_, stderr, exit_code) = run_wrapper_with_config(&["run", &image], &config_path);
+    assert_eq!(exit_code, 1, "stderr: {}", stderr);
+    assert!(
+        stderr.contains("has no verified signature"),
+        "Expected signature reason, got stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, exit_code) = run_wrapper_with_config(&["run", "ubuntu:24.04"], &config_path);
+    assert_eq!(exit_code, 0, "stderr: {}", stderr);
+}
+
+#[test]
+fn test_wrapper_dockerfile_lint() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let context = root.join("app");
+    std::fs::create_dir(&context).unwrap();
+    std::fs::write(
+        context.join("Dockerfile"),
+        "FROM alpine:3.19\nRUN --security=insecure mount -t tmpfs none /mnt\nCOPY . /app\n",
+    )
+    .unwrap();
+    std::fs::write(context.join(".env"), "TOKEN=x\n").unwrap();
+    let config_path = root.join("config.toml");
+    let write_config = |extra: &str| {
+        std::fs::write(
+            &config_path,
+            format!("allowed_paths = [\"{}\"]\n{}", root.display(), extra),
+        )
+        .unwrap();
+    };
+    let context_arg = context.to_string_lossy();
+
+    write_config("");
+    let (_, stderr, exit_code) =
+        run_wrapper_with_config(&["build", "-t", "app", &context_arg], &config_path);
+    assert_eq!(exit_code, 1, "stderr: {}", stderr);
+    assert!(
+        stderr.contains("Dockerfile rule DF002") && stderr.contains("Dockerfile:2"),
+        "Expected DF002 reason, got stderr: {}",
+        stderr
+    );
+
+    // .dockerignore で .env を除外し、DF002 を無効にすると通る
+    std::fs::write(context.join(".dockerignore"), ".env\n").unwrap();
+    write_config("[dockerfile_lint]\nignore_rules = [\"DF002\"]\n");
+    let (_, stderr, exit_code) =
+        run_wrapper_with_config(&["build", "-t", "app", &context_arg], &config_path);
+    assert_eq!(exit_code, 0, "stderr: {}", stderr);
+}
+
+#[test]
+fn test_wrapper_build_context_scan() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let context = root.join("app");
+    std::fs::create_dir_all(context.join("certs")).unwrap();
+    std::fs::write(context.join("Dockerfile"), "FROM alpine:3.19\n").unwrap();
+    std::fs::write(context.join(".env"), "TOKEN=x\n").unwrap();
+    std::fs::write(context.join("certs/server.key"), "key\n").unwrap();
+    let config_path = root.join("config.toml");
+    std::fs::write(
+        &config_path,
+        format!("allowed_paths = [\"{}\"]\n", root.display()),
+    )
+    .unwrap();
+    let context_arg = context.to_string_lossy();
+
+    let (_, stderr, exit_code) =
+        run_wrapper_with_config(&["build", "-t", "app", &context_arg], &config_path);
+    assert_eq!(exit_code, 1, "stderr: {}", stderr);
+    assert!(
+        stderr.contains("not excluded by .dockerignore: .env, certs/server.key")
+            || stderr.contains("not excluded by .dockerignore: certs/server.key, .env"),
+        "Expected build context reason, got stderr: {}",
+        stderr
+    );
+
+    // .dockerignore で除外すると通る
+    std::fs::write(context.join(".dockerignore"), ".env\ncerts\n").unwrap();
+    let (_, stderr, exit_code) =
+        run_wrapper_with_config(&["build", "-t", "app", &context_arg], &config_path);
+    assert_eq!(exit_code, 0, "stderr: {}", stderr);
+}
+
+#[test]
+fn test_wrapper_published_ports() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let config_path = root.join("config.toml");
+    std::fs::write(
+        &config_path,
+        "[published_ports]\nnon_loopback = \"deny\"\nprivileged = \"deny\"\n",
+    )
+    .unwrap();
+
+    let (_, stderr, exit_code) =
+        run_wrapper_with_config(&["run", "-d", "-p", "5432:5432", "postgres"], &config_path);
+    assert_eq!(exit_code, 1, "stderr: {}", stderr);
+    assert!(
+        stderr.contains("Published port 5432:5432 (docker run) is bound on all interfaces"),
+        "Expected published port reason, got stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, exit_code) =
+        run_wrapper_with_config(&["run", "-d", "-P", "nginx"], &config_path);
+    assert_eq!(exit_code, 1, "stderr: {}", stderr);
+    assert!(stderr.contains("--publish-all"), "stderr: {}", stderr);
+
+    let (_, stderr, exit_code) = run_wrapper_with_config(
+        &["run", "-d", "-p", "127.0.0.1:80:80", "nginx"],
+        &config_path,
+    );
+    assert_eq!(exit_code, 1, "stderr: {}", stderr);
+    assert!(
+        stderr.contains("privileged host port 80"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (stdout, stderr, exit_code) = run_wrapper_with_config(
+        &["run", "-d", "-p", "127.0.0.1:5432:5432", "postgres"],
+        &config_path,
+    );
+    assert_eq!(exit_code, 0, "stderr: {}", stderr);
+    assert!(stdout.contains("127.0.0.1:5432:5432"));
+}
+
+#[test]
+fn test_wrapper_network_policy() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let config_path = root.join("config.toml");
+    std::fs::write(
+        &config_path,
+        "[network_policy]\nbridging = \"deny\"\nisolated_images = [\"busybox\"]\nnot_isolated = \"deny\"\n",
+    )
+    .unwrap();
+
+    let (_, stderr, exit_code) = run_wrapper_with_config(
+        &[
+            "network",
+            "create",
+            "-d",
+            "macvlan",
+            "-o",
+            "parent=eth0",
+            "lan",
+        ],
+        &config_path,
+    );
+    assert_eq!(exit_code, 1, "stderr: {}", stderr);
+    assert!(
+        stderr.contains("Network 'lan' (docker network create) uses the macvlan driver"),
+        "Expected bridging driver reason, got stderr: {}",
+        stderr
+    );
+
+    // 調べられないネットワークは分離されていないものとして扱う
+    let (_, stderr, exit_code) = run_wrapper_with_config(
+        &["run", "--rm", "--network", "sandbox", "busybox"],
+        &config_path,
+    );
+    assert_eq!(exit_code, 1, "stderr: {}", stderr);
+    assert!(
+        stderr.contains("must be isolated by network_policy.isolated_images"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (stdout, stderr, exit_code) = run_wrapper_with_config(
+        &["run", "--rm", "--network", "none", "busybox"],
+        &config_path,
+    );
+    assert_eq!(exit_code, 0, "stderr: {}", stderr);
+    assert!(stdout.contains("--network none"));
+}